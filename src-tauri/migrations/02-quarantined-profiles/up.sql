@@ -0,0 +1,12 @@
+CREATE TABLE quarantined_profiles (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    profile_id INT NOT NULL,
+    name TEXT NOT NULL,
+    path TEXT NOT NULL,
+    game_slug TEXT NOT NULL,
+    mods TEXT,
+    modpack TEXT,
+    ignored_updates TEXT,
+    error TEXT NOT NULL,
+    quarantined_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1 @@
+ALTER TABLE managed_games DROP COLUMN last_played;
@@ -0,0 +1 @@
+ALTER TABLE managed_games ADD COLUMN last_played TEXT;
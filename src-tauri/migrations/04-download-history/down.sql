@@ -0,0 +1,2 @@
+DROP TABLE download_samples;
+DROP TABLE watched_packages;
@@ -0,0 +1,11 @@
+CREATE TABLE watched_packages (
+    package_uuid BLOB PRIMARY KEY NOT NULL,
+    added_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE download_samples (
+    package_uuid BLOB NOT NULL,
+    sampled_at TEXT NOT NULL,
+    downloads INTEGER NOT NULL,
+    PRIMARY KEY (package_uuid, sampled_at)
+);
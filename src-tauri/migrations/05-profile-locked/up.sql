@@ -0,0 +1 @@
+ALTER TABLE profiles ADD COLUMN locked INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,4 @@
+CREATE TABLE cache_access (
+    ident TEXT PRIMARY KEY NOT NULL,
+    accessed_at TEXT NOT NULL
+);
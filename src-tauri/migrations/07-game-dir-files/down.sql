@@ -0,0 +1,3 @@
+DROP TABLE game_dir_files;
+
+ALTER TABLE profiles DROP COLUMN game_dir_install;
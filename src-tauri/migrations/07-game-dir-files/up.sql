@@ -0,0 +1,9 @@
+ALTER TABLE profiles ADD COLUMN game_dir_install INTEGER NOT NULL DEFAULT 0;
+
+CREATE TABLE game_dir_files (
+    game_slug TEXT NOT NULL,
+    game_dir TEXT NOT NULL,
+    path TEXT NOT NULL,
+    profile_id INTEGER NOT NULL,
+    PRIMARY KEY (game_slug, path)
+);
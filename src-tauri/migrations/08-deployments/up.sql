@@ -0,0 +1,8 @@
+CREATE TABLE deployments (
+    game_slug TEXT PRIMARY KEY NOT NULL,
+    profile_id INTEGER NOT NULL,
+    target TEXT NOT NULL,
+    method TEXT NOT NULL,
+    files TEXT NOT NULL,
+    complete INTEGER NOT NULL DEFAULT 0
+);
@@ -0,0 +1,8 @@
+CREATE TABLE cache_files (
+    ident TEXT NOT NULL,
+    path TEXT NOT NULL,
+    hash TEXT NOT NULL,
+    PRIMARY KEY (ident, path)
+);
+
+CREATE INDEX cache_files_hash ON cache_files (hash);
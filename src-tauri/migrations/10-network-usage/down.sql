@@ -0,0 +1,3 @@
+DROP TABLE network_downloads;
+
+DROP TABLE network_usage;
@@ -0,0 +1,15 @@
+CREATE TABLE network_usage (
+    day TEXT NOT NULL,
+    game_slug TEXT NOT NULL,
+    bytes INTEGER NOT NULL,
+    PRIMARY KEY (day, game_slug)
+);
+
+CREATE TABLE network_downloads (
+    name TEXT NOT NULL,
+    game_slug TEXT NOT NULL,
+    bytes INTEGER NOT NULL,
+    downloaded_at TEXT NOT NULL
+);
+
+CREATE INDEX network_downloads_time ON network_downloads (downloaded_at);
@@ -0,0 +1,3 @@
+DROP TABLE cache_versions;
+
+ALTER TABLE cache_files DROP COLUMN size;
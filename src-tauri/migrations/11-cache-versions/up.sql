@@ -0,0 +1,7 @@
+ALTER TABLE cache_files ADD COLUMN size INTEGER;
+
+CREATE TABLE cache_versions (
+    ident TEXT PRIMARY KEY NOT NULL
+);
+
+INSERT INTO cache_versions (ident) SELECT DISTINCT ident FROM cache_files;
@@ -0,0 +1,4 @@
+CREATE TABLE package_aliases (
+    old_name TEXT NOT NULL PRIMARY KEY,
+    new_name TEXT NOT NULL
+);
@@ -0,0 +1,11 @@
+CREATE TABLE launches (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    game_slug TEXT NOT NULL,
+    profile_id INTEGER NOT NULL,
+    launched_at TEXT NOT NULL,
+    duration_ms INTEGER,
+    exit_code INTEGER,
+    crashed INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE INDEX launches_game_slug ON launches (game_slug, launched_at);
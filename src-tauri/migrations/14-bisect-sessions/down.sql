@@ -0,0 +1 @@
+DROP TABLE bisect_sessions;
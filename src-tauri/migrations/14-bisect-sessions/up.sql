@@ -0,0 +1,4 @@
+CREATE TABLE bisect_sessions (
+    profile_id INTEGER NOT NULL PRIMARY KEY,
+    data TEXT NOT NULL
+);
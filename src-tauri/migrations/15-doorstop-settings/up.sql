@@ -0,0 +1 @@
+ALTER TABLE profiles ADD COLUMN doorstop TEXT;
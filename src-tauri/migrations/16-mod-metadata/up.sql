@@ -0,0 +1,5 @@
+CREATE TABLE mod_metadata (
+    package_uuid BLOB PRIMARY KEY NOT NULL,
+    data JSON NOT NULL,
+    updated_at TEXT NOT NULL
+);
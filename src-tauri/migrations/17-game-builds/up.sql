@@ -0,0 +1,4 @@
+CREATE TABLE game_builds (
+    game_slug TEXT NOT NULL PRIMARY KEY,
+    data TEXT NOT NULL
+);
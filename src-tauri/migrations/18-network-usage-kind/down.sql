@@ -0,0 +1 @@
+ALTER TABLE network_usage DROP COLUMN metadata_bytes;
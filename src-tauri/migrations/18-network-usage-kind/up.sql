@@ -0,0 +1 @@
+ALTER TABLE network_usage ADD COLUMN metadata_bytes INTEGER NOT NULL DEFAULT 0;
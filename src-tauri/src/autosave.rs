@@ -0,0 +1,176 @@
+//! Saves the prefs and profiles in the background whenever they change,
+//! so a crash loses at most the last couple of seconds of changes.
+//!
+//! Changes are detected by [`StateMutex`], which marks itself dirty when its
+//! value is borrowed mutably. Saves happen at most once every [`INTERVAL`],
+//! and pending changes are [`flush`]ed when the window is closed.
+//!
+//! [`StateMutex`]: crate::state::StateMutex
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use eyre::Result;
+use log::warn;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::Notify;
+
+use crate::state::{AppState, ManagerExt};
+
+/// The shortest time between two autosaves.
+pub const INTERVAL: Duration = Duration::from_secs(2);
+
+/// Sent to the frontend as the `save-status` event.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SaveStatus {
+    Saved,
+    Saving,
+    /// There are changes that haven't been saved yet.
+    Dirty,
+}
+
+fn report(status: SaveStatus, app: &AppHandle) {
+    app.emit("save-status", status).ok();
+}
+
+/// Starts saving changes in the background. `on_dirty` is notified
+/// by the tracked state mutexes when they're changed.
+pub fn start(on_dirty: Arc<Notify>, app: &AppHandle) {
+    let save_app = app.clone();
+    let report_app = app.clone();
+
+    tauri::async_runtime::spawn(run(
+        on_dirty,
+        INTERVAL,
+        move || save_app.app_state().save_dirty().map(|_| ()),
+        move |status| report(status, &report_app),
+    ));
+}
+
+/// Saves any pending changes right away, for example before the app exits.
+pub fn flush(app: &AppHandle) {
+    // the state isn't managed if setup failed
+    let Some(state) = app.try_state::<AppState>() else {
+        return;
+    };
+
+    match state.save_dirty() {
+        Ok(true) => report(SaveStatus::Saved, app),
+        Ok(false) => (),
+        Err(err) => warn!("failed to save changes: {:#}", err),
+    }
+}
+
+async fn run<S, R>(on_dirty: Arc<Notify>, interval: Duration, save: S, mut report: R)
+where
+    S: Fn() -> Result<()> + Send + Sync + 'static,
+    R: FnMut(SaveStatus),
+{
+    let save = Arc::new(save);
+    let mut last_save: Option<Instant> = None;
+
+    loop {
+        on_dirty.notified().await;
+        report(SaveStatus::Dirty);
+
+        // changes made while waiting are included in the same save
+        if let Some(last_save) = last_save {
+            tokio::time::sleep_until((last_save + interval).into()).await;
+        }
+
+        report(SaveStatus::Saving);
+        last_save = Some(Instant::now());
+
+        // saving waits on the state locks and writes to the database
+        let save = save.clone();
+        let result = tauri::async_runtime::spawn_blocking(move || save())
+            .await
+            .map_err(eyre::Error::from)
+            .and_then(|result| result);
+
+        match result {
+            Ok(()) => report(SaveStatus::Saved),
+            Err(err) => {
+                warn!("autosave failed: {:#}", err);
+                report(SaveStatus::Dirty);
+
+                // the state stays dirty, so try again after the interval
+                on_dirty.notify_one();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::state::StateMutex;
+
+    #[tokio::test]
+    async fn rapid_changes_are_saved_in_batches() {
+        const INTERVAL: Duration = Duration::from_millis(50);
+
+        let on_dirty = Arc::new(Notify::new());
+        let state = Arc::new(StateMutex::new(0, "test", |_| Ok(())).tracked(on_dirty.clone()));
+        let saves = Arc::new(Mutex::new(Vec::new()));
+
+        let task = tokio::spawn(run(
+            on_dirty,
+            INTERVAL,
+            {
+                let state = state.clone();
+                let saves = saves.clone();
+
+                move || {
+                    let value = state.lock()?;
+                    if state.is_dirty() {
+                        saves.lock().unwrap().push(*value);
+                        state.clear_dirty();
+                    }
+                    Ok(())
+                }
+            },
+            |_| (),
+        ));
+
+        let start = Instant::now();
+
+        for i in 1..=200 {
+            *state.lock().unwrap() = i;
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+
+        let elapsed = start.elapsed();
+        tokio::time::sleep(INTERVAL * 3).await;
+        task.abort();
+
+        let saves = saves.lock().unwrap();
+        let max_saves = (elapsed.as_millis() / INTERVAL.as_millis()) as usize + 2;
+
+        assert!(
+            saves.len() <= max_saves,
+            "{} saves in {:?}",
+            saves.len(),
+            elapsed
+        );
+        assert_eq!(saves.last(), Some(&200));
+        assert!(!state.is_dirty());
+    }
+
+    #[test]
+    fn untracked_changes_arent_dirty() {
+        let state = StateMutex::new(0, "test", |_| Ok(())).tracked(Arc::new(Notify::new()));
+
+        *state.lock().unwrap().untracked() = 1;
+        assert!(!state.is_dirty());
+
+        *state.lock().unwrap() = 2;
+        assert!(state.is_dirty());
+    }
+}
@@ -19,7 +19,7 @@ pub fn run(app: &App) -> Result<()> {
                 return Ok(());
             }
 
-            let mut manager = app.lock_manager();
+            let mut manager = app.lock_manager()?;
 
             if let Some(Value::String(slug)) = matches.args.get("game").map(|arg| &arg.value) {
                 let game = game::from_slug(slug).ok_or_eyre("unknown game id")?;
@@ -52,7 +52,7 @@ pub fn run(app: &App) -> Result<()> {
             if let Some(Value::Bool(true)) = matches.args.get("launch").map(|arg| &arg.value) {
                 manager
                     .active_game()
-                    .launch(&app.lock_prefs(), app.handle())
+                    .launch(&*app.lock_prefs()?, app.handle())
                     .context("failed to launch game")?;
             }
 
@@ -74,7 +74,7 @@ pub fn run(app: &App) -> Result<()> {
 }
 
 async fn install_local_mod(path: PathBuf, handle: tauri::AppHandle) {
-    profile::import::import_local_mod(
+    let result = profile::import::import_local_mod(
         path,
         &handle,
         InstallOptions::default().on_progress(Box::new(|progress, _| {
@@ -85,7 +85,11 @@ async fn install_local_mod(path: PathBuf, handle: tauri::AppHandle) {
                 (progress.total_progress * 100.0).round()
             )
         })),
+        true,
     )
-    .await
-    .unwrap_or_else(|err| error!("failed to install mod from cli: {:#}", err));
+    .await;
+
+    if let Err(err) = result {
+        error!("failed to install mod from cli: {:#}", err);
+    }
 }
@@ -117,6 +117,7 @@ impl EntryBuilder {
 
         Ok(Entry {
             name,
+            display_name: None,
             type_name,
             default_value,
             value,
@@ -172,6 +173,46 @@ impl EntryBuilder {
     }
 }
 
+impl Entry {
+    /// Parses `value` the way this entry's value would be read from the file,
+    /// keeping its options and range.
+    pub(super) fn parse_raw(&self, value: &str) -> Result<Value> {
+        let value = value.trim();
+
+        if let Some(options) = self.value.options() {
+            let is_flags = matches!(self.value, Value::Flags { .. });
+
+            let unknown = match is_flags {
+                true => value
+                    .split(", ")
+                    .find(|value| !options.iter().any(|opt| opt == value)),
+                false => (!options.iter().any(|opt| opt == value)).then_some(value),
+            };
+
+            if let Some(unknown) = unknown {
+                bail!("'{}' is not one of {}", unknown, options.join(", "));
+            }
+
+            return Ok(EntryBuilder::parse_enum(
+                value.to_owned(),
+                options.to_vec(),
+                is_flags,
+            ));
+        }
+
+        let mut parsed = EntryBuilder::parse_simple_value(value.to_owned(), &self.type_name, None)?;
+
+        match (&mut parsed, &self.value) {
+            (Value::Int32(new), Value::Int32(old)) => new.range.clone_from(&old.range),
+            (Value::Single(new), Value::Single(old)) => new.range.clone_from(&old.range),
+            (Value::Double(new), Value::Double(old)) => new.range.clone_from(&old.range),
+            _ => (),
+        }
+
+        Ok(parsed)
+    }
+}
+
 impl<R: Read + BufRead> Parser<R> {
     fn parse(&mut self) -> Result<()> {
         while let Some(line) = self.peek()? {
@@ -276,6 +317,7 @@ impl<R: Read + BufRead> Parser<R> {
         let name = &line[1..line.len() - 1];
         self.sections.push(Section {
             name: name.to_owned(),
+            display_name: None,
             entries: Vec::new(),
         });
 
@@ -1,6 +1,7 @@
 use std::io::{BufRead, Write};
 
-use eyre::{eyre, OptionExt, Result};
+use eyre::{bail, ensure, eyre, OptionExt, Result};
+use serde::{Deserialize, Serialize};
 
 use super::frontend::{self, Num};
 
@@ -10,7 +11,7 @@ pub mod ser;
 #[cfg(test)]
 mod tests;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Default, PartialEq)]
 pub struct File {
     metadata: Option<Metadata>,
     sections: Vec<Section>,
@@ -23,6 +24,12 @@ impl File {
             .map(|metadata| metadata.plugin_name.as_str())
     }
 
+    pub fn plugin_guid(&self) -> Option<&str> {
+        self.metadata
+            .as_ref()
+            .map(|metadata| metadata.plugin_guid.as_str())
+    }
+
     pub fn read(reader: impl BufRead) -> Result<Self> {
         de::from_reader(reader)
     }
@@ -54,6 +61,172 @@ impl File {
         self.find_section(section)
             .and_then(|section| section.find_entry(entry))
     }
+
+    /// Returns the value of an entry as it's written in the file.
+    pub fn get_raw(&self, section: &str, entry: &str) -> Option<String> {
+        let entry = self
+            .sections
+            .iter()
+            .find(|s| s.name == section)?
+            .entries
+            .iter()
+            .find(|e| e.name() == entry)?;
+
+        Some(match entry {
+            EntryKind::Normal(entry) => ser::value_to_string(&entry.value),
+            EntryKind::Orphaned { value, .. } => value.clone(),
+        })
+    }
+
+    /// Sets an entry from its textual form, as it would appear in the file.
+    ///
+    /// Sections and entries that don't exist yet are added as untagged entries,
+    /// which the mod picks up the next time it loads its config.
+    ///
+    /// Returns whether the file was changed.
+    pub fn set_raw(&mut self, section: &str, entry: &str, value: &str) -> Result<bool> {
+        ensure!(!value.contains('\n'), "value cannot contain line breaks");
+
+        let index = match self.sections.iter().position(|s| s.name == section) {
+            Some(index) => index,
+            None => {
+                check_key(section)?;
+                self.sections.push(Section {
+                    name: section.to_owned(),
+                    display_name: None,
+                    entries: Vec::new(),
+                });
+                self.sections.len() - 1
+            }
+        };
+
+        let section = &mut self.sections[index];
+
+        match section.entries.iter_mut().find(|e| e.name() == entry) {
+            Some(EntryKind::Normal(entry)) => {
+                let value = entry.parse_raw(value)?;
+                if entry.value == value {
+                    return Ok(false);
+                }
+
+                entry.value = value;
+            }
+            Some(EntryKind::Orphaned { value: old, .. }) => {
+                if old == value.trim() {
+                    return Ok(false);
+                }
+
+                *old = value.trim().to_owned();
+            }
+            None => {
+                check_key(entry)?;
+                section.entries.push(EntryKind::Orphaned {
+                    name: entry.to_owned(),
+                    value: value.trim().to_owned(),
+                });
+            }
+        }
+
+        Ok(true)
+    }
+
+    pub fn rename_section(&mut self, name: &str, new_name: &str, mode: RenameMode) -> Result<()> {
+        if mode == RenameMode::Key && name != new_name {
+            check_key(new_name)?;
+            ensure!(
+                self.sections.iter().all(|section| section.name != new_name),
+                "a section named '{}' already exists",
+                new_name
+            );
+        }
+
+        let section = self.find_section(name)?;
+
+        match mode {
+            RenameMode::Display => section.display_name = display_name(name, new_name),
+            RenameMode::Key => {
+                section.name = new_name.to_owned();
+                section.display_name = None;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn rename_entry(
+        &mut self,
+        section: &str,
+        name: &str,
+        new_name: &str,
+        mode: RenameMode,
+    ) -> Result<()> {
+        let section = self.find_section(section)?;
+
+        if mode == RenameMode::Key && name != new_name {
+            check_key(new_name)?;
+            ensure!(
+                section.entries.iter().all(|entry| entry.name() != new_name),
+                "an entry named '{}' already exists in this section",
+                new_name
+            );
+        }
+
+        let entry = section.find_entry(name)?;
+
+        match mode {
+            RenameMode::Display => {
+                entry.as_normal_mut()?.display_name = display_name(name, new_name);
+            }
+            RenameMode::Key => match entry {
+                EntryKind::Normal(entry) => {
+                    entry.name = new_name.to_owned();
+                    entry.display_name = None;
+                }
+                EntryKind::Orphaned { name, .. } => *name = new_name.to_owned(),
+            },
+        }
+
+        Ok(())
+    }
+}
+
+/// How a section or entry is renamed.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum RenameMode {
+    /// Only change the name shown in the config editor. The file is left as is,
+    /// and the name is forgotten once the file is reloaded from disk.
+    Display,
+    /// Change the name in the file itself. The mod looks up its settings by
+    /// name, so it will most likely treat the renamed one as a new setting.
+    Key,
+}
+
+/// Characters BepInEx doesn't allow in section or entry names.
+const INVALID_KEY_CHARS: [char; 8] = ['=', '\n', '\t', '\\', '"', '\'', '[', ']'];
+
+fn check_key(name: &str) -> Result<()> {
+    if name.trim().is_empty() {
+        bail!("name cannot be empty");
+    }
+
+    ensure!(
+        name.trim() == name,
+        "name cannot start or end with whitespace"
+    );
+
+    if let Some(char) = name.chars().find(|char| INVALID_KEY_CHARS.contains(char)) {
+        bail!("name cannot contain {:?}", char);
+    }
+
+    Ok(())
+}
+
+/// An empty name, or the key itself, resets the display name.
+pub(super) fn display_name(key: &str, new_name: &str) -> Option<String> {
+    let new_name = new_name.trim();
+
+    (!new_name.is_empty() && new_name != key).then(|| new_name.to_owned())
 }
 
 #[derive(Debug, PartialEq)]
@@ -66,6 +239,8 @@ pub struct Metadata {
 #[derive(Debug, PartialEq)]
 pub struct Section {
     name: String,
+    /// Set by [`RenameMode::Display`], never written to the file.
+    display_name: Option<String>,
     entries: Vec<EntryKind>,
 }
 
@@ -79,6 +254,7 @@ impl Section {
 
         frontend::Section {
             name: self.name.clone(),
+            display_name: self.display_name.clone(),
             entries,
         }
     }
@@ -138,6 +314,8 @@ impl From<Entry> for EntryKind {
 #[derive(Debug, PartialEq)]
 pub struct Entry {
     name: String,
+    /// Set by [`RenameMode::Display`], never written to the file.
+    display_name: Option<String>,
     description: Option<String>,
     type_name: String,
     default_value: Option<Value>,
@@ -171,6 +349,7 @@ impl Entry {
     fn to_frontend(&self) -> frontend::Entry {
         frontend::Entry {
             name: self.name.clone(),
+            display_name: self.display_name.clone(),
             description: self.description.clone(),
             default: self.default_value.clone().map(|value| value.into()),
             value: self.value.clone().into(),
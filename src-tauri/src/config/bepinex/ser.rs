@@ -164,6 +164,16 @@ pub fn to_writer<W: Write>(file: &File, writer: W) -> io::Result<()> {
     serializer.writer.flush()
 }
 
+/// Formats a value the way it's written in the file.
+pub fn value_to_string(value: &Value) -> String {
+    let mut serializer = Serializer { writer: Vec::new() };
+    serializer
+        .write_value(value)
+        .expect("writing to a vec should not fail");
+
+    String::from_utf8_lossy(&serializer.writer).into_owned()
+}
+
 #[allow(unused)]
 pub fn to_string(file: &File) -> io::Result<String> {
     let mut vec = Vec::new();
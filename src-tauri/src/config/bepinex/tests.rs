@@ -4,6 +4,7 @@ impl Section {
     fn new(name: &str, entries: Vec<EntryKind>) -> Self {
         Self {
             name: name.to_owned(),
+            display_name: None,
             entries,
         }
     }
@@ -39,6 +40,7 @@ impl EntryKind {
     ) -> Self {
         Entry {
             name: name.to_owned(),
+            display_name: None,
             description: Some(description.to_owned()),
             type_name: type_name.to_owned(),
             default_value,
@@ -194,3 +196,87 @@ fn check_from_string() {
 
     assert_eq!(left, right);
 }
+
+#[test]
+fn display_rename_keeps_key() {
+    let mut file = test_file();
+
+    file.rename_entry("Section1", "Entry1", "Nicer name", RenameMode::Display)
+        .unwrap();
+    file.rename_section("Section2", "Other", RenameMode::Display)
+        .unwrap();
+
+    assert_eq!(ser::to_string(&file).unwrap(), TEST_STR);
+
+    let data = file.to_frontend();
+    assert_eq!(data.sections[0].entries[0].name, "Entry1");
+    assert_eq!(
+        data.sections[0].entries[0].display_name.as_deref(),
+        Some("Nicer name")
+    );
+    assert_eq!(data.sections[1].display_name.as_deref(), Some("Other"));
+
+    // orphaned entries aren't shown in the first place
+    assert!(file
+        .rename_entry("Section2", "OrphanedEntry", "Hi", RenameMode::Display)
+        .is_err());
+}
+
+#[test]
+fn key_rename_is_written() {
+    let mut file = test_file();
+
+    file.rename_entry("Section1", "Entry1", "Renamed", RenameMode::Key)
+        .unwrap();
+    file.rename_section("Section2", "Renamed Section", RenameMode::Key)
+        .unwrap();
+
+    let expected = TEST_STR
+        .replace("Entry1 = Value1", "Renamed = Value1")
+        .replace("[Section2]", "[Renamed Section]");
+
+    assert_eq!(ser::to_string(&file).unwrap(), expected);
+}
+
+#[test]
+fn key_rename_is_guarded() {
+    let mut file = test_file();
+
+    for name in ["", " Padded", "Key = Value", "[Section]", "Entry3"] {
+        assert!(
+            file.rename_entry("Section1", "Entry1", name, RenameMode::Key)
+                .is_err(),
+            "{:?} was allowed",
+            name
+        );
+    }
+
+    assert!(file
+        .rename_section("Section1", "Section2", RenameMode::Key)
+        .is_err());
+    assert_eq!(file, test_file());
+}
+
+#[test]
+fn set_raw_parses_by_type() {
+    let mut file = test_file();
+
+    assert!(!file.set_raw("Section1", "Entry1", "Value1").unwrap());
+    assert!(file
+        .set_raw("Section1", "LogLevels", "Debug, Error")
+        .unwrap());
+    assert!(file.set_raw("Section1", "LogLevels", "Verbose").is_err());
+    assert!(file.set_raw("Section2", "OrphanedEntry", "Other").unwrap());
+    assert!(file.set_raw("New Section", "NewEntry", "true").unwrap());
+
+    let expected = TEST_STR
+        .replace("LogLevels = Info, Warning", "LogLevels = Debug, Error")
+        .replace("OrphanedEntry = Hi!", "OrphanedEntry = Other")
+        + "[New Section]\n\nNewEntry = true\n\n";
+
+    assert_eq!(ser::to_string(&file).unwrap(), expected);
+
+    let mut empty = File::default();
+    assert!(empty.set_raw("Logging.Console", "Enabled", "true").unwrap());
+    assert!(!empty.set_raw("Logging.Console", "Enabled", "true").unwrap());
+}
@@ -1,14 +1,16 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use eyre::{eyre, Context};
+use serde::Serialize;
 use tauri::{command, AppHandle};
+use uuid::Uuid;
 
-use super::{frontend, AnyFileKind};
+use super::{bepinex, frontend, AnyFileKind, ModConfigFile, RenameMode};
 use crate::{state::ManagerExt, util::cmd::Result};
 
 #[command]
 pub fn get_config_files(app: AppHandle) -> Result<Vec<frontend::File>> {
-    let mut manager = app.lock_manager();
+    let mut manager = app.lock_manager()?;
     let profile = manager.active_profile_mut();
 
     profile.refresh_config();
@@ -16,6 +18,18 @@ pub fn get_config_files(app: AppHandle) -> Result<Vec<frontend::File>> {
     Ok(profile.config_cache.to_frontend())
 }
 
+#[command]
+pub fn get_mod_config_files(uuid: Uuid, app: AppHandle) -> Result<Vec<ModConfigFile>> {
+    let prefs = app.lock_prefs()?;
+    let mut manager = app.lock_manager()?;
+
+    let profile = manager.active_profile_mut();
+    profile.refresh_config_if_stale();
+
+    let profile_mod = profile.get_mod(uuid)?;
+    Ok(profile.mod_config_files(profile_mod, &prefs))
+}
+
 #[command]
 pub fn set_config_entry(
     file: &Path,
@@ -24,9 +38,11 @@ pub fn set_config_entry(
     value: frontend::Value,
     app: AppHandle,
 ) -> Result<()> {
-    let mut manager = app.lock_manager();
+    let mut manager = app.lock_manager()?;
 
     let profile = manager.active_profile_mut();
+    profile.ensure_unlocked()?;
+
     let file = profile.config_cache.find_file(file)?;
 
     match &mut file.kind {
@@ -46,9 +62,11 @@ pub fn reset_config_entry(
     entry: &str,
     app: AppHandle,
 ) -> Result<frontend::Value> {
-    let mut manager = app.lock_manager();
+    let mut manager = app.lock_manager()?;
 
     let profile = manager.active_profile_mut();
+    profile.ensure_unlocked()?;
+
     let file = profile.config_cache.find_file(file)?;
 
     let value = match &mut file.kind {
@@ -60,9 +78,103 @@ pub fn reset_config_entry(
     Ok(value)
 }
 
+/// What [`rename_config_entry`] ended up changing.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigRename {
+    pub mode: RenameMode,
+    /// The name in the file, which is what the mod reads.
+    pub key: String,
+    /// The name shown in the config editor, if it differs from `key`.
+    pub display_name: Option<String>,
+    /// Whether the file on disk was changed.
+    pub written: bool,
+    /// Set if the mod may not recognize the renamed setting anymore.
+    pub warning: Option<String>,
+}
+
+/// Renames a section, or one of its entries if `entry` is given, in a BepInEx config file.
+///
+/// See [`RenameMode`] for what each mode changes.
+#[command]
+pub fn rename_config_entry(
+    file: &Path,
+    section: &str,
+    entry: Option<String>,
+    new_name: &str,
+    mode: RenameMode,
+    app: AppHandle,
+) -> Result<ConfigRename> {
+    let mut manager = app.lock_manager()?;
+
+    let profile = manager.active_profile_mut();
+    if mode == RenameMode::Key {
+        profile.ensure_unlocked()?;
+    }
+
+    let file = profile.config_cache.find_file(file)?;
+
+    let AnyFileKind::BepInEx(config) = &mut file.kind else {
+        return Err(eyre!("unsupported for this format").into());
+    };
+
+    let old_name = entry.as_deref().unwrap_or(section);
+
+    match &entry {
+        Some(entry) => config.rename_entry(section, entry, new_name, mode),
+        None => config.rename_section(section, new_name, mode),
+    }?;
+
+    let rename = match mode {
+        RenameMode::Display => ConfigRename {
+            mode,
+            key: old_name.to_owned(),
+            display_name: bepinex::display_name(old_name, new_name),
+            written: false,
+            warning: None,
+        },
+        RenameMode::Key => {
+            file.write(&profile.path).context("failed to write file")?;
+
+            let warning = (old_name != new_name).then(|| {
+                format!(
+                    "The mod looks for '{}' by name, so it will most likely ignore '{}' \
+                    and recreate the original with its default value.",
+                    old_name, new_name
+                )
+            });
+
+            ConfigRename {
+                mode,
+                key: new_name.to_owned(),
+                display_name: None,
+                written: true,
+                warning,
+            }
+        }
+    };
+
+    Ok(rename)
+}
+
+/// Copies a config file to a new name next to it, returning the copy's relative path.
+#[command]
+pub fn duplicate_config_file(
+    relative_path: PathBuf,
+    new_name: &str,
+    app: AppHandle,
+) -> Result<PathBuf> {
+    let mut manager = app.lock_manager()?;
+
+    let profile = manager.active_profile_mut();
+    profile.ensure_unlocked()?;
+
+    Ok(profile.duplicate_config_file(&relative_path, new_name)?)
+}
+
 #[command]
 pub fn open_config_file(file: &Path, app: AppHandle) -> Result<()> {
-    let manager = app.lock_manager();
+    let manager = app.lock_manager()?;
 
     let profile = manager.active_profile();
     let path = profile.path.join(file);
@@ -74,20 +186,21 @@ pub fn open_config_file(file: &Path, app: AppHandle) -> Result<()> {
 
 #[command]
 pub fn delete_config_file(file: &Path, app: AppHandle) -> Result<()> {
-    let mut manager = app.lock_manager();
+    let mut manager = app.lock_manager()?;
 
     let profile = manager.active_profile_mut();
+    profile.ensure_unlocked()?;
 
     let Some(index) = profile
         .config_cache
-        .0
+        .files
         .iter()
         .position(|f| f.relative_path == file)
     else {
         return Ok(()); // ignore if the file is not in the list
     };
 
-    profile.config_cache.0.remove(index);
+    profile.config_cache.files.remove(index);
 
     let path = profile.path.join(file);
     trash::delete(path).context("failed to move file to recycle bin")?;
@@ -45,6 +45,7 @@ pub struct Metadata {
 #[serde(rename_all = "camelCase")]
 pub struct Section {
     pub name: String,
+    pub display_name: Option<String>,
     pub entries: Vec<Entry>,
 }
 
@@ -52,6 +53,7 @@ pub struct Section {
 #[serde(rename_all = "camelCase")]
 pub struct Entry {
     pub name: String,
+    pub display_name: Option<String>,
     pub description: Option<String>,
     pub default: Option<Value>,
     pub value: Value,
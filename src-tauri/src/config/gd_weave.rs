@@ -6,7 +6,7 @@ use serde_json::{Number, Value};
 
 use super::frontend::{self, Num};
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct File {
     entries: IndexMap<String, Value>,
 }
@@ -27,6 +27,29 @@ impl File {
         Ok(())
     }
 
+    /// Returns the value of an entry in its textual form, with strings unquoted.
+    pub fn get_raw(&self, name: &str) -> Option<String> {
+        self.entries.get(name).map(|value| match value {
+            Value::String(str) => str.clone(),
+            value => value.to_string(),
+        })
+    }
+
+    /// Sets an entry from its textual form. Anything that isn't valid JSON
+    /// is stored as a string.
+    ///
+    /// Returns whether the file was changed.
+    pub fn set_raw(&mut self, name: &str, value: &str) -> bool {
+        let value = serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_owned()));
+
+        if self.entries.get(name) == Some(&value) {
+            return false;
+        }
+
+        self.entries.insert(name.to_owned(), value);
+        true
+    }
+
     pub fn to_frontend(&self) -> Result<frontend::FileData> {
         let entries = self
             .entries
@@ -37,6 +60,7 @@ impl File {
 
                 Ok(frontend::Entry {
                     name,
+                    display_name: None,
                     value,
                     description: None,
                     default: None,
@@ -48,6 +72,7 @@ impl File {
             metadata: None,
             sections: vec![frontend::Section {
                 name: "Default".into(),
+                display_name: None,
                 entries,
             }],
         })
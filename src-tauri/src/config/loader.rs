@@ -0,0 +1,323 @@
+//! Common mod loader settings, like the console window and logging, which
+//! support often asks users to change by editing the loader's config by hand.
+
+use std::{fs, io, path::Path};
+
+use eyre::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{game::ModLoaderKind, profile::Profile, util::error::IoResultExt};
+
+const BEPINEX_CONFIG: &str = "BepInEx/config/BepInEx.cfg";
+/// MelonLoader's own config, in TOML.
+const MELON_LOADER_CONFIG: &str = "UserData/Loader.cfg";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum LoaderSettingKey {
+    Console,
+    LogLevel,
+    DumpAssemblies,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoaderSetting {
+    Console(bool),
+    LogLevel(LogLevel),
+    DumpAssemblies(bool),
+}
+
+impl LoaderSetting {
+    pub fn new(key: LoaderSettingKey, value: serde_json::Value) -> Result<Self> {
+        let setting = match key {
+            LoaderSettingKey::Console => Self::Console(serde_json::from_value(value)?),
+            LoaderSettingKey::LogLevel => Self::LogLevel(serde_json::from_value(value)?),
+            LoaderSettingKey::DumpAssemblies => {
+                Self::DumpAssemblies(serde_json::from_value(value)?)
+            }
+        };
+
+        Ok(setting)
+    }
+}
+
+/// The least severe level of log messages shown, from least to most verbose.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub enum LogLevel {
+    Fatal,
+    Error,
+    Warning,
+    Message,
+    Info,
+    Debug,
+}
+
+impl LogLevel {
+    const ALL: [LogLevel; 6] = [
+        Self::Fatal,
+        Self::Error,
+        Self::Warning,
+        Self::Message,
+        Self::Info,
+        Self::Debug,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Fatal => "Fatal",
+            Self::Error => "Error",
+            Self::Warning => "Warning",
+            Self::Message => "Message",
+            Self::Info => "Info",
+            Self::Debug => "Debug",
+        }
+    }
+
+    /// The value of BepInEx's `LogLevels` flags that shows this level and everything above it.
+    fn to_bepinex_flags(self) -> String {
+        Self::ALL
+            .iter()
+            .filter(|level| **level <= self)
+            .map(|level| level.name())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn from_bepinex_flags(flags: &str) -> Self {
+        let flags = flags.split(',').map(str::trim).collect::<Vec<_>>();
+
+        if flags.contains(&"All") {
+            return Self::Debug;
+        }
+
+        Self::ALL
+            .into_iter()
+            .rev()
+            .find(|level| flags.contains(&level.name()))
+            .unwrap_or(Self::Fatal)
+    }
+}
+
+/// The effective values of the loader settings, as read from disk.
+///
+/// Settings the game hasn't written yet have the loader's default value.
+#[derive(Serialize, Debug, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub struct LoaderSettings {
+    pub console: bool,
+    pub log_level: LogLevel,
+    /// `None` if the mod loader can't dump assemblies.
+    pub dump_assemblies: Option<bool>,
+}
+
+impl Profile {
+    /// Reads the loader settings from disk, or returns `None` if the game's
+    /// mod loader doesn't have any.
+    pub fn loader_settings(&self) -> Result<Option<LoaderSettings>> {
+        match &self.game.mod_loader.kind {
+            ModLoaderKind::BepInEx { .. } => {
+                let get = |section, entry| {
+                    self.config_value(Path::new(BEPINEX_CONFIG), Some(section), entry)
+                };
+
+                let is_true = |value: Option<String>| value.is_some_and(|value| value == "true");
+
+                Ok(Some(LoaderSettings {
+                    console: is_true(get("Logging.Console", "Enabled")?),
+                    log_level: get("Logging.Console", "LogLevels")?
+                        .map(|flags| LogLevel::from_bepinex_flags(&flags))
+                        .unwrap_or(LogLevel::Info),
+                    dump_assemblies: Some(is_true(get("Preloader", "DumpAssemblies")?)),
+                }))
+            }
+            ModLoaderKind::MelonLoader { .. } => {
+                let text = read_melon_loader_config(&self.path)?;
+                let get = |section, key| toml_value(&text, section, key);
+
+                Ok(Some(LoaderSettings {
+                    console: get("console", "hide_console") != Some("true"),
+                    log_level: match get("loader", "debug_mode") {
+                        Some("true") => LogLevel::Debug,
+                        _ => LogLevel::Info,
+                    },
+                    dump_assemblies: None,
+                }))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Writes a loader setting to the loader's config, creating the file if
+    /// the game hasn't generated it yet.
+    ///
+    /// MelonLoader only distinguishes between debug logging and everything else,
+    /// so any level other than [`LogLevel::Debug`] turns debug mode off.
+    pub fn set_loader_setting(&mut self, setting: LoaderSetting) -> Result<()> {
+        match &self.game.mod_loader.kind {
+            ModLoaderKind::BepInEx { .. } => {
+                let (section, entry, value) = match setting {
+                    LoaderSetting::Console(enabled) => {
+                        ("Logging.Console", "Enabled", enabled.to_string())
+                    }
+                    LoaderSetting::LogLevel(level) => {
+                        ("Logging.Console", "LogLevels", level.to_bepinex_flags())
+                    }
+                    LoaderSetting::DumpAssemblies(enabled) => {
+                        ("Preloader", "DumpAssemblies", enabled.to_string())
+                    }
+                };
+
+                self.set_config_value(Path::new(BEPINEX_CONFIG), Some(section), entry, &value)?;
+            }
+            ModLoaderKind::MelonLoader { .. } => {
+                let (section, key, value) = match setting {
+                    LoaderSetting::Console(enabled) => ("console", "hide_console", !enabled),
+                    LoaderSetting::LogLevel(level) => {
+                        ("loader", "debug_mode", level == LogLevel::Debug)
+                    }
+                    LoaderSetting::DumpAssemblies(_) => {
+                        bail!("MelonLoader can't dump assemblies")
+                    }
+                };
+
+                let text = read_melon_loader_config(&self.path)?;
+                let text = set_toml_value(&text, section, key, &value.to_string());
+
+                let path = self.path.join(MELON_LOADER_CONFIG);
+                fs::create_dir_all(path.parent().unwrap())
+                    .fs_context("creating config directory", &path)?;
+                fs::write(&path, text).fs_context("writing MelonLoader config", &path)?;
+
+                self.config_cache.mark_stale();
+            }
+            _ => bail!(
+                "{} doesn't have any loader settings",
+                self.game.mod_loader.to_str()
+            ),
+        }
+
+        Ok(())
+    }
+}
+
+fn read_melon_loader_config(profile_dir: &Path) -> Result<String> {
+    let path = profile_dir.join(MELON_LOADER_CONFIG);
+
+    match fs::read_to_string(&path) {
+        Ok(text) => Ok(text),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(String::new()),
+        Err(err) => Err(err).context("failed to read MelonLoader config"),
+    }
+}
+
+/// Finds the range of lines belonging to `[section]`, excluding the header.
+fn toml_section(lines: &[&str], section: &str) -> Option<(usize, usize)> {
+    let header = format!("[{}]", section);
+    let start = lines.iter().position(|line| line.trim() == header)? + 1;
+    let end = lines[start..]
+        .iter()
+        .position(|line| line.trim_start().starts_with('['))
+        .map_or(lines.len(), |len| start + len);
+
+    Some((start, end))
+}
+
+fn toml_key<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let (name, value) = line.split_once('=')?;
+    let value = value.split_once('#').map_or(value, |(value, _)| value);
+
+    (name.trim() == key).then(|| value.trim())
+}
+
+/// Reads a top-level value from a simple TOML file, without parsing it fully.
+fn toml_value<'a>(text: &'a str, section: &str, key: &str) -> Option<&'a str> {
+    let lines = text.lines().collect::<Vec<_>>();
+    let (start, end) = toml_section(&lines, section)?;
+
+    lines[start..end]
+        .iter()
+        .find_map(|line| toml_key(line, key))
+}
+
+/// Sets a value in a simple TOML file, keeping everything else as is.
+fn set_toml_value(text: &str, section: &str, key: &str, value: &str) -> String {
+    let mut lines = text.lines().map(str::to_owned).collect::<Vec<_>>();
+    let entry = format!("{} = {}", key, value);
+
+    let borrowed = lines.iter().map(String::as_str).collect::<Vec<_>>();
+    match toml_section(&borrowed, section) {
+        Some((start, end)) => match (start..end).find(|&i| toml_key(&lines[i], key).is_some()) {
+            Some(index) => lines[index] = entry,
+            None => lines.insert(start, entry),
+        },
+        None => {
+            if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+                lines.push(String::new());
+            }
+
+            lines.push(format!("[{}]", section));
+            lines.push(entry);
+        }
+    }
+
+    let mut text = lines.join("\n");
+    text.push('\n');
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profile::tests::test_profile;
+
+    #[test]
+    fn console_setting_creates_bepinex_config() {
+        let temp = tempfile::tempdir().unwrap();
+        let mut profile = test_profile(temp.path().to_path_buf());
+
+        let path = temp.path().join(BEPINEX_CONFIG);
+        assert!(!path.exists());
+
+        profile
+            .set_loader_setting(LoaderSetting::Console(true))
+            .unwrap();
+
+        let text = fs::read_to_string(&path).unwrap();
+        assert!(text.contains("[Logging.Console]"));
+        assert!(text.contains("Enabled = true"));
+    }
+
+    #[test]
+    fn bepinex_log_levels() {
+        assert_eq!(
+            LogLevel::Info.to_bepinex_flags(),
+            "Fatal, Error, Warning, Message, Info"
+        );
+
+        assert_eq!(
+            LogLevel::from_bepinex_flags("Fatal, Error"),
+            LogLevel::Error
+        );
+        assert_eq!(LogLevel::from_bepinex_flags("All"), LogLevel::Debug);
+        assert_eq!(LogLevel::from_bepinex_flags("None"), LogLevel::Fatal);
+    }
+
+    #[test]
+    fn toml_values_are_replaced_in_place() {
+        let text = "[loader]\ndebug_mode = false\n\n[console]\nhide_console = true # comment\n";
+
+        assert_eq!(toml_value(text, "console", "hide_console"), Some("true"));
+        assert_eq!(toml_value(text, "logs", "max_logs"), None);
+
+        let text = set_toml_value(text, "loader", "debug_mode", "true");
+        assert_eq!(
+            text,
+            "[loader]\ndebug_mode = true\n\n[console]\nhide_console = true # comment\n"
+        );
+
+        let text = set_toml_value(&text, "logs", "max_logs", "10");
+        assert!(text.ends_with("hide_console = true # comment\n\n[logs]\nmax_logs = 10\n"));
+        assert_eq!(toml_value(&text, "logs", "max_logs"), Some("10"));
+    }
+}
@@ -0,0 +1,183 @@
+use std::{
+    cmp,
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use itertools::Itertools;
+use serde::Serialize;
+use walkdir::WalkDir;
+
+use super::AnyFile;
+use crate::{
+    prefs::Prefs,
+    profile::{install, Profile, ProfileMod},
+};
+
+/// How sure we are that a config file belongs to a mod.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub enum ConfigConfidence {
+    /// The file name ends with the mod's name, e.g. `com.someone.ModName.cfg`.
+    Possible,
+    /// The file name matches a common plugin GUID pattern, such as
+    /// `Author.ModName.cfg` or `com.author.modname.cfg`.
+    Likely,
+    /// The file is bundled with the mod, or its metadata names the mod.
+    Exact,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ModConfigFile {
+    /// The file's path relative to the profile, as used by the config commands.
+    pub relative_path: PathBuf,
+    pub confidence: ConfigConfidence,
+}
+
+impl Profile {
+    /// Finds the config files that belong to a mod, best matches first.
+    ///
+    /// Besides what [`Profile::match_config`] finds, this also checks which
+    /// config files are bundled with the mod, which requires it to be cached.
+    pub fn mod_config_files(&self, profile_mod: &ProfileMod, prefs: &Prefs) -> Vec<ModConfigFile> {
+        let config_dir = self.game.mod_loader.config_path();
+        let bundled = bundled_config(&install::cache_path(&profile_mod.ident(), prefs));
+
+        let bundled_files = self.config_cache.files.iter().filter(|file| {
+            file.relative_path
+                .strip_prefix(&config_dir)
+                .is_ok_and(|path| bundled.contains(path))
+        });
+
+        bundled_files
+            .map(|file| (file, ConfigConfidence::Exact))
+            .chain(self.match_config(profile_mod))
+            .sorted_by_key(|(_, confidence)| cmp::Reverse(*confidence))
+            .unique_by(|(file, _)| &file.relative_path)
+            .map(|(file, confidence)| ModConfigFile {
+                relative_path: file.relative_path.clone(),
+                confidence,
+            })
+            .collect()
+    }
+
+    /// Matches config files to a mod by their metadata and names, best matches first.
+    pub(super) fn match_config(
+        &self,
+        profile_mod: &ProfileMod,
+    ) -> Vec<(&AnyFile, ConfigConfidence)> {
+        let ident = profile_mod.ident();
+        let owner = normalize(ident.owner());
+        let name = normalize(ident.name());
+
+        self.config_cache
+            .files
+            .iter()
+            .filter_map(|file| confidence(file, &owner, &name).map(|confidence| (file, confidence)))
+            .sorted_by_key(|(_, confidence)| cmp::Reverse(*confidence))
+            .collect()
+    }
+}
+
+fn confidence(file: &AnyFile, owner: &str, name: &str) -> Option<ConfigConfidence> {
+    if file
+        .kind
+        .mod_name()
+        .is_some_and(|mod_name| normalize(mod_name) == name)
+    {
+        return Some(ConfigConfidence::Exact);
+    }
+
+    let stem = normalize(&file.file_stem());
+    let guid = file.kind.plugin_guid().map(normalize);
+
+    for candidate in [Some(stem), guid].into_iter().flatten() {
+        let is_guid = candidate == name
+            || candidate == format!("{}.{}", owner, name)
+            || candidate == format!("com.{}.{}", owner, name);
+
+        if is_guid {
+            return Some(ConfigConfidence::Likely);
+        }
+
+        if candidate.ends_with(&format!(".{}", name)) {
+            return Some(ConfigConfidence::Possible);
+        }
+    }
+
+    None
+}
+
+/// Lowercases a name and removes separators, since mods are inconsistent
+/// about using them in their names, GUIDs and config files.
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, '_' | '-' | ' '))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Finds the config files in an extracted package, relative to its `config` directory.
+fn bundled_config(package_dir: &Path) -> HashSet<PathBuf> {
+    if !package_dir.exists() {
+        return HashSet::new();
+    }
+
+    WalkDir::new(package_dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| {
+            let relative = entry.path().strip_prefix(package_dir).ok()?;
+            let mut components = relative.components();
+
+            // skip everything up to and including the config directory
+            components
+                .by_ref()
+                .find(|component| component.as_os_str().eq_ignore_ascii_case("config"))?;
+
+            Some(components.as_path().to_path_buf())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::SystemTime;
+
+    use crate::config::AnyFileKind;
+
+    fn file(name: &str) -> AnyFile {
+        AnyFile {
+            display_name: name.to_owned(),
+            relative_path: ["BepInEx", "config", &format!("{}.cfg", name)]
+                .iter()
+                .collect(),
+            read_time: SystemTime::now(),
+            kind: AnyFileKind::Unsupported,
+        }
+    }
+
+    #[test]
+    fn file_names_are_matched_by_guid_patterns() {
+        let matches = |file_name| confidence(&file(file_name), "notnotnotswipez", "morecompany");
+
+        assert_eq!(matches("MoreCompany"), Some(ConfigConfidence::Likely));
+        assert_eq!(
+            matches("notnotnotswipez.More_Company"),
+            Some(ConfigConfidence::Likely)
+        );
+        assert_eq!(
+            matches("com.notnotnotswipez.morecompany"),
+            Some(ConfigConfidence::Likely)
+        );
+        assert_eq!(
+            matches("me.swipez.MoreCompany"),
+            Some(ConfigConfidence::Possible)
+        );
+        assert_eq!(matches("MoreCompanyPlus"), None);
+    }
+}
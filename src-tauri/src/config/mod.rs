@@ -2,29 +2,40 @@ use std::{
     borrow::Cow,
     collections::HashMap,
     fs::{self},
-    io::{BufReader, BufWriter},
+    io::{self, BufReader, BufWriter},
     path::{Path, PathBuf},
     time::SystemTime,
 };
 
-use eyre::{Context, OptionExt, Result};
+use eyre::{bail, ensure, eyre, Context, OptionExt, Result};
 use log::debug;
 use rayon::prelude::*;
+use serde::Serialize;
 use walkdir::WalkDir;
 
 use crate::{
-    game::{ModLoader, ModLoaderKind},
-    profile::Profile,
-    util::error::IoResultExt,
+    game::{ConfigDefault, ModLoader, ModLoaderKind},
+    profile::{name, Profile},
+    util::{self, error::IoResultExt},
 };
 
 mod bepinex;
 pub mod commands;
 mod frontend;
 mod gd_weave;
+mod loader;
+mod matching;
+
+pub use bepinex::RenameMode;
+pub use loader::{LoaderSetting, LoaderSettingKey, LoaderSettings};
+pub use matching::ModConfigFile;
 
 #[derive(Debug, Default)]
-pub struct ConfigCache(Vec<AnyFile>);
+pub struct ConfigCache {
+    files: Vec<AnyFile>,
+    /// When the config directory was last scanned, or `None` if it needs a rescan.
+    scanned_at: Option<SystemTime>,
+}
 
 #[derive(Debug)]
 struct AnyFile {
@@ -42,6 +53,14 @@ enum AnyFileKind {
     Unsupported,
 }
 
+/// The outcome of [`Profile::apply_game_defaults`].
+#[derive(Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct GameDefaultsReport {
+    pub changed: Vec<&'static ConfigDefault<'static>>,
+    pub already_set: Vec<&'static ConfigDefault<'static>>,
+}
+
 impl AnyFile {
     fn file_stem(&self) -> Cow<str> {
         self.relative_path
@@ -74,6 +93,13 @@ impl AnyFileKind {
             _ => None,
         }
     }
+
+    fn plugin_guid(&self) -> Option<&str> {
+        match self {
+            Self::BepInEx(file) => file.plugin_guid(),
+            _ => None,
+        }
+    }
 }
 
 impl Profile {
@@ -82,37 +108,224 @@ impl Profile {
         self.link_config();
     }
 
+    /// Refreshes the config cache if the config directory changed since it
+    /// was last scanned, or if the game was launched since then.
+    pub fn refresh_config_if_stale(&mut self) {
+        let config_dir = self.path.join(self.game.mod_loader.config_path());
+        let modified = config_dir.metadata().and_then(|meta| meta.modified()).ok();
+
+        let is_stale = match (self.config_cache.scanned_at, modified) {
+            (Some(scanned_at), Some(modified)) => modified > scanned_at,
+            (Some(_), None) => false,
+            (None, _) => true,
+        };
+
+        if is_stale {
+            self.refresh_config();
+        }
+    }
+
+    /// Copies the config file at `relative_path` to `new_name` in the same directory,
+    /// then rescans the config so the copy shows up. Returns the copy's relative path.
+    ///
+    /// If `new_name` has no extension, the one of the original file is used.
+    pub fn duplicate_config_file(
+        &mut self,
+        relative_path: &Path,
+        new_name: &str,
+    ) -> Result<PathBuf> {
+        name::check(new_name).map_err(|reason| eyre!("invalid file name: {}", reason))?;
+
+        let config_dir = self.game.mod_loader.config_path();
+        ensure!(
+            relative_path.starts_with(&config_dir),
+            "file is not in the config directory"
+        );
+
+        self.config_cache.find_file(relative_path)?;
+
+        let mut new_path = relative_path.with_file_name(new_name);
+        if new_path.extension().is_none() {
+            if let Some(extension) = relative_path.extension() {
+                new_path.set_extension(extension);
+            }
+        }
+
+        // some file systems ignore case, so don't rely on them to catch this
+        let collides = |path: &Path| {
+            path.to_string_lossy()
+                .eq_ignore_ascii_case(&new_path.to_string_lossy())
+        };
+
+        ensure!(
+            !self.path.join(&new_path).exists()
+                && !self
+                    .config_cache
+                    .files
+                    .iter()
+                    .any(|file| collides(&file.relative_path)),
+            "a file named {} already exists",
+            new_path.file_name().unwrap().to_string_lossy()
+        );
+
+        let src = self.path.join(relative_path);
+        let dest = self.path.join(&new_path);
+        fs::copy(&src, &dest).fs_context("copying config file", &src)?;
+
+        self.refresh_config();
+
+        Ok(new_path)
+    }
+
+    /// Sets a config entry from its textual form, creating the file if it doesn't exist.
+    ///
+    /// Supports BepInEx `.cfg` files, where `section` is required, and flat
+    /// `.json` files. Returns whether the file was changed.
+    pub fn set_config_value(
+        &mut self,
+        relative_path: &Path,
+        section: Option<&str>,
+        entry: &str,
+        value: &str,
+    ) -> Result<bool> {
+        let path = self.config_file_path(relative_path)?;
+        let extension = relative_path.extension().and_then(|ext| ext.to_str());
+
+        let kind = match extension {
+            Some("cfg") => {
+                let section = section.ok_or_eyre("BepInEx config entries need a section")?;
+                let mut file = read_or_default(&path, bepinex::File::read)?;
+
+                match file.set_raw(section, entry, value)? {
+                    true => AnyFileKind::BepInEx(file),
+                    false => return Ok(false),
+                }
+            }
+            Some("json") => {
+                let mut file = read_or_default(&path, gd_weave::File::read)?;
+
+                match file.set_raw(entry, value) {
+                    true => AnyFileKind::GDWeave(file),
+                    false => return Ok(false),
+                }
+            }
+            _ => bail!("unsupported config file format"),
+        };
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).fs_context("creating config directory", parent)?;
+        }
+
+        let writer = fs::File::create(&path)
+            .map(BufWriter::new)
+            .fs_context("opening file", &path)?;
+
+        match kind {
+            AnyFileKind::BepInEx(file) => file.write(writer)?,
+            AnyFileKind::GDWeave(file) => file.write(writer)?,
+            _ => unreachable!(),
+        }
+
+        self.config_cache.mark_stale();
+
+        Ok(true)
+    }
+
+    /// Reads a config entry from disk in its textual form.
+    ///
+    /// Returns `None` if the file or the entry doesn't exist.
+    pub fn config_value(
+        &self,
+        relative_path: &Path,
+        section: Option<&str>,
+        entry: &str,
+    ) -> Result<Option<String>> {
+        let path = self.config_file_path(relative_path)?;
+
+        match relative_path.extension().and_then(|ext| ext.to_str()) {
+            Some("cfg") => {
+                let section = section.ok_or_eyre("BepInEx config entries need a section")?;
+                Ok(read_or_default(&path, bepinex::File::read)?.get_raw(section, entry))
+            }
+            Some("json") => Ok(read_or_default(&path, gd_weave::File::read)?.get_raw(entry)),
+            _ => bail!("unsupported config file format"),
+        }
+    }
+
+    fn config_file_path(&self, relative_path: &Path) -> Result<PathBuf> {
+        ensure!(
+            relative_path.is_relative() && util::fs::is_enclosed(relative_path),
+            "config file must be inside the profile"
+        );
+
+        Ok(self.path.join(relative_path))
+    }
+
+    /// Sets the default config entries declared by the profile's game.
+    ///
+    /// Entries that already have the default value are left alone.
+    pub fn apply_game_defaults(&mut self) -> Result<GameDefaultsReport> {
+        let game = self.game;
+        let mut report = GameDefaultsReport::default();
+
+        for default in &game.config_defaults {
+            let changed = self
+                .set_config_value(
+                    Path::new(default.file),
+                    default.section,
+                    default.entry,
+                    default.value,
+                )
+                .with_context(|| format!("failed to set {} in {}", default.entry, default.file))?;
+
+            match changed {
+                true => report.changed.push(default),
+                false => report.already_set.push(default),
+            }
+        }
+
+        Ok(report)
+    }
+
     fn link_config(&mut self) {
+        self.linked_config.clear();
+
         for profile_mod in &self.mods {
-            let ident = profile_mod.ident();
-            let file = self
-                .config_cache
-                .0
-                .iter()
-                .find(|file| matches(file, ident.name()));
-
-            if let Some(file) = file {
+            if let Some((file, _)) = self.match_config(profile_mod).first() {
                 self.linked_config
                     .insert(profile_mod.uuid(), file.relative_path.clone());
             }
         }
+    }
+}
 
-        fn matches(file: &AnyFile, mod_name: &str) -> bool {
-            if file.relative_path.as_os_str() == mod_name {
-                return true;
-            }
-
-            let Some(metadata_name) = file.kind.mod_name() else {
-                return false;
-            };
-
-            metadata_name == mod_name
+/// Reads a config file, or returns an empty one if it doesn't exist.
+fn read_or_default<T, F>(path: &Path, f: F) -> Result<T>
+where
+    T: Default,
+    F: FnOnce(BufReader<fs::File>) -> Result<T>,
+{
+    match fs::File::open(path) {
+        Ok(file) => {
+            f(BufReader::new(file)).with_context(|| format!("failed to read {}", path.display()))
         }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(T::default()),
+        Err(err) => Err(err).fs_context("opening file", path),
     }
 }
 
 impl ConfigCache {
+    /// Makes the next [`Profile::refresh_config_if_stale`] rescan the config directory.
+    ///
+    /// Mods often generate their config files the first time the game runs,
+    /// so this is called whenever the game is launched.
+    pub fn mark_stale(&mut self) {
+        self.scanned_at = None;
+    }
+
     pub fn refresh(&mut self, root: &Path, mod_loader: &ModLoader) {
+        self.scanned_at = Some(SystemTime::now());
+
         let config_dir = root.join(mod_loader.config_path());
 
         let files = WalkDir::new(&config_dir)
@@ -126,8 +339,8 @@ impl ConfigCache {
 
         for (file, index) in files {
             match index {
-                Some(index) => self.0[index] = file,
-                None => self.0.push(file),
+                Some(index) => self.files[index] = file,
+                None => self.files.push(file),
             };
         }
 
@@ -152,7 +365,7 @@ impl ConfigCache {
             .to_path_buf();
 
         let curr_index = self
-            .0
+            .files
             .iter()
             .position(|file| file.relative_path == relative_path);
 
@@ -217,7 +430,7 @@ impl ConfigCache {
         let Some(curr_index) = curr_index else {
             return true;
         };
-        let Some(curr_file) = self.0.get(curr_index) else {
+        let Some(curr_file) = self.files.get(curr_index) else {
             return true;
         };
         let Ok(metadata) = entry.metadata() else {
@@ -236,8 +449,8 @@ impl ConfigCache {
     fn resolve_duplicate_names(&mut self) {
         let mut name_changes = HashMap::new();
 
-        for (i, file_a) in self.0.iter().enumerate() {
-            for (j, file_b) in self.0[i + 1..].iter().enumerate() {
+        for (i, file_a) in self.files.iter().enumerate() {
+            for (j, file_b) in self.files[i + 1..].iter().enumerate() {
                 let name_a = &file_a.display_name;
                 let name_b = &file_b.display_name;
 
@@ -272,14 +485,14 @@ impl ConfigCache {
         }
 
         for (index, new_name) in name_changes {
-            self.0[index].display_name = new_name;
+            self.files[index].display_name = new_name;
         }
     }
 
     fn to_frontend(&self) -> Vec<frontend::File> {
         use frontend::FileKind;
 
-        self.0
+        self.files
             .iter()
             .map(|file| {
                 let kind = match &file.kind {
@@ -302,9 +515,44 @@ impl ConfigCache {
     }
 
     fn find_file(&mut self, file: &Path) -> Result<&mut AnyFile> {
-        self.0
+        self.files
             .iter_mut()
             .find(|f| f.relative_path == file)
             .ok_or_eyre("file not found")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profile::tests::test_profile;
+
+    #[test]
+    fn duplicate_config_file() {
+        let temp = tempfile::tempdir().unwrap();
+        let config_dir = temp.path().join("BepInEx").join("config");
+        fs::create_dir_all(&config_dir).unwrap();
+        fs::write(config_dir.join("Mod.cfg"), "[General]\n\nKey = Value\n").unwrap();
+
+        let mut profile = test_profile(temp.path().to_path_buf());
+        profile.refresh_config();
+
+        let original = Path::new("BepInEx/config/Mod.cfg");
+        let copy = profile.duplicate_config_file(original, "Mod B").unwrap();
+
+        assert_eq!(copy, Path::new("BepInEx/config/Mod B.cfg"));
+        assert!(profile.config_cache.find_file(&copy).is_ok());
+        assert_eq!(
+            fs::read_to_string(temp.path().join(&copy)).unwrap(),
+            "[General]\n\nKey = Value\n"
+        );
+
+        for name in ["mod b.cfg", "../Mod.cfg", "sub/Mod.cfg", ""] {
+            assert!(
+                profile.duplicate_config_file(original, name).is_err(),
+                "{:?} was allowed",
+                name
+            );
+        }
+    }
+}
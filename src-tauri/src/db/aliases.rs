@@ -0,0 +1,35 @@
+use std::collections::HashMap;
+
+use eyre::Result;
+
+use super::Db;
+
+impl Db {
+    /// Returns the known package renames, mapping old full names to new ones.
+    pub fn package_aliases(&self) -> Result<HashMap<String, String>> {
+        let aliases = self
+            .conn()
+            .prepare("SELECT old_name, new_name FROM package_aliases")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+
+        Ok(aliases)
+    }
+
+    pub fn set_package_alias(&self, old_name: &str, new_name: &str) -> Result<()> {
+        self.conn().execute(
+            "INSERT INTO package_aliases (old_name, new_name) VALUES (?, ?)
+            ON CONFLICT (old_name) DO UPDATE SET new_name = excluded.new_name",
+            [old_name, new_name],
+        )?;
+
+        Ok(())
+    }
+
+    pub fn remove_package_alias(&self, old_name: &str) -> Result<()> {
+        self.conn()
+            .execute("DELETE FROM package_aliases WHERE old_name = ?", [old_name])?;
+
+        Ok(())
+    }
+}
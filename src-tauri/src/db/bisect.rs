@@ -0,0 +1,42 @@
+use eyre::Result;
+use rusqlite::{params, OptionalExtension};
+
+use super::Db;
+use crate::profile::BisectSession;
+
+impl Db {
+    pub fn bisect_session(&self, profile_id: i64) -> Result<Option<BisectSession>> {
+        let data = self
+            .conn()
+            .query_row(
+                "SELECT data FROM bisect_sessions WHERE profile_id = ?",
+                [profile_id],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?;
+
+        match data {
+            Some(data) => Ok(Some(serde_json::from_str(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn save_bisect_session(&self, profile_id: i64, session: &BisectSession) -> Result<()> {
+        self.conn().execute(
+            "INSERT INTO bisect_sessions (profile_id, data) VALUES (?, ?)
+            ON CONFLICT (profile_id) DO UPDATE SET data = excluded.data",
+            params![profile_id, serde_json::to_string(session)?],
+        )?;
+
+        Ok(())
+    }
+
+    pub fn remove_bisect_session(&self, profile_id: i64) -> Result<()> {
+        self.conn().execute(
+            "DELETE FROM bisect_sessions WHERE profile_id = ?",
+            [profile_id],
+        )?;
+
+        Ok(())
+    }
+}
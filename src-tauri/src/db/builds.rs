@@ -0,0 +1,34 @@
+use eyre::Result;
+use rusqlite::{params, OptionalExtension};
+
+use super::Db;
+use crate::profile::launch::GameBuild;
+
+impl Db {
+    /// Returns the build of a game that was recorded when it was last launched.
+    pub fn game_build(&self, game_slug: &str) -> Result<Option<GameBuild>> {
+        let data = self
+            .conn()
+            .query_row(
+                "SELECT data FROM game_builds WHERE game_slug = ?",
+                [game_slug],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?;
+
+        match data {
+            Some(data) => Ok(Some(serde_json::from_str(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn save_game_build(&self, game_slug: &str, build: &GameBuild) -> Result<()> {
+        self.conn().execute(
+            "INSERT INTO game_builds (game_slug, data) VALUES (?, ?)
+            ON CONFLICT (game_slug) DO UPDATE SET data = excluded.data",
+            params![game_slug, serde_json::to_string(build)?],
+        )?;
+
+        Ok(())
+    }
+}
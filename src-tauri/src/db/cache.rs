@@ -0,0 +1,174 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Utc};
+use eyre::Result;
+use rusqlite::{params, OptionalExtension};
+
+use super::Db;
+use crate::thunderstore::VersionIdent;
+
+/// A file of a cached version, as recorded once the version is fully extracted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheFile {
+    /// The path of the file, relative to the version's directory.
+    pub path: String,
+    pub hash: String,
+    /// `None` for versions compacted before sizes were recorded.
+    pub size: Option<u64>,
+}
+
+impl Db {
+    /// Records that a cached version was used at `time`.
+    pub fn touch_cache_entry(&self, ident: &VersionIdent, time: DateTime<Utc>) -> Result<()> {
+        self.conn().execute(
+            "INSERT OR REPLACE INTO cache_access (ident, accessed_at) VALUES (?, ?)",
+            params![ident.as_str(), time],
+        )?;
+
+        Ok(())
+    }
+
+    /// Returns when each cached version was last used, keyed by its dependency string.
+    pub fn cache_access_times(&self) -> Result<HashMap<String, DateTime<Utc>>> {
+        let times = self
+            .conn()
+            .prepare("SELECT ident, accessed_at FROM cache_access")?
+            .query_map((), |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+
+        Ok(times)
+    }
+
+    pub fn remove_cache_entries<'a>(
+        &self,
+        idents: impl IntoIterator<Item = &'a VersionIdent>,
+    ) -> Result<()> {
+        self.with_transaction(|tx| {
+            let mut stmt = tx.prepare("DELETE FROM cache_access WHERE ident = ?")?;
+
+            for ident in idents {
+                stmt.execute([ident.as_str()])?;
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Records which stored file each file of a compacted cached version links to.
+    ///
+    /// This also marks the version as fully extracted.
+    pub fn set_cache_manifest(&self, ident: &VersionIdent, files: &[CacheFile]) -> Result<()> {
+        self.with_transaction(|tx| {
+            tx.execute("DELETE FROM cache_files WHERE ident = ?", [ident.as_str()])?;
+            tx.execute(
+                "INSERT OR IGNORE INTO cache_versions (ident) VALUES (?)",
+                [ident.as_str()],
+            )?;
+
+            let mut stmt = tx
+                .prepare("INSERT INTO cache_files (ident, path, hash, size) VALUES (?, ?, ?, ?)")?;
+
+            for file in files {
+                stmt.execute(params![ident.as_str(), file.path, file.hash, file.size])?;
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Returns the recorded files of a cached version, or `None` if
+    /// the version was never fully extracted.
+    pub fn cache_manifest(&self, ident: &VersionIdent) -> Result<Option<Vec<CacheFile>>> {
+        let conn = self.conn();
+
+        let complete = conn
+            .query_row(
+                "SELECT 1 FROM cache_versions WHERE ident = ?",
+                [ident.as_str()],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some();
+
+        if !complete {
+            return Ok(None);
+        }
+
+        let files = conn
+            .prepare("SELECT path, hash, size FROM cache_files WHERE ident = ?")?
+            .query_map([ident.as_str()], |row| {
+                Ok(CacheFile {
+                    path: row.get(0)?,
+                    hash: row.get(1)?,
+                    size: row.get(2)?,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+
+        Ok(Some(files))
+    }
+
+    /// Returns the dependency strings of every compacted cached version.
+    pub fn compacted_cache_entries(&self) -> Result<HashSet<String>> {
+        let idents = self
+            .conn()
+            .prepare("SELECT ident FROM cache_versions")?
+            .query_map((), |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+
+        Ok(idents)
+    }
+
+    /// Returns the recorded files of every compacted cached version,
+    /// keyed by dependency string.
+    pub fn cache_manifests(&self) -> Result<HashMap<String, Vec<CacheFile>>> {
+        let mut versions = HashMap::<_, Vec<_>>::new();
+
+        let conn = self.conn();
+        let mut stmt = conn.prepare("SELECT ident, path, hash, size FROM cache_files")?;
+        let rows = stmt.query_map((), |row| {
+            let file = CacheFile {
+                path: row.get(1)?,
+                hash: row.get(2)?,
+                size: row.get(3)?,
+            };
+
+            Ok((row.get::<_, String>(0)?, file))
+        })?;
+
+        for row in rows {
+            let (ident, file) = row?;
+            versions.entry(ident).or_default().push(file);
+        }
+
+        Ok(versions)
+    }
+
+    /// Returns the hashes of stored files referenced by any cached version.
+    pub fn referenced_cache_hashes(&self) -> Result<HashSet<String>> {
+        let hashes = self
+            .conn()
+            .prepare("SELECT DISTINCT hash FROM cache_files")?
+            .query_map((), |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+
+        Ok(hashes)
+    }
+
+    pub fn remove_cache_manifests<'a>(
+        &self,
+        idents: impl IntoIterator<Item = &'a str>,
+    ) -> Result<()> {
+        self.with_transaction(|tx| {
+            let mut files = tx.prepare("DELETE FROM cache_files WHERE ident = ?")?;
+            let mut versions = tx.prepare("DELETE FROM cache_versions WHERE ident = ?")?;
+
+            for ident in idents {
+                files.execute([ident])?;
+                versions.execute([ident])?;
+            }
+
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,105 @@
+use std::path::PathBuf;
+
+use eyre::{eyre, Result};
+use rusqlite::{params, OptionalExtension};
+use serde::Serialize;
+
+use super::Db;
+
+/// How a profile's files were placed in the game directory.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum DeployMethod {
+    /// `target` is a symlink (or junction) to the profile's directory.
+    Link,
+    /// The files were copied into `target`.
+    Copy,
+}
+
+impl DeployMethod {
+    fn as_str(self) -> &'static str {
+        match self {
+            DeployMethod::Link => "link",
+            DeployMethod::Copy => "copy",
+        }
+    }
+
+    fn from_str(str: &str) -> Result<Self> {
+        match str {
+            "link" => Ok(DeployMethod::Link),
+            "copy" => Ok(DeployMethod::Copy),
+            _ => Err(eyre!("unknown deploy method {}", str)),
+        }
+    }
+}
+
+/// A profile deployed to the location its game loads mods from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deployment {
+    pub profile_id: i64,
+    pub target: PathBuf,
+    pub method: DeployMethod,
+    /// Copied files, relative to `target`. Empty for links.
+    pub files: Vec<PathBuf>,
+    /// Whether the deployment finished. If not, Gale was interrupted while
+    /// deploying and `target` may be incomplete.
+    pub complete: bool,
+}
+
+impl Db {
+    pub fn deployment(&self, game_slug: &str) -> Result<Option<Deployment>> {
+        let row = self
+            .conn()
+            .prepare(
+                "SELECT profile_id, target, method, files, complete
+                FROM deployments WHERE game_slug = ?",
+            )?
+            .query_row([game_slug], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, bool>(4)?,
+                ))
+            })
+            .optional()?;
+
+        let Some((profile_id, target, method, files, complete)) = row else {
+            return Ok(None);
+        };
+
+        Ok(Some(Deployment {
+            profile_id,
+            target: target.into(),
+            method: DeployMethod::from_str(&method)?,
+            files: serde_json::from_str(&files)?,
+            complete,
+        }))
+    }
+
+    pub fn save_deployment(&self, game_slug: &str, deployment: &Deployment) -> Result<()> {
+        self.conn().execute(
+            "INSERT OR REPLACE INTO deployments
+            (game_slug, profile_id, target, method, files, complete)
+            VALUES (?, ?, ?, ?, ?, ?)",
+            params![
+                game_slug,
+                deployment.profile_id,
+                deployment.target.to_string_lossy(),
+                deployment.method.as_str(),
+                serde_json::to_string(&deployment.files)?,
+                deployment.complete
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    pub fn remove_deployment(&self, game_slug: &str) -> Result<()> {
+        self.conn()
+            .execute("DELETE FROM deployments WHERE game_slug = ?", [game_slug])?;
+
+        Ok(())
+    }
+}
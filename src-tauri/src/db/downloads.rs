@@ -0,0 +1,114 @@
+use chrono::{DateTime, Utc};
+use eyre::Result;
+use rusqlite::{params, OptionalExtension};
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::Db;
+
+/// The maximum number of samples kept per package, the oldest ones are removed first.
+const MAX_SAMPLES: u32 = 1000;
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadSample {
+    pub time: DateTime<Utc>,
+    pub downloads: u64,
+}
+
+impl Db {
+    pub fn watch_package(&self, uuid: Uuid) -> Result<()> {
+        self.conn().execute(
+            "INSERT OR IGNORE INTO watched_packages (package_uuid) VALUES (?)",
+            [uuid],
+        )?;
+
+        Ok(())
+    }
+
+    /// Stops recording downloads for a package and deletes its history.
+    pub fn unwatch_package(&self, uuid: Uuid) -> Result<()> {
+        self.with_transaction(|tx| {
+            tx.execute(
+                "DELETE FROM watched_packages WHERE package_uuid = ?",
+                [uuid],
+            )?;
+            tx.execute(
+                "DELETE FROM download_samples WHERE package_uuid = ?",
+                [uuid],
+            )?;
+
+            Ok(())
+        })
+    }
+
+    pub fn watched_packages(&self) -> Result<Vec<Uuid>> {
+        let uuids = self
+            .conn()
+            .prepare("SELECT package_uuid FROM watched_packages")?
+            .query_map((), |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+
+        Ok(uuids)
+    }
+
+    /// Finds when the downloads of a package were last recorded.
+    pub fn last_download_sample(&self, uuid: Uuid) -> Result<Option<DateTime<Utc>>> {
+        let time = self
+            .conn()
+            .prepare("SELECT MAX(sampled_at) FROM download_samples WHERE package_uuid = ?")?
+            .query_row([uuid], |row| row.get(0))
+            .optional()?
+            .flatten();
+
+        Ok(time)
+    }
+
+    /// Records the download counts of packages at `time`, removing
+    /// the oldest samples of each package once it has more than [`MAX_SAMPLES`].
+    pub fn insert_download_samples(
+        &self,
+        samples: &[(Uuid, u64)],
+        time: DateTime<Utc>,
+    ) -> Result<()> {
+        self.with_transaction(|tx| {
+            let mut insert = tx.prepare(
+                "INSERT OR REPLACE INTO download_samples (package_uuid, sampled_at, downloads)
+                VALUES (?, ?, ?)",
+            )?;
+
+            let mut prune = tx.prepare(
+                "DELETE FROM download_samples WHERE package_uuid = ?1 AND sampled_at NOT IN (
+                    SELECT sampled_at FROM download_samples WHERE package_uuid = ?1
+                    ORDER BY sampled_at DESC LIMIT ?2
+                )",
+            )?;
+
+            for (uuid, downloads) in samples {
+                insert.execute(params![uuid, time, downloads])?;
+                prune.execute(params![uuid, MAX_SAMPLES])?;
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Returns the recorded downloads of a package, oldest first.
+    pub fn download_history(&self, uuid: Uuid) -> Result<Vec<DownloadSample>> {
+        let samples = self
+            .conn()
+            .prepare(
+                "SELECT sampled_at, downloads FROM download_samples
+                WHERE package_uuid = ? ORDER BY sampled_at",
+            )?
+            .query_map([uuid], |row| {
+                Ok(DownloadSample {
+                    time: row.get(0)?,
+                    downloads: row.get(1)?,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+
+        Ok(samples)
+    }
+}
@@ -0,0 +1,61 @@
+use std::path::PathBuf;
+
+use eyre::Result;
+use rusqlite::params;
+
+use super::Db;
+
+/// A file that was copied into a game's directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameDirFile {
+    pub game_dir: PathBuf,
+    /// The path of the file, relative to `game_dir`.
+    pub path: PathBuf,
+    /// The profile the file was deployed from.
+    pub profile_id: i64,
+}
+
+impl Db {
+    /// Returns the files Gale has written to a game's directory.
+    pub fn game_dir_files(&self, game_slug: &str) -> Result<Vec<GameDirFile>> {
+        let files = self
+            .conn()
+            .prepare("SELECT game_dir, path, profile_id FROM game_dir_files WHERE game_slug = ?")?
+            .query_map([game_slug], |row| {
+                Ok(GameDirFile {
+                    game_dir: row.get::<_, String>(0)?.into(),
+                    path: row.get::<_, String>(1)?.into(),
+                    profile_id: row.get(2)?,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+
+        Ok(files)
+    }
+
+    /// Replaces the recorded files of a game.
+    pub fn set_game_dir_files(&self, game_slug: &str, files: &[GameDirFile]) -> Result<()> {
+        self.with_transaction(|tx| {
+            tx.execute(
+                "DELETE FROM game_dir_files WHERE game_slug = ?",
+                [game_slug],
+            )?;
+
+            let mut stmt = tx.prepare(
+                "INSERT INTO game_dir_files (game_slug, game_dir, path, profile_id)
+                VALUES (?, ?, ?, ?)",
+            )?;
+
+            for file in files {
+                stmt.execute(params![
+                    game_slug,
+                    file.game_dir.to_string_lossy(),
+                    file.path.to_string_lossy(),
+                    file.profile_id
+                ])?;
+            }
+
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,93 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use eyre::Result;
+use rusqlite::params;
+use serde::Serialize;
+
+use super::Db;
+
+/// The number of launches kept per game, the oldest ones are removed first.
+const MAX_LAUNCHES: u32 = 200;
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct LaunchRecord {
+    pub profile_id: i64,
+    pub launched_at: DateTime<Utc>,
+    /// How long the game ran, or `None` if it's still running or wasn't found.
+    pub duration_ms: Option<u64>,
+    /// Only known for games launched directly.
+    pub exit_code: Option<i32>,
+    pub crashed: bool,
+}
+
+impl Db {
+    /// Records the start of a launch, returning its id for [`Db::finish_launch`].
+    pub fn record_launch(
+        &self,
+        game_slug: &str,
+        profile_id: i64,
+        launched_at: DateTime<Utc>,
+    ) -> Result<i64> {
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+
+        tx.execute(
+            "INSERT INTO launches (game_slug, profile_id, launched_at) VALUES (?, ?, ?)",
+            params![game_slug, profile_id, launched_at],
+        )?;
+
+        let id = tx.last_insert_rowid();
+
+        tx.execute(
+            "DELETE FROM launches WHERE game_slug = ?1 AND id NOT IN (
+                SELECT id FROM launches WHERE game_slug = ?1
+                ORDER BY launched_at DESC LIMIT ?2
+            )",
+            params![game_slug, MAX_LAUNCHES],
+        )?;
+
+        tx.commit()?;
+
+        Ok(id)
+    }
+
+    pub fn finish_launch(
+        &self,
+        id: i64,
+        duration: Duration,
+        exit_code: Option<i32>,
+        crashed: bool,
+    ) -> Result<()> {
+        self.conn().execute(
+            "UPDATE launches SET duration_ms = ?, exit_code = ?, crashed = ? WHERE id = ?",
+            params![duration.as_millis() as u64, exit_code, crashed, id],
+        )?;
+
+        Ok(())
+    }
+
+    /// Returns the latest launches of a game, newest first.
+    pub fn launch_history(&self, game_slug: &str, limit: u32) -> Result<Vec<LaunchRecord>> {
+        let records = self
+            .conn()
+            .prepare(
+                "SELECT profile_id, launched_at, duration_ms, exit_code, crashed
+                FROM launches WHERE game_slug = ?
+                ORDER BY launched_at DESC LIMIT ?",
+            )?
+            .query_map(params![game_slug, limit], |row| {
+                Ok(LaunchRecord {
+                    profile_id: row.get(0)?,
+                    launched_at: row.get(1)?,
+                    duration_ms: row.get(2)?,
+                    exit_code: row.get(3)?,
+                    crashed: row.get(4)?,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+
+        Ok(records)
+    }
+}
@@ -0,0 +1,41 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+use eyre::Result;
+use rusqlite::params;
+use uuid::Uuid;
+
+use super::{map_json_row, Db};
+use crate::thunderstore::ModMetadata;
+
+impl Db {
+    /// Returns the cached metadata of installed packages, keyed by package uuid.
+    pub fn mod_metadata(&self) -> Result<HashMap<Uuid, ModMetadata>> {
+        let metadata = self
+            .conn()
+            .prepare("SELECT package_uuid, data FROM mod_metadata")?
+            .query_map((), |row| Ok((row.get(0)?, map_json_row(row, 1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+
+        Ok(metadata)
+    }
+
+    pub fn save_mod_metadata<'a>(
+        &self,
+        metadata: impl IntoIterator<Item = (Uuid, &'a ModMetadata)>,
+    ) -> Result<()> {
+        let now = Utc::now();
+
+        self.with_transaction(|tx| {
+            let mut stmt = tx.prepare(
+                "INSERT OR REPLACE INTO mod_metadata (package_uuid, data, updated_at) VALUES (?, ?, ?)",
+            )?;
+
+            for (uuid, metadata) in metadata {
+                stmt.execute(params![uuid, serde_json::to_string(metadata)?, now])?;
+            }
+
+            Ok(())
+        })
+    }
+}
@@ -105,6 +105,9 @@ fn read_manager_data(prefs: &Prefs) -> Result<SaveData> {
                 mods: profile_data.mods.into_iter().map_into().collect(),
                 modpack: profile_data.modpack.map(Into::into),
                 ignored_updates: Some(profile_data.ignored_updates),
+                locked: false,
+                game_dir_install: false,
+                doorstop: None,
             });
 
             if data.active_profile_index == index {
@@ -117,6 +120,7 @@ fn read_manager_data(prefs: &Prefs) -> Result<SaveData> {
             slug: game.slug.to_string(),
             favorite: data.favorite,
             active_profile_id,
+            last_played: None,
         });
     }
 
@@ -124,6 +128,7 @@ fn read_manager_data(prefs: &Prefs) -> Result<SaveData> {
         manager,
         games,
         profiles,
+        quarantined_profiles: Vec::new(),
     })
 }
 
@@ -139,12 +144,14 @@ impl From<legacy::Prefs> for Prefs {
             data_dir: legacy.data_dir.into(),
             send_telemetry: legacy.send_telemetry,
             fetch_mods_automatically: legacy.fetch_mods_automatically,
+            enable_mod_cache: true,
             zoom_factor: legacy.zoom_factor,
             game_prefs: legacy
                 .game_prefs
                 .into_iter()
                 .map(|(key, value)| (key, value.into()))
                 .collect(),
+            ..Default::default()
         }
     }
 }
@@ -156,6 +163,7 @@ impl From<legacy::GamePrefs> for GamePrefs {
             custom_args: legacy.custom_args,
             launch_mode: legacy.launch_mode.into(),
             platform: legacy.platform.map(Into::into),
+            webhook: None,
         }
     }
 }
@@ -193,6 +201,8 @@ impl From<legacy::ProfileMod> for ProfileMod {
             enabled: legacy.enabled,
             install_time: legacy.install_time,
             kind: legacy.kind.into(),
+            side: None,
+            origin: None,
         }
     }
 }
@@ -258,6 +268,7 @@ impl From<legacy::ModpackArgs> for ModpackArgs {
             website_url: legacy.website_url,
             include_disabled: legacy.include_disabled,
             include_files: legacy.include_files,
+            target: None,
         }
     }
 }
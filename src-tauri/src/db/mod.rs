@@ -1,12 +1,13 @@
 use std::{
     collections::HashSet,
     iter,
-    sync::{Mutex, MutexGuard},
+    sync::{Mutex, MutexGuard, PoisonError},
 };
 
-use eyre::{Context, Result};
+use chrono::{DateTime, Utc};
+use eyre::{ensure, Context, OptionExt, Result};
 use include_dir::include_dir;
-use log::info;
+use log::{info, warn};
 use rusqlite::{params, types::Type as SqliteType, OptionalExtension};
 use rusqlite_migration::Migrations;
 use serde::de::DeserializeOwned;
@@ -18,7 +19,28 @@ use crate::{
     util,
 };
 
+mod aliases;
+mod bisect;
+mod builds;
+mod cache;
+mod deployments;
+mod downloads;
+mod game_dir;
+mod launches;
+mod metadata;
 mod migrate;
+mod network;
+mod slugs;
+
+pub use cache::CacheFile;
+pub use deployments::{DeployMethod, Deployment};
+pub use downloads::DownloadSample;
+pub use game_dir::GameDirFile;
+pub use launches::LaunchRecord;
+pub use network::{DailyUsage, DownloadRecord, UsageKind};
+
+#[cfg(test)]
+mod tests;
 
 pub const FILE_NAME: &str = "data.sqlite3";
 pub const SHM_FILE_NAME: &str = "data.sqlite3-shm";
@@ -50,6 +72,16 @@ pub fn init() -> Result<(Db, bool)> {
     Ok((Db(Mutex::new(conn)), existed))
 }
 
+impl Db {
+    #[cfg(test)]
+    pub fn in_memory() -> Self {
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        run_migrations(&mut conn).unwrap();
+
+        Db(Mutex::new(conn))
+    }
+}
+
 static MIGRATIONS_DIR: include_dir::Dir = include_dir!("$CARGO_MANIFEST_DIR/migrations");
 
 fn run_migrations(conn: &mut rusqlite::Connection) -> Result<()> {
@@ -71,16 +103,11 @@ where
     })
 }
 
-fn map_json_option_row<I, T>(row: &rusqlite::Row, idx: I) -> rusqlite::Result<Option<T>>
-where
-    I: rusqlite::RowIndex,
-    T: DeserializeOwned,
-{
-    match map_json_row(row, idx) {
-        Ok(value) => Ok(Some(value)),
-        Err(rusqlite::Error::InvalidColumnType(_, _, SqliteType::Null)) => Ok(None),
-        Err(err) => Err(err),
-    }
+fn parse_json_column<T: DeserializeOwned>(value: Option<&str>, name: &str) -> Result<Option<T>> {
+    value
+        .map(serde_json::from_str)
+        .transpose()
+        .with_context(|| format!("{} column contains invalid json", name))
 }
 
 pub struct ManagerData {
@@ -93,6 +120,7 @@ pub struct ManagedGameData {
     pub slug: String,
     pub favorite: bool,
     pub active_profile_id: i64,
+    pub last_played: Option<DateTime<Utc>>,
 }
 
 pub struct ProfileData {
@@ -103,17 +131,58 @@ pub struct ProfileData {
     pub mods: Vec<profile::ProfileMod>,
     pub modpack: Option<profile::export::modpack::ModpackArgs>,
     pub ignored_updates: Option<HashSet<Uuid>>,
+    pub locked: bool,
+    pub game_dir_install: bool,
+    pub doorstop: Option<profile::launch::DoorstopSettings>,
+}
+
+/// A row of the profiles table, before the json columns are parsed.
+struct ProfileRow {
+    id: i64,
+    name: String,
+    path: String,
+    game_slug: String,
+    mods: Option<String>,
+    modpack: Option<String>,
+    ignored_updates: Option<String>,
+    locked: bool,
+    game_dir_install: bool,
+    doorstop: Option<String>,
+}
+
+impl ProfileRow {
+    fn parse(&self) -> Result<ProfileData> {
+        let mods = parse_json_column(self.mods.as_deref(), "mods")?
+            .ok_or_eyre("mods column is missing")?;
+
+        Ok(ProfileData {
+            id: self.id,
+            name: self.name.clone(),
+            path: self.path.clone(),
+            game_slug: self.game_slug.clone(),
+            mods,
+            modpack: parse_json_column(self.modpack.as_deref(), "modpack")?,
+            ignored_updates: parse_json_column(self.ignored_updates.as_deref(), "ignored_updates")?,
+            locked: self.locked,
+            game_dir_install: self.game_dir_install,
+            doorstop: parse_json_column(self.doorstop.as_deref(), "doorstop")?,
+        })
+    }
 }
 
 pub struct SaveData {
     pub manager: ManagerData,
     pub games: Vec<ManagedGameData>,
     pub profiles: Vec<ProfileData>,
+    /// Names of profiles that couldn't be read and were moved out of the way.
+    pub quarantined_profiles: Vec<String>,
 }
 
 impl Db {
     fn conn(&self) -> MutexGuard<'_, rusqlite::Connection> {
-        self.0.lock().unwrap()
+        // an interrupted transaction is rolled back when dropped,
+        // so the connection is always safe to reuse
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
     }
 
     fn with_transaction<F>(&self, f: F) -> Result<()>
@@ -144,6 +213,19 @@ impl Db {
         Ok(res)
     }
 
+    /// Like [`Db::next_profile_id`], but for managed games.
+    ///
+    /// This includes rows of games that weren't loaded, so they aren't overwritten.
+    pub fn next_game_id(&self) -> Result<i64> {
+        let res = self.conn().query_row(
+            "SELECT IFNULL(MAX(id), 0) + 1 FROM managed_games",
+            (),
+            |row| row.get(0),
+        )?;
+
+        Ok(res)
+    }
+
     pub fn user_id(&self) -> Result<Option<Uuid>> {
         let conn = self.conn();
 
@@ -176,7 +258,7 @@ impl Db {
             return Ok((data, prefs, true));
         }
 
-        let conn = self.conn();
+        let mut conn = self.conn();
 
         let manager = conn
             .prepare("SELECT id, active_game_slug FROM manager")?
@@ -193,34 +275,57 @@ impl Db {
             });
 
         let games = conn
-            .prepare("SELECT id, slug, favorite, active_profile_id FROM managed_games")?
+            .prepare(
+                "SELECT id, slug, favorite, active_profile_id, last_played FROM managed_games",
+            )?
             .query_map((), |row| {
                 Ok(ManagedGameData {
                     id: row.get(0)?,
                     slug: row.get(1)?,
                     favorite: row.get(2)?,
                     active_profile_id: row.get(3)?,
+                    last_played: row.get(4)?,
                 })
             })?
             .collect::<rusqlite::Result<Vec<_>>>()?;
 
-        let profiles = conn
+        let rows = conn
             .prepare(
-                "SELECT id, name, path, game_slug, mods, modpack, ignored_updates FROM profiles",
+                "SELECT id, name, path, game_slug, mods, modpack, ignored_updates, locked, game_dir_install, doorstop FROM profiles",
             )?
             .query_map((), |row| {
-                Ok(ProfileData {
+                Ok(ProfileRow {
                     id: row.get(0)?,
                     name: row.get(1)?,
                     path: row.get(2)?,
                     game_slug: row.get(3)?,
-                    mods: map_json_row(row, 4)?,
-                    modpack: map_json_option_row(row, 5)?,
-                    ignored_updates: map_json_option_row(row, 6)?,
+                    mods: row.get(4)?,
+                    modpack: row.get(5)?,
+                    ignored_updates: row.get(6)?,
+                    locked: row.get(7)?,
+                    game_dir_install: row.get(8)?,
+                    doorstop: row.get(9)?,
                 })
             })?
             .collect::<rusqlite::Result<Vec<_>>>()?;
 
+        let mut profiles = Vec::with_capacity(rows.len());
+        let mut quarantined_profiles = Vec::new();
+
+        for row in rows {
+            match row.parse() {
+                Ok(profile) => profiles.push(profile),
+                Err(err) => {
+                    warn!("profile {} is corrupt, quarantining: {:#}", row.name, err);
+
+                    quarantine_profile(&mut conn, &row, &err)
+                        .with_context(|| format!("failed to quarantine profile {}", row.name))?;
+
+                    quarantined_profiles.push(row.name);
+                }
+            }
+        }
+
         let prefs = conn
             .prepare("SELECT data FROM prefs")?
             .query_row((), |row| map_json_row(row, 0))
@@ -232,6 +337,7 @@ impl Db {
                 manager,
                 games,
                 profiles,
+                quarantined_profiles,
             },
             prefs,
             false,
@@ -242,6 +348,8 @@ impl Db {
         self.with_transaction(|tx| {
             tx.prepare("DELETE FROM profiles WHERE id = ?")?
                 .execute([id])?;
+            tx.prepare("DELETE FROM bisect_sessions WHERE profile_id = ?")?
+                .execute([id])?;
 
             Ok(())
         })
@@ -281,8 +389,9 @@ impl Db {
         games: impl Iterator<Item = &'a ManagedGame>,
     ) -> Result<()> {
         let mut stmt = tx.prepare(
-            "INSERT OR REPLACE INTO managed_games (id, slug, favorite, active_profile_id)
-                VALUES (?, ?, ?, ?)",
+            "INSERT OR REPLACE INTO managed_games
+                (id, slug, favorite, active_profile_id, last_played)
+                VALUES (?, ?, ?, ?, ?)",
         )?;
 
         for game in games {
@@ -290,7 +399,8 @@ impl Db {
                 game.id,
                 game.game.slug,
                 game.favorite,
-                game.active_profile_id
+                game.active_profile_id,
+                game.last_played
             ])?;
         }
 
@@ -308,10 +418,12 @@ impl Db {
     ) -> Result<()> {
         let mut stmt = tx.prepare(
             "INSERT OR REPLACE INTO profiles 
-                (id, name, path, game_slug, mods, modpack, ignored_updates) 
-                VALUES (?, ?, ?, ?, ?, ?, ?)",
+                (id, name, path, game_slug, mods, modpack, ignored_updates, locked, game_dir_install, doorstop) 
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
         )?;
 
+        let mut verify_stmt = tx.prepare("SELECT mods FROM profiles WHERE id = ?")?;
+
         for profile in profiles {
             let mods = serde_json::to_string(&profile.mods)?;
             let modpack = profile
@@ -320,6 +432,10 @@ impl Db {
                 .map(serde_json::to_string)
                 .transpose()?;
             let ignored_updates = serde_json::to_string(&profile.ignored_updates)?;
+            let doorstop = match profile.doorstop.is_default() {
+                true => None,
+                false => Some(serde_json::to_string(&profile.doorstop)?),
+            };
 
             stmt.execute(params![
                 profile.id,
@@ -328,8 +444,19 @@ impl Db {
                 profile.game.slug,
                 mods,
                 modpack,
-                ignored_updates
+                ignored_updates,
+                profile.locked,
+                profile.game_dir_install,
+                doorstop
             ])?;
+
+            // read the mods back before committing, since losing them is the worst case
+            let stored: String = verify_stmt.query_row([profile.id], |row| row.get(0))?;
+            ensure!(
+                stored == mods,
+                "failed to verify saved mods of profile {}",
+                profile.name
+            );
         }
 
         Ok(())
@@ -346,3 +473,36 @@ impl Db {
         })
     }
 }
+
+/// Moves a profile row that failed to parse into the `quarantined_profiles` table,
+/// so that it doesn't prevent the rest of the data from loading.
+///
+/// The raw data is kept for manual recovery.
+fn quarantine_profile(
+    conn: &mut rusqlite::Connection,
+    row: &ProfileRow,
+    error: &eyre::Error,
+) -> Result<()> {
+    let tx = conn.transaction()?;
+
+    tx.execute(
+        "INSERT INTO quarantined_profiles
+            (profile_id, name, path, game_slug, mods, modpack, ignored_updates, error)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        params![
+            row.id,
+            row.name,
+            row.path,
+            row.game_slug,
+            row.mods,
+            row.modpack,
+            row.ignored_updates,
+            format!("{:#}", error)
+        ],
+    )?;
+
+    tx.execute("DELETE FROM profiles WHERE id = ?", [row.id])?;
+
+    tx.commit()?;
+    Ok(())
+}
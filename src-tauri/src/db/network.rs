@@ -0,0 +1,144 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use eyre::Result;
+use rusqlite::params;
+use serde::Serialize;
+
+use super::Db;
+
+/// The maximum number of individual downloads kept, the oldest ones are removed first.
+const MAX_DOWNLOADS: u32 = 1000;
+
+/// The bytes downloaded for a game on a single (UTC) day.
+#[derive(Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyUsage {
+    pub day: NaiveDate,
+    pub game_slug: String,
+    pub bytes: u64,
+}
+
+/// What a download was for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsageKind {
+    /// Mod packages, which count towards the download cap.
+    Package,
+    /// Things like the package index and icons, which don't.
+    Metadata,
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadRecord {
+    pub name: String,
+    pub game_slug: String,
+    pub bytes: u64,
+    pub time: DateTime<Utc>,
+}
+
+impl Db {
+    /// Adds a download of `bytes` to the day's total for the game.
+    pub fn record_network_usage(
+        &self,
+        game_slug: &str,
+        name: &str,
+        bytes: u64,
+        kind: UsageKind,
+        time: DateTime<Utc>,
+    ) -> Result<()> {
+        let metadata_bytes = match kind {
+            UsageKind::Package => 0,
+            UsageKind::Metadata => bytes,
+        };
+
+        self.with_transaction(|tx| {
+            tx.execute(
+                "INSERT INTO network_usage (day, game_slug, bytes, metadata_bytes)
+                VALUES (?, ?, ?, ?)
+                ON CONFLICT (day, game_slug) DO UPDATE SET
+                    bytes = bytes + excluded.bytes,
+                    metadata_bytes = metadata_bytes + excluded.metadata_bytes",
+                params![time.date_naive(), game_slug, bytes, metadata_bytes],
+            )?;
+
+            tx.execute(
+                "INSERT INTO network_downloads (name, game_slug, bytes, downloaded_at)
+                VALUES (?, ?, ?, ?)",
+                params![name, game_slug, bytes, time],
+            )?;
+
+            tx.execute(
+                "DELETE FROM network_downloads WHERE rowid NOT IN (
+                    SELECT rowid FROM network_downloads ORDER BY downloaded_at DESC LIMIT ?
+                )",
+                [MAX_DOWNLOADS],
+            )?;
+
+            Ok(())
+        })
+    }
+
+    /// Returns the daily totals per game from `since` onwards, oldest first.
+    pub fn network_usage(&self, since: NaiveDate) -> Result<Vec<DailyUsage>> {
+        let usage = self
+            .conn()
+            .prepare(
+                "SELECT day, game_slug, bytes FROM network_usage
+                WHERE day >= ? ORDER BY day, game_slug",
+            )?
+            .query_map([since], |row| {
+                Ok(DailyUsage {
+                    day: row.get(0)?,
+                    game_slug: row.get(1)?,
+                    bytes: row.get(2)?,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+
+        Ok(usage)
+    }
+
+    /// Returns the total bytes downloaded from `since` onwards, across all games.
+    pub fn total_network_usage(&self, since: NaiveDate) -> Result<u64> {
+        let total = self.conn().query_row(
+            "SELECT COALESCE(SUM(bytes), 0) FROM network_usage WHERE day >= ?",
+            [since],
+            |row| row.get(0),
+        )?;
+
+        Ok(total)
+    }
+
+    /// Returns the bytes of packages downloaded from `since` onwards, across all games.
+    pub fn total_package_usage(&self, since: NaiveDate) -> Result<u64> {
+        let total = self.conn().query_row(
+            "SELECT COALESCE(SUM(bytes - metadata_bytes), 0) FROM network_usage WHERE day >= ?",
+            [since],
+            |row| row.get(0),
+        )?;
+
+        Ok(total)
+    }
+
+    /// Returns the largest single downloads from `since` onwards.
+    pub fn largest_downloads(&self, since: NaiveDate, limit: u32) -> Result<Vec<DownloadRecord>> {
+        let since = since.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+
+        let downloads = self
+            .conn()
+            .prepare(
+                "SELECT name, game_slug, bytes, downloaded_at FROM network_downloads
+                WHERE downloaded_at >= ? ORDER BY bytes DESC LIMIT ?",
+            )?
+            .query_map(params![since, limit], |row| {
+                Ok(DownloadRecord {
+                    name: row.get(0)?,
+                    game_slug: row.get(1)?,
+                    bytes: row.get(2)?,
+                    time: row.get(3)?,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+
+        Ok(downloads)
+    }
+}
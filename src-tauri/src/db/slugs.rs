@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+
+use eyre::{ensure, Result};
+use rusqlite::params;
+
+use super::Db;
+
+/// Tables with a column that refers to a game by its slug.
+///
+/// The update is done with `OR REPLACE`, so for tables keyed by the slug,
+/// rows of the old slug take precedence over existing rows of the new one.
+const SLUG_COLUMNS: &[(&str, &str)] = &[
+    ("profiles", "game_slug"),
+    ("quarantined_profiles", "game_slug"),
+    ("deployments", "game_slug"),
+    ("game_dir_files", "game_slug"),
+    ("network_usage", "game_slug"),
+    ("network_downloads", "game_slug"),
+    ("manager", "active_game_slug"),
+];
+
+impl Db {
+    /// Moves everything stored under the old slugs in `mapping` to the new ones,
+    /// for example after a game was renamed in `games.json`.
+    ///
+    /// Either every table is updated or none are. Returns the number of profiles that were moved.
+    pub fn remap_game_slugs(&self, mapping: &HashMap<String, String>) -> Result<usize> {
+        ensure!(
+            mapping.values().all(|new| !mapping.contains_key(new)),
+            "slug mapping cannot be chained"
+        );
+
+        let mut moved = 0;
+
+        self.with_transaction(|tx| {
+            for (old, new) in mapping {
+                let old_exists = tx
+                    .prepare("SELECT 1 FROM managed_games WHERE slug = ?")?
+                    .exists([old])?;
+
+                // the old game's state wins, since that's where its profiles belong
+                if old_exists {
+                    tx.execute("DELETE FROM managed_games WHERE slug = ?", [new])?;
+                    tx.execute(
+                        "UPDATE managed_games SET slug = ? WHERE slug = ?",
+                        [new, old],
+                    )?;
+                }
+
+                for (table, column) in SLUG_COLUMNS {
+                    let changed = tx.execute(
+                        &format!(
+                            "UPDATE OR REPLACE {} SET {} = ? WHERE {} = ?",
+                            table, column, column
+                        ),
+                        params![new, old],
+                    )?;
+
+                    if *table == "profiles" {
+                        moved += changed;
+                    }
+                }
+            }
+
+            Ok(())
+        })?;
+
+        Ok(moved)
+    }
+}
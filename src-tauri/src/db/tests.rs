@@ -0,0 +1,333 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+
+use super::*;
+
+fn test_db() -> Db {
+    Db::in_memory()
+}
+
+fn insert_profile(db: &Db, id: i64, name: &str, mods: &str) {
+    db.conn()
+        .execute(
+            "INSERT INTO profiles (id, name, path, game_slug, mods) VALUES (?, ?, ?, ?, ?)",
+            params![
+                id,
+                name,
+                format!("/profiles/{}", name),
+                "lethal-company",
+                mods
+            ],
+        )
+        .unwrap();
+}
+
+#[test]
+fn corrupt_profile_is_quarantined() {
+    let db = test_db();
+
+    insert_profile(&db, 1, "Good", "[]");
+    insert_profile(&db, 2, "Bad", "[{\"enabled\": tr");
+
+    let (data, _, _) = db.read().unwrap();
+
+    assert_eq!(data.profiles.len(), 1);
+    assert_eq!(data.profiles[0].name, "Good");
+    assert_eq!(data.quarantined_profiles, ["Bad"]);
+
+    let conn = db.conn();
+
+    let remaining: i64 = conn
+        .query_row("SELECT COUNT(*) FROM profiles", (), |row| row.get(0))
+        .unwrap();
+    assert_eq!(remaining, 1);
+
+    let (profile_id, mods): (i64, String) = conn
+        .query_row(
+            "SELECT profile_id, mods FROM quarantined_profiles",
+            (),
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .unwrap();
+    assert_eq!(profile_id, 2);
+    assert_eq!(mods, "[{\"enabled\": tr");
+}
+
+#[test]
+fn profiles_are_unlocked_by_default() {
+    let db = test_db();
+
+    insert_profile(&db, 1, "Open", "[]");
+    insert_profile(&db, 2, "Tournament", "[]");
+    db.conn()
+        .execute("UPDATE profiles SET locked = 1 WHERE id = 2", ())
+        .unwrap();
+
+    let (data, _, _) = db.read().unwrap();
+
+    let locked = data
+        .profiles
+        .iter()
+        .map(|profile| (profile.name.as_str(), profile.locked))
+        .collect::<Vec<_>>();
+    assert_eq!(locked, [("Open", false), ("Tournament", true)]);
+}
+
+#[test]
+fn download_history_is_ordered_and_removed_on_unwatch() {
+    let db = test_db();
+    let uuid = Uuid::new_v4();
+
+    db.watch_package(uuid).unwrap();
+    assert_eq!(db.watched_packages().unwrap(), [uuid]);
+
+    let start = Utc::now();
+    db.insert_download_samples(&[(uuid, 20)], start + chrono::Duration::hours(1))
+        .unwrap();
+    db.insert_download_samples(&[(uuid, 10)], start).unwrap();
+
+    let history = db.download_history(uuid).unwrap();
+    let downloads = history
+        .iter()
+        .map(|sample| sample.downloads)
+        .collect::<Vec<_>>();
+    assert_eq!(downloads, [10, 20]);
+
+    db.unwatch_package(uuid).unwrap();
+    assert!(db.watched_packages().unwrap().is_empty());
+    assert!(db.download_history(uuid).unwrap().is_empty());
+}
+
+#[test]
+fn game_dir_files_are_replaced_per_game() {
+    let db = test_db();
+
+    let file = |path: &str, profile_id| GameDirFile {
+        game_dir: "/games/gta".into(),
+        path: path.into(),
+        profile_id,
+    };
+
+    db.set_game_dir_files("gta-v", &[file("dinput8.dll", 1), file("scripts/a.asi", 1)])
+        .unwrap();
+    db.set_game_dir_files("other", &[file("winmm.dll", 3)])
+        .unwrap();
+    db.set_game_dir_files("gta-v", &[file("dinput8.dll", 2)])
+        .unwrap();
+
+    assert_eq!(
+        db.game_dir_files("gta-v").unwrap(),
+        [file("dinput8.dll", 2)]
+    );
+    assert_eq!(db.game_dir_files("other").unwrap(), [file("winmm.dll", 3)]);
+}
+
+#[test]
+fn interrupted_deployment_is_remembered() {
+    let db = test_db();
+
+    let mut deployment = Deployment {
+        profile_id: 1,
+        target: "/games/tmodloader/Mods".into(),
+        method: DeployMethod::Copy,
+        files: vec!["enabled.json".into(), "Example/Example.tmod".into()],
+        complete: false,
+    };
+    db.save_deployment("tmodloader", &deployment).unwrap();
+
+    assert_eq!(
+        db.deployment("tmodloader").unwrap(),
+        Some(deployment.clone())
+    );
+    assert_eq!(db.deployment("other").unwrap(), None);
+
+    deployment.complete = true;
+    db.save_deployment("tmodloader", &deployment).unwrap();
+    assert!(db.deployment("tmodloader").unwrap().unwrap().complete);
+
+    db.remove_deployment("tmodloader").unwrap();
+    assert_eq!(db.deployment("tmodloader").unwrap(), None);
+}
+
+#[test]
+fn network_usage_is_summed_per_day_and_game() {
+    let db = test_db();
+    let day = |day: u32, hour: u32| {
+        NaiveDate::from_ymd_opt(2024, 5, day)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+            .and_utc()
+    };
+
+    let record = |game, name, bytes, kind, time| {
+        db.record_network_usage(game, name, bytes, kind, time)
+            .unwrap()
+    };
+
+    record(
+        "lethal-company",
+        "Index",
+        100,
+        UsageKind::Metadata,
+        day(1, 10),
+    );
+    record(
+        "lethal-company",
+        "BigMod",
+        500,
+        UsageKind::Package,
+        day(2, 9),
+    );
+    record(
+        "lethal-company",
+        "SmallMod",
+        20,
+        UsageKind::Package,
+        day(2, 18),
+    );
+    record(
+        "content-warning",
+        "OtherMod",
+        50,
+        UsageKind::Package,
+        day(2, 12),
+    );
+    record(
+        "content-warning",
+        "Icons",
+        30,
+        UsageKind::Metadata,
+        day(2, 13),
+    );
+
+    let since = NaiveDate::from_ymd_opt(2024, 5, 2).unwrap();
+
+    let usage = db
+        .network_usage(since)
+        .unwrap()
+        .into_iter()
+        .map(|usage| (usage.game_slug, usage.bytes))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        usage,
+        [
+            ("content-warning".to_owned(), 80),
+            ("lethal-company".to_owned(), 520)
+        ]
+    );
+
+    assert_eq!(db.total_network_usage(since).unwrap(), 600);
+    // metadata doesn't count towards the download cap
+    assert_eq!(db.total_package_usage(since).unwrap(), 570);
+
+    let largest = db
+        .largest_downloads(since, 2)
+        .unwrap()
+        .into_iter()
+        .map(|download| download.name)
+        .collect::<Vec<_>>();
+    assert_eq!(largest, ["BigMod", "OtherMod"]);
+}
+
+#[test]
+fn renamed_game_slug_keeps_profiles() {
+    let db = test_db();
+
+    insert_profile(&db, 1, "Default", "[]");
+    insert_profile(&db, 2, "Modded", "[]");
+
+    {
+        let conn = db.conn();
+        conn.execute(
+            "INSERT INTO manager (id, active_game_slug) VALUES (1, 'lethal-company')",
+            (),
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO managed_games (id, slug, favorite, active_profile_id)
+            VALUES (1, 'lethal-company', TRUE, 2), (2, 'lethal', FALSE, 0)",
+            (),
+        )
+        .unwrap();
+    }
+
+    let mapping = HashMap::from([("lethal-company".to_owned(), "lethal".to_owned())]);
+    assert_eq!(db.remap_game_slugs(&mapping).unwrap(), 2);
+
+    let (data, _, _) = db.read().unwrap();
+
+    assert_eq!(data.manager.active_game_slug.as_deref(), Some("lethal"));
+    assert!(data
+        .profiles
+        .iter()
+        .all(|profile| profile.game_slug == "lethal"));
+
+    // the old game's state replaces the empty one that was created for the new slug
+    assert_eq!(data.games.len(), 1);
+    assert_eq!(data.games[0].slug, "lethal");
+    assert!(data.games[0].favorite);
+    assert_eq!(data.games[0].active_profile_id, 2);
+
+    let chained = HashMap::from([
+        ("a".to_owned(), "b".to_owned()),
+        ("b".to_owned(), "c".to_owned()),
+    ]);
+    assert!(db.remap_game_slugs(&chained).is_err());
+}
+
+#[test]
+fn finished_launch_is_recorded() {
+    let db = test_db();
+
+    let start = Utc::now();
+    let first = db.record_launch("lethal-company", 1, start).unwrap();
+    db.record_launch("lethal-company", 2, start + chrono::Duration::seconds(60))
+        .unwrap();
+    db.record_launch("other-game", 3, start).unwrap();
+
+    db.finish_launch(first, std::time::Duration::from_secs(5), Some(1), true)
+        .unwrap();
+
+    let history = db.launch_history("lethal-company", 10).unwrap();
+
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].profile_id, 2);
+    assert_eq!(history[0].duration_ms, None);
+    assert_eq!(
+        history[1],
+        LaunchRecord {
+            profile_id: 1,
+            launched_at: start,
+            duration_ms: Some(5000),
+            exit_code: Some(1),
+            crashed: true,
+        }
+    );
+}
+
+#[test]
+fn mod_metadata_is_replaced() {
+    use crate::thunderstore::ModMetadata;
+
+    let db = test_db();
+    let uuid = Uuid::new_v4();
+
+    let mut metadata = ModMetadata {
+        description: "Old description".to_owned(),
+        icon_url: "https://example.com/icon.png".to_owned(),
+        categories: vec!["Tools".to_owned()],
+        author: "Kesomannen".to_owned(),
+    };
+
+    db.save_mod_metadata([(uuid, &metadata)]).unwrap();
+
+    metadata.description = "New description".to_owned();
+    db.save_mod_metadata([(uuid, &metadata)]).unwrap();
+
+    assert_eq!(
+        db.mod_metadata().unwrap(),
+        HashMap::from([(uuid, metadata)])
+    );
+}
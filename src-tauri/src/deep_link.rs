@@ -5,9 +5,11 @@ use tauri::{AppHandle, Emitter, Manager};
 use crate::{
     logger, profile,
     state::ManagerExt,
-    thunderstore::{BorrowedMod, IntoFrontendMod, Thunderstore},
+    thunderstore::{BorrowedMod, IntoFrontendMod, Thunderstore, VersionIdent},
 };
 
+const MOD_URL_PREFIX: &str = "ror2mm://v1/install/thunderstore.io/";
+
 pub fn handle(app: &AppHandle, args: Vec<String>) {
     debug!("received deep link: {:?}", args);
 
@@ -22,16 +24,18 @@ pub fn handle(app: &AppHandle, args: Vec<String>) {
     };
 
     if url.starts_with("ror2mm://") {
-        let thunderstore = app.lock_thunderstore();
-        let borrowed_mod = match resolve_mod_url(&url, &thunderstore) {
-            Ok(to_install) => to_install,
+        let frontend_mod = app.lock_thunderstore().and_then(|thunderstore| {
+            resolve_mod_url(&url, &thunderstore).map(|borrowed| borrowed.into_frontend(None))
+        });
+
+        let frontend_mod = match frontend_mod {
+            Ok(frontend_mod) => frontend_mod,
             Err(err) => {
                 logger::log_webview_err("Failed to install mod from deep link", err, app);
                 return;
             }
         };
 
-        let frontend_mod = borrowed_mod.into_frontend(None);
         app.emit("install_mod", frontend_mod).ok();
     } else if url.ends_with("r2z") {
         let import_data = match profile::import::import_file_from_path(url.into(), app) {
@@ -48,9 +52,17 @@ pub fn handle(app: &AppHandle, args: Vec<String>) {
     }
 }
 
+/// Creates a deep link that installs the given mod version.
+///
+/// This is the inverse of [`resolve_mod_url`].
+pub fn mod_url(ident: &VersionIdent) -> String {
+    let (owner, name, version) = ident.split();
+    format!("{}{}/{}/{}", MOD_URL_PREFIX, owner, name, version)
+}
+
 fn resolve_mod_url<'a>(url: &str, thunderstore: &'a Thunderstore) -> Result<BorrowedMod<'a>> {
     let (owner, name, version) = url
-        .strip_prefix("ror2mm://v1/install/thunderstore.io/")
+        .strip_prefix(MOD_URL_PREFIX)
         .and_then(|path| {
             let mut split = path.split('/');
 
@@ -60,3 +72,21 @@ fn resolve_mod_url<'a>(url: &str, thunderstore: &'a Thunderstore) -> Result<Borr
 
     thunderstore.find_mod(owner, name, version)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::thunderstore::tests::{test_package, test_thunderstore};
+
+    #[test]
+    fn mod_url_round_trips() {
+        let thunderstore = test_thunderstore([test_package("A", &[])]);
+        let ident = VersionIdent::new("Test", "A", "1.0.0");
+
+        let url = mod_url(&ident);
+        assert_eq!(url, "ror2mm://v1/install/thunderstore.io/Test/A/1.0.0");
+
+        let borrowed = resolve_mod_url(&url, &thunderstore).unwrap();
+        assert_eq!(borrowed.ident(), &ident);
+    }
+}
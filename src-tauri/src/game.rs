@@ -1,11 +1,13 @@
 use std::{
     borrow::Cow,
+    collections::HashSet,
     hash::{self, Hash},
     path::PathBuf,
     sync::LazyLock,
 };
 
 use heck::{ToKebabCase, ToPascalCase};
+use log::{error, warn};
 use serde::{Deserialize, Serialize};
 use strum::IntoEnumIterator;
 use strum_macros::{Display, EnumIter};
@@ -30,6 +32,94 @@ pub fn from_slug(slug: &str) -> Option<Game> {
     GAMES.iter().find(|game| game.slug == slug)
 }
 
+/// The names of the mod loaders in `games.json`, as in [`ModLoader::to_str`].
+const MOD_LOADER_NAMES: &[&str] = &[
+    "BepInEx",
+    "MelonLoader",
+    "Northstar",
+    "GDWeave",
+    "Shimloader",
+    "Lovely",
+    "ReturnOfModding",
+];
+
+/// A problem with an entry in `games.json`.
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum GameIssue {
+    #[error("entry is missing a name")]
+    MissingName,
+    #[error("slug {0} is used by another game")]
+    DuplicateSlug(String),
+    #[error("unknown mod loader {0}")]
+    UnknownModLoader(String),
+    #[error("entry has no platforms, use an empty object for games sold outside of stores")]
+    MissingPlatforms,
+    #[error("invalid entry: {0}")]
+    Invalid(String),
+}
+
+/// Checks every entry of a `games.json` file, returning the index of
+/// each entry with a problem alongside the problem.
+///
+/// Unlike loading the file, this doesn't stop at the first invalid entry.
+pub fn validate(json: &str) -> serde_json::Result<Vec<(usize, GameIssue)>> {
+    let entries: Vec<serde_json::Value> = serde_json::from_str(json)?;
+
+    let mut issues = Vec::new();
+    let mut slugs = HashSet::new();
+
+    for (index, entry) in entries.iter().enumerate() {
+        let Some(name) = entry["name"].as_str() else {
+            issues.push((index, GameIssue::MissingName));
+            continue;
+        };
+
+        let slug = match entry["slug"].as_str() {
+            Some(slug) => slug.to_owned(),
+            None => name.to_kebab_case(),
+        };
+
+        if !slugs.insert(slug.clone()) {
+            issues.push((index, GameIssue::DuplicateSlug(slug)));
+        }
+
+        match entry["modLoader"]["name"].as_str() {
+            Some(loader) if MOD_LOADER_NAMES.contains(&loader) => (),
+            loader => issues.push((
+                index,
+                GameIssue::UnknownModLoader(loader.unwrap_or_default().to_owned()),
+            )),
+        }
+
+        if !entry["platforms"].is_object() {
+            issues.push((index, GameIssue::MissingPlatforms));
+        }
+
+        // catch everything else, like fields with the wrong type
+        let text = entry.to_string();
+        if let Err(err) = serde_json::from_str::<GameData>(&text) {
+            issues.push((index, GameIssue::Invalid(err.to_string())));
+        }
+    }
+
+    Ok(issues)
+}
+
+/// Logs any problems with the bundled `games.json`.
+pub fn validate_bundled() {
+    let issues = match validate(GAMES_JSON) {
+        Ok(issues) => issues,
+        Err(err) => {
+            error!("games.json is not valid JSON: {}", err);
+            return;
+        }
+    };
+
+    for (index, issue) in issues {
+        warn!("games.json entry {}: {}", index, issue);
+    }
+}
+
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 struct JsonGame<'a> {
@@ -46,6 +136,10 @@ struct JsonGame<'a> {
     mod_loader: ModLoader<'a>,
     #[serde(borrow, default)]
     platforms: Platforms<'a>,
+    #[serde(borrow, default)]
+    categories: Vec<&'a str>,
+    #[serde(borrow, default)]
+    config_defaults: Vec<ConfigDefault<'a>>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Default)]
@@ -87,6 +181,26 @@ pub struct GameData<'a> {
     pub server: bool,
     pub mod_loader: ModLoader<'a>,
     pub platforms: Platforms<'a>,
+    /// Common Thunderstore categories for this game, shown as search presets.
+    ///
+    /// Empty if the game doesn't declare any.
+    pub categories: Vec<&'a str>,
+    /// Config entries set in every new profile of this game.
+    pub config_defaults: Vec<ConfigDefault<'a>>,
+}
+
+/// A config entry that's set when a profile is created.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigDefault<'a> {
+    /// The path of the config file, relative to the profile directory.
+    pub file: &'a str,
+    /// Only used by BepInEx config files.
+    #[serde(default)]
+    pub section: Option<&'a str>,
+    pub entry: &'a str,
+    /// The value as it would be written in the file.
+    pub value: &'a str,
 }
 
 impl<'a> From<JsonGame<'a>> for GameData<'a> {
@@ -99,6 +213,8 @@ impl<'a> From<JsonGame<'a>> for GameData<'a> {
             r2_dir_name,
             mod_loader,
             platforms,
+            categories,
+            config_defaults,
         } = value;
 
         let slug = match slug {
@@ -119,10 +235,52 @@ impl<'a> From<JsonGame<'a>> for GameData<'a> {
             server,
             mod_loader,
             platforms,
+            categories,
+            config_defaults,
+        }
+    }
+}
+
+impl GameData<'_> {
+    /// Whether `category` is one of the game's declared categories.
+    ///
+    /// Games without declared categories accept every category.
+    pub fn has_category(&self, category: &str) -> bool {
+        self.categories.is_empty() || self.categories.contains(&category)
+    }
+}
+
+impl GameData<'static> {
+    pub fn metadata(&'static self) -> GameMetadata {
+        let log_path = self.mod_loader.log_path();
+
+        GameMetadata {
+            mod_loader: self.mod_loader.to_str(),
+            subdirs: self.mod_loader.subdirs().collect(),
+            deploy_dir: self.mod_loader.deploy_dir,
+            log_path: (!log_path.is_empty()).then_some(log_path),
+            config_path: self.mod_loader.config_path(),
+            platforms: self.platforms.iter().collect(),
+            server: self.server,
         }
     }
 }
 
+/// How a game is modded, for the frontend to show the right help text.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GameMetadata {
+    pub mod_loader: &'static str,
+    pub subdirs: Vec<&'static Subdir<'static>>,
+    pub deploy_dir: Option<&'static str>,
+    /// The mod loader's log file, relative to the profile directory.
+    pub log_path: Option<&'static str>,
+    /// Empty if the mod loader doesn't have a config directory.
+    pub config_path: PathBuf,
+    pub platforms: Vec<Platform>,
+    pub server: bool,
+}
+
 impl PartialEq for GameData<'_> {
     fn eq(&self, other: &Self) -> bool {
         self.slug == other.slug
@@ -183,6 +341,14 @@ pub struct XboxStore<'a> {
 pub struct ModLoader<'a> {
     #[serde(default)]
     pub package_name: Option<&'a str>,
+    /// For loaders that only load mods from a fixed directory in the game's
+    /// installation, relative to both the game and profile directories.
+    ///
+    /// The active profile's copy of this directory is deployed there. None of
+    /// the supported loaders need this yet, since they all accept the
+    /// profile's path as a launch argument.
+    #[serde(default)]
+    pub deploy_dir: Option<&'a str>,
     #[serde(flatten)]
     pub kind: ModLoaderKind<'a>,
 }
@@ -221,7 +387,7 @@ impl ModLoader<'_> {
     }
 
     /// Checks for the mod loader's own package on Thunderstore.
-    fn is_loader_package(&self, full_name: &str) -> bool {
+    pub fn is_loader_package(&self, full_name: &str) -> bool {
         if let Some(package_name) = self.package_name {
             full_name == package_name
         } else {
@@ -264,25 +430,64 @@ impl ModLoader<'_> {
     }
 }
 
+const BEPINEX_SUBDIRS: &[Subdir] = &[
+    Subdir::flat_separated("plugins", "BepInEx/plugins"),
+    Subdir::flat_separated("patchers", "BepInEx/patchers"),
+    Subdir::flat_separated("monomod", "BepInEx/monomod").extension(".mm.dll"),
+    Subdir::flat_separated("core", "BepInEx/core"),
+    Subdir::untracked("config", "BepInEx/config").mutable(),
+];
+
+const MELON_LOADER_SUBDIRS: &[Subdir] = &[
+    Subdir::tracked("UserLibs", "UserLibs").extension(".lib.dll"),
+    Subdir::tracked("Managed", "MelonLoader/Managed").extension(".managed.dll"),
+    Subdir::tracked("Mods", "Mods").extension(".dll"),
+    Subdir::separated("ModManager", "UserData/ModManager"),
+    Subdir::tracked("MelonLoader", "MelonLoader"),
+    Subdir::tracked("Libs", "MelonLoader/Libs"),
+];
+
+const NORTHSTAR_SUBDIRS: &[Subdir] = &[Subdir::tracked("mods", "R2Northstar/mods")];
+
+const SHIMLOADER_SUBDIRS: &[Subdir] = &[
+    Subdir::flat_separated("mod", "shimloader/mod"),
+    Subdir::flat_separated("pak", "shimloader/pak"),
+    Subdir::untracked("cfg", "shimloader/cfg").mutable(),
+];
+
+const RETURN_OF_MODDING_SUBDIRS: &[Subdir] = &[
+    Subdir::separated("plugins", "ReturnOfModding/plugins"),
+    Subdir::separated("plugins_data", "ReturnOfModding/plugins_data"),
+    Subdir::separated("config", "ReturnOfModding/config").mutable(),
+];
+
+const LOVELY_SUBDIRS: &[Subdir] = &[Subdir::separated("", "mods")];
+
 impl ModLoader<'static> {
+    /// The directories mods are sorted into, including those declared
+    /// by the game. Empty for loaders with their own installation logic.
+    pub fn subdirs(&'static self) -> impl Iterator<Item = &'static Subdir<'static>> {
+        let (defaults, extras): (&[Subdir], &[Subdir]) = match &self.kind {
+            ModLoaderKind::BepInEx { extra_subdirs } => (BEPINEX_SUBDIRS, extra_subdirs),
+            ModLoaderKind::MelonLoader { extra_subdirs } => (MELON_LOADER_SUBDIRS, extra_subdirs),
+            ModLoaderKind::Northstar {} => (NORTHSTAR_SUBDIRS, &[]),
+            ModLoaderKind::Shimloader {} => (SHIMLOADER_SUBDIRS, &[]),
+            ModLoaderKind::ReturnOfModding { .. } => (RETURN_OF_MODDING_SUBDIRS, &[]),
+            ModLoaderKind::Lovely {} => (LOVELY_SUBDIRS, &[]),
+            ModLoaderKind::GDWeave {} => (&[], &[]),
+        };
+
+        defaults.iter().chain(extras)
+    }
+
     pub fn installer_for(&'static self, package_name: &str) -> Box<dyn PackageInstaller> {
         match (self.is_loader_package(package_name), &self.kind) {
             (true, ModLoaderKind::BepInEx { .. }) => Box::new(BepinexInstaller),
-            (false, ModLoaderKind::BepInEx { extra_subdirs, .. }) => {
-                const SUBDIRS: &[Subdir] = &[
-                    Subdir::flat_separated("plugins", "BepInEx/plugins"),
-                    Subdir::flat_separated("patchers", "BepInEx/patchers"),
-                    Subdir::flat_separated("monomod", "BepInEx/monomod").extension(".mm.dll"),
-                    Subdir::flat_separated("core", "BepInEx/core"),
-                    Subdir::untracked("config", "BepInEx/config").mutable(),
-                ];
-
-                Box::new(
-                    SubdirInstaller::new(SUBDIRS)
-                        .with_default(0)
-                        .with_extras(extra_subdirs),
-                )
-            }
+            (false, ModLoaderKind::BepInEx { extra_subdirs, .. }) => Box::new(
+                SubdirInstaller::new(BEPINEX_SUBDIRS)
+                    .with_default(0)
+                    .with_extras(extra_subdirs),
+            ),
 
             (true, ModLoaderKind::MelonLoader { .. }) => {
                 const FILES: &[&str] = &[
@@ -297,18 +502,10 @@ impl ModLoader<'static> {
                 Box::new(ExtractInstaller::new(FILES, FlattenTopLevel::No))
             }
             (false, ModLoaderKind::MelonLoader { extra_subdirs }) => {
-                const SUBDIRS: &[Subdir] = &[
-                    Subdir::tracked("UserLibs", "UserLibs").extension(".lib.dll"),
-                    Subdir::tracked("Managed", "MelonLoader/Managed").extension(".managed.dll"),
-                    Subdir::tracked("Mods", "Mods").extension(".dll"),
-                    Subdir::separated("ModManager", "UserData/ModManager"),
-                    Subdir::tracked("MelonLoader", "MelonLoader"),
-                    Subdir::tracked("Libs", "MelonLoader/Libs"),
-                ];
                 const IGNORED: &[&str] = &["manifest.json", "icon.png", "README.md"];
 
                 Box::new(
-                    SubdirInstaller::new(SUBDIRS)
+                    SubdirInstaller::new(MELON_LOADER_SUBDIRS)
                         .with_default(2)
                         .with_extras(extra_subdirs)
                         .with_ignored_files(IGNORED),
@@ -338,34 +535,21 @@ impl ModLoader<'static> {
                 Box::new(ExtractInstaller::new(FILES, FlattenTopLevel::Yes))
             }
             (false, ModLoaderKind::Northstar {}) => {
-                const SUBDIRS: &[Subdir] = &[Subdir::tracked("mods", "R2Northstar/mods")];
                 const IGNORED: &[&str] = &["manifest.json", "icon.png", "README.md", "LICENSE"];
 
-                Box::new(SubdirInstaller::new(SUBDIRS).with_ignored_files(IGNORED))
+                Box::new(SubdirInstaller::new(NORTHSTAR_SUBDIRS).with_ignored_files(IGNORED))
             }
 
             (true, ModLoaderKind::Shimloader {}) => Box::new(ShimloaderInstaller),
             (false, ModLoaderKind::Shimloader {}) => {
-                const SUBDIRS: &[Subdir] = &[
-                    Subdir::flat_separated("mod", "shimloader/mod"),
-                    Subdir::flat_separated("pak", "shimloader/pak"),
-                    Subdir::untracked("cfg", "shimloader/cfg").mutable(),
-                ];
-
-                Box::new(SubdirInstaller::new(SUBDIRS).with_default(0))
+                Box::new(SubdirInstaller::new(SHIMLOADER_SUBDIRS).with_default(0))
             }
 
             (true, ModLoaderKind::ReturnOfModding { files }) => {
                 Box::new(ExtractInstaller::new(files, FlattenTopLevel::Yes))
             }
             (false, ModLoaderKind::ReturnOfModding { .. }) => {
-                const SUBDIRS: &[Subdir] = &[
-                    Subdir::separated("plugins", "ReturnOfModding/plugins"),
-                    Subdir::separated("plugins_data", "ReturnOfModding/plugins_data"),
-                    Subdir::separated("config", "ReturnOfModding/config").mutable(),
-                ];
-
-                Box::new(SubdirInstaller::new(SUBDIRS).with_default(0))
+                Box::new(SubdirInstaller::new(RETURN_OF_MODDING_SUBDIRS).with_default(0))
             }
 
             (true, ModLoaderKind::Lovely {}) => {
@@ -374,9 +558,7 @@ impl ModLoader<'static> {
                 Box::new(ExtractInstaller::new(FILES, FlattenTopLevel::No))
             }
             (false, ModLoaderKind::Lovely {}) => {
-                const SUBDIRS: &[Subdir] = &[Subdir::separated("", "mods")];
-
-                Box::new(SubdirInstaller::new(SUBDIRS).with_default(0))
+                Box::new(SubdirInstaller::new(LOVELY_SUBDIRS).with_default(0))
             }
         }
     }
@@ -390,3 +572,40 @@ impl ModLoader<'static> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_games_are_valid() {
+        assert_eq!(validate(GAMES_JSON).unwrap(), []);
+    }
+
+    #[test]
+    fn validation_finds_bad_entries() {
+        let json = r#"[
+            { "name": "Some Game", "modLoader": { "name": "BepInEx" }, "platforms": {} },
+            { "name": "Other", "slug": "some-game", "modLoader": { "name": "BepInEx" } },
+            { "name": "Third", "modLoader": { "name": "Unknown" }, "platforms": {} }
+        ]"#;
+
+        let issues = validate(json).unwrap();
+
+        assert_eq!(issues[0], (1, GameIssue::DuplicateSlug("some-game".into())));
+        assert_eq!(issues[1], (1, GameIssue::MissingPlatforms));
+        assert_eq!(
+            issues[2],
+            (2, GameIssue::UnknownModLoader("Unknown".into()))
+        );
+        assert!(matches!(issues[3], (2, GameIssue::Invalid(_))));
+        assert_eq!(issues.len(), 4);
+    }
+
+    #[test]
+    fn mod_loader_names_are_known() {
+        for game in all() {
+            assert!(MOD_LOADER_NAMES.contains(&game.mod_loader.to_str()));
+        }
+    }
+}
@@ -1,25 +1,28 @@
 use std::{env, time::Instant};
 
 use itertools::Itertools;
-use log::{error, info};
-use tauri::App;
+use log::{error, info, warn};
+use tauri::{App, Manager, RunEvent, WindowEvent};
 use tauri_plugin_dialog::DialogExt;
 
 #[cfg(target_os = "linux")]
 extern crate webkit2gtk;
 
+mod autosave;
 mod cli;
 mod config;
 mod db;
 mod deep_link;
 mod game;
 mod logger;
+mod onboarding;
 mod prefs;
 mod profile;
 mod state;
 mod telemetry;
 mod thunderstore;
 mod util;
+mod webhook;
 
 fn setup(app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
     let start = Instant::now();
@@ -30,6 +33,8 @@ fn setup(app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
         std::env::consts::OS,
     );
 
+    game::validate_bundled();
+
     if let Err(err) = state::setup(app.handle()) {
         error!("failed to start app: {:#}", err);
 
@@ -49,6 +54,13 @@ fn setup(app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
         deep_link::handle(app.handle(), args);
     }
 
+    let handle = app.handle().to_owned();
+    tauri::async_runtime::spawn_blocking(move || {
+        profile::install::clean_temp_dir(&handle).unwrap_or_else(|err| {
+            warn!("failed to clean temp dir: {:#}", err);
+        });
+    });
+
     let handle = app.handle().to_owned();
     tauri::async_runtime::spawn(async move { telemetry::send_app_start_event(handle).await });
 
@@ -61,73 +73,145 @@ pub fn run() {
     logger::setup().unwrap_or_else(|err| {
         eprintln!("failed to set up logger: {:#}", err);
     });
+    logger::setup_panic_hook();
 
     tauri::Builder::default()
         .invoke_handler(tauri::generate_handler![
             logger::open_gale_log,
             logger::log_err,
             state::is_first_run,
+            onboarding::get_onboarding_info,
             thunderstore::commands::query_thunderstore,
+            thunderstore::commands::get_discovery,
             thunderstore::commands::stop_querying_thunderstore,
             thunderstore::commands::set_thunderstore_token,
             thunderstore::commands::has_thunderstore_token,
             thunderstore::commands::clear_thunderstore_token,
             thunderstore::commands::trigger_mod_fetch,
+            thunderstore::commands::get_my_packages,
+            thunderstore::commands::get_communities,
+            thunderstore::commands::get_package_markdown,
+            thunderstore::commands::get_mod_readme,
+            thunderstore::commands::open_mod_page,
+            thunderstore::commands::watch_package,
+            thunderstore::commands::unwatch_package,
+            thunderstore::commands::get_watched_packages,
+            thunderstore::commands::get_download_history,
+            thunderstore::commands::get_package_aliases,
+            thunderstore::commands::set_package_alias,
+            thunderstore::commands::remove_package_alias,
+            thunderstore::commands::get_mod_share_links,
             prefs::commands::get_prefs,
             prefs::commands::set_prefs,
             prefs::commands::zoom_window,
             profile::commands::get_game_info,
+            profile::commands::get_game_metadata,
+            profile::commands::get_games_overview,
+            profile::commands::get_app_stats,
             profile::commands::favorite_game,
             profile::commands::set_active_game,
+            profile::commands::remap_game_slugs,
             profile::commands::get_profile_info,
             profile::commands::set_active_profile,
             profile::commands::is_mod_installed,
             profile::commands::query_profile,
+            profile::commands::check_profile_dependencies,
+            profile::commands::fix_profile_dependencies,
+            profile::commands::find_orphaned_mod_dirs,
+            profile::commands::remove_orphaned_mod_dirs,
             profile::commands::get_dependants,
+            profile::commands::explain_mod,
             profile::commands::create_profile,
+            profile::commands::apply_game_defaults,
+            profile::commands::start_bisect,
+            profile::commands::bisect_step,
+            profile::commands::finish_bisect,
+            profile::commands::set_loader_setting,
+            profile::commands::set_console_enabled,
             profile::commands::delete_profile,
+            profile::commands::validate_profile_name,
             profile::commands::rename_profile,
+            profile::commands::set_profile_locked,
             profile::commands::duplicate_profile,
             profile::commands::remove_mod,
+            profile::commands::remove_mods,
             profile::commands::force_remove_mods,
             profile::commands::toggle_mod,
+            profile::commands::toggle_mods,
             profile::commands::force_toggle_mods,
+            profile::commands::set_mod_side,
             profile::commands::set_all_mods_state,
             profile::commands::remove_disabled_mods,
             profile::commands::open_profile_dir,
             profile::commands::open_mod_dir,
+            profile::commands::get_mod_subdirs,
             profile::commands::open_game_log,
+            profile::commands::get_game_log,
             profile::launch::commands::launch_game,
+            profile::launch::commands::get_launch_history,
+            profile::launch::commands::check_game_update,
+            profile::launch::commands::repair_loader,
+            profile::launch::commands::get_game_dir_install_info,
+            profile::launch::commands::set_game_dir_install,
+            profile::launch::commands::get_doorstop_settings,
+            profile::launch::commands::set_doorstop_settings,
+            profile::launch::commands::get_deployment_status,
             profile::launch::commands::get_launch_args,
+            profile::launch::commands::get_launch_command,
             profile::launch::commands::open_game_dir,
             profile::install::commands::install_mod,
+            profile::install::commands::install_and_launch,
             profile::install::commands::cancel_install,
             profile::install::commands::clear_download_cache,
+            profile::install::commands::clean_temp_now,
+            profile::install::commands::clear_cached_version,
+            profile::install::commands::get_cache_size,
+            profile::install::commands::compact_cache,
+            profile::install::commands::list_cache,
             profile::install::commands::get_download_size,
+            profile::install::commands::get_install_preview,
+            profile::install::commands::get_network_stats,
+            profile::install::commands::precache_profile,
+            profile::install::commands::get_precache_info,
             profile::update::commands::change_mod_version,
             profile::update::commands::update_mods,
+            profile::update::commands::update_safe,
             profile::update::commands::ignore_update,
+            profile::update::commands::get_update_summary,
             profile::import::commands::import_data,
+            profile::import::commands::preview_import,
             profile::import::commands::import_code,
             profile::import::commands::import_file,
             profile::import::commands::import_base64,
+            profile::import::commands::import_config_bundle,
             profile::import::commands::import_local_mod,
+            profile::import::commands::install_from_url,
+            profile::import::commands::get_local_mod_dependencies,
             profile::import::commands::get_r2modman_info,
             profile::import::commands::import_r2modman,
             profile::export::commands::export_code,
             profile::export::commands::export_file,
+            profile::export::commands::export_offline_archive,
+            profile::export::commands::export_config_bundle,
             profile::export::commands::export_pack,
+            profile::export::commands::find_deprecated_pack_mods,
             profile::export::commands::upload_pack,
             profile::export::commands::get_pack_args,
             profile::export::commands::set_pack_args,
             profile::export::commands::generate_changelog,
+            profile::export::commands::generate_manifest,
             profile::export::commands::copy_dependency_strings,
+            profile::export::commands::copy_mod_list,
+            profile::export::commands::copy_collection_description,
             profile::export::commands::copy_debug_info,
             config::commands::get_config_files,
             config::commands::set_config_entry,
             config::commands::reset_config_entry,
+            config::commands::rename_config_entry,
+            config::commands::get_mod_config_files,
             config::commands::open_config_file,
             config::commands::delete_config_file,
+            config::commands::duplicate_config_file,
         ])
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_deep_link::init())
@@ -142,7 +226,24 @@ pub fn run() {
         .plugin(tauri_plugin_single_instance::init(|app, args, _| {
             deep_link::handle(app, args)
         }))
+        .register_asynchronous_uri_scheme_protocol(
+            thunderstore::icons::SCHEME,
+            |ctx, request, responder| {
+                thunderstore::icons::handle(ctx.app_handle(), request, responder)
+            },
+        )
+        .on_window_event(|window, event| {
+            if let WindowEvent::CloseRequested { .. } = event {
+                autosave::flush(window.app_handle());
+            }
+        })
         .setup(setup)
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // also covers exits that don't close the window first
+            if let RunEvent::Exit = event {
+                autosave::flush(app);
+            }
+        });
 }
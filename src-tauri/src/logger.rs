@@ -1,6 +1,9 @@
 use std::{
     fs::{self, File},
+    panic,
     path::PathBuf,
+    sync::{Mutex, PoisonError},
+    thread,
 };
 
 use eyre::{Context, OptionExt, Result};
@@ -35,6 +38,33 @@ pub fn log_webview_err(name: &str, error: eyre::Error, app: &AppHandle) {
     })
 }
 
+static LAST_PANIC: Mutex<Option<String>> = Mutex::new(None);
+
+/// Logs panics (which would otherwise only be printed to stderr) and remembers
+/// the latest one, so that it can be referred to later, for example when
+/// recovering a poisoned lock.
+pub fn setup_panic_hook() {
+    let default_hook = panic::take_hook();
+
+    panic::set_hook(Box::new(move |info| {
+        let thread = thread::current();
+        let message = format!("thread '{}' {}", thread.name().unwrap_or("<unnamed>"), info);
+
+        log::error!("{}", message);
+        *LAST_PANIC.lock().unwrap_or_else(PoisonError::into_inner) = Some(message);
+
+        default_hook(info);
+    }));
+}
+
+/// The message of the latest panic, if any has occurred.
+pub fn last_panic() -> Option<String> {
+    LAST_PANIC
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
 fn log_path() -> PathBuf {
     util::path::default_app_data_dir().join(FILE_NAME)
 }
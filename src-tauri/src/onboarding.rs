@@ -0,0 +1,48 @@
+//! Information for the first-run wizard.
+
+use std::path::PathBuf;
+
+use log::warn;
+use serde::Serialize;
+use tauri::{command, AppHandle};
+
+use crate::{
+    profile::{
+        import::{self, R2modmanInstall},
+        launch,
+    },
+    state::ManagerExt,
+    util::cmd::Result,
+};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OnboardingInfo {
+    is_first_run: bool,
+    /// The Steam executable, if it was found or has been set.
+    steam_exe_path: Option<PathBuf>,
+    /// Slugs of the supported games installed through Steam.
+    installed_games: Vec<&'static str>,
+    r2modman: Option<R2modmanInstall>,
+}
+
+#[command]
+pub fn get_onboarding_info(app: AppHandle) -> Result<OnboardingInfo> {
+    let prefs = app.lock_prefs()?;
+
+    let installed_games = launch::installed_steam_games(&prefs)
+        .unwrap_or_else(|err| {
+            warn!("failed to detect installed games: {:#}", err);
+            Vec::new()
+        })
+        .into_iter()
+        .map(|game| &*game.slug)
+        .collect();
+
+    Ok(OnboardingInfo {
+        is_first_run: app.app_state().is_first_run,
+        steam_exe_path: prefs.steam_exe_path.clone().filter(|path| path.exists()),
+        installed_games,
+        r2modman: import::find_r2modman_install(),
+    })
+}
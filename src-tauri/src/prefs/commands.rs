@@ -9,13 +9,14 @@ use crate::{
 };
 
 #[command]
-pub fn get_prefs(app: AppHandle) -> Prefs {
-    app.lock_prefs().clone()
+pub fn get_prefs(app: AppHandle) -> Result<Prefs> {
+    let prefs = app.lock_prefs()?.clone();
+    Ok(prefs)
 }
 
 #[command]
 pub fn set_prefs(value: Prefs, app: AppHandle) -> Result<()> {
-    let mut prefs = app.lock_prefs();
+    let mut prefs = app.lock_prefs()?;
     prefs.set(value, &app)?;
     Ok(())
 }
@@ -29,7 +30,7 @@ pub enum Zoom {
 
 #[command]
 pub fn zoom_window(value: Zoom, window: Window, app: AppHandle) -> Result<()> {
-    let mut prefs = app.lock_prefs();
+    let mut prefs = app.lock_prefs()?;
     prefs.zoom_factor = match value {
         Zoom::Set { factor } => factor,
         Zoom::Modify { delta } => prefs.zoom_factor + delta,
@@ -3,6 +3,7 @@ use std::{
     fs,
     ops::Deref,
     path::{Path, PathBuf},
+    time::Duration,
 };
 
 use eyre::{bail, ensure, Context, Result};
@@ -14,8 +15,13 @@ use crate::{
     db::{self, Db},
     game::{self, Platform},
     logger,
-    profile::launch::LaunchMode,
+    profile::{
+        install::DisableStrategy,
+        launch::LaunchMode,
+        name::{self, DirNaming},
+    },
     state::ManagerExt,
+    thunderstore,
     util::{
         self,
         error::IoResultExt,
@@ -186,8 +192,46 @@ pub struct Prefs {
 
     pub send_telemetry: bool,
     pub fetch_mods_automatically: bool,
+    pub enable_mod_cache: bool,
+    /// The size in bytes the mod cache may grow to before the least recently
+    /// used versions are removed. Zero means there's no limit.
+    pub max_cache_size: u64,
+    /// The number of bytes that may be downloaded in a calendar month before
+    /// installs ask for confirmation. Zero means there's no cap.
+    pub monthly_download_cap: u64,
+    /// How old leftovers in the temp directory must be to be removed
+    /// when the app starts. Zero turns the cleanup off.
+    pub temp_cleanup_age_hours: u64,
+    /// Read-only directories with the extracted archives of packages,
+    /// laid out as `<full_name>/<version>` like r2modman's cache.
+    /// These are checked before downloading a mod.
+    pub extra_cache_dirs: Vec<PathBuf>,
     pub zoom_factor: f32,
 
+    /// How long to wait for a connection to be established.
+    pub connect_timeout_secs: u64,
+    /// How long a request may go without receiving any data.
+    pub request_timeout_secs: u64,
+    /// How long downloads of mods and the package list may take in total.
+    pub download_timeout_secs: u64,
+    /// Only use HTTP/1.1, instead of negotiating the protocol with the server.
+    ///
+    /// HTTP/2 is used by default where available, but some proxies
+    /// handle it poorly, in which case this can be turned on.
+    pub force_http1: bool,
+
+    /// How the files of disabled mods are kept away from the mod loader.
+    pub disable_strategy: DisableStrategy,
+    /// How the directories of profiles are named.
+    pub profile_dir_naming: DirNaming,
+
+    /// How often to record the download counts of watched packages.
+    pub download_sample_interval_hours: u64,
+
+    /// Where to fetch a list of packages flagged by the community from,
+    /// see [`crate::thunderstore::blocklist`]. Not fetched if unset.
+    pub blocklist_url: Option<String>,
+
     pub game_prefs: HashMap<String, GamePrefs>,
 }
 
@@ -198,6 +242,28 @@ pub struct GamePrefs {
     pub custom_args: Option<Vec<String>>,
     pub launch_mode: LaunchMode,
     pub platform: Option<Platform>,
+    /// Where to send notifications about completed operations, see [`crate::webhook`].
+    pub webhook: Option<WebhookPrefs>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default, rename_all = "camelCase")]
+pub struct WebhookPrefs {
+    pub url: String,
+    pub on_install: bool,
+    pub on_update: bool,
+    pub on_pack_upload: bool,
+}
+
+impl Default for WebhookPrefs {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            on_install: true,
+            on_update: true,
+            on_pack_upload: true,
+        }
+    }
 }
 
 #[cfg(target_os = "windows")]
@@ -251,9 +317,26 @@ impl Default for Prefs {
 
             send_telemetry: true,
             fetch_mods_automatically: true,
+            enable_mod_cache: true,
+            max_cache_size: 0,
+            monthly_download_cap: 0,
+            temp_cleanup_age_hours: 24,
+            extra_cache_dirs: Vec::new(),
 
             zoom_factor: 1.0,
 
+            connect_timeout_secs: 15,
+            request_timeout_secs: 60,
+            download_timeout_secs: 30 * 60,
+            force_http1: false,
+
+            disable_strategy: DisableStrategy::default(),
+            profile_dir_naming: DirNaming::default(),
+
+            download_sample_interval_hours: 24,
+
+            blocklist_url: None,
+
             game_prefs: HashMap::new(),
         }
     }
@@ -271,16 +354,30 @@ impl Prefs {
         let window = app.get_webview_window("main").unwrap();
         window.zoom(self.zoom_factor as f64).ok();
 
+        name::set_dir_naming(self.profile_dir_naming);
+
         self.save(db)?;
 
         Ok(())
     }
 
-    fn save(&self, db: &Db) -> Result<()> {
+    pub fn save(&self, db: &Db) -> Result<()> {
         db.save_prefs(self)
     }
 
     fn set(&mut self, value: Self, app: &AppHandle) -> Result<()> {
+        ensure!(
+            value.connect_timeout_secs > 0
+                && value.request_timeout_secs > 0
+                && value.download_timeout_secs > 0,
+            "timeouts must be greater than zero"
+        );
+
+        ensure!(
+            value.download_sample_interval_hours > 0,
+            "download sample interval must be greater than zero"
+        );
+
         // prevent the user from setting the steam exe to the game's exe, for example
         let is_valid_steam_exe = value.steam_exe_path.as_ref().is_some_and(|path| {
             path.file_name()
@@ -300,7 +397,7 @@ impl Prefs {
 
         if self.data_dir != value.data_dir {
             // move profile paths
-            let mut manager = app.lock_manager();
+            let mut manager = app.lock_manager()?;
 
             let mut path = value.data_dir.to_path_buf();
             for (key, game) in &mut manager.games {
@@ -311,7 +408,9 @@ impl Prefs {
                 path.push("profiles");
 
                 for profile in &mut game.profiles {
-                    profile.path = path.join(&profile.name);
+                    // the directory keeps its name, whichever naming it follows
+                    let dir_name = profile.path.file_name().unwrap().to_owned();
+                    profile.path = path.join(dir_name);
                 }
 
                 path.pop();
@@ -333,6 +432,50 @@ impl Prefs {
 
         self.send_telemetry = value.send_telemetry;
         self.fetch_mods_automatically = value.fetch_mods_automatically;
+        self.enable_mod_cache = value.enable_mod_cache;
+        self.max_cache_size = value.max_cache_size;
+        self.monthly_download_cap = value.monthly_download_cap;
+        self.temp_cleanup_age_hours = value.temp_cleanup_age_hours;
+        self.extra_cache_dirs = value.extra_cache_dirs;
+
+        let client_changed = self.connect_timeout_secs != value.connect_timeout_secs
+            || self.request_timeout_secs != value.request_timeout_secs
+            || self.force_http1 != value.force_http1;
+
+        self.connect_timeout_secs = value.connect_timeout_secs;
+        self.request_timeout_secs = value.request_timeout_secs;
+        self.download_timeout_secs = value.download_timeout_secs;
+        self.force_http1 = value.force_http1;
+        self.download_sample_interval_hours = value.download_sample_interval_hours;
+
+        if let Some(url) = &value.blocklist_url {
+            let is_valid_url =
+                reqwest::Url::parse(url).is_ok_and(|url| matches!(url.scheme(), "http" | "https"));
+
+            ensure!(is_valid_url, "The blocklist URL must be an http(s) URL.");
+        }
+
+        self.blocklist_url = value.blocklist_url;
+
+        // mods are enabled the same way regardless, so this can
+        // be changed while mods are disabled
+        self.disable_strategy = value.disable_strategy;
+
+        if self.profile_dir_naming != value.profile_dir_naming {
+            self.profile_dir_naming = value.profile_dir_naming;
+            name::set_dir_naming(self.profile_dir_naming);
+
+            let mut manager = app.lock_manager()?;
+            for game in manager.games.values_mut() {
+                game.apply_dir_naming(app.db());
+            }
+        }
+
+        if client_changed {
+            app.app_state()
+                .rebuild_http(self)
+                .context("failed to apply network settings")?;
+        }
 
         self.save(app.db()).context("failed save prefs")
     }
@@ -369,6 +512,16 @@ impl Prefs {
                     slug
                 );
             }
+
+            if let Some(webhook) = &value.webhook {
+                let is_valid_url = reqwest::Url::parse(&webhook.url)
+                    .is_ok_and(|url| matches!(url.scheme(), "http" | "https"));
+
+                if !is_valid_url {
+                    value.webhook = None;
+                    bail!("Webhook URL for {} must be an http(s) URL.", slug);
+                }
+            }
         }
 
         Ok(())
@@ -378,6 +531,21 @@ impl Prefs {
         self.data_dir.join("cache")
     }
 
+    /// Where mod icons are cached, see [`crate::thunderstore::icons`].
+    pub fn icon_cache_dir(&self) -> PathBuf {
+        self.cache_dir().join(thunderstore::icons::DIR_NAME)
+    }
+
+    /// Scratch space for extraction and staging.
+    ///
+    /// Operations should use their own [`TempDir`](crate::util::temp::TempDir) in here.
+    ///
+    /// This is kept inside of the data directory (instead of using the OS temp dir)
+    /// so that files can be hard linked into profiles.
+    pub fn temp_dir(&self) -> PathBuf {
+        self.data_dir.join("temp")
+    }
+
     pub fn fetch_mods_automatically(&self) -> bool {
         self.fetch_mods_automatically
     }
@@ -385,4 +553,53 @@ impl Prefs {
     pub fn send_telemetry(&self) -> bool {
         self.send_telemetry
     }
+
+    pub fn enable_mod_cache(&self) -> bool {
+        self.enable_mod_cache
+    }
+
+    pub fn max_cache_size(&self) -> Option<u64> {
+        match self.max_cache_size {
+            0 => None,
+            size => Some(size),
+        }
+    }
+
+    pub fn monthly_download_cap(&self) -> Option<u64> {
+        match self.monthly_download_cap {
+            0 => None,
+            cap => Some(cap),
+        }
+    }
+
+    pub fn temp_cleanup_age(&self) -> Option<Duration> {
+        match self.temp_cleanup_age_hours {
+            0 => None,
+            hours => Some(Duration::from_secs(hours * 3600)),
+        }
+    }
+
+    pub fn extra_cache_dirs(&self) -> &[PathBuf] {
+        &self.extra_cache_dirs
+    }
+
+    pub fn connect_timeout(&self) -> Duration {
+        Duration::from_secs(self.connect_timeout_secs)
+    }
+
+    pub fn request_timeout(&self) -> Duration {
+        Duration::from_secs(self.request_timeout_secs)
+    }
+
+    pub fn download_timeout(&self) -> Duration {
+        Duration::from_secs(self.download_timeout_secs)
+    }
+
+    pub fn force_http1(&self) -> bool {
+        self.force_http1
+    }
+
+    pub fn download_sample_interval(&self) -> Duration {
+        Duration::from_secs(self.download_sample_interval_hours * 60 * 60)
+    }
 }
@@ -1,12 +1,12 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::HashSet,
     fs,
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
-use eyre::{anyhow, ensure, Context, OptionExt, Result};
+use eyre::{anyhow, ensure, Context, Result};
 use itertools::Itertools;
-use log::info;
+use log::{info, warn};
 use serde::{Deserialize, Serialize};
 use tauri::{AppHandle, Listener};
 use uuid::Uuid;
@@ -14,17 +14,18 @@ use uuid::Uuid;
 use super::{
     export::{self, IncludeExtensions, IncludeGenerated},
     import,
-    install::PackageInstaller,
+    install::{DisableStrategy, PackageInstaller, SubdirFiles},
+    name::{self, DirNaming},
     Dependant, ManagedGame, Profile, ProfileMod,
 };
 use crate::{
-    config::ConfigCache,
     db::Db,
     logger,
     state::ManagerExt,
     thunderstore::Thunderstore,
     util::{
         self,
+        cmd::UserError,
         error::IoResultExt,
         fs::{Overwrite, UseLinks},
     },
@@ -52,47 +53,263 @@ pub fn setup(app: &AppHandle) -> Result<()> {
 #[serde(rename_all = "camelCase", tag = "type")]
 pub enum ActionResult {
     Done,
-    Confirm { dependants: Vec<Dependant> },
+    #[serde(rename_all = "camelCase")]
+    Confirm {
+        dependants: Vec<Dependant>,
+        /// Mods that are only used by the modpack being removed,
+        /// which the user can choose to remove along with it.
+        orphans: Option<Vec<RemovalCandidate>>,
+    },
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemovalCandidate {
+    #[serde(flatten)]
+    pub dependant: Dependant,
+    /// The download size of the installed version, in bytes.
+    pub size: u64,
+}
+
+impl RemovalCandidate {
+    pub(super) fn new(profile_mod: &ProfileMod, thunderstore: &Thunderstore) -> Self {
+        let size = profile_mod
+            .as_thunderstore()
+            .and_then(|(ts_mod, _)| ts_mod.id.borrow(thunderstore).ok())
+            .map(|borrowed| borrowed.version.file_size)
+            .unwrap_or_default();
+
+        Self {
+            dependant: profile_mod.into(),
+            size,
+        }
+    }
+}
+
+/// The outcome of a batch operation which carries on past individual failures.
+#[derive(Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchReport {
+    /// The mods that the operation applies to in the end.
+    pub succeeded: Vec<Uuid>,
+    pub failed: Vec<BatchFailure>,
+    /// Whether the successful changes were undone because of a failure.
+    pub reverted: bool,
+    /// Whether a strict batch went through only in part, because the
+    /// successful changes couldn't be undone. See [`Profile::try_remove_mods`].
+    pub partial: bool,
+}
+
+/// The outcome of [`Profile::toggle_all_mods`].
+#[derive(Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ToggleAllReport {
+    pub changed: Vec<Uuid>,
+    /// Mods left as they were to keep the profile consistent, like the
+    /// mod loader or the dependencies of mods that failed to be disabled.
+    pub skipped: Vec<Uuid>,
+    pub failed: Vec<BatchFailure>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchFailure {
+    pub uuid: Uuid,
+    pub name: String,
+    pub reason: String,
+}
+
+impl BatchFailure {
+    fn new(uuid: Uuid, profile: &Profile, err: eyre::Error) -> Self {
+        let name = match profile.get_mod(uuid) {
+            Ok(profile_mod) => profile_mod.full_name().into_owned(),
+            Err(_) => uuid.to_string(),
+        };
+
+        Self {
+            uuid,
+            name,
+            reason: format!("{:#}", err),
+        }
+    }
+}
+
+/// Renames a directory, going through a temporary name if only the case changes.
+///
+/// Fails with [`UserError::FilesInUse`] if another program has files in it open.
+fn rename_dir(from: &Path, to: &Path, case_only: bool) -> Result<()> {
+    let result = match case_only {
+        true => {
+            let mut temp = from.as_os_str().to_owned();
+            temp.push(".gale-rename");
+            let temp = PathBuf::from(temp);
+
+            fs::rename(from, &temp).and_then(|()| {
+                fs::rename(&temp, to).inspect_err(|_| {
+                    fs::rename(&temp, from).ok();
+                })
+            })
+        }
+        false => fs::rename(from, to),
+    };
+
+    match result {
+        Ok(()) => Ok(()),
+        Err(err) if util::fs::is_in_use(&err) => Err(UserError::FilesInUse {
+            path: from.display().to_string(),
+            processes: util::fs::processes_using(from),
+        }
+        .into()),
+        Err(err) => Err(err).fs_context("renaming profile directory", from),
+    }
 }
 
 impl Profile {
-    pub fn rename(&mut self, name: String) -> Result<()> {
-        ensure!(
-            Self::is_valid_name(&name),
-            "invalid profile name '{}'",
-            name
-        );
+    /// Renames the profile and saves it to `db`.
+    ///
+    /// With [`DirNaming::Name`], the directory is renamed as well.
+    pub fn rename(&mut self, name: String, db: &Db) -> Result<()> {
+        name::ensure_valid(&name)?;
+
+        if name == self.name {
+            return Ok(());
+        }
 
-        let new_path = self.path.parent().unwrap().join(&name);
+        let old_name = std::mem::replace(&mut self.name, name);
 
-        ensure!(
-            !new_path.exists(),
-            "profile with name '{}' already exists",
-            name
-        );
+        let result = match name::dir_naming() {
+            DirNaming::Id => self
+                .save(db)
+                .map_err(|err| err.wrap_err("failed to save renamed profile")),
+            DirNaming::Name => {
+                let new_path = self.path.parent().unwrap().join(&self.name);
+                self.move_dir(new_path, db)
+            }
+        };
+
+        if let Err(err) = result {
+            self.name = old_name;
+            return Err(err);
+        }
+
+        info!("renamed profile: {} -> {}", old_name, self.name);
+
+        Ok(())
+    }
+
+    /// Moves the profile's directory to `new_path` and saves the profile to `db`.
+    ///
+    /// If the profile can't be saved, the directory is moved back.
+    fn move_dir(&mut self, new_path: PathBuf, db: &Db) -> Result<()> {
+        let parent = new_path.parent().unwrap();
+        let new_name = new_path.file_name().unwrap();
+
+        // on case-insensitive file systems, the new path "exists" if only the case
+        // differs, so check for a directory with exactly the new name instead
+        let case_only = self.path.parent() == Some(parent)
+            && self.path.file_name().is_some_and(|old_name| {
+                old_name.to_string_lossy().to_lowercase()
+                    == new_name.to_string_lossy().to_lowercase()
+            });
+
+        let exists = match case_only {
+            true => parent
+                .read_dir()
+                .fs_context("reading profiles directory", parent)?
+                .filter_map(Result::ok)
+                .any(|entry| entry.file_name() == new_name),
+            false => new_path.exists(),
+        };
+
+        ensure!(!exists, "{} already exists", new_path.display());
+
+        rename_dir(&self.path, &new_path, case_only)?;
 
-        fs::rename(&self.path, &new_path).fs_context("renaming profile directory", &self.path)?;
+        let old_path = std::mem::replace(&mut self.path, new_path);
 
-        info!("renamed profile: {} -> {}", self.name, name);
+        if let Err(err) = self.save(db) {
+            if let Err(err) = rename_dir(&self.path, &old_path, case_only) {
+                warn!("failed to roll back move of profile directory: {:#}", err);
+            }
+
+            self.path = old_path;
 
-        self.name = name;
-        self.path = new_path;
+            return Err(err.wrap_err("failed to save moved profile"));
+        }
 
         Ok(())
     }
 
     pub fn remove_mod(&mut self, uuid: Uuid, thunderstore: &Thunderstore) -> Result<ActionResult> {
-        if self.get_mod(uuid)?.enabled {
-            if let Some(dependants) = self.check_dependants(uuid, thunderstore) {
-                return Ok(ActionResult::Confirm { dependants });
-            }
+        self.ensure_unlocked()?;
+
+        let dependants = match self.get_mod(uuid)?.enabled {
+            true => self.check_dependants(uuid, thunderstore),
+            false => None,
+        };
+
+        let orphans = self.orphaned_dependencies(uuid, thunderstore);
+
+        if dependants.is_some() || orphans.is_some() {
+            return Ok(ActionResult::Confirm {
+                dependants: dependants.unwrap_or_default(),
+                orphans,
+            });
         }
 
         self.force_remove_mod(uuid)?;
         Ok(ActionResult::Done)
     }
 
+    /// Removes several mods at once, asking for confirmation if any other mods depend on them.
+    ///
+    /// Dependants that are part of `uuids` are ignored, so removing
+    /// a group of mods that depend on each other doesn't need confirmation.
+    pub fn remove_mods(
+        &mut self,
+        uuids: &[Uuid],
+        thunderstore: &Thunderstore,
+    ) -> Result<ActionResult> {
+        self.ensure_unlocked()?;
+
+        let removed = uuids.iter().copied().collect::<HashSet<_>>();
+
+        let mut dependants = Vec::new();
+        for &uuid in uuids {
+            if !self.get_mod(uuid)?.enabled {
+                continue;
+            }
+
+            dependants.extend(
+                self.check_dependants(uuid, thunderstore)
+                    .into_iter()
+                    .flatten()
+                    .filter(|dependant| !removed.contains(&dependant.uuid)),
+            );
+        }
+
+        if !dependants.is_empty() {
+            let dependants = dependants
+                .into_iter()
+                .unique_by(|dependant| dependant.uuid)
+                .collect();
+
+            return Ok(ActionResult::Confirm {
+                dependants,
+                orphans: None,
+            });
+        }
+
+        for &uuid in uuids {
+            self.force_remove_mod(uuid)?;
+        }
+
+        Ok(ActionResult::Done)
+    }
+
     pub fn force_remove_mod(&mut self, uuid: Uuid) -> Result<()> {
+        self.ensure_unlocked()?;
+
         let index = self.index_of(uuid)?;
         let profile_mod = &self.mods[index];
 
@@ -104,33 +321,283 @@ impl Profile {
         Ok(())
     }
 
-    pub fn toggle_mod(&mut self, uuid: Uuid, thunderstore: &Thunderstore) -> Result<ActionResult> {
+    pub fn toggle_mod(
+        &mut self,
+        uuid: Uuid,
+        strategy: DisableStrategy,
+        thunderstore: &Thunderstore,
+    ) -> Result<ActionResult> {
+        self.ensure_unlocked()?;
+
         let dependants = match self.get_mod(uuid)?.enabled {
             true => self.check_dependants(uuid, thunderstore),
             false => self.check_dependencies(uuid, thunderstore),
         };
 
         match dependants {
-            Some(dependants) => Ok(ActionResult::Confirm { dependants }),
+            Some(dependants) => Ok(ActionResult::Confirm {
+                dependants,
+                orphans: None,
+            }),
             None => {
-                self.force_toggle_mod(uuid)?;
+                self.force_toggle_mod(uuid, strategy)?;
                 Ok(ActionResult::Done)
             }
         }
     }
 
-    pub fn force_toggle_mod(&mut self, uuid: Uuid) -> Result<()> {
+    /// Toggles a mod without checking its dependencies or dependants.
+    ///
+    /// `strategy` is only used when disabling, see [`Prefs::disable_strategy`].
+    ///
+    /// [`Prefs::disable_strategy`]: crate::prefs::Prefs::disable_strategy
+    pub fn force_toggle_mod(&mut self, uuid: Uuid, strategy: DisableStrategy) -> Result<()> {
+        self.ensure_unlocked()?;
+
         let profile_mod = self.get_mod(uuid)?;
         let enabled = profile_mod.enabled;
 
         self.installer_for(profile_mod)
-            .toggle(enabled, profile_mod, self)?;
+            .toggle(enabled, strategy, profile_mod, self)?;
 
         self.get_mod_mut(uuid).unwrap().enabled = !enabled;
 
         Ok(())
     }
 
+    /// Enables or disables several mods at once.
+    ///
+    /// When enabling, asks for confirmation if any of the mods have disabled dependencies.
+    /// When disabling, asks for confirmation if any enabled mods depend on them.
+    /// Mods that are part of `uuids` are ignored in both cases.
+    pub fn toggle_mods(
+        &mut self,
+        uuids: &[Uuid],
+        enabled: bool,
+        strategy: DisableStrategy,
+        thunderstore: &Thunderstore,
+    ) -> Result<ActionResult> {
+        self.ensure_unlocked()?;
+
+        let mut targets = Vec::new();
+        for &uuid in uuids {
+            if self.get_mod(uuid)?.enabled != enabled {
+                targets.push(uuid);
+            }
+        }
+
+        let included = targets.iter().copied().collect::<HashSet<_>>();
+
+        let dependants = targets
+            .iter()
+            .flat_map(|&uuid| match enabled {
+                true => self.check_dependencies(uuid, thunderstore),
+                false => self.check_dependants(uuid, thunderstore),
+            })
+            .flatten()
+            .filter(|dependant| !included.contains(&dependant.uuid))
+            .unique_by(|dependant| dependant.uuid)
+            .collect_vec();
+
+        if !dependants.is_empty() {
+            return Ok(ActionResult::Confirm {
+                dependants,
+                orphans: None,
+            });
+        }
+
+        self.force_toggle_mods(&targets, strategy)?;
+        Ok(ActionResult::Done)
+    }
+
+    /// Toggles all of `uuids`, or none of them if any toggle fails.
+    pub fn force_toggle_mods(&mut self, uuids: &[Uuid], strategy: DisableStrategy) -> Result<()> {
+        for (i, &uuid) in uuids.iter().enumerate() {
+            if let Err(err) = self.force_toggle_mod(uuid, strategy) {
+                for &toggled in uuids[..i].iter().rev() {
+                    if let Err(err) = self.force_toggle_mod(toggled, strategy) {
+                        warn!("failed to revert toggle of {}: {:#}", toggled, err);
+                    }
+                }
+
+                return Err(err);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Toggles each of `uuids`, carrying on if some of them fail.
+    ///
+    /// If `strict` is set and anything fails, the successful toggles are undone.
+    /// `on_progress` is called with the index of each mod before it's toggled.
+    pub fn try_toggle_mods(
+        &mut self,
+        uuids: &[Uuid],
+        strict: bool,
+        strategy: DisableStrategy,
+        mut on_progress: impl FnMut(usize),
+    ) -> Result<BatchReport> {
+        self.ensure_unlocked()?;
+
+        let mut report = BatchReport::default();
+
+        for (i, &uuid) in uuids.iter().enumerate() {
+            on_progress(i);
+
+            match self.force_toggle_mod(uuid, strategy) {
+                Ok(()) => report.succeeded.push(uuid),
+                Err(err) => report.failed.push(BatchFailure::new(uuid, self, err)),
+            }
+        }
+
+        if strict && !report.failed.is_empty() {
+            for toggled in report.succeeded.drain(..).rev() {
+                if let Err(err) = self.force_toggle_mod(toggled, strategy) {
+                    warn!("failed to revert toggle of {}: {:#}", toggled, err);
+                }
+            }
+
+            report.reverted = true;
+        }
+
+        Ok(report)
+    }
+
+    /// Enables or disables every mod, carrying on past mods that fail.
+    ///
+    /// Dependencies are enabled before and disabled after their dependants. Mods
+    /// that depend on one which couldn't be enabled are skipped, as are the
+    /// dependencies of mods that couldn't be disabled. The mod loader package
+    /// is kept enabled unless `include_loader` is set.
+    pub fn toggle_all_mods(
+        &mut self,
+        enable: bool,
+        include_loader: bool,
+        strategy: DisableStrategy,
+        thunderstore: &Thunderstore,
+    ) -> Result<ToggleAllReport> {
+        self.ensure_unlocked()?;
+
+        let mod_loader = &self.game.mod_loader;
+
+        // a mod has more dependencies than any of its dependencies,
+        // so sorting by their count puts dependencies first
+        let mut targets = self
+            .mods
+            .iter()
+            .filter(|profile_mod| profile_mod.enabled != enable)
+            .map(|profile_mod| {
+                let is_loader = mod_loader.is_loader_package(&profile_mod.full_name());
+                let deps = profile_mod
+                    .dependencies(thunderstore)
+                    .map(|dep| dep.package.uuid)
+                    .collect::<HashSet<_>>();
+
+                (profile_mod.uuid(), is_loader, deps)
+            })
+            .sorted_by_key(|(_, _, deps)| deps.len())
+            .collect_vec();
+
+        if !enable {
+            targets.reverse();
+        }
+
+        let mut report = ToggleAllReport::default();
+        // when enabling, the mods that stayed disabled;
+        // when disabling, the mods that must stay enabled
+        let mut blocked = HashSet::new();
+
+        for (uuid, is_loader, deps) in targets {
+            let skip = match enable {
+                true => deps.iter().any(|dep| blocked.contains(dep)),
+                false => blocked.contains(&uuid) || (is_loader && !include_loader),
+            };
+
+            let changed = !skip
+                && match self.force_toggle_mod(uuid, strategy) {
+                    Ok(()) => true,
+                    Err(err) => {
+                        report.failed.push(BatchFailure::new(uuid, self, err));
+                        false
+                    }
+                };
+
+            if changed {
+                report.changed.push(uuid);
+                continue;
+            }
+
+            if skip {
+                report.skipped.push(uuid);
+            }
+
+            match enable {
+                true => {
+                    blocked.insert(uuid);
+                }
+                false => blocked.extend(deps),
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Removes each of `uuids`, carrying on if some of them fail.
+    ///
+    /// Removed files can't be brought back, so with `strict` the mods are toggled first,
+    /// which fails on the same files in use. If any toggle fails, they're toggled back and
+    /// nothing is removed. Otherwise the mods are removed, now that nothing is holding them.
+    ///
+    /// A removal can still fail after that, for example if a file is opened in between.
+    /// The mods removed before it stay removed, which is reported with
+    /// [`BatchReport::partial`].
+    ///
+    /// `on_progress` is called with the index of each mod before it's removed.
+    pub fn try_remove_mods(
+        &mut self,
+        uuids: &[Uuid],
+        strict: bool,
+        mut on_progress: impl FnMut(usize),
+    ) -> Result<BatchReport> {
+        self.ensure_unlocked()?;
+
+        if strict {
+            // the probe is undone or followed by removal, so the strategy doesn't matter
+            let probe = self.try_toggle_mods(uuids, true, DisableStrategy::Rename, |_| ())?;
+
+            if probe.reverted {
+                return Ok(probe);
+            }
+        }
+
+        let mut report = BatchReport::default();
+
+        for (i, &uuid) in uuids.iter().enumerate() {
+            on_progress(i);
+
+            match self.force_remove_mod(uuid) {
+                Ok(()) => report.succeeded.push(uuid),
+                Err(err) => {
+                    let failure = BatchFailure::new(uuid, self, err);
+
+                    // put the mod back the way it was before the probe
+                    if strict {
+                        if let Err(err) = self.force_toggle_mod(uuid, DisableStrategy::Rename) {
+                            warn!("failed to revert toggle of {}: {:#}", uuid, err);
+                        }
+                    }
+
+                    report.failed.push(failure);
+                }
+            }
+        }
+
+        report.partial = strict && !report.failed.is_empty() && !report.succeeded.is_empty();
+
+        Ok(report)
+    }
+
     fn check_dependants(&self, uuid: Uuid, thunderstore: &Thunderstore) -> Option<Vec<Dependant>> {
         let dependants = self
             .dependants(uuid, thunderstore)
@@ -157,6 +624,63 @@ impl Profile {
         }
     }
 
+    /// Finds the dependencies of a modpack that nothing else
+    /// in the profile would use if it was removed.
+    ///
+    /// Returns `None` if the mod is not a modpack.
+    fn orphaned_dependencies(
+        &self,
+        uuid: Uuid,
+        thunderstore: &Thunderstore,
+    ) -> Option<Vec<RemovalCandidate>> {
+        let profile_mod = self.get_mod(uuid).ok()?;
+        let (ts_mod, _) = profile_mod.as_thunderstore()?;
+
+        if !ts_mod.id.borrow(thunderstore).ok()?.package.is_modpack() {
+            return None;
+        }
+
+        let dependants = self.direct_dependants(thunderstore);
+        let candidates = profile_mod
+            .dependencies(thunderstore)
+            .filter_map(|dep| self.get_mod(dep.package.uuid).ok())
+            .collect_vec();
+
+        // a dependency is orphaned once all of its dependants are being removed,
+        // which in turn might orphan its own dependencies
+        let mut removed = HashSet::from([uuid]);
+        loop {
+            let prev_len = removed.len();
+
+            for candidate in &candidates {
+                let unused = dependants
+                    .get(&candidate.uuid())
+                    .into_iter()
+                    .flatten()
+                    .all(|dependant| removed.contains(&dependant.uuid()));
+
+                if unused {
+                    removed.insert(candidate.uuid());
+                }
+            }
+
+            if removed.len() == prev_len {
+                break;
+            }
+        }
+
+        let orphans = candidates
+            .into_iter()
+            .filter(|candidate| removed.contains(&candidate.uuid()))
+            .map(|candidate| RemovalCandidate::new(candidate, thunderstore))
+            .collect_vec();
+
+        match orphans.is_empty() {
+            true => None,
+            false => Some(orphans),
+        }
+    }
+
     /// Finds disabled dependencies in the profile.
     fn check_dependencies(
         &self,
@@ -194,16 +718,28 @@ impl Profile {
         }
     }
 
+    /// Lists where the files of a mod were installed, to help debug
+    /// how its package was laid out.
+    pub fn mod_subdirs(&self, uuid: Uuid) -> Result<Vec<SubdirFiles>> {
+        let profile_mod = self.get_mod(uuid)?;
+
+        Ok(self
+            .installer_for(profile_mod)
+            .subdir_files(profile_mod, self))
+    }
+
     fn installer_for(&self, profile_mod: &ProfileMod) -> Box<dyn PackageInstaller> {
         self.game.mod_loader.installer_for(&profile_mod.full_name())
     }
 
     fn reorder_mod(&mut self, uuid: Uuid, delta: i32) -> Result<()> {
+        self.ensure_unlocked()?;
+
         let index = self
             .mods
             .iter()
             .position(|m| m.uuid() == uuid)
-            .ok_or_eyre("mod not found in profile")?;
+            .ok_or(UserError::ModNotFound)?;
 
         let target = (index as i32 + delta).clamp(0, self.mods.len() as i32 - 1) as usize;
         let profile_mod = self.mods.remove(index);
@@ -223,14 +759,14 @@ fn handle_reorder_event(event: tauri::Event, app: &AppHandle) -> Result<()> {
 
     let Payload { uuid, delta } = serde_json::from_str(event.payload())?;
 
-    let mut manager = app.lock_manager();
+    let mut manager = app.lock_manager()?;
     manager.active_profile_mut().reorder_mod(uuid, delta)?;
 
     Ok(())
 }
 
 fn handle_finish_reorder_event(app: &AppHandle) -> Result<()> {
-    app.lock_manager().save_active_profile(app.db())
+    app.lock_manager()?.save_active_profile(app.db())
 }
 
 impl ManagedGame {
@@ -240,17 +776,9 @@ impl ManagedGame {
         override_path: Option<PathBuf>,
         db: &Db,
     ) -> Result<&mut Profile> {
-        ensure!(
-            Profile::is_valid_name(&name),
-            "profile name '{}' is invalid",
-            name
-        );
+        self.check_profile_name(&name, None)?;
 
-        ensure!(
-            !self.profiles.iter().any(|profile| profile.name == name),
-            "profile with name {} already exists",
-            name
-        );
+        let id = db.next_profile_id()?;
 
         let path = match override_path {
             Some(path) => {
@@ -263,7 +791,7 @@ impl ManagedGame {
             }
             None => {
                 let mut path = self.path.join("profiles");
-                path.push(&name);
+                path.push(name::dir_name(id, &name));
 
                 ensure!(
                     !path.exists(),
@@ -277,19 +805,7 @@ impl ManagedGame {
 
         fs::create_dir_all(&path).fs_context("creating profile directory", &path)?;
 
-        let id = db.next_profile_id()?;
-
-        self.profiles.push(Profile {
-            id,
-            name,
-            path,
-            mods: Vec::new(),
-            game: self.game,
-            ignored_updates: HashSet::new(),
-            config_cache: ConfigCache::default(),
-            linked_config: HashMap::new(),
-            modpack: None,
-        });
+        self.profiles.push(Profile::new(id, name, path, self.game));
 
         self.active_profile_id = id;
         Ok(self.active_profile_mut())
@@ -304,7 +820,17 @@ impl ManagedGame {
         let profile = self.profile_at(index)?;
         let id = profile.id;
 
-        fs::remove_dir_all(&profile.path)?;
+        match fs::remove_dir_all(&profile.path) {
+            Ok(()) => (),
+            Err(err) if util::fs::is_in_use(&err) => {
+                return Err(UserError::FilesInUse {
+                    path: profile.path.display().to_string(),
+                    processes: util::fs::processes_using(&profile.path),
+                }
+                .into())
+            }
+            Err(err) => return Err(err).fs_context("deleting profile directory", &profile.path),
+        }
         self.profiles.remove(index);
 
         if !self.profiles.is_empty() {
@@ -316,6 +842,34 @@ impl ManagedGame {
         Ok(())
     }
 
+    /// Moves the directories of profiles to match [`name::dir_naming`].
+    ///
+    /// Profiles outside of the game's profiles directory are left alone, since
+    /// the user picked their location. Profiles that can't be moved, for example
+    /// because their files are in use, are tried again the next time.
+    pub fn apply_dir_naming(&mut self, db: &Db) {
+        let parent = self.path.join("profiles");
+
+        for profile in &mut self.profiles {
+            if profile.path.parent() != Some(&parent) {
+                continue;
+            }
+
+            let dir_name = name::dir_name(profile.id, &profile.name);
+            if profile.path.file_name() == Some(dir_name.as_ref()) {
+                continue;
+            }
+
+            match profile.move_dir(parent.join(&dir_name), db) {
+                Ok(()) => info!("moved profile {} to {}", profile.name, dir_name),
+                Err(err) => warn!(
+                    "failed to move directory of profile {}: {:#}",
+                    profile.name, err
+                ),
+            }
+        }
+    }
+
     pub fn duplicate_profile(&mut self, duplicate_name: String, id: i64, db: &Db) -> Result<()> {
         self.create_profile(duplicate_name, None, db)?;
 
@@ -0,0 +1,400 @@
+//! Finding the mod that breaks a profile by repeatedly disabling half of the
+//! remaining suspects, launching the game and asking whether the problem is gone.
+
+use std::collections::{HashMap, HashSet};
+
+use eyre::{bail, ensure, Result};
+use itertools::Itertools;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{install::DisableStrategy, Dependant, Profile};
+use crate::thunderstore::Thunderstore;
+
+/// A bisection in progress, saved in the database so it survives restarts
+/// between game launches.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct BisectSession {
+    /// The enabled state of every mod when the session started.
+    original: Vec<(Uuid, bool)>,
+    /// Mods that could still be the culprit.
+    suspects: Vec<Uuid>,
+    /// Mods disabled for the current step, including dependants of suspects.
+    disabled: Vec<Uuid>,
+    steps: u32,
+}
+
+/// How the game behaved with the current set of mods.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum BisectResult {
+    /// The problem is gone.
+    Good,
+    /// The problem is still there.
+    Bad,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BisectStatus {
+    pub suspects: Vec<Dependant>,
+    /// Mods disabled for the current step.
+    pub disabled: Vec<Dependant>,
+    pub steps: u32,
+    /// Roughly how many more steps are needed.
+    pub remaining_steps: u32,
+    /// Whether there's only one suspect left, or none at all if the
+    /// problem didn't depend on the mods. The suspects left can also be
+    /// mods that depend on each other, which can't be told apart.
+    pub done: bool,
+}
+
+/// The transitive dependencies of each mod within a profile.
+type DependencyMap = HashMap<Uuid, HashSet<Uuid>>;
+
+impl BisectSession {
+    fn is_done(&self) -> bool {
+        self.suspects.len() <= 1 || self.disabled.is_empty()
+    }
+
+    fn remaining_steps(&self) -> u32 {
+        match self.is_done() {
+            true => 0,
+            false => usize::BITS - (self.suspects.len() - 1).leading_zeros(),
+        }
+    }
+
+    /// Narrows down the suspects and picks the mods to disable for the next step.
+    fn step(&mut self, result: BisectResult, deps: &DependencyMap) {
+        let disabled = self.disabled.iter().copied().collect::<HashSet<_>>();
+
+        self.suspects.retain(|uuid| match result {
+            // the problem went away, so it's one of the disabled mods
+            BisectResult::Good => disabled.contains(uuid),
+            BisectResult::Bad => !disabled.contains(uuid),
+        });
+
+        self.steps += 1;
+        self.disabled = match self.suspects.len() {
+            0 | 1 => Vec::new(),
+            // suspects that can't be split up are reported together
+            _ => partition(&self.suspects, &self.enabled_originally(), deps).unwrap_or_default(),
+        };
+    }
+
+    fn enabled_originally(&self) -> Vec<Uuid> {
+        self.original
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(uuid, _)| *uuid)
+            .collect()
+    }
+
+    /// Whether each mod should be enabled for the current step.
+    fn targets(&self) -> impl Iterator<Item = (Uuid, bool)> + '_ {
+        self.original
+            .iter()
+            .map(|&(uuid, enabled)| (uuid, enabled && !self.disabled.contains(&uuid)))
+    }
+}
+
+/// Picks roughly half of `suspects` to disable, along with every enabled mod
+/// that depends on them so nothing is left without its dependencies.
+///
+/// Suspects with the fewest dependants are picked first, so disabling a library
+/// doesn't drag most of the profile along with it.
+///
+/// Suspects that would take every other suspect along are skipped, since the
+/// next step couldn't rule anything out. Returns `None` if that's all of them,
+/// for example when the suspects depend on each other in a cycle.
+fn partition(suspects: &[Uuid], enabled: &[Uuid], deps: &DependencyMap) -> Option<Vec<Uuid>> {
+    let dependants = |uuid: Uuid| {
+        enabled
+            .iter()
+            .copied()
+            .filter(move |other| deps.get(other).is_some_and(|deps| deps.contains(&uuid)))
+    };
+
+    let target = suspects.len() / 2;
+    let mut disabled = HashSet::new();
+
+    let by_dependants = suspects
+        .iter()
+        .copied()
+        .sorted_by_cached_key(|&uuid| dependants(uuid).count());
+
+    for uuid in by_dependants {
+        if disabled.len() >= target.max(1) {
+            break;
+        }
+
+        let mut picked = disabled.clone();
+        picked.insert(uuid);
+        picked.extend(dependants(uuid));
+
+        if suspects.iter().all(|suspect| picked.contains(suspect)) {
+            continue;
+        }
+
+        disabled = picked;
+    }
+
+    if disabled.is_empty() {
+        return None;
+    }
+
+    // keep the order of the profile, for predictable toggling
+    let disabled = enabled
+        .iter()
+        .copied()
+        .filter(|uuid| disabled.contains(uuid))
+        .collect();
+
+    Some(disabled)
+}
+
+impl Profile {
+    fn dependency_map(&self, thunderstore: &Thunderstore) -> DependencyMap {
+        let installed = self.mods.iter().map(|m| m.uuid()).collect::<HashSet<_>>();
+
+        self.mods
+            .iter()
+            .map(|profile_mod| {
+                let deps = profile_mod
+                    .dependencies(thunderstore)
+                    .map(|dep| dep.package.uuid)
+                    .filter(|uuid| installed.contains(uuid))
+                    .collect();
+
+                (profile_mod.uuid(), deps)
+            })
+            .collect()
+    }
+
+    /// Starts a bisection with every enabled mod as a suspect, and disables
+    /// the first half of them.
+    pub fn start_bisect(
+        &mut self,
+        strategy: DisableStrategy,
+        thunderstore: &Thunderstore,
+    ) -> Result<BisectSession> {
+        self.ensure_unlocked()?;
+
+        let original = self
+            .mods
+            .iter()
+            .map(|profile_mod| (profile_mod.uuid(), profile_mod.enabled))
+            .collect_vec();
+
+        let mut session = BisectSession {
+            original,
+            suspects: Vec::new(),
+            disabled: Vec::new(),
+            steps: 0,
+        };
+
+        session.suspects = session.enabled_originally();
+        ensure!(
+            session.suspects.len() > 1,
+            "there must be at least two enabled mods to bisect"
+        );
+
+        let Some(disabled) = partition(
+            &session.suspects,
+            &session.suspects,
+            &self.dependency_map(thunderstore),
+        ) else {
+            bail!("the enabled mods depend on each other, so they can't be bisected");
+        };
+
+        session.disabled = disabled;
+
+        self.apply_bisect(&session, strategy)?;
+
+        Ok(session)
+    }
+
+    /// Records the result of the current step and applies the next one.
+    pub fn bisect_step(
+        &mut self,
+        session: &mut BisectSession,
+        result: BisectResult,
+        strategy: DisableStrategy,
+        thunderstore: &Thunderstore,
+    ) -> Result<()> {
+        ensure!(!session.is_done(), "the bisection is already finished");
+
+        session.step(result, &self.dependency_map(thunderstore));
+        self.apply_bisect(session, strategy)
+    }
+
+    /// Restores the enabled state of every mod from before the bisection.
+    pub fn finish_bisect(
+        &mut self,
+        session: &BisectSession,
+        strategy: DisableStrategy,
+    ) -> Result<()> {
+        let toggles = session
+            .original
+            .iter()
+            .filter(|(uuid, enabled)| {
+                self.get_mod(*uuid)
+                    .is_ok_and(|profile_mod| profile_mod.enabled != *enabled)
+            })
+            .map(|(uuid, _)| *uuid)
+            .collect_vec();
+
+        self.force_toggle_mods(&toggles, strategy)
+    }
+
+    fn apply_bisect(&mut self, session: &BisectSession, strategy: DisableStrategy) -> Result<()> {
+        let toggles = session
+            .targets()
+            .filter(|(uuid, enabled)| match self.get_mod(*uuid) {
+                Ok(profile_mod) => profile_mod.enabled != *enabled,
+                Err(_) => {
+                    warn!("mod {} was removed during bisection, ignoring", uuid);
+                    false
+                }
+            })
+            .map(|(uuid, _)| uuid)
+            .collect_vec();
+
+        self.force_toggle_mods(&toggles, strategy)
+    }
+
+    pub fn bisect_status(&self, session: &BisectSession) -> BisectStatus {
+        let to_dependants = |uuids: &[Uuid]| {
+            uuids
+                .iter()
+                .filter_map(|uuid| self.get_mod(*uuid).ok())
+                .map(Dependant::from)
+                .collect()
+        };
+
+        BisectStatus {
+            suspects: to_dependants(&session.suspects),
+            disabled: to_dependants(&session.disabled),
+            steps: session.steps,
+            remaining_steps: session.remaining_steps(),
+            done: session.is_done(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uuids(count: u128) -> Vec<Uuid> {
+        (1..=count).map(Uuid::from_u128).collect()
+    }
+
+    fn session(mods: &[Uuid], deps: &DependencyMap) -> BisectSession {
+        BisectSession {
+            original: mods.iter().map(|&uuid| (uuid, true)).collect(),
+            suspects: mods.to_vec(),
+            disabled: partition(mods, mods, deps).unwrap(),
+            steps: 0,
+        }
+    }
+
+    #[test]
+    fn partition_takes_dependants_along() {
+        let mods = uuids(6);
+        let library = mods[0];
+
+        // mods 2 and 3 need the library, mod 3 indirectly through mod 2
+        let deps = DependencyMap::from([
+            (mods[1], HashSet::from([library])),
+            (mods[2], HashSet::from([library, mods[1]])),
+        ]);
+
+        let disabled = partition(&mods, &mods, &deps).unwrap();
+
+        // leaves are disabled before the library, which would take three mods with it
+        assert_eq!(disabled.len(), 3);
+        assert!(!disabled.contains(&library));
+
+        let disabled = partition(&[library, mods[3]], &mods, &deps).unwrap();
+        assert_eq!(disabled, [mods[3]]);
+
+        let disabled = partition(&[library, mods[1]], &mods, &deps).unwrap();
+        assert_eq!(disabled, [mods[1], mods[2]]);
+
+        // a single suspect can't be split up
+        assert_eq!(partition(&[library], &mods, &deps), None);
+    }
+
+    #[test]
+    fn bisection_finds_culprit() {
+        let mods = uuids(10);
+        let deps = DependencyMap::from([
+            (mods[4], HashSet::from([mods[0]])),
+            (mods[7], HashSet::from([mods[0], mods[4]])),
+        ]);
+
+        for culprit in mods.iter().copied() {
+            let mut session = session(&mods, &deps);
+
+            while !session.is_done() {
+                let enabled = session
+                    .targets()
+                    .filter(|(_, enabled)| *enabled)
+                    .map(|(uuid, _)| uuid)
+                    .collect::<HashSet<_>>();
+
+                // every enabled mod has its dependencies
+                for uuid in &enabled {
+                    if let Some(deps) = deps.get(uuid) {
+                        assert!(deps.is_subset(&enabled));
+                    }
+                }
+
+                let result = match enabled.contains(&culprit) {
+                    true => BisectResult::Bad,
+                    false => BisectResult::Good,
+                };
+
+                session.step(result, &deps);
+                assert!(session.steps <= 10, "bisection didn't converge");
+            }
+
+            assert_eq!(session.suspects, [culprit]);
+        }
+    }
+
+    #[test]
+    fn cycles_are_suspected_together() {
+        let mods = uuids(5);
+        let cycle = [mods[0], mods[1]];
+
+        let deps = DependencyMap::from([
+            (mods[0], HashSet::from([mods[1]])),
+            (mods[1], HashSet::from([mods[0]])),
+        ]);
+
+        assert_eq!(partition(&cycle, &mods, &deps), None);
+
+        for culprit in mods.iter().copied() {
+            let mut session = session(&mods, &deps);
+
+            while !session.is_done() {
+                let result = match session.targets().any(|target| target == (culprit, true)) {
+                    true => BisectResult::Bad,
+                    false => BisectResult::Good,
+                };
+
+                session.step(result, &deps);
+                assert!(session.steps <= 10, "bisection didn't converge");
+            }
+
+            match cycle.contains(&culprit) {
+                true => assert_eq!(session.suspects, cycle),
+                false => assert_eq!(session.suspects, [culprit]),
+            }
+        }
+    }
+}
@@ -1,15 +1,26 @@
-use std::path::PathBuf;
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
 
-use eyre::{Context, OptionExt};
+use eyre::{eyre, Context, OptionExt};
 use itertools::Itertools;
 use log::warn;
 use serde::Serialize;
-use tauri::{command, AppHandle};
+use tauri::{command, AppHandle, Emitter};
 use uuid::Uuid;
 
-use super::{actions::ActionResult, Dependant, Profile};
+use super::{
+    actions::{ActionResult, BatchReport, ToggleAllReport},
+    install::{self, InstallOptions, ModInstall, SubdirFiles},
+    launch,
+    update::UpdateSummary,
+    AppStats, BisectResult, BisectStatus, Dependant, DependencyCheck, GameOverview, InstallOrigin,
+    LogEntry, ModExplanation, ModManager, ModSide, NameValidation, OrphanedDir, Profile,
+};
 use crate::{
-    game::{self, Game, Platform},
+    config::{GameDefaultsReport, LoaderSetting, LoaderSettingKey, LoaderSettings},
+    game::{self, Game, GameMetadata, Platform},
     state::ManagerExt,
     thunderstore::{query::QueryModsArgs, FrontendProfileMod, Thunderstore, VersionIdent},
     util::cmd::Result,
@@ -23,6 +34,7 @@ pub struct FrontendGame {
     popular: bool,
     mod_loader: &'static str,
     platforms: Vec<Platform>,
+    categories: &'static [&'static str],
 }
 
 impl From<Game> for FrontendGame {
@@ -35,6 +47,7 @@ impl From<Game> for FrontendGame {
             popular: value.popular,
             mod_loader: value.mod_loader.to_str(),
             platforms,
+            categories: &value.categories,
         }
     }
 }
@@ -48,8 +61,8 @@ pub struct GameInfo {
 }
 
 #[command]
-pub fn get_game_info(app: AppHandle) -> GameInfo {
-    let manager = app.lock_manager();
+pub fn get_game_info(app: AppHandle) -> Result<GameInfo> {
+    let manager = app.lock_manager()?;
 
     let favorites = manager
         .games
@@ -60,17 +73,39 @@ pub fn get_game_info(app: AppHandle) -> GameInfo {
         })
         .collect();
 
-    GameInfo {
+    Ok(GameInfo {
         all: game::all().map_into().collect(),
         active: manager.active_game.into(),
         favorites,
-    }
+    })
+}
+
+/// Returns how a game is modded, like its mod loader and where logs end up.
+#[command]
+pub fn get_game_metadata(slug: String) -> Result<GameMetadata> {
+    let game = game::from_slug(&slug).ok_or_eyre("unknown game")?;
+
+    Ok(game.metadata())
+}
+
+#[command]
+pub fn get_games_overview(app: AppHandle) -> Result<Vec<GameOverview>> {
+    let overview = super::overview::get(&app)?;
+
+    Ok(overview)
+}
+
+#[command]
+pub async fn get_app_stats(app: AppHandle) -> Result<AppStats> {
+    let stats = super::overview::stats(&app).await?;
+
+    Ok(stats)
 }
 
 #[command]
 pub fn favorite_game(slug: String, app: AppHandle) -> Result<()> {
-    let prefs = app.lock_prefs();
-    let mut manager = app.lock_manager();
+    let prefs = app.lock_prefs()?;
+    let mut manager = app.lock_manager()?;
 
     let game = game::from_slug(&slug).ok_or_eyre("unknown game")?;
     let managed_game = manager.ensure_game(game, &prefs, app.db())?;
@@ -83,7 +118,7 @@ pub fn favorite_game(slug: String, app: AppHandle) -> Result<()> {
 
 #[command]
 pub fn set_active_game(slug: &str, app: AppHandle) -> Result<()> {
-    let mut manager = app.lock_manager();
+    let mut manager = app.lock_manager()?;
 
     let game = game::from_slug(slug).ok_or_eyre("unknown game")?;
 
@@ -93,6 +128,36 @@ pub fn set_active_game(slug: &str, app: AppHandle) -> Result<()> {
     Ok(())
 }
 
+/// Moves the profiles and other data of games from old slugs to new ones,
+/// for when a game's slug changed in `games.json`.
+///
+/// Profiles stored under an unknown slug aren't loaded, so the manager is reloaded
+/// afterwards to pick them up. Returns the number of profiles that were moved.
+#[command]
+pub fn remap_game_slugs(mapping: HashMap<String, String>, app: AppHandle) -> Result<usize> {
+    for new in mapping.values() {
+        game::from_slug(new).ok_or_eyre(format!("unknown game: {}", new))?;
+    }
+
+    let mut prefs = app.lock_prefs()?;
+    let mut manager = app.lock_manager()?;
+
+    manager.save_all(app.db())?;
+    let moved = app.db().remap_game_slugs(&mapping)?;
+
+    for (old, new) in &mapping {
+        if let Some(game_prefs) = prefs.game_prefs.remove(old) {
+            prefs.game_prefs.insert(new.clone(), game_prefs);
+        }
+    }
+    app.db().save_prefs(&prefs)?;
+
+    let (data, _, _) = app.db().read()?;
+    *manager = ModManager::create(data, &prefs, app.db())?;
+
+    Ok(moved)
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ProfilesInfo {
@@ -106,14 +171,17 @@ pub struct ProfileInfo {
     id: i64,
     name: String,
     mod_count: usize,
+    locked: bool,
+    /// `None` if the mod loader has no settings or they couldn't be read.
+    loader_settings: Option<LoaderSettings>,
 }
 
 #[command]
-pub fn get_profile_info(app: AppHandle) -> ProfilesInfo {
-    let manager = app.lock_manager();
+pub fn get_profile_info(app: AppHandle) -> Result<ProfilesInfo> {
+    let manager = app.lock_manager()?;
     let game = manager.active_game();
 
-    ProfilesInfo {
+    Ok(ProfilesInfo {
         profiles: game
             .profiles
             .iter()
@@ -121,32 +189,33 @@ pub fn get_profile_info(app: AppHandle) -> ProfilesInfo {
                 id: profile.id,
                 name: profile.name.clone(),
                 mod_count: profile.mods.len(),
+                locked: profile.locked,
+                loader_settings: profile.loader_settings().unwrap_or_else(|err| {
+                    warn!(
+                        "failed to read loader settings of {}: {:#}",
+                        profile.name, err
+                    );
+                    None
+                }),
             })
             .collect(),
         active_id: game.active_profile_id,
-    }
+    })
 }
 
 #[command]
 pub fn set_active_profile(index: usize, app: AppHandle) -> Result<()> {
-    let mut manager = app.lock_manager();
+    let mut manager = app.lock_manager()?;
 
     let game = manager.active_game_mut();
     game.set_active_profile(index)?;
     game.save(app.db())?;
 
-    Ok(())
-}
+    drop(manager);
+    launch::sync_game_dir(&app)?;
+    launch::deploy_active_profile(&app)?;
 
-#[derive(Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct FrontendAvailableUpdate {
-    full_name: VersionIdent,
-    ignore: bool,
-    package_uuid: Uuid,
-    version_uuid: Uuid,
-    old: semver::Version,
-    new: semver::Version,
+    Ok(())
 }
 
 #[derive(Serialize)]
@@ -154,45 +223,27 @@ pub struct FrontendAvailableUpdate {
 pub struct ProfileQuery {
     mods: Vec<FrontendProfileMod>,
     total_mod_count: usize,
-    updates: Vec<FrontendAvailableUpdate>,
+    updates: UpdateSummary,
     unknown_mods: Vec<Dependant>,
 }
 
 #[command]
 pub fn query_profile(args: QueryModsArgs, app: AppHandle) -> Result<ProfileQuery> {
-    let manager = app.lock_manager();
-    let thunderstore = app.lock_thunderstore();
+    let mut manager = app.lock_manager()?;
+    let thunderstore = app.lock_thunderstore()?;
+
+    // new config files may have been generated since the last query
+    manager
+        .untracked()
+        .active_profile_mut()
+        .refresh_config_if_stale();
 
     let profile = manager.active_profile();
 
     let (mods, unknown_mods) = profile.query_mods(&args, &thunderstore);
     let total_mod_count = profile.mods.len();
 
-    let updates = profile
-        .mods
-        .iter()
-        .filter_map(|profile_mod| {
-            profile
-                .check_update(profile_mod.uuid(), false, &thunderstore)
-                .transpose()
-        })
-        .map_ok(|update| {
-            let ignore = profile.ignored_updates.contains(&update.latest.uuid);
-
-            FrontendAvailableUpdate {
-                full_name: update.latest.ident.clone(),
-                package_uuid: update.package.uuid,
-                version_uuid: update.latest.uuid,
-                old: update.current.parsed_version().clone(),
-                new: update.latest.parsed_version().clone(),
-                ignore,
-            }
-        })
-        .collect::<eyre::Result<Vec<_>>>()
-        .unwrap_or_else(|err| {
-            warn!("failed to check for updates: {:#}", err);
-            Vec::new()
-        });
+    let updates = profile.update_summary(&thunderstore);
 
     Ok(ProfileQuery {
         mods,
@@ -204,7 +255,7 @@ pub fn query_profile(args: QueryModsArgs, app: AppHandle) -> Result<ProfileQuery
 
 #[command]
 pub fn is_mod_installed(uuid: Uuid, app: AppHandle) -> Result<bool> {
-    let manager = app.lock_manager();
+    let manager = app.lock_manager()?;
 
     let result = manager.active_profile().has_mod(uuid);
 
@@ -213,33 +264,226 @@ pub fn is_mod_installed(uuid: Uuid, app: AppHandle) -> Result<bool> {
 
 #[command]
 pub fn create_profile(name: String, override_path: Option<PathBuf>, app: AppHandle) -> Result<()> {
-    let mut manager = app.lock_manager();
+    let mut manager = app.lock_manager()?;
 
-    manager
+    let profile = manager
         .active_game_mut()
         .create_profile(name, override_path, app.db())?;
+
+    if let Err(err) = profile.apply_game_defaults() {
+        warn!(
+            "failed to apply default config to {}: {:#}",
+            profile.name, err
+        );
+    }
+
     manager.save_all(app.db())?;
 
     Ok(())
 }
 
+/// Changes a common mod loader setting of a profile, like showing the console.
+#[command]
+pub fn set_loader_setting(
+    profile_id: i64,
+    key: LoaderSettingKey,
+    value: serde_json::Value,
+    app: AppHandle,
+) -> Result<()> {
+    let setting = LoaderSetting::new(key, value)?;
+
+    let mut manager = app.lock_manager()?;
+
+    let profile = manager.active_game_mut().find_profile_mut(profile_id)?;
+    profile.ensure_unlocked()?;
+    profile.set_loader_setting(setting)?;
+
+    Ok(())
+}
+
+/// Shows or hides the mod loader console of the active profile, which
+/// is usually the first step when looking for errors.
+#[command]
+pub fn set_console_enabled(enabled: bool, app: AppHandle) -> Result<()> {
+    let mut manager = app.lock_manager()?;
+
+    let profile = manager.active_profile_mut();
+    profile.ensure_unlocked()?;
+    profile.set_loader_setting(LoaderSetting::Console(enabled))?;
+
+    Ok(())
+}
+
+/// Sets the default config entries of the game in a profile, reporting
+/// which ones were changed and which already had their default value.
+#[command]
+pub fn apply_game_defaults(profile_id: i64, app: AppHandle) -> Result<GameDefaultsReport> {
+    let mut manager = app.lock_manager()?;
+
+    let profile = manager.active_game_mut().find_profile_mut(profile_id)?;
+    profile.ensure_unlocked()?;
+
+    Ok(profile.apply_game_defaults()?)
+}
+
+/// Starts looking for the mod that breaks a profile, by disabling half of its
+/// enabled mods for the user to test.
+#[command]
+pub fn start_bisect(profile_id: i64, app: AppHandle) -> Result<BisectStatus> {
+    let strategy = app.lock_prefs()?.disable_strategy;
+    let mut manager = app.lock_manager()?;
+    let thunderstore = app.lock_thunderstore()?;
+
+    if app.db().bisect_session(profile_id)?.is_some() {
+        return Err(eyre!("a bisection is already in progress for this profile").into());
+    }
+
+    let profile = manager.active_game_mut().find_profile_mut(profile_id)?;
+    let _guard = app
+        .profile_ops()
+        .try_acquire(profile.id, "bisecting mods")?;
+
+    let session = profile.start_bisect(strategy, &thunderstore)?;
+    profile.save(app.db())?;
+    app.db().save_bisect_session(profile_id, &session)?;
+
+    let status = profile.bisect_status(&session);
+
+    drop(thunderstore);
+    drop(manager);
+    launch::sync_game_dir(&app)?;
+
+    Ok(status)
+}
+
+/// Records whether the problem still happens with the current mods, and
+/// disables the next half of the suspects.
+#[command]
+pub fn bisect_step(profile_id: i64, result: BisectResult, app: AppHandle) -> Result<BisectStatus> {
+    let strategy = app.lock_prefs()?.disable_strategy;
+    let mut manager = app.lock_manager()?;
+    let thunderstore = app.lock_thunderstore()?;
+
+    let mut session = app
+        .db()
+        .bisect_session(profile_id)?
+        .ok_or_eyre("no bisection is in progress for this profile")?;
+
+    let profile = manager.active_game_mut().find_profile_mut(profile_id)?;
+    let _guard = app
+        .profile_ops()
+        .try_acquire(profile.id, "bisecting mods")?;
+
+    profile.bisect_step(&mut session, result, strategy, &thunderstore)?;
+    profile.save(app.db())?;
+    app.db().save_bisect_session(profile_id, &session)?;
+
+    let status = profile.bisect_status(&session);
+
+    drop(thunderstore);
+    drop(manager);
+    launch::sync_game_dir(&app)?;
+
+    Ok(status)
+}
+
+/// Ends a bisection, restoring which mods were enabled before it started.
+///
+/// Returns the final status, with the remaining suspects.
+#[command]
+pub fn finish_bisect(profile_id: i64, app: AppHandle) -> Result<BisectStatus> {
+    let strategy = app.lock_prefs()?.disable_strategy;
+    let mut manager = app.lock_manager()?;
+
+    let session = app
+        .db()
+        .bisect_session(profile_id)?
+        .ok_or_eyre("no bisection is in progress for this profile")?;
+
+    let profile = manager.active_game_mut().find_profile_mut(profile_id)?;
+    let _guard = app
+        .profile_ops()
+        .try_acquire(profile.id, "bisecting mods")?;
+
+    let status = profile.bisect_status(&session);
+
+    profile.finish_bisect(&session, strategy)?;
+    profile.save(app.db())?;
+    app.db().remove_bisect_session(profile_id)?;
+
+    drop(manager);
+    launch::sync_game_dir(&app)?;
+
+    Ok(status)
+}
+
 #[command]
 pub fn delete_profile(index: usize, app: AppHandle) -> Result<()> {
-    let mut manager = app.lock_manager();
+    let prefs = app.lock_prefs()?;
+    let mut manager = app.lock_manager()?;
 
     let game = manager.active_game_mut();
-    game.delete_profile(index, false, app.db())?;
+    game.delete_profile(index, false, app.db())
+        .map_err(|err| launch::blame_running_game(err, game.game, &prefs))?;
     game.save(app.db())?;
 
+    drop(manager);
+    drop(prefs);
+    launch::sync_game_dir(&app)?;
+    launch::deploy_active_profile(&app)?;
+
     Ok(())
 }
 
+/// Checks a name for a new or renamed profile of the active game, so the
+/// frontend can explain what's wrong while the user types.
+///
+/// `except` is the id of the profile being renamed, if any.
+#[command]
+pub fn validate_profile_name(
+    name: String,
+    except: Option<i64>,
+    app: AppHandle,
+) -> Result<NameValidation> {
+    let manager = app.lock_manager()?;
+
+    Ok(manager.active_game().validate_profile_name(&name, except))
+}
+
 #[command]
 pub fn rename_profile(name: String, app: AppHandle) -> Result<()> {
-    let mut manager = app.lock_manager();
+    let prefs = app.lock_prefs()?;
+    let mut manager = app.lock_manager()?;
+
+    let game = manager.active_game_mut();
+    let old_name = game.active_profile().name.clone();
+
+    game.check_profile_name(&name, Some(game.active_profile_id))?;
+
+    game.active_profile_mut()
+        .rename(name, app.db())
+        .map_err(|err| launch::blame_running_game(err, game.game, &prefs))?;
+
+    // a linked deployment still points to the old directory
+    if let Err(err) = game.deploy_active_profile(&prefs, app.db()) {
+        if let Err(err) = game.active_profile_mut().rename(old_name, app.db()) {
+            warn!("failed to roll back profile rename: {:#}", err);
+        } else {
+            game.deploy_active_profile(&prefs, app.db()).ok();
+        }
+
+        return Err(err.wrap_err("failed to redeploy renamed profile").into());
+    }
+
+    Ok(())
+}
+
+#[command]
+pub fn set_profile_locked(locked: bool, app: AppHandle) -> Result<()> {
+    let mut manager = app.lock_manager()?;
 
     let profile = manager.active_profile_mut();
-    profile.rename(name)?;
+    profile.locked = locked;
     profile.save(app.db())?;
 
     Ok(())
@@ -247,7 +491,7 @@ pub fn rename_profile(name: String, app: AppHandle) -> Result<()> {
 
 #[command]
 pub fn duplicate_profile(name: String, app: AppHandle) -> Result<()> {
-    let mut manager = app.lock_manager();
+    let mut manager = app.lock_manager()?;
 
     let game = manager.active_game_mut();
     game.duplicate_profile(name, game.active_profile_id, app.db())?;
@@ -263,10 +507,28 @@ pub fn remove_mod(uuid: Uuid, app: AppHandle) -> Result<ActionResult> {
     })
 }
 
+#[command]
+pub fn remove_mods(uuids: Vec<Uuid>, app: AppHandle) -> Result<ActionResult> {
+    mod_action_command(app, |profile, thunderstore| {
+        profile.remove_mods(&uuids, thunderstore)
+    })
+}
+
+#[command]
+pub fn toggle_mods(uuids: Vec<Uuid>, enabled: bool, app: AppHandle) -> Result<ActionResult> {
+    let strategy = app.lock_prefs()?.disable_strategy;
+
+    mod_action_command(app, |profile, thunderstore| {
+        profile.toggle_mods(&uuids, enabled, strategy, thunderstore)
+    })
+}
+
 #[command]
 pub fn toggle_mod(uuid: Uuid, app: AppHandle) -> Result<ActionResult> {
+    let strategy = app.lock_prefs()?.disable_strategy;
+
     mod_action_command(app, |profile, thunderstore| {
-        profile.toggle_mod(uuid, thunderstore)
+        profile.toggle_mod(uuid, strategy, thunderstore)
     })
 }
 
@@ -274,61 +536,102 @@ fn mod_action_command<F>(app: AppHandle, action: F) -> Result<ActionResult>
 where
     F: FnOnce(&mut Profile, &Thunderstore) -> eyre::Result<ActionResult>,
 {
-    let mut manager = app.lock_manager();
-    let thunderstore = app.lock_thunderstore();
+    let mut manager = app.lock_manager()?;
+    let thunderstore = app.lock_thunderstore()?;
 
     let profile = manager.active_profile_mut();
+    let _guard = app.profile_ops().try_acquire(profile.id, "changing mods")?;
     let response = action(profile, &thunderstore)?;
 
     if let ActionResult::Done = response {
         profile.save(app.db())?;
+
+        drop(thunderstore);
+        drop(manager);
+        launch::sync_game_dir(&app)?;
     }
 
     Ok(response)
 }
 
+/// Removes mods without checking dependants, carrying on past mods that fail.
+///
+/// With `strict`, nothing is removed if any of them can't be toggled first. A
+/// removal that fails after that is reported with [`BatchReport::partial`].
 #[command]
-pub fn force_remove_mods(uuids: Vec<Uuid>, app: AppHandle) -> Result<()> {
-    let mut manager = app.lock_manager();
+pub fn force_remove_mods(
+    uuids: Vec<Uuid>,
+    strict: Option<bool>,
+    app: AppHandle,
+) -> Result<BatchReport> {
+    let mut manager = app.lock_manager()?;
 
     let profile = manager.active_profile_mut();
-    for package_uuid in uuids {
-        profile.force_remove_mod(package_uuid)?;
+    let _guard = app.profile_ops().try_acquire(profile.id, "removing mods")?;
+    let total = uuids.len();
+
+    let result = profile.try_remove_mods(&uuids, strict.unwrap_or(false), |i| {
+        if total > 1 {
+            let status = format!("Removing mods... {}/{}", i + 1, total);
+            app.emit("status_update", Some(status)).ok();
+        }
+    });
+
+    if total > 1 {
+        app.emit("status_update", None::<String>).ok();
     }
 
-    profile.save(app.db())?;
+    let report = result?;
 
-    Ok(())
+    if !report.succeeded.is_empty() {
+        profile.save(app.db())?;
+
+        drop(manager);
+        launch::sync_game_dir(&app)?;
+    }
+
+    Ok(report)
 }
 
+/// Enables or disables every mod in the active profile.
+///
+/// See [`Profile::toggle_all_mods`] for the order and which mods are skipped.
 #[command]
-pub fn set_all_mods_state(enable: bool, app: AppHandle) -> Result<usize> {
-    let mut manager = app.lock_manager();
+pub fn set_all_mods_state(
+    enable: bool,
+    include_loader: Option<bool>,
+    app: AppHandle,
+) -> Result<ToggleAllReport> {
+    let strategy = app.lock_prefs()?.disable_strategy;
+    let mut manager = app.lock_manager()?;
+    let thunderstore = app.lock_thunderstore()?;
 
     let profile = manager.active_profile_mut();
-    let uuids = profile
-        .mods
-        .iter()
-        .filter(|profile_mod| profile_mod.enabled != enable)
-        .map(|profile_mod| profile_mod.uuid())
-        .collect_vec();
-
-    let count = uuids.len();
+    let _guard = app.profile_ops().try_acquire(profile.id, "toggling mods")?;
+    let report = profile.toggle_all_mods(
+        enable,
+        include_loader.unwrap_or(false),
+        strategy,
+        &thunderstore,
+    )?;
+
+    if !report.changed.is_empty() {
+        profile.save(app.db())?;
 
-    for uuid in uuids {
-        profile.force_toggle_mod(uuid)?;
+        drop(thunderstore);
+        drop(manager);
+        launch::sync_game_dir(&app)?;
     }
 
-    profile.save(app.db())?;
-
-    Ok(count)
+    Ok(report)
 }
 
 #[command]
 pub fn remove_disabled_mods(app: AppHandle) -> Result<usize> {
-    let mut manager = app.lock_manager();
+    let mut manager = app.lock_manager()?;
 
     let profile = manager.active_profile_mut();
+    let _guard = app.profile_ops().try_acquire(profile.id, "removing mods")?;
     let uuids = profile
         .mods
         .iter()
@@ -344,40 +647,221 @@ pub fn remove_disabled_mods(app: AppHandle) -> Result<usize> {
 
     profile.save(app.db())?;
 
+    drop(manager);
+    launch::sync_game_dir(&app)?;
+
     Ok(len)
 }
 
+/// Toggles mods without checking dependencies, carrying on past mods that fail.
+///
+/// With `strict`, the toggles are undone if any of them fail.
 #[command]
-pub fn force_toggle_mods(uuids: Vec<Uuid>, app: AppHandle) -> Result<()> {
-    let mut manager = app.lock_manager();
+pub fn force_toggle_mods(
+    uuids: Vec<Uuid>,
+    strict: Option<bool>,
+    app: AppHandle,
+) -> Result<BatchReport> {
+    let strategy = app.lock_prefs()?.disable_strategy;
+    let mut manager = app.lock_manager()?;
 
     let profile = manager.active_profile_mut();
-    for package_uuid in uuids {
-        profile.force_toggle_mod(package_uuid)?;
+    let _guard = app.profile_ops().try_acquire(profile.id, "toggling mods")?;
+    let report = profile.try_toggle_mods(&uuids, strict.unwrap_or(false), strategy, |_| ())?;
+
+    if !report.succeeded.is_empty() {
+        profile.save(app.db())?;
+
+        drop(manager);
+        launch::sync_game_dir(&app)?;
     }
 
+    Ok(report)
+}
+
+/// Sets whether a mod is needed on clients, servers or both.
+///
+/// `None` goes back to the guessed side.
+#[command]
+pub fn set_mod_side(uuid: Uuid, side: Option<ModSide>, app: AppHandle) -> Result<()> {
+    let mut manager = app.lock_manager()?;
+
+    let profile = manager.active_profile_mut();
+    profile.ensure_unlocked()?;
+    profile.get_mod_mut(uuid)?.side = side;
     profile.save(app.db())?;
 
     Ok(())
 }
 
 #[command]
-pub fn get_dependants(uuid: Uuid, app: AppHandle) -> Result<Vec<VersionIdent>> {
-    let manager = app.lock_manager();
-    let thunderstore = app.lock_thunderstore();
+pub fn check_profile_dependencies(app: AppHandle) -> Result<DependencyCheck> {
+    let prefs = app.lock_prefs()?;
+    let manager = app.lock_manager()?;
+    let thunderstore = app.lock_thunderstore()?;
+
+    let profile = manager.active_profile();
+
+    let mut check = profile.find_dependency_issues(&thunderstore);
+    check.compatibility = profile.find_compat_warnings(&prefs);
+
+    Ok(check)
+}
+
+/// Enables the disabled dependencies found by [`check_profile_dependencies`]
+/// and installs the missing ones.
+#[command]
+pub async fn fix_profile_dependencies(app: AppHandle) -> Result<()> {
+    let installs = {
+        let strategy = app.lock_prefs()?.disable_strategy;
+        let mut manager = app.lock_manager()?;
+        let thunderstore = app.lock_thunderstore()?;
+
+        let profile = manager.active_profile_mut();
+        let _guard = app.profile_ops().try_acquire(profile.id, "enabling mods")?;
+
+        let check = profile.find_dependency_issues(&thunderstore);
+        if check.is_healthy() {
+            return Ok(());
+        }
+
+        // resolve everything before changing the profile, so a failed
+        // lookup doesn't leave the fix half applied
+        let installs = check
+            .missing
+            .iter()
+            .map(|issue| thunderstore.find_ident(&issue.dependency.ident))
+            .map_ok(|dep| ModInstall::from(dep).with_origin(Some(InstallOrigin::Dependency)))
+            .collect::<eyre::Result<Vec<_>>>()?;
+
+        let disabled = check
+            .disabled
+            .iter()
+            .map(|issue| issue.dependency.uuid)
+            .collect_vec();
+
+        if !disabled.is_empty() {
+            profile.force_toggle_mods(&disabled, strategy)?;
+            profile.save(app.db())?;
+        }
+
+        installs
+    };
+
+    launch::sync_game_dir(&app)?;
+
+    if !installs.is_empty() {
+        install::install_with_deps(
+            installs,
+            // the dependencies are required either way
+            InstallOptions::default().confirm_flagged(false),
+            true,
+            &HashSet::new(),
+            &app,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileOrphans {
+    profile_id: i64,
+    profile_name: String,
+    dirs: Vec<OrphanedDir>,
+}
+
+/// Finds mod directories that don't belong to any mod, in every profile of the active game.
+///
+/// Profiles without any are left out.
+#[command]
+pub fn find_orphaned_mod_dirs(app: AppHandle) -> Result<Vec<ProfileOrphans>> {
+    let manager = app.lock_manager()?;
+
+    let mut result = Vec::new();
+
+    for profile in &manager.active_game().profiles {
+        let dirs = profile.find_orphaned_dirs()?;
+
+        if !dirs.is_empty() {
+            result.push(ProfileOrphans {
+                profile_id: profile.id,
+                profile_name: profile.name.clone(),
+                dirs,
+            });
+        }
+    }
+
+    Ok(result)
+}
+
+/// Removes directories found by [`find_orphaned_mod_dirs`]. Paths that aren't
+/// orphaned (anymore) are ignored.
+///
+/// Returns the number of bytes freed.
+#[command]
+pub fn remove_orphaned_mod_dirs(
+    profile_id: i64,
+    paths: Vec<PathBuf>,
+    app: AppHandle,
+) -> Result<u64> {
+    let manager = app.lock_manager()?;
+
+    let profile = manager.active_game().find_profile(profile_id)?;
+    let _guard = app
+        .profile_ops()
+        .try_acquire(profile.id, "removing orphaned mods")?;
+
+    let freed = profile.remove_orphaned_dirs(&paths)?;
+
+    Ok(freed)
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DependantList {
+    dependants: Vec<VersionIdent>,
+    /// The number of dependants, including those left out by the limit.
+    total: usize,
+}
+
+/// Finds the mods that depend on a mod in the active profile, returning
+/// at most `limit` of them.
+#[command]
+pub fn get_dependants(uuid: Uuid, limit: Option<usize>, app: AppHandle) -> Result<DependantList> {
+    let manager = app.lock_manager()?;
+    let thunderstore = app.lock_thunderstore()?;
 
     let dependants = manager
         .active_profile()
         .dependants(uuid, &thunderstore)
+        .collect_vec();
+
+    let total = dependants.len();
+    let dependants = dependants
+        .into_iter()
+        .take(limit.unwrap_or(usize::MAX))
         .map(|profile_mod| profile_mod.ident().into_owned())
         .collect();
 
-    Ok(dependants)
+    Ok(DependantList { dependants, total })
+}
+
+#[command]
+pub fn explain_mod(uuid: Uuid, app: AppHandle) -> Result<ModExplanation> {
+    let manager = app.lock_manager()?;
+    let thunderstore = app.lock_thunderstore()?;
+
+    let explanation = manager.active_profile().explain_mod(uuid, &thunderstore)?;
+
+    Ok(explanation)
 }
 
 #[command]
 pub fn open_profile_dir(app: AppHandle) -> Result<()> {
-    let manager = app.lock_manager();
+    let manager = app.lock_manager()?;
 
     let path = &manager.active_profile().path;
     open::that(path).context("failed to open directory")?;
@@ -387,19 +871,35 @@ pub fn open_profile_dir(app: AppHandle) -> Result<()> {
 
 #[command]
 pub fn open_mod_dir(uuid: Uuid, app: AppHandle) -> Result<()> {
-    let manager = app.lock_manager();
+    let manager = app.lock_manager()?;
 
     manager.active_profile().open_mod_dir(uuid)?;
 
     Ok(())
 }
 
+#[command]
+pub fn get_mod_subdirs(uuid: Uuid, app: AppHandle) -> Result<Vec<SubdirFiles>> {
+    let manager = app.lock_manager()?;
+
+    let subdirs = manager.active_profile().mod_subdirs(uuid)?;
+
+    Ok(subdirs)
+}
+
 #[command]
 pub fn open_game_log(app: AppHandle) -> Result<()> {
-    let manager = app.lock_manager();
+    let manager = app.lock_manager()?;
 
     let path = manager.active_profile().log_path()?;
     open::that_detached(path).context("failed to open log file")?;
 
     Ok(())
 }
+
+#[command]
+pub fn get_game_log(app: AppHandle) -> Result<Vec<LogEntry>> {
+    let manager = app.lock_manager()?;
+
+    Ok(manager.active_profile().read_log()?)
+}
@@ -0,0 +1,324 @@
+//! Known-incompatible mods and superseded packages.
+//!
+//! Rules are bundled in `compatibility.json` and can be extended with a file
+//! of the same name in the data directory. Both map game slugs to rules:
+//!
+//! ```json
+//! {
+//!     "lethal-company": [
+//!         {
+//!             "type": "incompatible",
+//!             "packages": ["Author-SuitsA", "Author-SuitsB@<2.0.0"],
+//!             "reason": "both replace the suit rack"
+//!         },
+//!         { "type": "superseded", "package": "Author-OldFork", "by": "Author-NewFork" }
+//!     ]
+//! }
+//! ```
+//!
+//! Packages are matched by full name, optionally followed by `@` and
+//! a [semver requirement](semver::VersionReq). Matches only ever produce
+//! warnings, never errors.
+
+use std::{collections::HashMap, fmt::Display, path::PathBuf, str::FromStr, sync::OnceLock};
+
+use eyre::{eyre, Context, Result};
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::{game::Game, prefs::Prefs, thunderstore::VersionIdent, util};
+
+const BUNDLED_JSON: &str = include_str!("../../compatibility.json");
+
+const FILE_NAME: &str = "compatibility.json";
+
+type RuleFile = HashMap<String, Vec<Rule>>;
+
+fn bundled() -> &'static RuleFile {
+    static BUNDLED: OnceLock<RuleFile> = OnceLock::new();
+
+    BUNDLED.get_or_init(|| serde_json::from_str(BUNDLED_JSON).unwrap())
+}
+
+/// A package full name with an optional version requirement,
+/// written as `Owner-Name` or `Owner-Name@<requirement>`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct PackagePattern {
+    full_name: String,
+    versions: Option<semver::VersionReq>,
+}
+
+impl PackagePattern {
+    fn matches(&self, ident: &VersionIdent) -> bool {
+        if ident.full_name() != self.full_name {
+            return false;
+        }
+
+        match (&self.versions, ident.version().parse::<semver::Version>()) {
+            (None, _) => true,
+            (Some(req), Ok(version)) => req.matches(&version),
+            // can't tell, so assume it's a different version
+            (Some(_), Err(_)) => false,
+        }
+    }
+}
+
+impl FromStr for PackagePattern {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (full_name, versions) = match s.split_once('@') {
+            Some((full_name, req)) => {
+                let req = semver::VersionReq::parse(req.trim())
+                    .with_context(|| format!("invalid version range in {}", s))?;
+
+                (full_name.trim(), Some(req))
+            }
+            None => (s.trim(), None),
+        };
+
+        match full_name.split_once('-') {
+            Some((owner, name)) if !owner.is_empty() && !name.is_empty() => Ok(Self {
+                full_name: full_name.to_owned(),
+                versions,
+            }),
+            _ => Err(eyre!("invalid package name {}", full_name)),
+        }
+    }
+}
+
+impl TryFrom<String> for PackagePattern {
+    type Error = eyre::Report;
+
+    fn try_from(value: String) -> Result<Self> {
+        value.parse()
+    }
+}
+
+impl Display for PackagePattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.versions {
+            Some(req) => write!(f, "{}@{}", self.full_name, req),
+            None => write!(f, "{}", self.full_name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+enum Rule {
+    /// The packages shouldn't be installed together.
+    Incompatible {
+        packages: [PackagePattern; 2],
+        #[serde(default)]
+        reason: Option<String>,
+    },
+    /// The package has been replaced by another one, usually a fork.
+    Superseded {
+        package: PackagePattern,
+        by: String,
+        #[serde(default)]
+        reason: Option<String>,
+    },
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum CompatWarning {
+    #[serde(rename_all = "camelCase")]
+    Incompatible {
+        mods: [VersionIdent; 2],
+        reason: Option<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Superseded {
+        installed: VersionIdent,
+        by: String,
+        reason: Option<String>,
+    },
+}
+
+impl CompatWarning {
+    fn involves(&self, ident: &VersionIdent) -> bool {
+        match self {
+            CompatWarning::Incompatible { mods, .. } => mods.contains(ident),
+            CompatWarning::Superseded { installed, .. } => installed == ident,
+        }
+    }
+}
+
+/// The compatibility rules for one game.
+#[derive(Debug, Default)]
+pub struct CompatRules {
+    rules: Vec<Rule>,
+}
+
+impl CompatRules {
+    /// Loads the bundled rules for `game`, along with the user's own.
+    ///
+    /// A malformed user file is logged and skipped.
+    pub fn load(game: Game, prefs: &Prefs) -> Self {
+        let mut rules = bundled().get(&*game.slug).cloned().unwrap_or_default();
+
+        match read_user_file(user_path(prefs)) {
+            Ok(Some(mut file)) => rules.extend(file.remove(&*game.slug).unwrap_or_default()),
+            Ok(None) => (),
+            Err(err) => warn!("failed to read user compatibility rules: {:#}", err),
+        }
+
+        Self { rules }
+    }
+
+    /// Checks the given mods against each other.
+    pub fn check<'a>(
+        &self,
+        mods: impl IntoIterator<Item = &'a VersionIdent>,
+    ) -> Vec<CompatWarning> {
+        let mods: Vec<_> = mods.into_iter().collect();
+        let find = |pattern: &PackagePattern| -> Vec<&VersionIdent> {
+            mods.iter()
+                .copied()
+                .filter(|ident| pattern.matches(ident))
+                .collect()
+        };
+
+        let mut warnings = Vec::new();
+
+        for rule in &self.rules {
+            match rule {
+                Rule::Incompatible {
+                    packages: [a, b],
+                    reason,
+                } => {
+                    for first in find(a) {
+                        for second in find(b).into_iter().filter(|second| *second != first) {
+                            warnings.push(CompatWarning::Incompatible {
+                                mods: [first.clone(), second.clone()],
+                                reason: reason.clone(),
+                            });
+                        }
+                    }
+                }
+                Rule::Superseded {
+                    package,
+                    by,
+                    reason,
+                } => {
+                    for installed in find(package) {
+                        warnings.push(CompatWarning::Superseded {
+                            installed: installed.clone(),
+                            by: by.clone(),
+                            reason: reason.clone(),
+                        });
+                    }
+                }
+            }
+        }
+
+        warnings
+    }
+
+    /// Checks what installing `new` alongside `installed` would cause.
+    ///
+    /// Only warnings involving at least one of the new mods are returned.
+    pub fn check_install<'a>(
+        &self,
+        installed: impl IntoIterator<Item = &'a VersionIdent>,
+        new: &'a [VersionIdent],
+    ) -> Vec<CompatWarning> {
+        self.check(installed.into_iter().chain(new))
+            .into_iter()
+            .filter(|warning| new.iter().any(|ident| warning.involves(ident)))
+            .collect()
+    }
+}
+
+fn user_path(prefs: &Prefs) -> PathBuf {
+    prefs.data_dir.join(FILE_NAME)
+}
+
+fn read_user_file(path: PathBuf) -> Result<Option<RuleFile>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    util::fs::read_json(path).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern(str: &str) -> PackagePattern {
+        str.parse().unwrap()
+    }
+
+    fn ident(full_name: &str, version: &str) -> VersionIdent {
+        let (owner, name) = full_name.split_once('-').unwrap();
+        VersionIdent::new(owner, name, version)
+    }
+
+    #[test]
+    fn patterns_are_parsed() {
+        let plain = pattern("Author-Mod");
+        assert_eq!(plain.full_name, "Author-Mod");
+        assert_eq!(plain.versions, None);
+
+        let ranged = pattern("Author-Mod@>=1.2.0, <2.0.0");
+        assert_eq!(ranged.full_name, "Author-Mod");
+        assert_eq!(
+            ranged.versions,
+            Some(semver::VersionReq::parse(">=1.2.0, <2.0.0").unwrap())
+        );
+
+        assert_eq!(pattern(" Author-Mod @ <1 ").to_string(), "Author-Mod@<1");
+
+        assert!("AuthorMod".parse::<PackagePattern>().is_err());
+        assert!("-Mod".parse::<PackagePattern>().is_err());
+        assert!("Author-Mod@not a range".parse::<PackagePattern>().is_err());
+    }
+
+    #[test]
+    fn patterns_match_version_ranges() {
+        let ranged = pattern("Author-Mod@>=1.2.0, <2.0.0");
+
+        assert!(ranged.matches(&ident("Author-Mod", "1.2.0")));
+        assert!(ranged.matches(&ident("Author-Mod", "1.9.3")));
+        assert!(!ranged.matches(&ident("Author-Mod", "1.1.9")));
+        assert!(!ranged.matches(&ident("Author-Mod", "2.0.0")));
+        assert!(!ranged.matches(&ident("Author-Other", "1.5.0")));
+
+        assert!(pattern("Author-Mod").matches(&ident("Author-Mod", "0.0.1")));
+    }
+
+    #[test]
+    fn install_warnings_involve_new_mods() {
+        let rules: Vec<Rule> = serde_json::from_str(
+            r#"[
+                { "type": "incompatible", "packages": ["A-Suits", "B-Suits@<2"] },
+                { "type": "superseded", "package": "C-Old", "by": "C-New" }
+            ]"#,
+        )
+        .unwrap();
+
+        let rules = CompatRules { rules };
+
+        let installed = [ident("B-Suits", "1.0.0"), ident("C-Old", "1.0.0")];
+
+        let warnings = rules.check_install(&installed, &[ident("A-Suits", "1.0.0")]);
+        assert_eq!(
+            warnings,
+            vec![CompatWarning::Incompatible {
+                mods: [ident("A-Suits", "1.0.0"), ident("B-Suits", "1.0.0")],
+                reason: None
+            }]
+        );
+
+        // the superseded mod was already installed
+        assert_eq!(rules.check(&installed).len(), 1);
+        assert!(rules
+            .check_install(&installed, &[ident("D-Other", "1.0.0")])
+            .is_empty());
+    }
+}
@@ -0,0 +1,184 @@
+//! Looking up the dependants of a mod without resolving the dependencies of
+//! every other mod in the profile, which is slow for libraries that hundreds
+//! of mods depend on.
+
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    sync::{Mutex, PoisonError},
+};
+
+use uuid::Uuid;
+
+use super::{Profile, ProfileMod, ProfileModKind};
+use crate::thunderstore::Thunderstore;
+
+/// Maps each mod to the indices of the mods that depend on it, directly or
+/// indirectly.
+///
+/// The map is rebuilt whenever the mod list changes, which is detected by
+/// hashing it, so nothing needs to invalidate it by hand.
+#[derive(Debug, Default)]
+pub struct DependantCache(Mutex<Option<ReverseDeps>>);
+
+#[derive(Debug)]
+struct ReverseDeps {
+    key: u64,
+    map: HashMap<Uuid, Vec<usize>>,
+}
+
+impl Profile {
+    /// Finds all the dependants of a mod in this profile.
+    ///
+    /// This includes both direct and indirect dependencies.
+    pub(super) fn dependants<'a>(
+        &'a self,
+        uuid: Uuid,
+        thunderstore: &'a Thunderstore,
+    ) -> impl Iterator<Item = &'a ProfileMod> + 'a {
+        let key = self.dependants_key(thunderstore);
+
+        let mut cache = self
+            .dependant_cache
+            .0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        let reverse_deps = match &mut *cache {
+            Some(reverse_deps) if reverse_deps.key == key => reverse_deps,
+            cache => cache.insert(ReverseDeps {
+                key,
+                map: reverse_dependencies(&self.mods, thunderstore),
+            }),
+        };
+
+        let indices = reverse_deps.map.get(&uuid).cloned().unwrap_or_default();
+
+        indices.into_iter().map(|index| &self.mods[index])
+    }
+
+    /// Hashes everything the dependants of a mod depend on.
+    fn dependants_key(&self, thunderstore: &Thunderstore) -> u64 {
+        let mut hasher = DefaultHasher::new();
+
+        for profile_mod in &self.mods {
+            match &profile_mod.kind {
+                ProfileModKind::Thunderstore(ts_mod) => {
+                    ts_mod.id.package_uuid.hash(&mut hasher);
+                    ts_mod.id.version_uuid.hash(&mut hasher);
+                }
+                ProfileModKind::Local(local_mod) => {
+                    local_mod.uuid.hash(&mut hasher);
+                    local_mod.dependencies.hash(&mut hasher);
+                }
+            }
+        }
+
+        // dependencies can't be resolved until the packages are fetched
+        thunderstore.last_fetched(self.game).hash(&mut hasher);
+        thunderstore.packages_fetched().hash(&mut hasher);
+
+        hasher.finish()
+    }
+}
+
+fn reverse_dependencies(
+    mods: &[ProfileMod],
+    thunderstore: &Thunderstore,
+) -> HashMap<Uuid, Vec<usize>> {
+    let mut map: HashMap<Uuid, Vec<usize>> = HashMap::new();
+
+    for (index, profile_mod) in mods.iter().enumerate() {
+        let uuid = profile_mod.uuid();
+
+        for dep in profile_mod.dependencies(thunderstore) {
+            if dep.package.uuid == uuid {
+                continue;
+            }
+
+            let dependants = map.entry(dep.package.uuid).or_default();
+            if dependants.last() != Some(&index) {
+                dependants.push(index);
+            }
+        }
+    }
+
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use itertools::Itertools;
+
+    use super::*;
+    use crate::{
+        profile::tests::{test_mod, test_profile},
+        thunderstore::tests::{test_package, test_thunderstore},
+    };
+
+    fn naive_dependants(profile: &Profile, uuid: Uuid, thunderstore: &Thunderstore) -> Vec<Uuid> {
+        profile
+            .mods
+            .iter()
+            .filter(|other| other.uuid() != uuid)
+            .filter(|other| {
+                other
+                    .dependencies(thunderstore)
+                    .any(|dep| dep.package.uuid == uuid)
+            })
+            .map(ProfileMod::uuid)
+            .collect()
+    }
+
+    #[test]
+    fn large_profile_dependants() {
+        // a core library, a few mid-level libraries on top of it and
+        // hundreds of mods using one of them
+        let mut packages = vec![test_package("Core", &[])];
+        let libs = (0..10).map(|i| format!("Lib{}", i)).collect_vec();
+
+        for lib in &libs {
+            packages.push(test_package(lib, &["Core"]));
+        }
+
+        for i in 0..489 {
+            let name = format!("Mod{}", i);
+            packages.push(test_package(&name, &[&libs[i % libs.len()]]));
+        }
+
+        let mut profile = Profile {
+            name: "Large".to_owned(),
+            mods: packages.iter().map(test_mod).collect(),
+            ..test_profile(PathBuf::new())
+        };
+
+        let thunderstore = test_thunderstore(packages);
+        assert_eq!(profile.mods.len(), 500);
+
+        for profile_mod in &profile.mods {
+            let uuid = profile_mod.uuid();
+            let dependants = profile
+                .dependants(uuid, &thunderstore)
+                .map(ProfileMod::uuid)
+                .collect_vec();
+
+            assert_eq!(dependants, naive_dependants(&profile, uuid, &thunderstore));
+        }
+
+        let core = profile.mods[0].uuid();
+        assert_eq!(profile.dependants(core, &thunderstore).count(), 499);
+
+        // removing a mod rebuilds the map
+        profile.mods.remove(1);
+        assert_eq!(profile.dependants(core, &thunderstore).count(), 498);
+        assert_eq!(
+            profile
+                .dependants(core, &thunderstore)
+                .map(ProfileMod::uuid)
+                .collect_vec(),
+            naive_dependants(&profile, core, &thunderstore)
+        );
+    }
+}
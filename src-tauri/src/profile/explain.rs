@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use eyre::Result;
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::{Dependant, InstallOrigin, Profile, ProfileMod};
+use crate::thunderstore::Thunderstore;
+
+/// The maximum number of dependant chains returned by [`Profile::explain_mod`].
+///
+/// Mods like BepInEx can be depended on through hundreds of paths,
+/// most of which aren't interesting to the user.
+const MAX_CHAINS: usize = 10;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum InstallReason {
+    /// The user installed it.
+    Explicit,
+    /// It was installed for another mod in the profile, which depends on it.
+    Dependency,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModExplanation {
+    pub reason: InstallReason,
+    /// Chains of mods which lead to this one, each starting with an explicitly
+    /// installed mod and ending with the direct dependant.
+    ///
+    /// These are found even for explicitly installed mods.
+    pub chains: Vec<Vec<Dependant>>,
+    /// What added the mod to the profile, or `None` if it was installed
+    /// before this was recorded.
+    pub origin: Option<InstallOrigin>,
+    /// When the mod was added to the profile. This is kept when the mod is updated.
+    pub install_time: DateTime<Utc>,
+}
+
+impl Profile {
+    /// Explains why a mod is installed in this profile.
+    ///
+    /// The reason comes from the recorded [`InstallOrigin`]. Imported mods and mods
+    /// without a recorded origin count as dependencies if any other mod depends on them.
+    ///
+    /// Dependencies are matched by package, so the chains stay
+    /// the same when mods are updated to other versions.
+    pub fn explain_mod(&self, uuid: Uuid, thunderstore: &Thunderstore) -> Result<ModExplanation> {
+        let target = self.get_mod(uuid)?;
+        let dependants = self.direct_dependants(thunderstore);
+
+        let mut chains = Vec::new();
+        let mut path = vec![target];
+        collect_chains(&mut path, &dependants, &mut chains);
+
+        let reason = match target.origin {
+            Some(InstallOrigin::Explicit) => InstallReason::Explicit,
+            Some(InstallOrigin::Dependency | InstallOrigin::Update) => InstallReason::Dependency,
+            Some(InstallOrigin::Import) | None => match chains.is_empty() {
+                true => InstallReason::Explicit,
+                false => InstallReason::Dependency,
+            },
+        };
+
+        Ok(ModExplanation {
+            reason,
+            chains,
+            origin: target.origin,
+            install_time: target.install_time,
+        })
+    }
+
+    /// Maps each mod's uuid to the mods in the profile which directly depend on it.
+    pub(super) fn direct_dependants<'a>(
+        &'a self,
+        thunderstore: &'a Thunderstore,
+    ) -> HashMap<Uuid, Vec<&'a ProfileMod>> {
+        let by_name = self
+            .mods
+            .iter()
+            .map(|profile_mod| (profile_mod.full_name(), profile_mod))
+            .collect::<HashMap<_, _>>();
+
+        let mut result: HashMap<Uuid, Vec<&ProfileMod>> = HashMap::new();
+
+        for profile_mod in &self.mods {
+            for ident in profile_mod.kind.direct_dependencies(thunderstore) {
+                let Some(dependency) = by_name.get(ident.full_name()) else {
+                    continue;
+                };
+
+                if dependency.uuid() != profile_mod.uuid() {
+                    result
+                        .entry(dependency.uuid())
+                        .or_default()
+                        .push(profile_mod);
+                }
+            }
+        }
+
+        result
+    }
+}
+
+/// Walks up the dependant graph from the last mod in `path`,
+/// pushing a chain for every explicitly installed mod that is reached.
+///
+/// Mods already in `path` are skipped to avoid following circular dependencies.
+fn collect_chains<'a>(
+    path: &mut Vec<&'a ProfileMod>,
+    dependants: &HashMap<Uuid, Vec<&'a ProfileMod>>,
+    chains: &mut Vec<Vec<Dependant>>,
+) {
+    let current = path.last().unwrap().uuid();
+
+    let parents = dependants
+        .get(&current)
+        .into_iter()
+        .flatten()
+        .filter(|parent| !path.iter().any(|other| other.uuid() == parent.uuid()))
+        .copied()
+        .collect::<Vec<_>>();
+
+    if parents.is_empty() {
+        if path.len() > 1 {
+            // skip the target mod itself
+            let chain = path[1..]
+                .iter()
+                .rev()
+                .map(|&profile_mod| profile_mod.into());
+            chains.push(chain.collect());
+        }
+
+        return;
+    }
+
+    for parent in parents {
+        if chains.len() >= MAX_CHAINS {
+            return;
+        }
+
+        path.push(parent);
+        collect_chains(path, dependants, chains);
+        path.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        profile::tests::{test_mod, test_profile},
+        thunderstore::tests::{test_package, test_thunderstore},
+    };
+
+    #[test]
+    fn recorded_origin_decides_the_reason() {
+        let library = test_package("Library", &[]);
+        let dependant = test_package("Dependant", &["Library"]);
+
+        let mut profile = test_profile(Default::default());
+        profile.mods = vec![test_mod(&library), test_mod(&dependant)];
+        let library_uuid = library.uuid;
+
+        let thunderstore = test_thunderstore([library, dependant]);
+        let explain = |profile: &Profile| profile.explain_mod(library_uuid, &thunderstore).unwrap();
+
+        // without an origin, it's guessed from the dependants
+        let explanation = explain(&profile);
+        assert_eq!(explanation.reason, InstallReason::Dependency);
+        assert_eq!(explanation.chains.len(), 1);
+
+        profile.mods[0].origin = Some(InstallOrigin::Explicit);
+
+        let explanation = explain(&profile);
+        assert_eq!(explanation.reason, InstallReason::Explicit);
+        assert_eq!(explanation.origin, Some(InstallOrigin::Explicit));
+        assert_eq!(explanation.chains.len(), 1);
+    }
+}
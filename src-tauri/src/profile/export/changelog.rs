@@ -61,7 +61,10 @@ pub(super) fn generate_all(
     // (current version: 1.2.0) [1.1.0, 1.0.0, 0.2.0, 0.1.0]
 
     // first generate diff to current version
-    let current_mods = borrow_mods(profile.mods_to_pack(args).cloned(), thunderstore);
+    let current_mods = borrow_mods(
+        profile.mods_to_pack(args, thunderstore).cloned(),
+        thunderstore,
+    );
     let diff = generate_diff(&snapshots[0].0, &current_mods, game);
 
     push_diff(&mut changelog, &args.version_number, &diff);
@@ -116,7 +119,10 @@ pub(super) fn generate_latest(
     };
 
     let old_mods = borrow_mods(latest_snapshot, thunderstore);
-    let current_mods = borrow_mods(profile.mods_to_pack(args).cloned(), thunderstore);
+    let current_mods = borrow_mods(
+        profile.mods_to_pack(args, thunderstore).cloned(),
+        thunderstore,
+    );
 
     let version_header = format!("## {}", args.version_number);
     let index = match args.changelog.find(&version_header) {
@@ -169,7 +175,11 @@ where
 }
 
 impl Profile {
-    pub(super) fn take_snapshot(&self, args: &ModpackArgs) -> Result<()> {
+    pub(super) fn take_snapshot(
+        &self,
+        args: &ModpackArgs,
+        thunderstore: &Thunderstore,
+    ) -> Result<()> {
         let mut path = self.path.join("snapshots");
         fs::create_dir_all(&path)?;
 
@@ -178,7 +188,7 @@ impl Profile {
 
         util::fs::write_json(
             path,
-            &self.mods_to_pack(args).collect_vec(),
+            &self.mods_to_pack(args, thunderstore).collect_vec(),
             JsonStyle::Compact,
         )
     }
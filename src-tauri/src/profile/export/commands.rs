@@ -7,21 +7,45 @@ use std::{
 use eyre::{anyhow, Context};
 use itertools::Itertools;
 use log::{debug, warn};
+use serde::Serialize;
 use tauri::{command, AppHandle};
 use tauri_plugin_clipboard_manager::ClipboardExt;
 use uuid::Uuid;
 
 use super::{
     changelog,
+    mod_list::{self, DescriptionStyle, ModListFormat},
     modpack::{self, ModpackArgs},
 };
 use crate::{
-    profile::ProfileModKind,
+    profile::{
+        install::{self, InstallOptions},
+        ExportTarget, ProfileModKind,
+    },
     state::ManagerExt,
-    thunderstore::{self},
-    util::{cmd::Result, fs::PathExt},
+    thunderstore::{self, VersionIdent},
+    util::{self, cmd::Result, fs::PathExt},
+    webhook::{self, WebhookEvent},
 };
 
+/// A file written by an export, with a hash of its contents.
+///
+/// Exports are reproducible, so the hash only changes if the profile did.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedFile {
+    path: PathBuf,
+    sha256: String,
+}
+
+impl ExportedFile {
+    fn read(path: PathBuf) -> eyre::Result<Self> {
+        let sha256 = util::fs::hash_file(&path)?;
+
+        Ok(Self { path, sha256 })
+    }
+}
+
 #[command]
 pub async fn export_code(app: AppHandle) -> Result<Uuid> {
     let key = super::export_code(&app).await?;
@@ -29,9 +53,17 @@ pub async fn export_code(app: AppHandle) -> Result<Uuid> {
     Ok(key)
 }
 
+/// Exports the active profile as an `.r2z` file in `dir`.
+///
+/// With a `target`, mods which aren't needed on that side are left out.
 #[command]
-pub fn export_file(dir: PathBuf, app: AppHandle) -> Result<()> {
-    let manager = app.lock_manager();
+pub fn export_file(
+    dir: PathBuf,
+    target: Option<ExportTarget>,
+    app: AppHandle,
+) -> Result<ExportedFile> {
+    let manager = app.lock_manager()?;
+    let thunderstore = app.lock_thunderstore()?;
 
     let profile = manager.active_profile();
 
@@ -39,18 +71,78 @@ pub fn export_file(dir: PathBuf, app: AppHandle) -> Result<()> {
     path.push(&profile.name);
     path.set_extension("r2z");
 
+    let exported = profile.exported_mods(target, &thunderstore);
+
     let file = fs::File::create(&path).map_err(|err| anyhow!(err))?;
     let writer = BufWriter::new(file);
-    super::export_zip(manager.active_profile(), writer)?;
+    super::export_zip(
+        profile,
+        |profile_mod| exported.contains(&profile_mod.uuid()),
+        writer,
+    )?;
 
     open::that(path.parent().unwrap()).ok();
 
-    Ok(())
+    Ok(ExportedFile::read(path)?)
+}
+
+/// Exports a profile with its mods included, so it can be imported offline.
+///
+/// Returns the mods that were left out because they're missing from the cache.
+#[command]
+pub async fn export_offline_archive(
+    profile_id: i64,
+    path: PathBuf,
+    app: AppHandle,
+) -> Result<Vec<VersionIdent>> {
+    install::precache_profile(profile_id, InstallOptions::default(), &app)
+        .await
+        .context("failed to download missing mods")?;
+
+    let export = {
+        let prefs = app.lock_prefs()?;
+        let manager = app.lock_manager()?;
+
+        let profile = manager.active_game().find_profile(profile_id)?;
+        super::OfflineExport::new(profile, &prefs)
+    };
+
+    let missing = export.missing.clone();
+
+    let file = fs::File::create(&path)
+        .map(BufWriter::new)
+        .context("failed to create file")?;
+    export.write(file)?;
+
+    if let Some(parent) = path.parent() {
+        open::that(parent).ok();
+    }
+
+    Ok(missing)
+}
+
+/// Exports the config files of a profile to `path`, without its mods.
+#[command]
+pub fn export_config_bundle(
+    profile_id: i64,
+    path: PathBuf,
+    app: AppHandle,
+) -> Result<ExportedFile> {
+    let manager = app.lock_manager()?;
+
+    let profile = manager.active_game().find_profile(profile_id)?;
+
+    let file = fs::File::create(&path)
+        .map(BufWriter::new)
+        .context("failed to create file")?;
+    super::export_config_bundle(profile, file)?;
+
+    Ok(ExportedFile::read(path)?)
 }
 
 #[command]
 pub fn get_pack_args(app: AppHandle) -> Result<Option<ModpackArgs>> {
-    let mut manager = app.lock_manager();
+    let mut manager = app.lock_manager()?;
     let profile = manager.active_profile_mut();
 
     modpack::refresh_args(profile);
@@ -60,7 +152,7 @@ pub fn get_pack_args(app: AppHandle) -> Result<Option<ModpackArgs>> {
 
 #[command]
 pub fn set_pack_args(args: ModpackArgs, app: AppHandle) -> Result<()> {
-    let mut manager = app.lock_manager();
+    let mut manager = app.lock_manager()?;
 
     let profile = manager.active_profile_mut();
     profile.modpack = Some(args);
@@ -70,9 +162,9 @@ pub fn set_pack_args(args: ModpackArgs, app: AppHandle) -> Result<()> {
 }
 
 #[command]
-pub fn export_pack(dir: PathBuf, args: ModpackArgs, app: AppHandle) -> Result<()> {
-    let mut manager = app.lock_manager();
-    let thunderstore = app.lock_thunderstore();
+pub fn export_pack(dir: PathBuf, args: ModpackArgs, app: AppHandle) -> Result<ExportedFile> {
+    let mut manager = app.lock_manager()?;
+    let thunderstore = app.lock_thunderstore()?;
 
     let profile = manager.active_profile_mut();
 
@@ -89,20 +181,37 @@ pub fn export_pack(dir: PathBuf, args: ModpackArgs, app: AppHandle) -> Result<()
 
     debug!("taking snapshot of profile");
 
-    if let Err(err) = profile.take_snapshot(&args) {
+    if let Err(err) = profile.take_snapshot(&args, &thunderstore) {
         warn!("failed to take profile snapshot: {}", err);
     }
 
-    open::that(path).ok();
+    open::that(&path).ok();
 
-    Ok(())
+    Ok(ExportedFile::read(path)?)
+}
+
+/// Finds the mods that would be included in the pack, but are deprecated on Thunderstore.
+#[command]
+pub fn find_deprecated_pack_mods(args: ModpackArgs, app: AppHandle) -> Result<Vec<VersionIdent>> {
+    let manager = app.lock_manager()?;
+    let thunderstore = app.lock_thunderstore()?;
+
+    let deprecated = manager
+        .active_profile()
+        .mods_to_pack(&args, &thunderstore)
+        .filter_map(|mod_ref| mod_ref.borrow(&thunderstore).ok())
+        .filter(|borrowed| borrowed.package.is_deprecated)
+        .map(|borrowed| borrowed.ident().clone())
+        .collect();
+
+    Ok(deprecated)
 }
 
 #[command]
 pub async fn upload_pack(args: ModpackArgs, app: AppHandle) -> Result<()> {
-    let (data, game, args, token) = {
-        let manager = app.lock_manager();
-        let thunderstore = app.lock_thunderstore();
+    let (data, game, profile_id, args, token) = {
+        let manager = app.lock_manager()?;
+        let thunderstore = app.lock_thunderstore()?;
 
         let token = thunderstore::token::get()
             .context("failed to get thunderstore API token")?
@@ -113,27 +222,55 @@ pub async fn upload_pack(args: ModpackArgs, app: AppHandle) -> Result<()> {
         let mut data = Cursor::new(Vec::new());
         profile.export_pack(&args, &mut data, &thunderstore)?;
 
-        if let Err(err) = profile.take_snapshot(&args) {
+        if let Err(err) = profile.take_snapshot(&args, &thunderstore) {
             warn!("failed to take profile snapshot: {}", err);
         }
 
-        (data, manager.active_game, args, token)
+        (data, manager.active_game, profile.id, args, token)
+    };
+
+    let event = WebhookEvent::PackUpload {
+        name: args.name.clone(),
+        version: args.version_number.clone(),
     };
 
-    let client = app.http().clone();
+    let client = app.http();
     modpack::publish(data.into_inner().into(), game, args, token, client).await?;
 
+    webhook::dispatch(event, profile_id, &app);
+
     Ok(())
 }
 
+/// Generates a manifest from the active profile as pretty-printed JSON.
 #[command]
-pub fn copy_dependency_strings(app: AppHandle) -> Result<()> {
-    let manager = app.lock_manager();
+pub fn generate_manifest(include_disabled: Option<bool>, app: AppHandle) -> Result<String> {
+    let manager = app.lock_manager()?;
 
-    let content = manager
+    let manifest = manager
         .active_profile()
+        .generate_manifest(include_disabled.unwrap_or(false));
+
+    let json = serde_json::to_string_pretty(&manifest).context("failed to serialize manifest")?;
+
+    Ok(json)
+}
+
+/// Copies the dependency strings of the active profile's mods to the clipboard.
+///
+/// With a `target`, mods which aren't needed on that side are left out.
+#[command]
+pub fn copy_dependency_strings(target: Option<ExportTarget>, app: AppHandle) -> Result<()> {
+    let manager = app.lock_manager()?;
+    let thunderstore = app.lock_thunderstore()?;
+
+    let profile = manager.active_profile();
+    let exported = profile.exported_mods(target, &thunderstore);
+
+    let content = profile
         .mods
         .iter()
+        .filter(|profile_mod| exported.contains(&profile_mod.uuid()))
         .map(|profile_mod| profile_mod.ident())
         .join("\n");
 
@@ -144,9 +281,59 @@ pub fn copy_dependency_strings(app: AppHandle) -> Result<()> {
     Ok(())
 }
 
+/// Copies a human readable list of the active profile's mods to the clipboard,
+/// for example to post on a forum.
+#[command]
+pub fn copy_mod_list(
+    format: ModListFormat,
+    include_disabled: Option<bool>,
+    app: AppHandle,
+) -> Result<()> {
+    let manager = app.lock_manager()?;
+    let thunderstore = app.lock_thunderstore()?;
+
+    let entries = mod_list::entries(
+        manager.active_profile(),
+        include_disabled.unwrap_or(false),
+        &thunderstore,
+    );
+
+    app.clipboard()
+        .write_text(mod_list::generate(&entries, format))
+        .context("failed to write to clipboard")?;
+
+    Ok(())
+}
+
+/// Copies a description of the active profile to the clipboard, meant to be
+/// posted as a Steam Workshop collection or Discord message.
+#[command]
+pub fn copy_collection_description(
+    style: DescriptionStyle,
+    include_disabled: Option<bool>,
+    app: AppHandle,
+) -> Result<()> {
+    let manager = app.lock_manager()?;
+    let thunderstore = app.lock_thunderstore()?;
+
+    let profile = manager.active_profile();
+    let entries = mod_list::entries(profile, include_disabled.unwrap_or(false), &thunderstore);
+
+    app.clipboard()
+        .write_text(mod_list::collection_description(
+            &profile.name,
+            profile.game,
+            &entries,
+            style,
+        ))
+        .context("failed to write to clipboard")?;
+
+    Ok(())
+}
+
 #[command]
 pub fn copy_debug_info(app: AppHandle) -> Result<()> {
-    let manager = app.lock_manager();
+    let manager = app.lock_manager()?;
     let profile = manager.active_profile();
 
     let log = profile
@@ -154,9 +341,10 @@ pub fn copy_debug_info(app: AppHandle) -> Result<()> {
         .and_then(|path| fs::read_to_string(path).map_err(|err| anyhow!(err)));
 
     let content = format!(
-        "OS: {}\nGale version: {}\n\nMods ({}):\n{}\n\nLatest log:\n{}",
+        "OS: {}\nGale version: {}\nThunderstore API: {}\n\nMods ({}):\n{}\n\nLatest log:\n{}",
         std::env::consts::OS,
         env!("CARGO_PKG_VERSION"),
+        thunderstore::api::stats(),
         profile.mods.len(),
         profile
             .mods
@@ -185,8 +373,8 @@ pub fn copy_debug_info(app: AppHandle) -> Result<()> {
 
 #[command]
 pub fn generate_changelog(mut args: ModpackArgs, all: bool, app: AppHandle) -> Result<String> {
-    let manager = app.lock_manager();
-    let thunderstore = app.lock_thunderstore();
+    let manager = app.lock_manager()?;
+    let thunderstore = app.lock_thunderstore()?;
 
     if all {
         let changelog = changelog::generate_all(
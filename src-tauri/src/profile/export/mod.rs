@@ -2,27 +2,46 @@ use std::{
     fmt::Display,
     fs::File,
     io::{self, Cursor, Seek, Write},
+    iter,
     path::{Path, PathBuf},
 };
 
 use base64::{prelude::BASE64_STANDARD, Engine};
-use eyre::{anyhow, Context};
+use bytes::Bytes;
+use eyre::{anyhow, bail, ensure, Context};
+use futures_util::{stream, Stream};
+use itertools::Itertools;
+use log::{info, warn};
+use reqwest::{header::CONTENT_LENGTH, StatusCode};
 use serde::{Deserialize, Serialize};
 use tauri::AppHandle;
 use uuid::Uuid;
 use walkdir::WalkDir;
-use zip::{write::SimpleFileOptions, ZipWriter};
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
 
-use super::{install::ModInstall, Profile, Result};
+use super::{
+    install::{self, ModInstall},
+    InstallOrigin, ModSide, Profile, ProfileMod, Result,
+};
 use crate::{
+    game::Game,
+    prefs::Prefs,
     state::ManagerExt,
-    thunderstore::{LegacyProfileCreateResponse, ModId, Thunderstore},
+    thunderstore::{
+        api::{self, Endpoint},
+        LegacyProfileCreateResponse, ModId, Thunderstore, VersionIdent,
+    },
+    util,
 };
 
 mod changelog;
 pub mod commands;
+pub mod mod_list;
 pub mod modpack;
 
+#[cfg(test)]
+mod tests;
+
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct LegacyProfileManifest {
@@ -32,6 +51,26 @@ pub struct LegacyProfileManifest {
     pub source: ImportSource,
     #[serde(default)]
     pub ignored_updates: Vec<Uuid>,
+    /// Only set by Gale, other managers leave it out.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<ExportOrigin>,
+}
+
+/// The game and mod loader a profile was exported from.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportOrigin {
+    pub game: String,
+    pub mod_loader: String,
+}
+
+impl ExportOrigin {
+    pub fn new(game: Game) -> Self {
+        Self {
+            game: game.slug.to_string(),
+            mod_loader: game.mod_loader.to_str().to_owned(),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
@@ -49,27 +88,69 @@ pub struct R2Mod {
     #[serde(alias = "versionNumber")]
     pub version: R2Version,
     pub enabled: bool,
+    /// The side the user set in Gale, which r2modman doesn't know about.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub side: Option<ModSide>,
+}
+
+/// A mod which is imported with another version than the one in the profile,
+/// since that version is no longer available on Thunderstore.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionSubstitution {
+    pub full_name: String,
+    pub requested: String,
+    pub substitute: String,
 }
 
 impl R2Mod {
-    pub fn into_install(self, thunderstore: &Thunderstore) -> Result<ModInstall> {
+    /// Finds the mod on Thunderstore.
+    ///
+    /// If the exact version has been delisted, the nearest available version is
+    /// used instead and returned as a [`VersionSubstitution`]. This only fails
+    /// if the package itself can't be found.
+    pub fn into_install(
+        self,
+        thunderstore: &Thunderstore,
+    ) -> Result<(ModInstall, Option<VersionSubstitution>)> {
         let package = thunderstore.find_package(&self.full_name)?;
 
-        let version = self.version.to_string();
-        let version = package.get_version_with_num(&version).ok_or_else(|| {
-            anyhow!(
-                "failed to find version {} for package {}",
-                version,
-                self.full_name
-            )
-        })?;
+        let requested = self.version.to_string();
+        let (version, substitution) = match package.get_version_with_num(&requested) {
+            Some(version) => (version, None),
+            None => {
+                let version = package
+                    .get_nearest_version(&self.version.clone().into())
+                    .ok_or_else(|| anyhow!("package {} has no versions", self.full_name))?;
+
+                warn!(
+                    "version {} of {} is unavailable, using {} instead",
+                    requested,
+                    self.full_name,
+                    version.version()
+                );
+
+                let substitution = VersionSubstitution {
+                    full_name: self.full_name.clone(),
+                    requested,
+                    substitute: version.version().to_owned(),
+                };
+
+                (version, Some(substitution))
+            }
+        };
 
         let id = ModId {
             package_uuid: package.uuid,
             version_uuid: version.uuid,
         };
 
-        Ok(ModInstall::new(id).with_state(self.enabled))
+        let install = ModInstall::new(id)
+            .with_state(self.enabled)
+            .with_side(self.side)
+            .with_origin(Some(InstallOrigin::Import));
+
+        Ok((install, substitution))
     }
 
     pub fn ident(&self) -> String {
@@ -80,7 +161,7 @@ impl R2Mod {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct R2Version {
     pub major: u64,
@@ -94,6 +175,12 @@ impl Display for R2Version {
     }
 }
 
+impl From<R2Version> for semver::Version {
+    fn from(value: R2Version) -> Self {
+        semver::Version::new(value.major, value.minor, value.patch)
+    }
+}
+
 impl From<semver::Version> for R2Version {
     fn from(value: semver::Version) -> Self {
         Self {
@@ -106,12 +193,187 @@ impl From<semver::Version> for R2Version {
 
 pub const PROFILE_DATA_PREFIX: &str = "#r2modman\n";
 
-fn export_zip(profile: &Profile, writer: impl Write + Seek) -> Result<()> {
+/// File at the root of config bundles, holding the [`ExportOrigin`] of the bundle.
+pub const CONFIG_BUNDLE_MARKER: &str = "config_bundle.json";
+
+/// Directory in offline archives which holds the extracted packages,
+/// laid out the same way as the mod cache (`<full_name>/<version>`).
+pub const OFFLINE_MODS_DIR: &str = "_offline_mods";
+
+/// The largest profile code payload we try to upload, including the base64
+/// encoding. Thunderstore rejects larger legacy profiles.
+const CODE_SIZE_LIMIT: usize = 10 * 1024 * 1024;
+
+/// Offline archives larger than this get a warning in the log.
+const OFFLINE_SIZE_WARNING: u64 = 2 * 1024 * 1024 * 1024;
+
+/// Offline archives may not be larger than this.
+const OFFLINE_SIZE_LIMIT: u64 = 16 * 1024 * 1024 * 1024;
+
+/// Exports a profile in the `.r2z` format, with the mods for which `include` returns true.
+fn export_zip(
+    profile: &Profile,
+    include: impl Fn(&ProfileMod) -> bool,
+    writer: impl Write + Seek,
+) -> Result<()> {
+    let mut zip = ZipWriter::new(writer);
+    write_profile(
+        profile,
+        include,
+        find_default_config(&profile.path).sorted(),
+        &mut zip,
+    )?;
+
+    zip.finish()?;
+
+    Ok(())
+}
+
+/// Exports a profile to be shared as a code, leaving out files which
+/// only bloat the code (see [`is_code_excluded`]).
+fn export_code_zip(profile: &Profile, writer: impl Write + Seek) -> Result<()> {
+    let config = find_default_config(&profile.path)
+        .filter(|path| !is_code_excluded(path))
+        .sorted();
+
     let mut zip = ZipWriter::new(writer);
+    write_profile(profile, |_| true, config, &mut zip)?;
+
+    zip.finish()?;
+
+    Ok(())
+}
+
+/// Whether a file is irrelevant to other users, like the logs and
+/// backups some mods leave next to their config.
+fn is_code_excluded(path: &Path) -> bool {
+    let in_logs_dir = path
+        .parent()
+        .is_some_and(|parent| parent.iter().any(|dir| dir.eq_ignore_ascii_case("logs")));
+
+    let has_excluded_ext = path.extension().is_some_and(|ext| {
+        ["log", "old", "bak"]
+            .iter()
+            .any(|excluded| ext.eq_ignore_ascii_case(excluded))
+    });
+
+    in_logs_dir || has_excluded_ext
+}
+
+/// Exports a profile in the regular `.r2z` format, but with the extracted files
+/// of every Thunderstore mod included, so it can be imported without an
+/// internet connection.
+///
+/// The mods are read from the cache, so make sure they've been
+/// downloaded beforehand (see [`install::precache_profile`]).
+///
+/// Everything is gathered up front by [`OfflineExport::new`], so the profile
+/// doesn't have to stay locked while the archive is written.
+struct OfflineExport {
+    manifest: LegacyProfileManifest,
+    profile_path: PathBuf,
+    config: Vec<PathBuf>,
+    packages: Vec<(VersionIdent, PathBuf)>,
+    /// Mods that are missing from the cache, which are left out.
+    missing: Vec<VersionIdent>,
+}
+
+impl OfflineExport {
+    fn new(profile: &Profile, prefs: &Prefs) -> Self {
+        let (packages, missing): (Vec<_>, Vec<_>) = profile
+            .thunderstore_mods()
+            .map(|(ts_mod, _)| {
+                (
+                    ts_mod.ident.clone(),
+                    install::cache_path(&ts_mod.ident, prefs),
+                )
+            })
+            .partition(|(_, path)| path.exists());
+
+        let missing = missing.into_iter().map(|(ident, _)| ident).collect_vec();
+
+        if !missing.is_empty() {
+            warn!(
+                "{} missing from the cache, skipping",
+                missing.iter().join(", ")
+            );
+        }
+
+        Self {
+            manifest: profile_manifest(profile, |_| true),
+            profile_path: profile.path.clone(),
+            config: find_default_config(&profile.path).sorted().collect(),
+            packages,
+            missing,
+        }
+    }
+
+    fn write(self, writer: impl Write + Seek) -> Result<()> {
+        let total_size: u64 = self
+            .packages
+            .iter()
+            .map(|(_, path)| util::fs::get_directory_size(path))
+            .sum();
+
+        ensure!(
+            total_size <= OFFLINE_SIZE_LIMIT,
+            "profile is too large to export offline ({} GB, the limit is {} GB)",
+            total_size / 1_000_000_000,
+            OFFLINE_SIZE_LIMIT / 1_000_000_000
+        );
+
+        if total_size > OFFLINE_SIZE_WARNING {
+            warn!(
+                "exporting {} MB of mods to an offline archive, this may take a while",
+                total_size / 1_000_000
+            );
+        }
+
+        let mut zip = ZipWriter::new(writer);
+        write_manifest(&self.manifest, &mut zip)?;
+        write_config(self.config.into_iter(), &self.profile_path, &mut zip)?;
+
+        for (ident, path) in self.packages {
+            let prefix = Path::new(OFFLINE_MODS_DIR)
+                .join(ident.full_name())
+                .join(ident.version());
+
+            util::zip::write_dir(&path, &prefix, CompressionMethod::Deflated, &mut zip)
+                .with_context(|| format!("failed to write {} to archive", ident))?;
+        }
+
+        zip.finish()?;
+
+        Ok(())
+    }
+}
+
+fn write_profile<W: Write + Seek>(
+    profile: &Profile,
+    include: impl Fn(&ProfileMod) -> bool,
+    config: impl Iterator<Item = PathBuf>,
+    zip: &mut ZipWriter<W>,
+) -> Result<()> {
+    write_manifest(&profile_manifest(profile, include), zip)?;
+    write_config(config, &profile.path, zip)?;
+
+    Ok(())
+}
 
+fn profile_manifest(
+    profile: &Profile,
+    include: impl Fn(&ProfileMod) -> bool,
+) -> LegacyProfileManifest {
     let mods = profile
-        .thunderstore_mods()
-        .map(|(ts_mod, enabled)| {
+        .mods
+        .iter()
+        .filter(|profile_mod| include(profile_mod))
+        .filter_map(|profile_mod| {
+            profile_mod
+                .as_thunderstore()
+                .map(|(ts_mod, enabled)| (ts_mod, enabled, profile_mod.side))
+        })
+        .map(|(ts_mod, enabled, side)| {
             let full_name = ts_mod.ident.full_name().to_string();
             let version = ts_mod
                 .ident
@@ -124,50 +386,83 @@ fn export_zip(profile: &Profile, writer: impl Write + Seek) -> Result<()> {
                 full_name,
                 version,
                 enabled,
+                side,
             }
         })
         .collect();
 
-    let manifest = LegacyProfileManifest {
+    LegacyProfileManifest {
         profile_name: profile.name.clone(),
-        ignored_updates: profile.ignored_updates.iter().cloned().collect(),
+        ignored_updates: profile.ignored_updates.iter().copied().sorted().collect(),
         source: ImportSource::Gale,
+        origin: Some(ExportOrigin::new(profile.game)),
         mods,
-    };
+    }
+}
 
-    zip.start_file("export.r2x", SimpleFileOptions::default())?;
-    serde_yaml::to_writer(&mut zip, &manifest).context("failed to write profile manifest")?;
+fn write_manifest<W: Write + Seek>(
+    manifest: &LegacyProfileManifest,
+    zip: &mut ZipWriter<W>,
+) -> Result<()> {
+    zip.start_file("export.r2x", util::zip::file_options())?;
+    serde_yaml::to_writer(&mut *zip, manifest).context("failed to write profile manifest")?;
 
-    write_config(find_default_config(&profile.path), &profile.path, &mut zip)?;
+    Ok(())
+}
+
+/// Exports just the config files of a profile, without a mod list, so they can
+/// be applied to other profiles of the same game.
+fn export_config_bundle(profile: &Profile, writer: impl Write + Seek) -> Result<()> {
+    let mut zip = ZipWriter::new(writer);
+
+    zip.start_file(CONFIG_BUNDLE_MARKER, util::zip::file_options())?;
+    serde_json::to_writer_pretty(&mut zip, &ExportOrigin::new(profile.game))
+        .context("failed to write bundle origin")?;
+
+    write_config(
+        find_default_config(&profile.path).sorted(),
+        &profile.path,
+        &mut zip,
+    )?;
+
+    zip.finish()?;
 
     Ok(())
 }
 
 async fn export_code(app: &AppHandle) -> Result<Uuid> {
-    let base64 = {
-        let mut manager = app.lock_manager();
+    let data = {
+        let mut manager = app.lock_manager()?;
 
         let profile = manager.active_profile_mut();
         profile.refresh_config();
 
         let mut data = Cursor::new(Vec::new());
-        export_zip(profile, &mut data)?;
-
-        let mut base64 = String::from(PROFILE_DATA_PREFIX);
-        base64.push_str(&BASE64_STANDARD.encode(data.get_ref()));
+        export_code_zip(profile, &mut data)?;
 
-        base64
+        Bytes::from(data.into_inner())
     };
 
+    let size = check_code_size(&data, CODE_SIZE_LIMIT)?;
+    info!("uploading profile code of {} KB", size / 1000);
+
     const URL: &str = "https://thunderstore.io/api/experimental/legacyprofile/create/";
 
-    let response = app
-        .http()
-        .post(URL)
-        .header("Content-Type", "application/octet-stream")
-        .body(base64)
-        .send()
-        .await?
+    // the streamed payload can't be cloned, so it's encoded again on retries
+    let response = api::send_rebuilt(Endpoint::Profile, || {
+        app.http()
+            .post(URL)
+            .header("Content-Type", "application/octet-stream")
+            .header(CONTENT_LENGTH, size)
+            .body(reqwest::Body::wrap_stream(code_payload(data.clone())))
+    })
+    .await?;
+
+    if response.status() == StatusCode::PAYLOAD_TOO_LARGE {
+        bail!("profile is too large to share as a code, export it as a file instead");
+    }
+
+    let response = response
         .error_for_status()?
         .json::<LegacyProfileCreateResponse>()
         .await?;
@@ -175,6 +470,59 @@ async fn export_code(app: &AppHandle) -> Result<Uuid> {
     Ok(response.key)
 }
 
+/// The size of the profile code payload of an exported zip of `len` bytes.
+fn code_payload_size(len: usize) -> usize {
+    PROFILE_DATA_PREFIX.len() + base64::encoded_len(len, true).expect("payload size overflows")
+}
+
+/// Checks that a profile code doesn't exceed `limit`, returning the size of its payload.
+///
+/// If it does, the error lists the files which take up the most space.
+fn check_code_size(data: &[u8], limit: usize) -> Result<usize> {
+    let size = code_payload_size(data.len());
+
+    if size <= limit {
+        return Ok(size);
+    }
+
+    let mut archive = ZipArchive::new(Cursor::new(data))?;
+    let mut files = Vec::with_capacity(archive.len());
+
+    for i in 0..archive.len() {
+        let file = archive.by_index_raw(i)?;
+        files.push((file.name().to_owned(), file.compressed_size()));
+    }
+
+    let largest = files
+        .into_iter()
+        .sorted_by(|(_, a), (_, b)| b.cmp(a))
+        .take(5)
+        .map(|(name, size)| format!("{} ({} KB)", name, size.div_ceil(1000)))
+        .join(", ");
+
+    bail!(
+        "profile is too large to share as a code ({} MB, the limit is {} MB), export it as a file instead. The largest files are: {}",
+        size / 1_000_000,
+        limit / 1_000_000,
+        largest
+    );
+}
+
+/// Encodes a profile code payload in chunks as it's sent, instead of
+/// building the whole base64 string up front.
+fn code_payload(data: Bytes) -> impl Stream<Item = io::Result<Bytes>> {
+    // a multiple of 3, so no chunk but the last is padded
+    const CHUNK_SIZE: usize = 3 * 64 * 1024;
+
+    let prefix = Bytes::from_static(PROFILE_DATA_PREFIX.as_bytes());
+    let chunks = (0..data.len()).step_by(CHUNK_SIZE).map(move |start| {
+        let end = (start + CHUNK_SIZE).min(data.len());
+        Bytes::from(BASE64_STANDARD.encode(&data[start..end]))
+    });
+
+    stream::iter(iter::once(prefix).chain(chunks).map(Ok))
+}
+
 fn write_config<P, I, W>(files: I, source: &Path, zip: &mut ZipWriter<W>) -> Result<()>
 where
     P: AsRef<Path>,
@@ -183,7 +531,7 @@ where
 {
     for file in files {
         let path = file.as_ref().to_string_lossy().replace('\\', "/");
-        zip.start_file(path, SimpleFileOptions::default())?;
+        zip.start_file(path, util::zip::file_options())?;
 
         let mut reader = File::open(source.join(file))?;
 
@@ -202,6 +550,10 @@ const GENERATED_FILES: &[&str] = &[
     "doorstop_config.ini",
     "snapshots",
     "_state",
+    OFFLINE_MODS_DIR,
+    CONFIG_BUNDLE_MARKER,
+    // files of disabled mods, see install::DisableStrategy::Stage
+    install::DISABLED_STAGING,
 ];
 
 pub enum IncludeExtensions {
@@ -0,0 +1,235 @@
+use std::fmt::Write;
+
+use itertools::Itertools;
+use serde::Deserialize;
+
+use crate::{
+    game::Game,
+    profile::{Profile, ProfileMod},
+    thunderstore::Thunderstore,
+};
+
+/// The format of a human readable mod list.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ModListFormat {
+    Markdown,
+    Bbcode,
+    /// One mod per line, with the url at the end.
+    Plain,
+    /// A spreadsheet with a header row.
+    Csv,
+}
+
+/// A mod in a [`generate`]d list.
+#[derive(Debug, Clone)]
+pub struct ModListEntry {
+    pub owner: String,
+    pub name: String,
+    pub version: String,
+    pub enabled: bool,
+    /// Total downloads of the package. `None` for local mods.
+    pub downloads: Option<u32>,
+    /// The Thunderstore page of the package. `None` for local mods.
+    pub url: Option<String>,
+    /// The description of the installed version. `None` for local mods.
+    pub description: Option<String>,
+}
+
+impl ModListEntry {
+    fn new(profile_mod: &ProfileMod, game: Game, thunderstore: &Thunderstore) -> Self {
+        let ident = profile_mod.ident();
+
+        let borrowed = profile_mod
+            .kind
+            .as_thunderstore()
+            .and_then(|ts_mod| ts_mod.id.borrow(thunderstore).ok());
+        let package = borrowed.map(|borrowed| borrowed.package);
+
+        Self {
+            owner: ident.owner().to_owned(),
+            name: ident.name().to_owned(),
+            version: ident.version().to_owned(),
+            enabled: profile_mod.enabled,
+            downloads: package.map(|package| package.total_downloads()),
+            url: package.map(|package| package.url(game)),
+            description: borrowed.map(|borrowed| borrowed.version.description.to_string()),
+        }
+    }
+
+    /// The name, owner and version, like `LethalLib by Evaisa (0.16.1)`.
+    fn title(&self, name: &str) -> String {
+        let mut title = name.to_owned();
+
+        if !self.owner.is_empty() {
+            write!(title, " by {}", self.owner).unwrap();
+        }
+
+        if !self.version.is_empty() {
+            write!(title, " ({})", self.version).unwrap();
+        }
+
+        if !self.enabled {
+            title.push_str(" [disabled]");
+        }
+
+        title
+    }
+}
+
+/// Collects the mods of `profile`, in load order.
+pub fn entries(
+    profile: &Profile,
+    include_disabled: bool,
+    thunderstore: &Thunderstore,
+) -> Vec<ModListEntry> {
+    profile
+        .mods
+        .iter()
+        .filter(|profile_mod| include_disabled || profile_mod.enabled)
+        .map(|profile_mod| ModListEntry::new(profile_mod, profile.game, thunderstore))
+        .collect()
+}
+
+pub fn generate(entries: &[ModListEntry], format: ModListFormat) -> String {
+    match format {
+        ModListFormat::Markdown => entries
+            .iter()
+            .map(|entry| {
+                let name = match &entry.url {
+                    Some(url) => format!("[{}]({})", entry.name, url),
+                    None => entry.name.clone(),
+                };
+
+                format!("- {}", entry.title(&name))
+            })
+            .join("\n"),
+        ModListFormat::Bbcode => {
+            let items = entries.iter().map(|entry| {
+                let name = match &entry.url {
+                    Some(url) => format!("[url={}]{}[/url]", url, entry.name),
+                    None => entry.name.clone(),
+                };
+
+                format!("[*]{}", entry.title(&name))
+            });
+
+            itertools::chain!(["[list]".to_owned()], items, ["[/list]".to_owned()]).join("\n")
+        }
+        ModListFormat::Plain => entries
+            .iter()
+            .map(|entry| match &entry.url {
+                Some(url) => format!("{} - {}", entry.title(&entry.name), url),
+                None => entry.title(&entry.name),
+            })
+            .join("\n"),
+        ModListFormat::Csv => {
+            let rows = entries.iter().map(|entry| {
+                [
+                    csv_field(&entry.owner),
+                    csv_field(&entry.name),
+                    csv_field(&entry.version),
+                    entry.enabled.to_string(),
+                    entry.downloads.map(|n| n.to_string()).unwrap_or_default(),
+                    csv_field(entry.url.as_deref().unwrap_or_default()),
+                ]
+                .join(",")
+            });
+
+            itertools::chain!(
+                ["owner,name,version,enabled,downloads,url".to_owned()],
+                rows
+            )
+            .join("\n")
+        }
+    }
+}
+
+/// Where a [`collection_description`] is going to be posted.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum DescriptionStyle {
+    /// A Steam Workshop collection, which uses BBCode.
+    Workshop,
+    /// A Discord message, which uses Markdown without tables.
+    Discord,
+}
+
+impl DescriptionStyle {
+    fn heading(self, text: &str) -> String {
+        match self {
+            DescriptionStyle::Workshop => format!("[h1]{}[/h1]", text),
+            DescriptionStyle::Discord => format!("# {}", text),
+        }
+    }
+
+    fn bold(self, text: &str) -> String {
+        match self {
+            DescriptionStyle::Workshop => format!("[b]{}[/b]", text),
+            DescriptionStyle::Discord => format!("**{}**", text),
+        }
+    }
+
+    fn italic(self, text: &str) -> String {
+        match self {
+            DescriptionStyle::Workshop => format!("[i]{}[/i]", text),
+            DescriptionStyle::Discord => format!("*{}*", text),
+        }
+    }
+
+    fn link(self, url: &str) -> String {
+        match self {
+            DescriptionStyle::Workshop => format!("[url={}]View on Thunderstore[/url]", url),
+            // the brackets stop Discord from embedding every link
+            DescriptionStyle::Discord => format!("<{}>", url),
+        }
+    }
+}
+
+/// Writes a description of a whole profile for sharing outside of Thunderstore,
+/// with a header, a paragraph per mod and a footer.
+pub fn collection_description(
+    profile_name: &str,
+    game: Game,
+    entries: &[ModListEntry],
+    style: DescriptionStyle,
+) -> String {
+    let count = match entries.len() {
+        1 => "1 mod".to_owned(),
+        n => format!("{} mods", n),
+    };
+
+    let header = format!(
+        "{}\nA collection of {} for {}, in load order.",
+        style.heading(profile_name),
+        count,
+        game.name
+    );
+
+    let paragraphs = entries.iter().map(|entry| {
+        let mut paragraph = entry.title(&style.bold(&entry.name));
+
+        if let Some(description) = entry.description.as_deref().filter(|desc| !desc.is_empty()) {
+            write!(paragraph, "\n{}", description).unwrap();
+        }
+
+        if let Some(url) = &entry.url {
+            write!(paragraph, "\n{}", style.link(url)).unwrap();
+        }
+
+        paragraph
+    });
+
+    let footer = style.italic("Install them all at once by importing this profile into Gale.");
+
+    itertools::chain!([header], paragraphs, [footer]).join("\n\n")
+}
+
+/// Quotes a field if it contains a separator, quote or newline, as per RFC 4180.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
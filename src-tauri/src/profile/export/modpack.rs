@@ -15,9 +15,17 @@ use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 use tauri::Url;
 use uuid::Uuid;
-use zip::{write::SimpleFileOptions, ZipWriter};
-
-use crate::{game::Game, profile::Profile, thunderstore::*};
+use zip::ZipWriter;
+
+use crate::{
+    game::Game,
+    profile::{ExportTarget, Profile, ProfileMod},
+    thunderstore::{
+        api::{self, Endpoint},
+        *,
+    },
+    util::{self, cmd::UserError},
+};
 
 pub fn refresh_args(profile: &mut Profile) {
     if profile.modpack.is_none() {
@@ -57,14 +65,55 @@ pub struct ModpackArgs {
     pub include_disabled: bool,
     #[serde(default, rename = "includeFileMap")]
     pub include_files: HashMap<PathBuf, bool>,
+    /// Leaves out mods that aren't needed on this side, see [`ModSide`](crate::profile::ModSide).
+    #[serde(default)]
+    pub target: Option<ExportTarget>,
 }
 
 impl Profile {
+    /// Builds a manifest for a modpack of this profile, for the user to edit.
+    ///
+    /// The name and version are taken from the saved modpack arguments if there are any.
+    pub fn generate_manifest(&self, include_disabled: bool) -> PackageManifest {
+        let args = self.modpack.as_ref();
+
+        let name = args
+            .map(|args| args.name.clone())
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| self.name.replace([' ', '-'], ""));
+
+        let version_number = args
+            .and_then(|args| semver::Version::parse(&args.version_number).ok())
+            .unwrap_or_else(|| semver::Version::new(1, 0, 0));
+
+        let dependencies = self
+            .thunderstore_mods()
+            .filter(|(_, enabled)| include_disabled || *enabled)
+            .map(|(ts_mod, _)| ts_mod.ident.clone())
+            .collect();
+
+        PackageManifest {
+            name,
+            author: None,
+            description: "Describe your modpack here.".to_owned(),
+            version_number,
+            dependencies,
+            website_url: String::new(),
+            installers: None,
+        }
+    }
+
     pub(super) fn mods_to_pack<'a>(
         &'a self,
         args: &'a ModpackArgs,
+        thunderstore: &'a Thunderstore,
     ) -> impl Iterator<Item = &'a ModId> + 'a {
-        self.thunderstore_mods()
+        let exported = self.exported_mods(args.target, thunderstore);
+
+        self.mods
+            .iter()
+            .filter(move |profile_mod| exported.contains(&profile_mod.uuid()))
+            .filter_map(ProfileMod::as_thunderstore)
             .filter(move |(_, enabled)| args.include_disabled || *enabled)
             .map(|(ts_mod, _)| &ts_mod.id)
     }
@@ -79,7 +128,7 @@ impl Profile {
         ensure!(!args.description.is_empty(), "description cannot be empty");
 
         let deps = self
-            .mods_to_pack(args)
+            .mods_to_pack(args, thunderstore)
             .map(|mod_ref| {
                 let borrowed = mod_ref.borrow(thunderstore)?;
                 Ok(borrowed.version.ident.clone())
@@ -104,18 +153,18 @@ impl Profile {
 
         if !args.readme.is_empty() {
             trace!("writing readme");
-            zip.start_file("README.md", SimpleFileOptions::default())?;
+            zip.start_file("README.md", util::zip::file_options())?;
             zip.write_all(args.readme.as_bytes())?;
         }
 
         if !args.changelog.is_empty() {
             trace!("writing changelog");
-            zip.start_file("CHANGELOG.md", SimpleFileOptions::default())?;
+            zip.start_file("CHANGELOG.md", util::zip::file_options())?;
             zip.write_all(args.changelog.as_bytes())?;
         }
 
         trace!("writing manifest");
-        zip.start_file("manifest.json", SimpleFileOptions::default())?;
+        zip.start_file("manifest.json", util::zip::file_options())?;
         serde_json::to_writer_pretty(&mut zip, &manifest)?;
 
         write_icon(&args.icon_path, &mut zip).context("failed to write icon")?;
@@ -124,11 +173,14 @@ impl Profile {
             args.include_files
                 .iter()
                 .filter(|(_, enabled)| **enabled)
-                .map(|(file, _)| file),
+                .map(|(file, _)| file)
+                .sorted(),
             &self.path,
             &mut zip,
         )?;
 
+        zip.finish()?;
+
         Ok(())
     }
 }
@@ -142,7 +194,7 @@ where
 
     let mut bytes = Vec::new();
     img.write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)?;
-    zip.start_file("icon.png", SimpleFileOptions::default())?;
+    zip.start_file("icon.png", util::zip::file_options())?;
     zip.write_all(&bytes)?;
 
     Ok(())
@@ -221,16 +273,19 @@ async fn initiate_upload(
         name, size
     );
 
-    let response = base_request("usermedia/initiate-upload", token, client)
-        .json(&UserMediaInitiateUploadParams {
-            filename: name,
-            file_size_bytes: size,
-        })
-        .send()
-        .await?
-        .map_auth_err()?
-        .json::<UserMediaInitiateUploadResponse>()
-        .await?;
+    let response = api::send(
+        Endpoint::Account,
+        base_request("usermedia/initiate-upload", token, client).json(
+            &UserMediaInitiateUploadParams {
+                filename: name,
+                file_size_bytes: size,
+            },
+        ),
+    )
+    .await?
+    .map_auth_err()?
+    .json::<UserMediaInitiateUploadResponse>()
+    .await?;
 
     debug!("recieved {} upload urls", response.upload_urls.len());
 
@@ -272,11 +327,12 @@ async fn upload_chunk(
 async fn abort_upload(uuid: &Uuid, token: &str, client: reqwest::Client) -> Result<()> {
     info!("aborting upload");
 
-    base_request(format!("usermedia/{}/abort-upload", uuid), token, &client)
-        .json(&uuid)
-        .send()
-        .await?
-        .map_auth_err()?;
+    api::send(
+        Endpoint::Account,
+        base_request(format!("usermedia/{}/abort-upload", uuid), token, &client).json(&uuid),
+    )
+    .await?
+    .map_auth_err()?;
 
     Ok(())
 }
@@ -289,11 +345,13 @@ async fn finish_upload(
 ) -> Result<()> {
     debug!("finishing upload");
 
-    base_request(format!("usermedia/{}/finish-upload", uuid), token, client)
-        .json(&UserMediaFinishUploadParams { parts })
-        .send()
-        .await?
-        .map_auth_err()?;
+    api::send(
+        Endpoint::Account,
+        base_request(format!("usermedia/{}/finish-upload", uuid), token, client)
+            .json(&UserMediaFinishUploadParams { parts }),
+    )
+    .await?
+    .map_auth_err()?;
 
     Ok(())
 }
@@ -316,10 +374,11 @@ async fn submit_package(
 
     debug!("submitting package");
 
-    let response = base_request("submission/submit", token, client)
-        .json(&metadata)
-        .send()
-        .await?;
+    let response = api::send(
+        Endpoint::Account,
+        base_request("submission/submit", token, client).json(&metadata),
+    )
+    .await?;
 
     let status = response.status();
 
@@ -375,7 +434,7 @@ impl ReqwestResponseExt for reqwest::Response {
     {
         self.error_for_status().map_err(|err| match err.status() {
             Some(status) => match status {
-                StatusCode::UNAUTHORIZED => eyre!("thunderstore API token is invalid"),
+                StatusCode::UNAUTHORIZED => UserError::TokenInvalid.into(),
                 _ => match f(status) {
                     Some(err) => err,
                     None => eyre!(err),
@@ -0,0 +1,403 @@
+use std::{collections::HashSet, fs};
+
+use uuid::Uuid;
+
+use super::*;
+use crate::{
+    game,
+    profile::{tests::test_profile, ExportTarget, ProfileModKind, ThunderstoreMod},
+    thunderstore::{
+        tests::{test_package, test_thunderstore},
+        VersionIdent,
+    },
+};
+
+fn r2_mod(full_name: &str, version: &str) -> R2Mod {
+    R2Mod {
+        full_name: full_name.to_owned(),
+        version: version.parse::<semver::Version>().unwrap().into(),
+        enabled: true,
+        side: None,
+    }
+}
+
+fn package_with_versions(versions: &[&str]) -> Thunderstore {
+    let mut package = test_package("Mod", &[]);
+    let template = package.versions.remove(0);
+
+    // thunderstore lists the newest version first
+    for version in versions.iter().rev() {
+        let mut version_info = template.clone();
+        version_info.ident = VersionIdent::new("Test", "Mod", version);
+        version_info.uuid = Uuid::new_v4();
+        package.versions.push(version_info);
+    }
+
+    test_thunderstore([package])
+}
+
+fn substitution(thunderstore: &Thunderstore, r2: R2Mod) -> Option<VersionSubstitution> {
+    let (_, substitution) = r2.into_install(thunderstore).unwrap();
+    substitution
+}
+
+fn substitute(thunderstore: &Thunderstore, r2: R2Mod) -> String {
+    substitution(thunderstore, r2).unwrap().substitute
+}
+
+#[test]
+fn delisted_version_falls_back_to_nearest() {
+    let thunderstore = package_with_versions(&["1.0.0", "1.2.0", "2.0.0"]);
+
+    assert_eq!(
+        substitution(&thunderstore, r2_mod("Test-Mod", "1.2.0")),
+        None
+    );
+
+    // newer versions are preferred
+    assert_eq!(
+        substitution(&thunderstore, r2_mod("Test-Mod", "1.1.0")),
+        Some(VersionSubstitution {
+            full_name: "Test-Mod".to_owned(),
+            requested: "1.1.0".to_owned(),
+            substitute: "1.2.0".to_owned(),
+        })
+    );
+    assert_eq!(
+        substitute(&thunderstore, r2_mod("Test-Mod", "0.9.0")),
+        "1.0.0"
+    );
+    assert_eq!(
+        substitute(&thunderstore, r2_mod("Test-Mod", "3.0.0")),
+        "2.0.0"
+    );
+}
+
+#[test]
+fn delisted_package_fails() {
+    let thunderstore = package_with_versions(&["1.0.0"]);
+
+    assert!(r2_mod("Test-Gone", "1.0.0")
+        .into_install(&thunderstore)
+        .is_err());
+}
+
+fn export_bytes(profile: &Profile) -> Vec<u8> {
+    let mut data = Cursor::new(Vec::new());
+    export_zip(profile, |_| true, &mut data).unwrap();
+    data.into_inner()
+}
+
+#[test]
+fn exports_are_reproducible() {
+    let temp = tempfile::tempdir().unwrap();
+    let config_dir = temp.path().join("BepInEx").join("config");
+    fs::create_dir_all(&config_dir).unwrap();
+
+    for name in ["b.cfg", "a.cfg", "c.json"] {
+        fs::write(config_dir.join(name), name).unwrap();
+    }
+
+    let ignored_updates = (0..16).map(|_| Uuid::new_v4()).collect_vec();
+
+    let mut profile = Profile {
+        ignored_updates: ignored_updates.iter().copied().collect(),
+        ..test_profile(temp.path().to_path_buf())
+    };
+
+    let first = export_bytes(&profile);
+
+    // a new set iterates in a different order, and rewriting
+    // the file gives it a new modification time
+    profile.ignored_updates = ignored_updates
+        .iter()
+        .rev()
+        .copied()
+        .collect::<HashSet<_>>();
+    fs::write(config_dir.join("a.cfg"), "a.cfg").unwrap();
+
+    let second = export_bytes(&profile);
+
+    assert!(first == second, "exports of the same profile differ");
+}
+
+#[test]
+fn code_exports_skip_logs_and_backups() {
+    for path in [
+        "BepInEx/config/Mod.cfg",
+        "BepInEx/config/Blog.json",
+        "BepInEx/config/Catalogs/items.json",
+        "BepInEx/config/Mod.logic.cfg",
+        "BepInEx/config/Mod.bakery.json",
+    ] {
+        assert!(!is_code_excluded(Path::new(path)), "{}", path);
+    }
+
+    for path in [
+        "BepInEx/config/ErrorLog.log",
+        "BepInEx/config/Mod.cfg.old",
+        "BepInEx/config/Mod.cfg.BAK",
+        "BepInEx/config/Mod/Logs/latest.txt",
+    ] {
+        assert!(is_code_excluded(Path::new(path)), "{}", path);
+    }
+}
+
+#[test]
+fn oversized_codes_name_the_largest_files() {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+
+    for (name, len) in [("small.cfg", 10), ("huge.cfg", 3000), ("medium.cfg", 500)] {
+        // stored, so the sizes aren't skewed by compression
+        let options = util::zip::file_options().compression_method(CompressionMethod::Stored);
+        zip.start_file(name, options).unwrap();
+        zip.write_all(&vec![b'a'; len]).unwrap();
+    }
+
+    let data = zip.finish().unwrap().into_inner();
+    let size = code_payload_size(data.len());
+
+    assert_eq!(check_code_size(&data, size).unwrap(), size);
+
+    let err = check_code_size(&data, size - 1).unwrap_err().to_string();
+    let huge = err.find("huge.cfg").unwrap();
+    let medium = err.find("medium.cfg").unwrap();
+    assert!(huge < medium, "{}", err);
+}
+
+#[tokio::test]
+async fn code_payload_matches_single_encoding() {
+    use futures_util::TryStreamExt;
+
+    let data = (0..500_000).map(|i| (i % 251) as u8).collect_vec();
+
+    let chunks: Vec<Bytes> = code_payload(Bytes::from(data.clone()))
+        .try_collect()
+        .await
+        .unwrap();
+    let streamed = chunks.concat();
+
+    let mut expected = PROFILE_DATA_PREFIX.as_bytes().to_vec();
+    expected.extend(BASE64_STANDARD.encode(&data).into_bytes());
+
+    assert_eq!(streamed.len(), code_payload_size(data.len()));
+    assert!(streamed == expected);
+}
+
+#[test]
+fn server_exports_skip_client_mods() {
+    let temp = tempfile::tempdir().unwrap();
+
+    let mut client = test_package("Sounds", &[]);
+    client.categories.insert("Client-side".into());
+
+    let packages = [test_package("Core", &[]), client];
+
+    let mut mods = packages
+        .iter()
+        .map(|package| {
+            ProfileMod::new(ProfileModKind::Thunderstore(ThunderstoreMod {
+                ident: package.latest().ident.clone(),
+                id: ModId {
+                    package_uuid: package.uuid,
+                    version_uuid: package.latest().uuid,
+                },
+            }))
+        })
+        .collect_vec();
+
+    let thunderstore = test_thunderstore(packages);
+
+    // the user knows better than the categories
+    mods[0].side = Some(ModSide::Server);
+
+    let profile = Profile {
+        name: "Server".to_owned(),
+        mods,
+        ..test_profile(temp.path().to_path_buf())
+    };
+
+    let export = |target| {
+        let exported = profile.exported_mods(target, &thunderstore);
+
+        let mut data = Cursor::new(Vec::new());
+        export_zip(
+            &profile,
+            |profile_mod| exported.contains(&profile_mod.uuid()),
+            &mut data,
+        )
+        .unwrap();
+
+        let mut zip = zip::ZipArchive::new(data).unwrap();
+        let manifest: LegacyProfileManifest =
+            serde_yaml::from_reader(zip.by_name("export.r2x").unwrap()).unwrap();
+
+        manifest
+            .mods
+            .into_iter()
+            .map(|r2| (r2.full_name, r2.side))
+            .collect_vec()
+    };
+
+    assert_eq!(
+        export(Some(ExportTarget::Server)),
+        [("Test-Core".to_owned(), Some(ModSide::Server))]
+    );
+    assert_eq!(
+        export(Some(ExportTarget::Client)),
+        [("Test-Sounds".to_owned(), None)]
+    );
+    assert_eq!(export(None).len(), 2);
+}
+
+#[test]
+fn server_exports_keep_dependencies_of_kept_mods() {
+    let temp = tempfile::tempdir().unwrap();
+
+    let mut library = test_package("Library", &[]);
+    library.categories.insert("Client-side".into());
+
+    let mut sounds = test_package("Sounds", &[]);
+    sounds.categories.insert("Client-side".into());
+
+    let packages = [test_package("Core", &["Library"]), library, sounds];
+
+    let mods = packages
+        .iter()
+        .map(|package| {
+            ProfileMod::new(ProfileModKind::Thunderstore(ThunderstoreMod {
+                ident: package.latest().ident.clone(),
+                id: ModId {
+                    package_uuid: package.uuid,
+                    version_uuid: package.latest().uuid,
+                },
+            }))
+        })
+        .collect_vec();
+
+    let uuids = mods.iter().map(ProfileMod::uuid).collect_vec();
+    let thunderstore = test_thunderstore(packages);
+
+    let profile = Profile {
+        name: "Server".to_owned(),
+        mods,
+        ..test_profile(temp.path().to_path_buf())
+    };
+
+    let exported = profile.exported_mods(Some(ExportTarget::Server), &thunderstore);
+
+    assert_eq!(exported, HashSet::from([uuids[0], uuids[1]]));
+}
+
+fn mod_list_entries() -> Vec<mod_list::ModListEntry> {
+    vec![
+        mod_list::ModListEntry {
+            owner: "Evaisa".to_owned(),
+            name: "LethalLib".to_owned(),
+            version: "0.16.1".to_owned(),
+            enabled: true,
+            downloads: Some(1200),
+            url: Some("https://thunderstore.io/c/lethal-company/p/Evaisa/LethalLib/".to_owned()),
+            description: Some("Personal modding tools for Lethal Company".to_owned()),
+        },
+        mod_list::ModListEntry {
+            owner: String::new(),
+            name: "My, \"Local\" Mod".to_owned(),
+            version: String::new(),
+            enabled: false,
+            downloads: None,
+            url: None,
+            description: None,
+        },
+    ]
+}
+
+fn mod_list(format: mod_list::ModListFormat) -> String {
+    mod_list::generate(&mod_list_entries(), format)
+}
+
+#[test]
+fn markdown_mod_list() {
+    assert_eq!(
+        mod_list(mod_list::ModListFormat::Markdown),
+        "- [LethalLib](https://thunderstore.io/c/lethal-company/p/Evaisa/LethalLib/) by Evaisa (0.16.1)\n\
+        - My, \"Local\" Mod [disabled]"
+    );
+}
+
+#[test]
+fn bbcode_mod_list() {
+    assert_eq!(
+        mod_list(mod_list::ModListFormat::Bbcode),
+        "[list]\n\
+        [*][url=https://thunderstore.io/c/lethal-company/p/Evaisa/LethalLib/]LethalLib[/url] by Evaisa (0.16.1)\n\
+        [*]My, \"Local\" Mod [disabled]\n\
+        [/list]"
+    );
+}
+
+#[test]
+fn plain_mod_list() {
+    assert_eq!(
+        mod_list(mod_list::ModListFormat::Plain),
+        "LethalLib by Evaisa (0.16.1) - https://thunderstore.io/c/lethal-company/p/Evaisa/LethalLib/\n\
+        My, \"Local\" Mod [disabled]"
+    );
+}
+
+#[test]
+fn csv_mod_list() {
+    assert_eq!(
+        mod_list(mod_list::ModListFormat::Csv),
+        "owner,name,version,enabled,downloads,url\n\
+        Evaisa,LethalLib,0.16.1,true,1200,https://thunderstore.io/c/lethal-company/p/Evaisa/LethalLib/\n\
+        ,\"My, \"\"Local\"\" Mod\",,false,,"
+    );
+}
+
+#[test]
+fn collection_descriptions() {
+    let game = game::from_slug("lethal-company").unwrap();
+    let description =
+        |style| mod_list::collection_description("Friends", game, &mod_list_entries(), style);
+
+    assert_eq!(
+        description(mod_list::DescriptionStyle::Workshop),
+        "[h1]Friends[/h1]\n\
+        A collection of 2 mods for Lethal Company, in load order.\n\n\
+        [b]LethalLib[/b] by Evaisa (0.16.1)\n\
+        Personal modding tools for Lethal Company\n\
+        [url=https://thunderstore.io/c/lethal-company/p/Evaisa/LethalLib/]View on Thunderstore[/url]\n\n\
+        [b]My, \"Local\" Mod[/b] [disabled]\n\n\
+        [i]Install them all at once by importing this profile into Gale.[/i]"
+    );
+
+    assert_eq!(
+        description(mod_list::DescriptionStyle::Discord),
+        "# Friends\n\
+        A collection of 2 mods for Lethal Company, in load order.\n\n\
+        **LethalLib** by Evaisa (0.16.1)\n\
+        Personal modding tools for Lethal Company\n\
+        <https://thunderstore.io/c/lethal-company/p/Evaisa/LethalLib/>\n\n\
+        **My, \"Local\" Mod** [disabled]\n\n\
+        *Install them all at once by importing this profile into Gale.*"
+    );
+}
+
+#[test]
+fn staged_files_of_disabled_mods_are_not_config() {
+    let temp = tempfile::tempdir().unwrap();
+
+    for path in [
+        "BepInEx/config/Mod.cfg",
+        "_disabled/BepInEx/config/Disabled.cfg",
+        "_disabled/BepInEx/plugins/Disabled/data.json",
+    ] {
+        let path = temp.path().join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "").unwrap();
+    }
+
+    let found = find_default_config(temp.path()).collect_vec();
+    assert_eq!(found, [Path::new("BepInEx/config/Mod.cfg")]);
+}
@@ -0,0 +1,250 @@
+use std::fs;
+
+use eyre::Result;
+use serde::Serialize;
+
+use super::Profile;
+use crate::{
+    game::{ModLoader, ModLoaderKind},
+    util::error::IoResultExt,
+};
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Message,
+    Warning,
+    Error,
+    Fatal,
+}
+
+impl LogLevel {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "debug" => Some(LogLevel::Debug),
+            "info" => Some(LogLevel::Info),
+            "message" | "msg" => Some(LogLevel::Message),
+            "warning" | "warn" => Some(LogLevel::Warning),
+            "error" => Some(LogLevel::Error),
+            "fatal" => Some(LogLevel::Fatal),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct LogEntry {
+    pub level: LogLevel,
+    /// The time of day the entry was written, if the loader includes it.
+    pub time: Option<String>,
+    /// The plugin or category that wrote the entry.
+    pub source: Option<String>,
+    /// The message, including any following lines without a prefix,
+    /// such as stack traces.
+    pub message: String,
+}
+
+impl Profile {
+    pub fn read_log(&self) -> Result<Vec<LogEntry>> {
+        let path = self.log_path()?;
+        let text = fs::read(&path).fs_context("reading log file", &path)?;
+
+        Ok(parse(
+            &String::from_utf8_lossy(&text),
+            &self.game.mod_loader,
+        ))
+    }
+}
+
+/// Parses a log file, using the format of the game's mod loader.
+///
+/// Loaders without a known format get one entry per line.
+pub fn parse(text: &str, mod_loader: &ModLoader) -> Vec<LogEntry> {
+    let parse_line = match &mod_loader.kind {
+        ModLoaderKind::BepInEx { .. } => parse_bepinex_line,
+        ModLoaderKind::MelonLoader { .. } => parse_melon_loader_line,
+        _ => {
+            return text
+                .lines()
+                .map(|line| LogEntry {
+                    level: LogLevel::Info,
+                    time: None,
+                    source: None,
+                    message: line.to_owned(),
+                })
+                .collect()
+        }
+    };
+
+    let mut entries: Vec<LogEntry> = Vec::new();
+
+    for line in text.lines() {
+        match (parse_line(line), entries.last_mut()) {
+            (Some(entry), _) => entries.push(entry),
+            (None, Some(last)) => {
+                last.message.push('\n');
+                last.message.push_str(line);
+            }
+            (None, None) => entries.push(LogEntry {
+                level: LogLevel::Info,
+                time: None,
+                source: None,
+                message: line.to_owned(),
+            }),
+        }
+    }
+
+    entries
+}
+
+/// Parses a line like `[Warning:  MyPlugin] Something happened`.
+fn parse_bepinex_line(line: &str) -> Option<LogEntry> {
+    let (prefix, message) = line.strip_prefix('[')?.split_once(']')?;
+    let (level, source) = prefix.split_once(':')?;
+
+    Some(LogEntry {
+        level: LogLevel::from_name(level.trim())?,
+        time: None,
+        source: Some(source.trim().to_owned()),
+        message: message.strip_prefix(' ').unwrap_or(message).to_owned(),
+    })
+}
+
+/// Parses a line like `[12:34:56.789] [MyMod] [WARNING] Something happened`.
+///
+/// Both the category and severity brackets are optional.
+fn parse_melon_loader_line(line: &str) -> Option<LogEntry> {
+    let (time, mut rest) = line.strip_prefix('[')?.split_once("] ")?;
+
+    let is_time = time.len() == 12
+        && time.char_indices().all(|(i, char)| match i {
+            2 | 5 => char == ':',
+            8 => char == '.',
+            _ => char.is_ascii_digit(),
+        });
+
+    if !is_time {
+        return None;
+    }
+
+    let mut level = None;
+    let mut source = None;
+
+    // at most one category and one severity marker, in either order
+    for _ in 0..2 {
+        let Some((bracket, after)) = rest.strip_prefix('[').and_then(|rest| rest.split_once(']'))
+        else {
+            break;
+        };
+
+        match LogLevel::from_name(bracket) {
+            Some(found) if level.is_none() => level = Some(found),
+            _ if source.is_none() => source = Some(bracket.to_owned()),
+            _ => break,
+        }
+
+        rest = after.strip_prefix(' ').unwrap_or(after);
+    }
+
+    Some(LogEntry {
+        level: level.unwrap_or(LogLevel::Info),
+        time: Some(time.to_owned()),
+        source,
+        message: rest.to_owned(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(level: LogLevel, time: Option<&str>, source: Option<&str>, message: &str) -> LogEntry {
+        LogEntry {
+            level,
+            time: time.map(str::to_owned),
+            source: source.map(str::to_owned),
+            message: message.to_owned(),
+        }
+    }
+
+    #[test]
+    fn parses_melon_loader_log() {
+        let text = "\
+[12:00:01.250] MelonLoader v0.6.1 Open-Beta
+[12:00:02.000] [MyMod] Initialized
+[12:00:03.500] [MyMod] [WARNING] Config missing
+[12:00:04.000] [ERROR] Failed to load Other.dll
+System.IO.FileNotFoundException
+  at Loader.Load()";
+
+        let mod_loader = ModLoader {
+            package_name: None,
+            deploy_dir: None,
+            kind: ModLoaderKind::MelonLoader {
+                extra_subdirs: Vec::new(),
+            },
+        };
+
+        assert_eq!(
+            parse(text, &mod_loader),
+            [
+                entry(
+                    LogLevel::Info,
+                    Some("12:00:01.250"),
+                    None,
+                    "MelonLoader v0.6.1 Open-Beta"
+                ),
+                entry(
+                    LogLevel::Info,
+                    Some("12:00:02.000"),
+                    Some("MyMod"),
+                    "Initialized"
+                ),
+                entry(
+                    LogLevel::Warning,
+                    Some("12:00:03.500"),
+                    Some("MyMod"),
+                    "Config missing"
+                ),
+                entry(
+                    LogLevel::Error,
+                    Some("12:00:04.000"),
+                    None,
+                    "Failed to load Other.dll\nSystem.IO.FileNotFoundException\n  at Loader.Load()"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_bepinex_log() {
+        let text = "\
+[Message:   BepInEx] BepInEx 5.4.21.0
+[Warning:  MyPlugin] Something happened
+  with details";
+
+        let mod_loader = ModLoader {
+            package_name: None,
+            deploy_dir: None,
+            kind: ModLoaderKind::BepInEx {
+                extra_subdirs: Vec::new(),
+            },
+        };
+
+        assert_eq!(
+            parse(text, &mod_loader),
+            [
+                entry(LogLevel::Message, None, Some("BepInEx"), "BepInEx 5.4.21.0"),
+                entry(
+                    LogLevel::Warning,
+                    None,
+                    Some("MyPlugin"),
+                    "Something happened\n  with details"
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,279 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::PathBuf,
+};
+
+use eyre::Result;
+use indexmap::IndexMap;
+use itertools::Itertools;
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::{
+    compat::{CompatRules, CompatWarning},
+    update::UpdateSummary,
+    Dependant, Profile, ProfileMod,
+};
+use crate::{
+    prefs::Prefs,
+    thunderstore::{BorrowedMod, Thunderstore, VersionIdent},
+    util::{self, error::IoResultExt},
+};
+
+/// A dependency which isn't available to the mods that need it.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyIssue {
+    pub dependency: Dependant,
+    /// The enabled mods which need the dependency, directly or indirectly.
+    pub required_by: Vec<Dependant>,
+}
+
+/// The result of [`Profile::find_dependency_issues`].
+#[derive(Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyCheck {
+    /// Dependencies that aren't installed.
+    pub missing: Vec<DependencyIssue>,
+    /// Dependencies that are installed, but disabled.
+    pub disabled: Vec<DependencyIssue>,
+    /// Dependency strings that don't match any package on Thunderstore.
+    pub unresolved: Vec<VersionIdent>,
+    /// Known conflicts between installed mods, see [`CompatRules`].
+    ///
+    /// These are only warnings, so they don't affect [`DependencyCheck::is_healthy`].
+    pub compatibility: Vec<CompatWarning>,
+}
+
+impl DependencyCheck {
+    pub fn is_healthy(&self) -> bool {
+        self.missing.is_empty() && self.disabled.is_empty() && self.unresolved.is_empty()
+    }
+}
+
+impl Profile {
+    /// Checks that every enabled mod has its dependencies installed and enabled.
+    ///
+    /// Unlike the file checks done when repairing a profile, this only
+    /// looks at the dependency graph.
+    pub fn find_dependency_issues(&self, thunderstore: &Thunderstore) -> DependencyCheck {
+        let mut missing = IndexMap::<Uuid, DependencyIssue>::new();
+        let mut disabled = IndexMap::<Uuid, DependencyIssue>::new();
+        let mut unresolved = Vec::new();
+
+        for profile_mod in self.mods.iter().filter(|profile_mod| profile_mod.enabled) {
+            for ident in profile_mod.kind.direct_dependencies(thunderstore) {
+                if thunderstore.find_package(ident.full_name()).is_err()
+                    && !unresolved.contains(ident)
+                {
+                    unresolved.push(ident.clone());
+                }
+            }
+
+            for dep in profile_mod.dependencies(thunderstore) {
+                let issues = match self.get_mod(dep.package.uuid) {
+                    Ok(installed) if installed.enabled => continue,
+                    Ok(_) => &mut disabled,
+                    Err(_) => &mut missing,
+                };
+
+                issues
+                    .entry(dep.package.uuid)
+                    .or_insert_with(|| DependencyIssue {
+                        dependency: dep.into(),
+                        required_by: Vec::new(),
+                    })
+                    .required_by
+                    .push(profile_mod.into());
+            }
+        }
+
+        DependencyCheck {
+            missing: missing.into_values().collect(),
+            disabled: disabled.into_values().collect(),
+            unresolved,
+            // filled in by the caller, since the rules depend on the prefs
+            compatibility: Vec::new(),
+        }
+    }
+
+    /// Checks the installed mods against the bundled and user compatibility rules.
+    pub fn find_compat_warnings(&self, prefs: &Prefs) -> Vec<CompatWarning> {
+        let idents = self.thunderstore_mods().map(|(ts_mod, _)| &ts_mod.ident);
+
+        CompatRules::load(self.game, prefs).check(idents)
+    }
+}
+
+/// A mod directory in a profile which doesn't belong to any of its mods,
+/// for example after a crash in the middle of an uninstall.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct OrphanedDir {
+    /// The path relative to the profile directory.
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+impl Profile {
+    /// Finds directories in the mod loader's per-mod directories (like
+    /// `BepInEx/plugins`) which aren't named after any mod in the profile.
+    ///
+    /// Loose files are ignored, since they can't be traced back to a mod.
+    pub fn find_orphaned_dirs(&self) -> Result<Vec<OrphanedDir>> {
+        // the installer of a regular mod, as opposed to the mod loader itself
+        let installer = self.game.mod_loader.installer_for("");
+
+        // file systems may be case insensitive
+        let names = self
+            .mods
+            .iter()
+            .map(|profile_mod| profile_mod.full_name().to_lowercase())
+            .collect::<HashSet<_>>();
+
+        let mut orphans = Vec::new();
+
+        for relative in installer.separated_dirs() {
+            let dir = self.path.join(&relative);
+
+            if !dir.is_dir() {
+                continue;
+            }
+
+            for entry in dir.read_dir().fs_context("reading mod directory", &dir)? {
+                let entry = entry.fs_context("reading mod directory", &dir)?;
+
+                if !entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+                    continue;
+                }
+
+                let name = entry.file_name().to_string_lossy().to_lowercase();
+                if names.contains(&name) {
+                    continue;
+                }
+
+                orphans.push(OrphanedDir {
+                    path: relative.join(entry.file_name()),
+                    size: util::fs::get_directory_size(entry.path()),
+                });
+            }
+        }
+
+        Ok(orphans)
+    }
+
+    /// Removes the given directories, as long as they are still orphaned.
+    ///
+    /// Returns the number of bytes freed.
+    pub fn remove_orphaned_dirs(&self, paths: &[PathBuf]) -> Result<u64> {
+        self.ensure_unlocked()?;
+
+        let mut freed = 0;
+
+        for orphan in self.find_orphaned_dirs()? {
+            if !paths.contains(&orphan.path) {
+                continue;
+            }
+
+            let path = self.path.join(&orphan.path);
+            fs::remove_dir_all(&path).fs_context("removing orphaned directory", &path)?;
+
+            freed += orphan.size;
+        }
+
+        Ok(freed)
+    }
+}
+
+/// Thunderstore data of an installed mod, which changes when the index is refreshed.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModMetadata {
+    pub uuid: Uuid,
+    pub downloads: u32,
+    pub rating: u32,
+    pub is_deprecated: bool,
+    pub latest_version: semver::Version,
+}
+
+/// Emitted as `profile-metadata-refreshed` after the index is fetched.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetadataRefresh {
+    pub profile_id: i64,
+    pub mods: Vec<ModMetadata>,
+    /// Mods which weren't deprecated before the refresh, but are now.
+    pub newly_deprecated: Vec<Dependant>,
+    pub updates: UpdateSummary,
+}
+
+impl Profile {
+    fn borrowed_mods<'a>(
+        &'a self,
+        thunderstore: &'a Thunderstore,
+    ) -> impl Iterator<Item = (&'a ProfileMod, BorrowedMod<'a>)> + 'a {
+        self.mods.iter().filter_map(|profile_mod| {
+            let (ts_mod, _) = profile_mod.as_thunderstore()?;
+            let borrowed = ts_mod.id.borrow(thunderstore).ok()?;
+
+            Some((profile_mod, borrowed))
+        })
+    }
+
+    /// Finds the installed mods which are deprecated on Thunderstore.
+    pub fn deprecated_mods<'a>(
+        &'a self,
+        thunderstore: &'a Thunderstore,
+    ) -> impl Iterator<Item = &'a ProfileMod> + 'a {
+        self.borrowed_mods(thunderstore)
+            .filter(|(_, borrowed)| borrowed.package.is_deprecated)
+            .map(|(profile_mod, _)| profile_mod)
+    }
+
+    /// Maps the installed mods found in the index to whether they're deprecated.
+    pub fn deprecation_status(&self, thunderstore: &Thunderstore) -> HashMap<Uuid, bool> {
+        self.borrowed_mods(thunderstore)
+            .map(|(profile_mod, borrowed)| (profile_mod.uuid(), borrowed.package.is_deprecated))
+            .collect()
+    }
+
+    /// Collects the current metadata of the installed mods.
+    ///
+    /// `status_before` is the [`Profile::deprecation_status`] in the previous index.
+    /// Only mods it lists as not deprecated count as newly deprecated, so nothing
+    /// is reported when there was no index yet.
+    pub fn refresh_metadata(
+        &self,
+        status_before: &HashMap<Uuid, bool>,
+        thunderstore: &Thunderstore,
+    ) -> MetadataRefresh {
+        let mods = self
+            .borrowed_mods(thunderstore)
+            .map(|(_, borrowed)| {
+                let package = borrowed.package;
+
+                ModMetadata {
+                    uuid: package.uuid,
+                    downloads: package.total_downloads(),
+                    rating: package.rating_score,
+                    is_deprecated: package.is_deprecated,
+                    latest_version: package.latest().parsed_version(),
+                }
+            })
+            .collect();
+
+        let newly_deprecated = self
+            .deprecated_mods(thunderstore)
+            .filter(|profile_mod| status_before.get(&profile_mod.uuid()) == Some(&false))
+            .map_into()
+            .collect();
+
+        MetadataRefresh {
+            profile_id: self.id,
+            mods,
+            newly_deprecated,
+            updates: self.update_summary(thunderstore),
+        }
+    }
+}
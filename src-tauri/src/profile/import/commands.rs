@@ -1,14 +1,19 @@
-use std::path::PathBuf;
+use std::{fs::File, io::BufReader, path::PathBuf};
 
 use eyre::anyhow;
 use tauri::{command, AppHandle};
 use uuid::Uuid;
 
-use crate::{profile::install::InstallOptions, thunderstore, util::cmd::Result};
+use crate::{
+    profile::install::InstallOptions,
+    state::ManagerExt,
+    thunderstore,
+    util::{self, cmd::Result, error::IoResultExt},
+};
 
 use super::{
-    r2modman::{self, ProfileImportData},
-    ImportData,
+    r2modman::{self, ImportReport, ProfileImportData},
+    ImportData, ImportPreview, LocalModDependencies,
 };
 
 #[command]
@@ -18,11 +23,18 @@ pub async fn import_data(data: ImportData, import_all: bool, app: AppHandle) ->
     Ok(())
 }
 
+#[command]
+pub fn preview_import(data: ImportData, app: AppHandle) -> Result<ImportPreview> {
+    let preview = data.preview(&app)?;
+
+    Ok(preview)
+}
+
 #[command]
 pub async fn import_code(key: &str, app: AppHandle) -> Result<ImportData> {
     let key = Uuid::parse_str(key).map_err(|_| anyhow!("invalid code format"))?;
 
-    thunderstore::wait_for_fetch(&app).await;
+    thunderstore::wait_for_fetch(&app).await?;
 
     let data = super::import_code(key, &app).await?;
 
@@ -31,7 +43,10 @@ pub async fn import_code(key: &str, app: AppHandle) -> Result<ImportData> {
 
 #[command]
 pub async fn import_file(path: PathBuf, app: AppHandle) -> Result<ImportData> {
-    thunderstore::wait_for_fetch(&app).await;
+    // check before waiting for the fetch, which can take a while
+    util::fs::ensure_readable_file(&path)?;
+
+    thunderstore::wait_for_fetch(&app).await?;
 
     let data = super::import_file_from_path(path, &app)?;
 
@@ -40,20 +55,99 @@ pub async fn import_file(path: PathBuf, app: AppHandle) -> Result<ImportData> {
 
 #[command]
 pub async fn import_base64(base64: String, app: AppHandle) -> Result<ImportData> {
-    thunderstore::wait_for_fetch(&app).await;
+    thunderstore::wait_for_fetch(&app).await?;
 
     let data = super::import_base64(&base64, &app)?;
 
     Ok(data)
 }
 
+/// Applies a config bundle from [`export_config_bundle`] to a profile,
+/// returning the imported files.
+///
+/// [`export_config_bundle`]: crate::profile::export::commands::export_config_bundle
 #[command]
-pub async fn import_local_mod(path: PathBuf, app: AppHandle) -> Result<()> {
-    thunderstore::wait_for_fetch(&app).await;
+pub fn import_config_bundle(
+    profile_id: i64,
+    path: PathBuf,
+    app: AppHandle,
+) -> Result<Vec<PathBuf>> {
+    util::fs::ensure_readable_file(&path)?;
+    let file = File::open(&path).fs_context("opening bundle", &path)?;
 
-    super::import_local_mod(path, &app, InstallOptions::default().can_cancel(false)).await?;
+    let prefs = app.lock_prefs()?;
+    let mut manager = app.lock_manager()?;
 
-    Ok(())
+    let profile = manager.active_game_mut().find_profile_mut(profile_id)?;
+    profile.ensure_unlocked()?;
+
+    let files = super::import_config_bundle(
+        BufReader::new(file),
+        &profile.path,
+        profile.game,
+        &prefs.temp_dir(),
+    )?;
+
+    profile.refresh_config();
+
+    Ok(files)
+}
+
+#[command]
+pub async fn import_local_mod(
+    path: PathBuf,
+    install_deps: Option<bool>,
+    profile_id: Option<i64>,
+    app: AppHandle,
+) -> Result<LocalModDependencies> {
+    util::fs::ensure_readable_file(&path)?;
+
+    thunderstore::wait_for_fetch(&app).await?;
+
+    let mut options = InstallOptions::default().can_cancel(false);
+    if let Some(profile_id) = profile_id {
+        options = options.target_profile(profile_id);
+    }
+
+    let deps = super::import_local_mod(path, &app, options, install_deps.unwrap_or(true)).await?;
+
+    Ok(deps)
+}
+
+/// Downloads a zip archive from `url`, like a GitHub release asset,
+/// and installs it as a local mod named `name`.
+#[command]
+pub async fn install_from_url(
+    url: String,
+    name: String,
+    install_deps: Option<bool>,
+    profile_id: Option<i64>,
+    app: AppHandle,
+) -> Result<LocalModDependencies> {
+    thunderstore::wait_for_fetch(&app).await?;
+
+    let mut options = InstallOptions::default().can_cancel(false);
+    if let Some(profile_id) = profile_id {
+        options = options.target_profile(profile_id);
+    }
+
+    let deps = super::import_mod_from_url(&url, &name, &app, options, install_deps.unwrap_or(true))
+        .await?;
+
+    Ok(deps)
+}
+
+#[command]
+pub async fn get_local_mod_dependencies(
+    path: PathBuf,
+    profile_id: Option<i64>,
+    app: AppHandle,
+) -> Result<LocalModDependencies> {
+    util::fs::ensure_readable_file(&path)?;
+
+    thunderstore::wait_for_fetch(&app).await?;
+
+    Ok(super::local_mod_dependencies(&path, profile_id, &app)?)
 }
 
 #[command]
@@ -67,8 +161,12 @@ pub fn get_r2modman_info(
 }
 
 #[command]
-pub async fn import_r2modman(path: PathBuf, include: Vec<bool>, app: AppHandle) -> Result<()> {
-    r2modman::import(path, &include, &app).await?;
+pub async fn import_r2modman(
+    path: PathBuf,
+    include: Vec<bool>,
+    app: AppHandle,
+) -> Result<ImportReport> {
+    let report = r2modman::import(path, &include, &app).await?;
 
-    Ok(())
+    Ok(report)
 }
@@ -1,10 +1,14 @@
 use std::{
-    fs,
-    io::{Cursor, Read},
+    collections::HashSet,
+    fs::{self, File},
+    io::{self, Read},
     path::{Path, PathBuf},
 };
 
 use eyre::{bail, ensure, Context, Result};
+use itertools::Itertools;
+use log::{info, warn};
+use serde::Serialize;
 use tauri::AppHandle;
 use uuid::Uuid;
 use zip::ZipArchive;
@@ -13,43 +17,131 @@ use crate::{
     game::{ModLoader, ModLoaderKind},
     prefs::Prefs,
     profile::{
-        install::{self, InstallOptions},
-        LocalMod, Profile, ProfileMod,
+        install::{self, InstallOptions, ModInstall, PackageSource},
+        InstallOrigin, LocalMod, Profile, ProfileMod,
     },
     state::ManagerExt,
-    thunderstore::PackageManifest,
-    util::{self, fs::PathExt},
+    thunderstore::{BorrowedMod, PackageManifest, Thunderstore, VersionIdent},
+    util::{self, fs::PathExt, temp::TempDir},
 };
 
+/// The dependencies listed in a local mod's manifest, checked against the
+/// active profile and Thunderstore.
+#[derive(Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalModDependencies {
+    /// Dependencies that were found but aren't installed in the active profile.
+    pub missing: Vec<VersionIdent>,
+    /// Dependencies that weren't found on Thunderstore.
+    pub unresolved: Vec<VersionIdent>,
+}
+
+/// Resolves the dependencies of a local mod against the index.
+///
+/// If the exact version isn't available, the latest version of
+/// the package is used instead.
+fn resolve_dependencies(
+    deps: &[VersionIdent],
+    profile: &Profile,
+    thunderstore: &Thunderstore,
+) -> (Vec<ModInstall>, LocalModDependencies) {
+    let mut installs = Vec::new();
+    let mut result = LocalModDependencies::default();
+
+    for dep in deps {
+        let borrowed = thunderstore.find_ident(dep).or_else(|_| {
+            thunderstore
+                .find_package(dep.full_name())
+                .map(|package| BorrowedMod::from((package, package.latest())))
+        });
+
+        match borrowed {
+            Ok(borrowed) if profile.has_mod(borrowed.package.uuid) => (),
+            Ok(borrowed) => {
+                result.missing.push(borrowed.ident().clone());
+                installs.push(borrowed.into());
+            }
+            Err(_) => result.unresolved.push(dep.clone()),
+        }
+    }
+
+    (installs, result)
+}
+
+/// Reads the dependencies of the local mod at `path`, without importing it.
+///
+/// They're checked against the profile with `profile_id`, or the active profile if `None`.
+pub fn local_mod_dependencies(
+    path: &Path,
+    profile_id: Option<i64>,
+    app: &AppHandle,
+) -> Result<LocalModDependencies> {
+    let (local_mod, _) = read_local_mod(path)?;
+
+    let Some(deps) = &local_mod.dependencies else {
+        return Ok(LocalModDependencies::default());
+    };
+
+    let manager = app.lock_manager()?;
+    let thunderstore = app.lock_thunderstore()?;
+
+    let game = manager.active_game();
+    let profile = match profile_id {
+        Some(id) => game.find_profile(id)?,
+        None => game.active_profile(),
+    };
+
+    Ok(resolve_dependencies(deps, profile, &thunderstore).1)
+}
+
+/// Imports a local mod into the target profile of `options`.
+///
+/// If `install_deps` is set, missing dependencies from the mod's manifest are
+/// installed first. Dependencies that can't be resolved don't stop the import,
+/// and are returned instead.
 pub async fn import_local_mod(
     path: PathBuf,
     app: &AppHandle,
-    options: InstallOptions,
-) -> Result<()> {
+    mut options: InstallOptions,
+    install_deps: bool,
+) -> Result<LocalModDependencies> {
     let (mut local_mod, kind) = read_local_mod(&path)?;
 
-    if let Some(deps) = &local_mod.dependencies {
-        let mods = {
-            let manager = app.lock_manager();
-            let profile = manager.active_profile();
+    let profile_id = options.resolve_profile(&*app.lock_manager()?);
 
-            app.lock_thunderstore()
-                .dependencies(deps)
-                .filter(|dep| !profile.has_mod(dep.package.uuid))
-                .map(|borrowed| borrowed.into())
-                .collect::<Vec<_>>()
-        };
+    let (installs, deps) = match &local_mod.dependencies {
+        Some(deps) => {
+            let manager = app.lock_manager()?;
+            let thunderstore = app.lock_thunderstore()?;
 
-        install::install_mods(mods, options, app)
+            let profile = manager.active_game().find_profile(profile_id)?;
+            resolve_dependencies(deps, profile, &thunderstore)
+        }
+        None => Default::default(),
+    };
+
+    if !deps.unresolved.is_empty() {
+        warn!(
+            "could not resolve dependencies of {}: {}",
+            local_mod.name,
+            deps.unresolved.iter().join(", ")
+        );
+    }
+
+    if install_deps && !installs.is_empty() {
+        // the mod was imported by hand, so its dependencies are wanted regardless
+        let options = options.confirm_flagged(false);
+        install::install_with_deps(installs, options, true, &HashSet::new(), app)
             .await
             .context("failed to install dependencies")?;
     }
 
-    let prefs = app.lock_prefs();
-    let mut manager = app.lock_manager();
+    let prefs = app.lock_prefs()?;
+    let mut manager = app.lock_manager()?;
 
     let mod_loader = manager.active_mod_loader();
-    let profile = manager.active_profile_mut();
+    let profile = manager.active_game_mut().find_profile_mut(profile_id)?;
+    profile.ensure_unlocked()?;
 
     let existing = profile
         .local_mods()
@@ -85,11 +177,62 @@ pub async fn import_local_mod(
         },
     }
 
-    profile.mods.push(ProfileMod::new_local(local_mod));
+    profile.mods.push(ProfileMod {
+        origin: Some(InstallOrigin::Explicit),
+        ..ProfileMod::new_local(local_mod)
+    });
 
     profile.save(app.db())?;
 
-    Ok(())
+    Ok(deps)
+}
+
+/// Downloads a zip archive from `url` and imports it like a local mod.
+///
+/// `name` is used for the mod if the archive doesn't have a manifest.
+pub async fn import_mod_from_url(
+    url: &str,
+    name: &str,
+    app: &AppHandle,
+    options: InstallOptions,
+    install_deps: bool,
+) -> Result<LocalModDependencies> {
+    let is_valid_url =
+        reqwest::Url::parse(url).is_ok_and(|url| matches!(url.scheme(), "http" | "https"));
+    ensure!(is_valid_url, "the URL must be an http(s) URL");
+
+    let name = sanitize_name(name);
+    ensure!(!name.is_empty(), "the mod name cannot be empty");
+
+    info!("downloading {} from {}", name, url);
+
+    let mut file = install::download_archive(url, &name, app)
+        .await
+        .context("failed to download archive")?;
+
+    let temp_dir = TempDir::new_in(&app.lock_prefs()?.temp_dir(), "url")?;
+
+    // the file name becomes the mod name if there's no manifest
+    let path = temp_dir.path().join(format!("{}.zip", name));
+    let mut dest = File::create(&path).context("failed to create archive file")?;
+    io::copy(&mut file, &mut dest).context("failed to copy archive")?;
+    drop(dest);
+
+    import_local_mod(path, app, options, install_deps).await
+}
+
+/// Makes `name` safe to use as a file and directory name.
+fn sanitize_name(name: &str) -> String {
+    name.trim()
+        .chars()
+        .map(|char| match char {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            char if char.is_control() => '_',
+            char => char,
+        })
+        .collect::<String>()
+        .trim_matches('.')
+        .to_owned()
 }
 
 #[derive(PartialEq, Eq)]
@@ -168,22 +311,17 @@ fn install_from_zip(
     mod_loader: &'static ModLoader,
     prefs: &Prefs,
 ) -> Result<Option<PathBuf>> {
-    // dont use tempdir since we need the files on the same drive as the destination
+    // dont use the OS temp dir since we need the files on the same drive as the destination
     // for hard linking to work
 
-    let temp_path = prefs.data_dir.join("temp").join("extract");
-    fs::create_dir_all(&temp_path).context("failed to create temporary directory")?;
+    let temp_dir = TempDir::new_in(&prefs.temp_dir(), "extract")?;
 
-    let reader = fs::read(src)
-        .map(Cursor::new)
-        .context("failed to read file")?;
-    let archive = ZipArchive::new(reader).context("failed to read archive")?;
+    let file = File::open(src).context("failed to open file")?;
+    let archive = ZipArchive::new(PackageSource::from(file)).context("failed to read archive")?;
 
     let mut installer = mod_loader.installer_for(package_name);
-    installer.extract(archive, package_name, temp_path.clone())?;
-    installer.install(&temp_path, package_name, profile)?;
-
-    fs::remove_dir_all(temp_path).context("failed to remove temporary directory")?;
+    installer.extract(archive, package_name, temp_dir.path().to_path_buf())?;
+    installer.install(temp_dir.path(), package_name, profile)?;
 
     let icon = installer
         .mod_dir(package_name, profile)
@@ -5,29 +5,43 @@ use std::{
 };
 
 use base64::{prelude::BASE64_STANDARD, Engine};
-use eyre::{eyre, Context, Result};
+use eyre::{ensure, eyre, Context, OptionExt, Result};
 use itertools::Itertools;
+use log::{info, warn};
 use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 use tauri::AppHandle;
-use tempfile::tempdir;
 use uuid::Uuid;
+use walkdir::WalkDir;
 
 use crate::{
+    game::{self, Game},
     profile::{
-        export::{self, ImportSource, LegacyProfileManifest, R2Mod, PROFILE_DATA_PREFIX},
-        install::{self, InstallOptions, ModInstall},
+        export::{
+            self, ExportOrigin, ImportSource, LegacyProfileManifest, R2Mod, VersionSubstitution,
+            PROFILE_DATA_PREFIX,
+        },
+        install::{self, InstallOptions, InstallPlan, InstallSummary, ModInstall},
     },
     state::ManagerExt,
-    thunderstore::Thunderstore,
-    util::{self, error::IoResultExt},
+    thunderstore::{
+        api::{self, Endpoint},
+        Thunderstore,
+    },
+    util::{self, error::IoResultExt, temp::TempDir},
 };
 
 pub mod commands;
 mod local;
 mod r2modman;
 
-pub use local::import_local_mod;
+#[cfg(test)]
+mod tests;
+
+pub use local::{
+    import_local_mod, import_mod_from_url, local_mod_dependencies, LocalModDependencies,
+};
+pub use r2modman::{find_install as find_r2modman_install, R2modmanInstall};
 
 use super::export::{IncludeExtensions, IncludeGenerated};
 
@@ -47,6 +61,79 @@ pub struct ImportData {
     delete_after_import: bool,
     ignored_updates: Vec<Uuid>,
     source: ImportSource,
+    /// Mods whose version was delisted and replaced with the nearest one.
+    #[serde(default)]
+    substitutions: Vec<VersionSubstitution>,
+    /// Mods whose package couldn't be found, which are skipped.
+    #[serde(default)]
+    missing: Vec<String>,
+    #[serde(default)]
+    loader_mismatch: Option<LoaderMismatch>,
+}
+
+/// A profile that was exported from a game with a different mod loader.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LoaderMismatch {
+    /// The mod loader of the exported profile.
+    from: String,
+    /// The mod loader of the game it's imported into.
+    to: String,
+    /// Mods which need the other loader, which are skipped.
+    skipped: Vec<String>,
+}
+
+impl LoaderMismatch {
+    /// Removes the mods from `installs` that need `origin`'s loader, if it's different from `target`'s.
+    ///
+    /// A mod needs a loader if it is, or depends on, the loader's package. If the origin
+    /// game is unknown, mods can't be checked and only the mismatch is reported.
+    fn check(
+        origin: &ExportOrigin,
+        target: Game,
+        installs: &mut Vec<ModInstall>,
+        thunderstore: &Thunderstore,
+    ) -> Option<Self> {
+        if origin.mod_loader == target.mod_loader.to_str() {
+            return None;
+        }
+
+        let mut skipped = Vec::new();
+
+        if let Some(source) = game::from_slug(&origin.game) {
+            installs.retain(|install| {
+                let Ok(borrowed) = install.id().borrow(thunderstore) else {
+                    return true;
+                };
+
+                let needs_loader = source
+                    .mod_loader
+                    .is_loader_package(borrowed.package.full_name())
+                    || thunderstore
+                        .dependencies(borrowed.version.dependencies.iter())
+                        .any(|dep| source.mod_loader.is_loader_package(dep.package.full_name()));
+
+                if needs_loader {
+                    skipped.push(borrowed.ident().to_string());
+                }
+
+                !needs_loader
+            });
+        }
+
+        warn!(
+            "importing a {} profile into a {} game, skipping {} mods",
+            origin.mod_loader,
+            target.mod_loader.to_str(),
+            skipped.len()
+        );
+
+        Some(Self {
+            from: origin.mod_loader.clone(),
+            to: target.mod_loader.to_str().to_owned(),
+            skipped,
+        })
+    }
 }
 
 impl ImportData {
@@ -60,28 +147,94 @@ impl ImportData {
         thunderstore: &Thunderstore,
     ) -> Result<Self> {
         let mod_names = mods.iter().map(|r2| r2.ident()).collect();
-        let mods = mods
-            .into_iter()
-            .map(|r2| r2.into_install(thunderstore))
-            .filter_map(Result::ok)
-            .collect_vec();
+
+        let mut installs = Vec::with_capacity(mods.len());
+        let mut substitutions = Vec::new();
+        let mut missing = Vec::new();
+
+        for r2 in mods {
+            let ident = r2.ident();
+
+            match r2.into_install(thunderstore) {
+                Ok((install, substitution)) => {
+                    installs.push(install);
+                    substitutions.extend(substitution);
+                }
+                Err(err) => {
+                    warn!("skipping {} in import: {:#}", ident, err);
+                    missing.push(ident);
+                }
+            }
+        }
 
         Ok(Self {
             name,
             mod_names,
-            mods,
+            mods: installs,
             path,
             delete_after_import,
             ignored_updates,
             source,
+            substitutions,
+            missing,
+            loader_mismatch: None,
+        })
+    }
+
+    /// Skips the mods which need a different mod loader than `target` has.
+    fn check_loader(&mut self, origin: &ExportOrigin, target: Game, thunderstore: &Thunderstore) {
+        self.loader_mismatch = LoaderMismatch::check(origin, target, &mut self.mods, thunderstore);
+    }
+}
+
+/// What importing an [`ImportData`] into the active game would do.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportPreview {
+    #[serde(flatten)]
+    plan: InstallPlan,
+    substitutions: Vec<VersionSubstitution>,
+    missing: Vec<String>,
+    loader_mismatch: Option<LoaderMismatch>,
+}
+
+impl ImportData {
+    /// Plans the import without installing anything.
+    ///
+    /// Mods are marked as installed if they're in the active profile.
+    pub fn preview(&self, app: &AppHandle) -> Result<ImportPreview> {
+        let prefs = app.lock_prefs()?;
+        let manager = app.lock_manager()?;
+        let thunderstore = app.lock_thunderstore()?;
+
+        let offline_mods = self.path.join(export::OFFLINE_MODS_DIR);
+
+        let plan = install::plan(
+            &self.mods,
+            Some(&offline_mods),
+            manager.active_profile(),
+            &prefs,
+            &thunderstore,
+            app.db(),
+        )?;
+
+        Ok(ImportPreview {
+            plan,
+            substitutions: self.substitutions.clone(),
+            missing: self.missing.clone(),
+            loader_mismatch: self.loader_mismatch.clone(),
         })
     }
 }
 
 fn import_file(source: impl Read + Seek, app: &AppHandle) -> Result<ImportData> {
-    let thunderstore = app.lock_thunderstore();
+    let prefs = app.lock_prefs()?;
+    let manager = app.lock_manager()?;
+    let thunderstore = app.lock_thunderstore()?;
 
-    let temp_dir = tempdir().context("failed to create temporary directory")?;
+    // offline archives are installed by hard linking the extracted files,
+    // so they need to be on the same drive as the profiles
+    let temp_dir = TempDir::new_in(&prefs.temp_dir(), "import")?;
     util::zip::extract(source, temp_dir.path())?;
 
     let reader = File::open(temp_dir.path().join("export.r2x"))
@@ -91,7 +244,7 @@ fn import_file(source: impl Read + Seek, app: &AppHandle) -> Result<ImportData>
     let manifest: LegacyProfileManifest =
         serde_yaml::from_reader(reader).context("failed to read profile manifest")?;
 
-    ImportData::create_r2(
+    let mut data = ImportData::create_r2(
         manifest.profile_name,
         manifest.mods,
         manifest.ignored_updates,
@@ -99,7 +252,13 @@ fn import_file(source: impl Read + Seek, app: &AppHandle) -> Result<ImportData>
         true,
         manifest.source,
         &thunderstore,
-    )
+    )?;
+
+    if let Some(origin) = &manifest.origin {
+        data.check_loader(origin, manager.active_game, &thunderstore);
+    }
+
+    Ok(data)
 }
 
 async fn import_data(
@@ -107,22 +266,42 @@ async fn import_data(
     options: InstallOptions,
     import_all: bool,
     app: &AppHandle,
-) -> Result<()> {
-    let path = {
-        let mut manager = app.lock_manager();
+) -> Result<InstallSummary> {
+    // remove the extracted files even if the import fails
+    let _temp_dir = data
+        .delete_after_import
+        .then(|| TempDir::adopt(data.path.clone()));
+
+    let (profile_id, path) = {
+        let mut manager = app.lock_manager()?;
 
         let game = manager.active_game_mut();
-        if let Some(index) = game.profiles.iter().position(|p| p.name == data.name) {
+
+        // names from other mod managers may not be valid here
+        let name = game.sanitize_profile_name(&data.name);
+        if name != data.name {
+            info!("renamed imported profile '{}' to '{}'", data.name, name);
+        }
+
+        if let Some(index) = game.profile_index(&name) {
+            game.profiles[index].ensure_unlocked()?;
             game.delete_profile(index, true, app.db())
                 .context("failed to delete existing profile")?;
         }
 
-        let profile = game.create_profile(data.name, None, app.db())?;
+        let profile = game.create_profile(name, None, app.db())?;
         profile.ignored_updates.extend(data.ignored_updates);
-        profile.path.clone()
+        (profile.id, profile.path.clone())
     };
 
-    install::install_mods(data.mods, options, app)
+    let offline_mods = data.path.join(export::OFFLINE_MODS_DIR);
+    let options = match offline_mods.exists() {
+        true => options.local_source(offline_mods),
+        false => options,
+    }
+    .target_profile(profile_id);
+
+    let summary = install::install_mods(data.mods, options, app)
         .await
         .context("error while importing mods")?;
 
@@ -137,11 +316,7 @@ async fn import_data(
     );
     import_config(&path, &data.path, includes).context("failed to import config")?;
 
-    if data.delete_after_import {
-        fs::remove_dir_all(&data.path).ok();
-    }
-
-    Ok(())
+    Ok(summary)
 }
 
 pub fn import_config(
@@ -168,13 +343,77 @@ pub fn import_config(
     Ok(())
 }
 
+/// Applies a config bundle to a profile, overwriting the files it contains.
+///
+/// The bundle may be wrapped in extra directories, for example if it was
+/// extracted and zipped again by hand, which are stripped off.
+///
+/// Returns the imported files, relative to the profile.
+pub fn import_config_bundle(
+    source: impl Read + Seek,
+    profile_dir: &Path,
+    game: Game,
+    temp_root: &Path,
+) -> Result<Vec<PathBuf>> {
+    let temp_dir = TempDir::new_in(temp_root, "config")?;
+    util::zip::extract(source, temp_dir.path()).context("failed to extract bundle")?;
+
+    let root = WalkDir::new(temp_dir.path())
+        .sort_by_key(|entry| entry.depth())
+        .into_iter()
+        .filter_map(Result::ok)
+        .find(|entry| entry.file_name() == export::CONFIG_BUNDLE_MARKER)
+        .and_then(|entry| entry.path().parent().map(Path::to_path_buf))
+        .ok_or_eyre("file is not a config bundle")?;
+
+    let origin: ExportOrigin = util::fs::read_json(root.join(export::CONFIG_BUNDLE_MARKER))
+        .context("failed to read bundle origin")?;
+
+    ensure!(
+        origin.game == game.slug,
+        "bundle is for {}, not {}",
+        origin.game,
+        game.name
+    );
+
+    // only config files, so a bundle can't be used to sneak plugins into the profile
+    let config_dir = game.mod_loader.config_path();
+    let (files, skipped): (Vec<_>, Vec<_>) =
+        export::find_config(&root, IncludeExtensions::Default, IncludeGenerated::No)
+            .partition(|file| file.starts_with(&config_dir));
+
+    if !skipped.is_empty() {
+        warn!(
+            "skipped {} files outside of {} in config bundle: {}",
+            skipped.len(),
+            config_dir.display(),
+            skipped.iter().map(|file| file.display()).join(", ")
+        );
+    }
+
+    for file in &files {
+        let target = profile_dir.join(file);
+
+        fs::create_dir_all(target.parent().unwrap())
+            .fs_context("creating config directory", &target)?;
+        fs::copy(root.join(file), &target).fs_context("copying config file", &target)?;
+    }
+
+    info!(
+        "imported {} config files into {}",
+        files.len(),
+        profile_dir.display()
+    );
+
+    Ok(files)
+}
+
 async fn import_code(key: Uuid, app: &AppHandle) -> Result<ImportData> {
-    let response = app
-        .http()
-        .get(format!(
-            "https://thunderstore.io/api/experimental/legacyprofile/get/{key}/"
-        ))
-        .send()
+    let request = app.http().get(format!(
+        "https://thunderstore.io/api/experimental/legacyprofile/get/{key}/"
+    ));
+
+    let response = api::send(Endpoint::Profile, request)
         .await?
         .error_for_status()
         .map_err(|err| match err.status() {
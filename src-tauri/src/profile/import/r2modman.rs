@@ -4,16 +4,17 @@ use std::{
 };
 
 use eyre::{bail, Context, Result};
+use itertools::Itertools;
 use log::{info, warn};
 use serde::Serialize;
 use tauri::{AppHandle, Emitter};
 
 use super::ImportData;
 use crate::{
-    logger,
+    game,
     profile::{
         export::{ImportSource, R2Mod},
-        install::InstallOptions,
+        install::{InstallOptions, InstallSummary, InstallTask},
     },
     state::ManagerExt,
     thunderstore::{self},
@@ -42,60 +43,202 @@ pub(super) fn gather_info(
     Ok(Some(ProfileImportData { path, profiles }))
 }
 
-pub(super) async fn import(path: PathBuf, include: &[bool], app: &AppHandle) -> Result<()> {
-    emit_update("Fetching mods from Thunderstore...", app);
+/// An r2modman or Thunderstore Mod Manager installation found in its default location.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct R2modmanInstall {
+    path: PathBuf,
+    /// Slugs of the games which have at least one profile.
+    games: Vec<&'static str>,
+}
 
-    thunderstore::wait_for_fetch(app).await;
+pub fn find_install() -> Option<R2modmanInstall> {
+    let path = find_path()?;
+
+    let games = game::all()
+        .filter(|game| path.join(&*game.r2_dir_name).join("profiles").is_dir())
+        .map(|game| &*game.slug)
+        .collect();
+
+    Some(R2modmanInstall { path, games })
+}
+
+/// What the r2modman import is currently doing, sent with [`ImportProgress`].
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportPhase {
+    FetchingMods,
+    ReadingManifest,
+    Resolving,
+    Installing,
+    CopyingConfigs,
+}
+
+/// Emitted as `r2modman-import-progress` during [`import`].
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ImportProgress<'a> {
+    game: &'a str,
+    profile: Option<&'a str>,
+    profile_index: usize,
+    total_profiles: usize,
+    mods_processed: usize,
+    total_mods: usize,
+    phase: ImportPhase,
+}
+
+/// Emitted as `r2modman-import-report` once [`import`] is finished.
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    imported: Vec<String>,
+    /// Profiles without a `mods.yml`.
+    skipped: Vec<String>,
+    failed: Vec<ImportFailure>,
+    /// Number of mods taken from r2modman's cache.
+    reused: usize,
+    downloaded: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportFailure {
+    profile: String,
+    error: String,
+}
+
+/// Sends progress events for a single profile.
+#[derive(Clone)]
+struct ProgressSender {
+    app: AppHandle,
+    game: &'static str,
+    profile: Option<String>,
+    profile_index: usize,
+    total_profiles: usize,
+}
+
+impl ProgressSender {
+    fn send(&self, phase: ImportPhase, mods_processed: usize, total_mods: usize) {
+        let progress = ImportProgress {
+            game: self.game,
+            profile: self.profile.as_deref(),
+            profile_index: self.profile_index,
+            total_profiles: self.total_profiles,
+            mods_processed,
+            total_mods,
+            phase,
+        };
+
+        self.app.emit("r2modman-import-progress", &progress).ok();
+    }
+}
+
+pub(super) async fn import(
+    path: PathBuf,
+    include: &[bool],
+    app: &AppHandle,
+) -> Result<ImportReport> {
+    let game = app.lock_manager()?.active_game;
+
+    let profiles = find_profiles(path.clone(), app)?
+        .zip(include)
+        .filter_map(|(profile_dir, include)| include.then_some(profile_dir))
+        .collect_vec();
+
+    let mut progress = ProgressSender {
+        app: app.clone(),
+        game: game.name,
+        profile: None,
+        profile_index: 0,
+        total_profiles: profiles.len(),
+    };
+
+    progress.send(ImportPhase::FetchingMods, 0, 0);
+    thunderstore::wait_for_fetch(app).await?;
 
     info!("importing r2modman profiles from {}", path.display());
 
-    for (i, profile_dir) in find_profiles(path, app)?.enumerate() {
-        if !include[i] {
-            continue;
-        }
+    // r2modman keeps the extracted archives of every mod it has installed,
+    // which saves us from downloading them again
+    let cache = path.join(&*game.r2_dir_name).join("cache").exists_or_none();
+    if let Some(cache) = &cache {
+        info!("reusing r2modman cache at {}", cache.display());
+    }
+
+    let mut report = ImportReport::default();
 
-        let name = profile_dir.file_name().unwrap().to_string_lossy();
+    for (index, profile_dir) in profiles.into_iter().enumerate() {
+        let name = util::fs::file_name_owned(&profile_dir);
 
-        let data = match prepare_import(profile_dir.clone(), app) {
+        progress.profile = Some(name.clone());
+        progress.profile_index = index;
+
+        let data = match prepare_import(profile_dir, &progress, app) {
             Ok(Some(data)) => data,
             Ok(None) => {
+                report.skipped.push(name);
                 continue;
             }
             Err(err) => {
-                logger::log_webview_err(
-                    "Error while importing from r2modman",
-                    err.wrap_err(format!("Failed to prepare import of profile '{}'", name)),
-                    app,
-                );
+                let err = err.wrap_err(format!("failed to prepare import of profile '{}'", name));
+                warn!("{:#}", err);
+
+                report.failed.push(ImportFailure {
+                    profile: name,
+                    error: format!("{:#}", err),
+                });
                 continue;
             }
         };
 
-        if let Err(err) = import_profile(data, app).await {
-            logger::log_webview_err(
-                "Error while importing from r2modman",
-                err.wrap_err(format!("Failed to import profile '{}'", name)),
-                app,
-            );
-
-            let mut manager = app.lock_manager();
-
-            let game = manager.active_game_mut();
-
-            if let Some(index) = game.profile_index(&name) {
-                game.delete_profile(index, true, app.db())
-                    .unwrap_or_else(|_| {
-                        warn!("failed to delete possibly corrupted profile '{}'", name)
-                    });
+        match import_profile(data, cache.clone(), &progress, app).await {
+            Ok(summary) => {
+                report.reused += summary.reused;
+                report.downloaded += summary.downloaded;
+                report.imported.push(name);
             }
-        };
+            Err(err) => {
+                let err = err.wrap_err(format!("failed to import profile '{}'", name));
+                warn!("{:#}", err);
+
+                {
+                    let mut manager = app.lock_manager()?;
+
+                    let game = manager.active_game_mut();
+
+                    if let Some(index) = game.profile_index(&name) {
+                        game.delete_profile(index, true, app.db())
+                            .unwrap_or_else(|_| {
+                                warn!("failed to delete possibly corrupted profile '{}'", name)
+                            });
+                    }
+                }
+
+                report.failed.push(ImportFailure {
+                    profile: name,
+                    error: format!("{:#}", err),
+                });
+            }
+        }
     }
 
-    Ok(())
+    info!(
+        "imported {} profiles from r2modman, {} failed and {} were skipped \
+        ({} mods reused from r2modman's cache, {} downloaded)",
+        report.imported.len(),
+        report.failed.len(),
+        report.skipped.len(),
+        report.reused,
+        report.downloaded
+    );
+
+    app.emit("r2modman-import-report", &report).ok();
+
+    Ok(report)
 }
 
 fn find_profiles(mut path: PathBuf, app: &AppHandle) -> Result<impl Iterator<Item = PathBuf>> {
-    let manager = app.lock_manager();
+    let manager = app.lock_manager()?;
 
     let game = &manager.active_game;
 
@@ -117,33 +260,48 @@ fn find_profiles(mut path: PathBuf, app: &AppHandle) -> Result<impl Iterator<Ite
         .map(|entry| entry.path()))
 }
 
-async fn import_profile(data: ImportData, app: &AppHandle) -> Result<()> {
+async fn import_profile(
+    data: ImportData,
+    cache: Option<PathBuf>,
+    progress: &ProgressSender,
+    app: &AppHandle,
+) -> Result<InstallSummary> {
     info!("importing profile '{}'", data.name);
-    emit_update(&format!("Importing profile '{}'... 0%", data.name), app);
-
-    let name = data.name.clone();
-
-    super::import_data(
-        data,
-        InstallOptions::default()
-            .can_cancel(false)
-            .send_progress(false)
-            .on_progress(Box::new(move |progress, app| {
-                let percentage = (progress.total_progress * 100.0).round();
-                emit_update(
-                    &format!("Importing profile '{}'... {}%", name, percentage),
-                    app,
-                );
-            })),
-        false,
-        app,
-    )
-    .await
+
+    let options = InstallOptions::default()
+        .can_cancel(false)
+        .send_progress(false)
+        .on_progress(Box::new({
+            let progress = progress.clone();
+
+            move |install, _| {
+                // configs are copied right after the mods are installed
+                let phase = match install.task {
+                    InstallTask::Done { .. } => ImportPhase::CopyingConfigs,
+                    _ => ImportPhase::Installing,
+                };
+
+                progress.send(phase, install.installed_mods, install.total_mods);
+            }
+        }));
+
+    let options = match cache {
+        Some(cache) => options.archive_source(cache),
+        None => options,
+    };
+
+    super::import_data(data, options, false, app).await
 }
 
-fn prepare_import(mut profile_dir: PathBuf, app: &AppHandle) -> Result<Option<ImportData>> {
-    let mut manager = app.lock_manager();
-    let thunderstore = app.lock_thunderstore();
+fn prepare_import(
+    mut profile_dir: PathBuf,
+    progress: &ProgressSender,
+    app: &AppHandle,
+) -> Result<Option<ImportData>> {
+    progress.send(ImportPhase::ReadingManifest, 0, 0);
+
+    let mut manager = app.lock_manager()?;
+    let thunderstore = app.lock_thunderstore()?;
 
     let name = util::fs::file_name_owned(&profile_dir);
 
@@ -158,6 +316,8 @@ fn prepare_import(mut profile_dir: PathBuf, app: &AppHandle) -> Result<Option<Im
 
     profile_dir.pop();
 
+    progress.send(ImportPhase::Resolving, 0, mods.len());
+
     if let Some(index) = manager.active_game().profile_index(&name) {
         info!("deleting existing profile '{}'", name);
 
@@ -197,7 +357,3 @@ fn find_path() -> Option<PathBuf> {
                 .exists_or_none()
         })
 }
-
-fn emit_update(message: &str, app: &AppHandle) {
-    app.emit("transfer_update", message).ok();
-}
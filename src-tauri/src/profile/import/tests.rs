@@ -0,0 +1,105 @@
+use super::*;
+use crate::thunderstore::{
+    tests::{test_package, test_thunderstore},
+    ModId, PackageIdent, PackageListing, VersionIdent,
+};
+
+fn install(package: &PackageListing) -> ModInstall {
+    ModInstall::new(ModId {
+        package_uuid: package.uuid,
+        version_uuid: package.latest().uuid,
+    })
+}
+
+#[test]
+fn mods_needing_another_loader_are_skipped() {
+    let mut bepinex = test_package("BepInExPack", &[]);
+    bepinex.ident = PackageIdent::new("BepInEx", "BepInExPack");
+    bepinex.versions[0].ident = VersionIdent::new("BepInEx", "BepInExPack", "1.0.0");
+
+    let mut plugin = test_package("Plugin", &[]);
+    plugin.versions[0].dependencies = vec![bepinex.versions[0].ident.clone()];
+
+    // depends on the loader through the plugin
+    let addon = test_package("Addon", &["Plugin"]);
+    let standalone = test_package("Standalone", &[]);
+
+    let mut installs = [&bepinex, &plugin, &addon, &standalone]
+        .map(install)
+        .to_vec();
+    let thunderstore = test_thunderstore([bepinex, plugin, addon, standalone]);
+
+    let origin = ExportOrigin::new(game::from_slug("lethal-company").unwrap());
+
+    let same_loader = game::from_slug("content-warning").unwrap();
+    assert!(LoaderMismatch::check(&origin, same_loader, &mut installs, &thunderstore).is_none());
+    assert_eq!(installs.len(), 4);
+
+    let melon_loader = game::from_slug("bonelab").unwrap();
+    let mismatch = LoaderMismatch::check(&origin, melon_loader, &mut installs, &thunderstore)
+        .expect("loaders differ");
+
+    assert_eq!(mismatch.from, "BepInEx");
+    assert_eq!(mismatch.to, "MelonLoader");
+    assert_eq!(
+        mismatch.skipped,
+        [
+            "BepInEx-BepInExPack-1.0.0",
+            "Test-Plugin-1.0.0",
+            "Test-Addon-1.0.0"
+        ]
+    );
+    assert_eq!(installs.len(), 1);
+}
+
+#[test]
+fn config_bundles_are_unwrapped() {
+    use std::io::Write;
+
+    let game = game::from_slug("lethal-company").unwrap();
+
+    let bundle = |origin: &ExportOrigin| {
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = util::zip::file_options();
+
+        // as if the bundle was extracted and zipped again
+        zip.start_file("Preset/config_bundle.json", options)
+            .unwrap();
+        serde_json::to_writer(&mut zip, origin).unwrap();
+
+        zip.start_file("Preset/BepInEx/config/Mod.cfg", options)
+            .unwrap();
+        zip.write_all(b"[Section]\nValue = 1\n").unwrap();
+
+        // neither of these are config
+        for name in [
+            "Preset/BepInEx/config/Payload.dll",
+            "Preset/BepInEx/plugins/Mod/data.json",
+        ] {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(b"data").unwrap();
+        }
+
+        zip.finish().unwrap()
+    };
+
+    let temp = tempfile::tempdir().unwrap();
+    let profile_dir = temp.path().join("profile");
+
+    let files = import_config_bundle(
+        bundle(&ExportOrigin::new(game)),
+        &profile_dir,
+        game,
+        temp.path(),
+    )
+    .unwrap();
+
+    assert_eq!(files, [Path::new("BepInEx/config/Mod.cfg")]);
+    assert!(profile_dir.join("BepInEx/config/Mod.cfg").exists());
+    assert!(!profile_dir.join("config_bundle.json").exists());
+    assert!(!profile_dir.join("BepInEx/config/Payload.dll").exists());
+    assert!(!profile_dir.join("BepInEx/plugins").exists());
+
+    let other = ExportOrigin::new(game::from_slug("valheim").unwrap());
+    assert!(import_config_bundle(bundle(&other), &profile_dir, game, temp.path()).is_err());
+}
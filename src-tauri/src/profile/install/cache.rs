@@ -1,10 +1,25 @@
-use std::{collections::HashSet, fs, path::PathBuf};
+use std::{
+    cmp,
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
 
+use chrono::{DateTime, Utc};
 use eyre::{Context, Result};
-use log::info;
+use itertools::Itertools;
+use log::{info, warn};
+use serde::Serialize;
 use tauri::AppHandle;
 
-use crate::{prefs::Prefs, state::ManagerExt, thunderstore::VersionIdent, util};
+use super::store;
+use crate::{
+    db::Db,
+    prefs::Prefs,
+    state::ManagerExt,
+    thunderstore::{icons, VersionIdent},
+    util::{self, error::IoResultExt, temp::TempDir},
+};
 
 pub(super) fn path(ident: &VersionIdent, prefs: &Prefs) -> PathBuf {
     let mut path = prefs.cache_dir();
@@ -15,6 +30,13 @@ pub(super) fn path(ident: &VersionIdent, prefs: &Prefs) -> PathBuf {
     path
 }
 
+/// Creates a directory to extract a package to when the cache is bypassed.
+///
+/// The directory is removed as soon as the package has been installed, or the install fails.
+pub(super) fn staging_dir(prefs: &Prefs) -> Result<TempDir> {
+    TempDir::new_in(&prefs.temp_dir(), "install")
+}
+
 pub(super) fn clear(path: PathBuf) -> Result<()> {
     if path.exists() {
         fs::remove_dir_all(&path).context("failed to delete cache directory")?;
@@ -24,10 +46,428 @@ pub(super) fn clear(path: PathBuf) -> Result<()> {
     Ok(())
 }
 
+/// Removes a single version from the cache, so it's downloaded again on the next install.
+///
+/// Returns whether the version was cached.
+pub(super) fn remove_version(ident: &VersionIdent, prefs: &Prefs, db: &Db) -> Result<bool> {
+    let path = path(ident, prefs);
+
+    if !path.exists() {
+        return Ok(false);
+    }
+
+    remove_entry(&path).with_context(|| format!("failed to delete cached version of {}", ident))?;
+    collect_garbage(&prefs.cache_dir(), db)?;
+
+    info!("removed {} from cache", ident);
+
+    Ok(true)
+}
+
+/// Removes a version directory from the cache, along with its
+/// package directory if it was the last cached version.
+fn remove_entry(path: &Path) -> Result<()> {
+    fs::remove_dir_all(path)?;
+
+    if let Some(parent) = path.parent() {
+        if parent.read_dir().is_ok_and(|mut dir| dir.next().is_none()) {
+            fs::remove_dir(parent).ok();
+        }
+    }
+
+    Ok(())
+}
+
+pub(super) struct CacheEntry {
+    pub ident: VersionIdent,
+    pub path: PathBuf,
+    pub size: u64,
+    /// When the version was last installed or extracted. Versions cached
+    /// before this was tracked fall back to their directory's modified time.
+    pub last_access: Option<DateTime<Utc>>,
+}
+
+/// A cached version, as shown in the cache manager.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CachedVersion {
+    pub full_name: String,
+    pub version: String,
+    pub size: u64,
+    pub last_accessed: Option<DateTime<Utc>>,
+}
+
+impl From<CacheEntry> for CachedVersion {
+    fn from(entry: CacheEntry) -> Self {
+        Self {
+            full_name: entry.ident.full_name().to_owned(),
+            version: entry.ident.version().to_owned(),
+            size: entry.size,
+            last_accessed: entry.last_access,
+        }
+    }
+}
+
+/// Lists every cached version, largest first.
+pub(super) fn list(
+    cache_dir: &Path,
+    access_times: &HashMap<String, DateTime<Utc>>,
+) -> Result<Vec<CachedVersion>> {
+    let entries = read_entries(cache_dir, access_times)?
+        .into_iter()
+        .sorted_by_key(|entry| cmp::Reverse(entry.size))
+        .map_into()
+        .collect();
+
+    Ok(entries)
+}
+
+/// Finds the directory of every cached version, across all games.
+fn versions(cache_dir: &Path) -> Result<Vec<(VersionIdent, PathBuf)>> {
+    if !cache_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut versions = Vec::new();
+
+    let packages = cache_dir
+        .read_dir()
+        .context("failed to read cache directory")?
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            entry.file_name() != store::STORE_DIR
+                && entry.file_name() != icons::DIR_NAME
+                && entry.path().is_dir()
+        });
+
+    for package in packages {
+        let package_name = util::fs::file_name_owned(package.path());
+
+        let entries = fs::read_dir(package.path())
+            .with_context(|| format!("failed to read cache for {}", &package_name))?
+            .filter_map(Result::ok);
+
+        for entry in entries {
+            let path = entry.path();
+            let version = util::fs::file_name_owned(&path);
+
+            if let Ok(ident) = format!("{}-{}", package_name, version).parse::<VersionIdent>() {
+                versions.push((ident, path));
+            }
+        }
+    }
+
+    Ok(versions)
+}
+
+/// Lists every cached version, across all games.
+fn read_entries(
+    cache_dir: &Path,
+    access_times: &HashMap<String, DateTime<Utc>>,
+) -> Result<Vec<CacheEntry>> {
+    let entries = versions(cache_dir)?
+        .into_iter()
+        .map(|(ident, path)| {
+            let last_access = access_times.get(ident.as_str()).copied().or_else(|| {
+                path.metadata()
+                    .and_then(|meta| meta.modified())
+                    .ok()
+                    .map(DateTime::from)
+            });
+
+            CacheEntry {
+                size: util::fs::get_directory_size(&path),
+                ident,
+                path,
+                last_access,
+            }
+        })
+        .collect();
+
+    Ok(entries)
+}
+
+/// The space the cache takes up on disk.
+///
+/// Files of compacted versions are only counted once, in the store.
+pub(super) fn disk_size(cache_dir: &Path, db: &Db) -> Result<u64> {
+    let compacted = db.compacted_cache_entries()?;
+
+    let uncompacted: u64 = versions(cache_dir)?
+        .iter()
+        .filter(|(ident, _)| !compacted.contains(ident.as_str()))
+        .map(|(_, path)| util::fs::get_directory_size(path))
+        .sum();
+
+    Ok(uncompacted + util::fs::get_directory_size(store::store_dir(cache_dir)))
+}
+
+/// Deduplicates a newly cached version against the store.
+pub(super) fn compact_version(
+    ident: &VersionIdent,
+    path: &Path,
+    cache_dir: &Path,
+    db: &Db,
+) -> Result<u64> {
+    let manifest = store::compact(path, &store::store_dir(cache_dir))
+        .with_context(|| format!("failed to compact cached version of {}", ident))?;
+
+    db.set_cache_manifest(ident, &manifest.files)?;
+
+    Ok(manifest.saved)
+}
+
+/// Marks a cache whose versions all have a manifest, see [`ensure_format`].
+const FORMAT_FILE: &str = ".format";
+const FORMAT_VERSION: &str = "1";
+
+/// Makes sure the cache is in the current format, before anything
+/// is written to or verified against it.
+///
+/// Caches from before versions were verified have no manifests, so
+/// their versions are taken as complete and compacted once. After that,
+/// a version without a manifest means its extraction was interrupted.
+pub(super) fn ensure_format(cache_dir: &Path, db: &Db) -> Result<()> {
+    let path = cache_dir.join(FORMAT_FILE);
+    if path.exists() {
+        return Ok(());
+    }
+
+    if let Err(err) = compact_all(cache_dir, db) {
+        // the versions that weren't compacted are downloaded again when needed
+        warn!("failed to compact existing cache: {:#}", err);
+    }
+
+    fs::create_dir_all(cache_dir).fs_context("creating cache directory", cache_dir)?;
+    fs::write(&path, FORMAT_VERSION).fs_context("writing cache format", &path)?;
+
+    Ok(())
+}
+
+/// Checks that a cached version was fully extracted, by comparing it with
+/// the manifest recorded afterwards. Incomplete versions are removed,
+/// so they're downloaded again.
+///
+/// Stored files the version linked to are left for the next garbage collection.
+pub(super) fn check_version(
+    ident: &VersionIdent,
+    path: &Path,
+    cache_dir: &Path,
+    db: &Db,
+) -> Result<bool> {
+    ensure_format(cache_dir, db)?;
+
+    let valid = match db.cache_manifest(ident)? {
+        Some(manifest) => store::verify(path, &manifest),
+        None => false,
+    };
+
+    if !valid {
+        warn!("cached version of {} is incomplete, removing it", ident);
+
+        remove_entry(path)
+            .with_context(|| format!("failed to remove incomplete cache of {}", ident))?;
+        db.remove_cache_manifests([ident.as_str()])?;
+    }
+
+    Ok(valid)
+}
+
+#[derive(Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheCompaction {
+    pub versions: usize,
+    /// The number of bytes saved by deduplicating files.
+    pub saved: u64,
+}
+
+/// Compacts every cached version that isn't already, such as
+/// those cached before deduplication was introduced.
+pub(super) fn compact_all(cache_dir: &Path, db: &Db) -> Result<CacheCompaction> {
+    let compacted = db.compacted_cache_entries()?;
+    let mut result = CacheCompaction::default();
+
+    for (ident, path) in versions(cache_dir)? {
+        if compacted.contains(ident.as_str()) {
+            continue;
+        }
+
+        result.saved += compact_version(&ident, &path, cache_dir, db)?;
+        result.versions += 1;
+    }
+
+    info!(
+        "compacted {} cached versions, saving {} bytes",
+        result.versions, result.saved
+    );
+
+    Ok(result)
+}
+
+/// Forgets versions that are no longer cached, then removes stored
+/// files that no remaining version links to.
+pub(super) fn collect_garbage(cache_dir: &Path, db: &Db) -> Result<()> {
+    let cached = versions(cache_dir)?
+        .into_iter()
+        .map(|(ident, _)| ident.as_str().to_owned())
+        .collect::<HashSet<_>>();
+
+    let compacted = db.compacted_cache_entries()?;
+    db.remove_cache_manifests(
+        compacted
+            .iter()
+            .filter(|ident| !cached.contains(*ident))
+            .map(String::as_str),
+    )?;
+
+    let freed =
+        store::collect_garbage(&store::store_dir(cache_dir), &db.referenced_cache_hashes()?)?;
+
+    if freed > 0 {
+        info!(
+            "removed {} bytes of unused files from the cache store",
+            freed
+        );
+    }
+
+    Ok(())
+}
+
+/// Picks the least recently used entries to remove until the cache fits in `max_size`.
+///
+/// Entries in `in_use` are never picked, so the result might not be enough
+/// to get below the limit.
+///
+/// `files` holds the hash and size of each file of the compacted versions.
+/// Those are hard links into the store, so a file shared between versions
+/// is only counted once, and only freed once every version using it is.
+///
+/// Returns the versions to evict and how many bytes that frees.
+pub(super) fn lru_evictions<'a>(
+    entries: &'a [CacheEntry],
+    files: &HashMap<String, Vec<(String, u64)>>,
+    in_use: &HashSet<VersionIdent>,
+    max_size: u64,
+) -> (Vec<&'a CacheEntry>, u64) {
+    let files_of = |entry: &CacheEntry| files.get(entry.ident.as_str());
+
+    // the number of versions linking to each stored file, and its size
+    let mut links = HashMap::<&str, (usize, u64)>::new();
+    for (hash, size) in entries.iter().filter_map(files_of).flatten() {
+        links.entry(hash).or_insert((0, *size)).0 += 1;
+    }
+
+    let unlinked_size = |entry: &CacheEntry| match files_of(entry) {
+        Some(_) => 0,
+        None => entry.size,
+    };
+
+    let start = entries.iter().map(unlinked_size).sum::<u64>()
+        + links.values().map(|(_, size)| size).sum::<u64>();
+    let mut total = start;
+
+    let evictions = entries
+        .iter()
+        .filter(|entry| !in_use.contains(&entry.ident))
+        .sorted_by_key(|entry| entry.last_access)
+        .take_while(|entry| {
+            if total <= max_size {
+                return false;
+            }
+
+            total -= unlinked_size(entry);
+
+            for (hash, _) in files_of(entry).into_iter().flatten() {
+                let (count, size) = links.get_mut(hash.as_str()).unwrap();
+                *count -= 1;
+
+                if *count == 0 {
+                    total -= *size;
+                }
+            }
+
+            true
+        })
+        .collect();
+
+    (evictions, start - total)
+}
+
+/// Removes the least recently used versions from the cache until it is under
+/// [`Prefs::max_cache_size`]. Versions installed in any profile are kept.
+pub(super) fn evict(app: &AppHandle) -> Result<()> {
+    let (max_size, cache_dir) = {
+        let prefs = app.lock_prefs()?;
+
+        match prefs.max_cache_size() {
+            Some(max_size) => (max_size, prefs.cache_dir()),
+            None => return Ok(()),
+        }
+    };
+
+    let in_use = app
+        .lock_manager()?
+        .games
+        .values()
+        .flat_map(|game| &game.profiles)
+        .flat_map(|profile| &profile.mods)
+        .filter_map(|profile_mod| profile_mod.as_thunderstore())
+        .map(|(ts_mod, _)| ts_mod.ident.clone())
+        .collect::<HashSet<_>>();
+
+    let access_times = app.db().cache_access_times()?;
+    let entries = read_entries(&cache_dir, &access_times)?;
+
+    let store = store::store_dir(&cache_dir);
+    let files = app
+        .db()
+        .cache_manifests()?
+        .into_iter()
+        .map(|(ident, files)| {
+            let files = files
+                .into_iter()
+                .map(|file| {
+                    // versions compacted before sizes were recorded
+                    let size = file
+                        .size
+                        .unwrap_or_else(|| store::stored_size(&store, &file.hash));
+                    (file.hash, size)
+                })
+                .collect();
+
+            (ident, files)
+        })
+        .collect();
+
+    let (evictions, freed) = lru_evictions(&entries, &files, &in_use, max_size);
+
+    if evictions.is_empty() {
+        return Ok(());
+    }
+
+    for entry in &evictions {
+        if let Err(err) = remove_entry(&entry.path) {
+            warn!("failed to evict {} from cache: {:#}", entry.ident, err);
+        }
+    }
+
+    app.db()
+        .remove_cache_entries(evictions.iter().map(|entry| &entry.ident))?;
+    collect_garbage(&cache_dir, app.db())?;
+
+    info!(
+        "evicted {} versions ({} bytes) from cache",
+        evictions.len(),
+        freed
+    );
+
+    Ok(())
+}
+
 pub(super) fn prepare_soft_clear(app: AppHandle) -> Result<Vec<PathBuf>> {
-    let prefs = app.lock_prefs();
-    let manager = app.lock_manager();
-    let thunderstore = app.lock_thunderstore();
+    let prefs = app.lock_prefs()?;
+    let manager = app.lock_manager()?;
+    let thunderstore = app.lock_thunderstore()?;
 
     let installed_mods = manager
         .active_game()
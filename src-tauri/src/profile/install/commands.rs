@@ -1,26 +1,188 @@
-use std::sync::atomic::Ordering;
+use std::{collections::HashSet, iter, sync::atomic::Ordering, time::Duration};
 
+use eyre::eyre;
+use log::info;
 use tauri::{command, AppHandle};
+use uuid::Uuid;
 
 use crate::{
+    profile::launch,
     state::ManagerExt,
-    thunderstore::ModId,
+    thunderstore::{blocklist, ModId, VersionIdent},
     util::{self, cmd::Result},
+    webhook::{self, WebhookEvent},
 };
 
-use super::{InstallOptions, ModInstall};
+use super::{
+    cache::{CacheCompaction, CachedVersion},
+    network::{NetworkStats, NetworkStatsRange},
+    AlreadyInstalled, InstallOptions, InstallPreview, InstallResponse, ModInstall,
+    NeedsConfirmation, PrecacheInfo,
+};
 
+/// Installs a mod and its dependencies into a profile of the active game,
+/// the active profile by default.
+///
+/// If the package is already installed, nothing happens and the ways
+/// to change the installed version are returned instead.
+///
+/// With `idempotent`, an installed package is switched to the requested
+/// version instead, or left alone if it's already on that version.
+///
+/// Deprecated and blocklisted packages are only installed with
+/// `acknowledge_flags`, otherwise they're returned to be confirmed.
 #[command]
-pub async fn install_mod(mod_ref: ModId, app: AppHandle) -> Result<()> {
-    super::install_with_deps(
+pub async fn install_mod(
+    mod_ref: ModId,
+    bypass_cache: Option<bool>,
+    exclude: Option<HashSet<Uuid>>,
+    profile_id: Option<i64>,
+    idempotent: Option<bool>,
+    acknowledge_flags: Option<bool>,
+    app: AppHandle,
+) -> Result<InstallResponse> {
+    let exclude = exclude.unwrap_or_default();
+    let idempotent = idempotent.unwrap_or(false);
+    let acknowledge_flags = acknowledge_flags.unwrap_or(false);
+
+    if !acknowledge_flags {
+        blocklist::refresh(&app).await;
+    }
+
+    let (warnings, skipped_dependencies, profile_id) = {
+        let prefs = app.lock_prefs()?;
+        let manager = app.lock_manager()?;
+        let thunderstore = app.lock_thunderstore()?;
+
+        let game = manager.active_game();
+        let profile = match profile_id {
+            Some(id) => game.find_profile(id)?,
+            None => game.active_profile(),
+        };
+
+        let borrowed = mod_ref.borrow(&thunderstore)?;
+
+        if !idempotent {
+            if let Some(installed) =
+                AlreadyInstalled::check(borrowed, profile, &prefs, &thunderstore)
+            {
+                return Ok(InstallResponse::AlreadyInstalled(installed));
+            }
+        }
+
+        let warnings = super::compat_warnings(borrowed, &exclude, profile, &prefs, &thunderstore);
+        let skipped = super::skipped_dependencies(borrowed, &exclude, &thunderstore);
+
+        (warnings, skipped, profile.id)
+    };
+
+    let result = super::install_with_deps(
         vec![ModInstall::new(mod_ref)],
-        InstallOptions::default(),
+        InstallOptions::default()
+            .bypass_cache(bypass_cache.unwrap_or(false))
+            .target_profile(profile_id)
+            .idempotent(idempotent)
+            .confirm_flagged(!acknowledge_flags),
         false,
+        &exclude,
         &app,
     )
-    .await?;
+    .await;
 
-    Ok(())
+    let installed = match result {
+        Ok(installed) => installed,
+        Err(err) => match err.downcast::<NeedsConfirmation>() {
+            Ok(NeedsConfirmation(flagged)) => {
+                return Ok(InstallResponse::NeedsConfirmation { flagged })
+            }
+            Err(err) => return Err(err.into()),
+        },
+    };
+
+    if installed.is_empty() {
+        return Ok(InstallResponse::AlreadySatisfied);
+    }
+
+    // a cancelled install returns normally, after cleaning up
+    if !app.app_state().cancel_install_flag.load(Ordering::Relaxed) {
+        webhook::dispatch(WebhookEvent::Install { mods: installed }, profile_id, &app);
+    }
+
+    let needs_game_dir_consent = app
+        .lock_manager()?
+        .active_game()
+        .find_profile(profile_id)?
+        .needs_game_dir_consent();
+
+    Ok(InstallResponse::Installed {
+        warnings,
+        skipped_dependencies,
+        needs_game_dir_consent,
+    })
+}
+
+/// Installs a mod and its dependencies into a profile of the active game, then
+/// launches the game with that profile, which becomes the active one.
+///
+/// Mods that are already installed are launched right away. Returns whether
+/// the game was launched, which it isn't if the install is cancelled.
+///
+/// Fails if flagged packages would be installed, unless `acknowledge_flags`
+/// is set. Use [`get_install_preview`] to ask the user first.
+#[command]
+pub async fn install_and_launch(
+    mod_ref: ModId,
+    profile_id: Option<i64>,
+    acknowledge_flags: Option<bool>,
+    app: AppHandle,
+) -> Result<bool> {
+    let (profile_id, is_installed) = {
+        let manager = app.lock_manager()?;
+        let thunderstore = app.lock_thunderstore()?;
+
+        let game = manager.active_game();
+        let profile = match profile_id {
+            Some(id) => game.find_profile(id)?,
+            None => game.active_profile(),
+        };
+
+        let uuid = mod_ref.borrow(&thunderstore)?.package.uuid;
+
+        (profile.id, profile.has_mod(uuid))
+    };
+
+    if !is_installed {
+        let installed = super::install_with_deps(
+            vec![ModInstall::new(mod_ref)],
+            InstallOptions::default()
+                .target_profile(profile_id)
+                .confirm_flagged(!acknowledge_flags.unwrap_or(false)),
+            false,
+            &HashSet::new(),
+            &app,
+        )
+        .await?;
+
+        // a cancelled install returns normally, after cleaning up
+        if app.app_state().cancel_install_flag.load(Ordering::Relaxed) {
+            info!("install was cancelled, not launching the game");
+            return Ok(false);
+        }
+
+        webhook::dispatch(WebhookEvent::Install { mods: installed }, profile_id, &app);
+    }
+
+    {
+        let mut manager = app.lock_manager()?;
+        let game = manager.active_game_mut();
+
+        game.active_profile_id = profile_id;
+        game.save(app.db())?;
+    }
+
+    launch::launch_active(&app)?;
+
+    Ok(true)
 }
 
 #[command]
@@ -32,37 +194,179 @@ pub fn cancel_install(app: AppHandle) -> Result<()> {
     Ok(())
 }
 
+/// Removes everything in the temp directory that isn't used by a running operation.
+///
+/// Returns the number of bytes freed.
+#[command]
+pub async fn clean_temp_now(app: AppHandle) -> Result<u64> {
+    let temp_dir = app.lock_prefs()?.temp_dir();
+
+    let freed =
+        tauri::async_runtime::spawn_blocking(move || util::temp::clean(&temp_dir, Duration::ZERO))
+            .await??;
+
+    Ok(freed)
+}
+
 #[command]
 pub async fn clear_download_cache(soft: bool, app: AppHandle) -> Result<u64> {
-    if soft {
-        let paths = super::cache::prepare_soft_clear(app)?;
+    let cache_dir = app.lock_prefs()?.cache_dir();
+
+    let size = if soft {
+        let paths = super::cache::prepare_soft_clear(app.clone())?;
 
         let size = paths.iter().map(util::fs::get_directory_size).sum();
 
         tauri::async_runtime::spawn_blocking(|| super::cache::do_soft_clear(paths)).await??;
 
-        Ok(size)
+        size
     } else {
-        let path = app.lock_prefs().cache_dir();
-
-        let size = util::fs::get_directory_size(&path);
+        let size = super::cache::disk_size(&cache_dir, app.db())?;
 
+        let path = cache_dir.clone();
         tauri::async_runtime::spawn_blocking(|| super::cache::clear(path)).await??;
 
-        Ok(size)
+        size
+    };
+
+    tauri::async_runtime::spawn_blocking(move || {
+        super::cache::collect_garbage(&cache_dir, app.db())
+    })
+    .await??;
+
+    Ok(size)
+}
+
+#[command]
+pub fn clear_cached_version(full_name: &str, version: &str, app: AppHandle) -> Result<bool> {
+    // these end up in a path, so make sure they can't point outside the cache
+    let (owner, name) = full_name.split_once('-').unwrap_or_default();
+    let is_valid = [owner, name, version]
+        .iter()
+        .all(|part| !part.is_empty() && *part != ".." && !part.contains(['/', '\\']));
+
+    if !is_valid {
+        return Err(eyre!("invalid package {}-{}", full_name, version).into());
     }
+
+    let ident = VersionIdent::new(owner, name, version);
+
+    let prefs = app.lock_prefs()?;
+    let removed = super::cache::remove_version(&ident, &prefs, app.db())?;
+
+    Ok(removed)
+}
+
+#[command]
+pub async fn get_cache_size(app: AppHandle) -> Result<u64> {
+    let path = app.lock_prefs()?.cache_dir();
+    let size =
+        tauri::async_runtime::spawn_blocking(move || super::cache::disk_size(&path, app.db()))
+            .await??;
+
+    Ok(size)
+}
+
+/// Deduplicates files across every cached version, returning the space saved.
+#[command]
+pub async fn compact_cache(app: AppHandle) -> Result<CacheCompaction> {
+    let cache_dir = app.lock_prefs()?.cache_dir();
+
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        super::cache::compact_all(&cache_dir, app.db())
+    })
+    .await??;
+
+    Ok(result)
+}
+
+#[command]
+pub async fn list_cache(app: AppHandle) -> Result<Vec<CachedVersion>> {
+    let cache_dir = app.lock_prefs()?.cache_dir();
+    let access_times = app.db().cache_access_times()?;
+
+    let entries =
+        tauri::async_runtime::spawn_blocking(move || super::cache::list(&cache_dir, &access_times))
+            .await??;
+
+    Ok(entries)
 }
 
 #[command]
 pub fn get_download_size(mod_ref: ModId, app: AppHandle) -> Result<u64> {
-    let prefs = app.lock_prefs();
-    let manager = app.lock_manager();
-    let thunderstore = app.lock_thunderstore();
+    let prefs = app.lock_prefs()?;
+    let manager = app.lock_manager()?;
+    let thunderstore = app.lock_thunderstore()?;
 
     Ok(super::total_download_size(
         mod_ref.borrow(&thunderstore)?,
+        &HashSet::new(),
         manager.active_profile(),
         &prefs,
         &thunderstore,
     ))
 }
+
+/// Describes what installing a mod into a profile of the active game would
+/// download, for the frontend to confirm before calling [`install_mod`].
+#[command]
+pub fn get_install_preview(
+    mod_ref: ModId,
+    exclude: Option<HashSet<Uuid>>,
+    profile_id: Option<i64>,
+    app: AppHandle,
+) -> Result<InstallPreview> {
+    let prefs = app.lock_prefs()?;
+    let manager = app.lock_manager()?;
+    let thunderstore = app.lock_thunderstore()?;
+
+    let borrowed = mod_ref.borrow(&thunderstore)?;
+    let exclude = exclude.unwrap_or_default();
+    let game = manager.active_game();
+    let profile = match profile_id {
+        Some(id) => game.find_profile(id)?,
+        None => game.active_profile(),
+    };
+
+    let download_size =
+        super::total_download_size(borrowed, &exclude, profile, &prefs, &thunderstore);
+
+    let flagged = super::flags::find(
+        iter::once(borrowed).chain(profile.missing_deps(
+            borrowed.dependencies(),
+            &exclude,
+            &thunderstore,
+        )),
+        &thunderstore,
+    );
+
+    Ok(InstallPreview {
+        download_cap: super::network::check_cap(download_size, &prefs, app.db())?,
+        download_size,
+        compatibility: super::compat_warnings(borrowed, &exclude, profile, &prefs, &thunderstore),
+        flagged,
+    })
+}
+
+#[command]
+pub fn get_network_stats(range: NetworkStatsRange, app: AppHandle) -> Result<NetworkStats> {
+    Ok(super::network::stats(range, app.db())?)
+}
+
+#[command]
+pub async fn precache_profile(profile_id: i64, app: AppHandle) -> Result<()> {
+    super::precache_profile(profile_id, InstallOptions::default(), &app).await?;
+
+    Ok(())
+}
+
+#[command]
+pub fn get_precache_info(profile_id: i64, app: AppHandle) -> Result<PrecacheInfo> {
+    let prefs = app.lock_prefs()?;
+    let manager = app.lock_manager()?;
+    let thunderstore = app.lock_thunderstore()?;
+
+    let profile = manager.active_game().find_profile(profile_id)?;
+
+    Ok(super::precache_info(profile, &prefs, &thunderstore))
+}
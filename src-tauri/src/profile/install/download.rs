@@ -1,26 +1,33 @@
 use std::{
-    fs,
-    io::Cursor,
-    path::Path,
+    fs::{self, File},
+    io::{BufWriter, Seek, Write},
+    mem,
+    path::{Path, PathBuf},
     sync::atomic::Ordering,
     time::{Duration, Instant},
 };
 
 use chrono::Utc;
 use core::str;
-use eyre::{Context, Result};
+use eyre::{ensure, eyre, Context, Result};
 use futures_util::StreamExt;
 use log::warn;
+use reqwest::header::CONTENT_TYPE;
+use serde::Deserialize;
 use tauri::{AppHandle, Emitter};
 use thiserror::Error;
 use zip::ZipArchive;
 
-use super::{cache, InstallOptions, InstallProgress, InstallTask, ModInstall};
+use super::{
+    cache, DisableStrategy, InstallOptions, InstallProgress, InstallSource, InstallSummary,
+    InstallTask, ModInstall, PackageSource,
+};
 use crate::{
-    profile::{ModManager, ProfileMod, ProfileModKind, ThunderstoreMod},
+    db::UsageKind,
+    profile::{launch, Profile, ProfileMod, ProfileModKind, ThunderstoreMod},
     state::ManagerExt,
-    thunderstore::Thunderstore,
-    util::error::IoResultExt,
+    thunderstore::{self, Thunderstore, VersionIdent},
+    util::{self, error::IoResultExt},
 };
 
 const DOWNLOAD_UPDATE_INTERVAL: Duration = Duration::from_millis(100);
@@ -29,18 +36,33 @@ pub struct Installer<'a> {
     options: InstallOptions,
     index: usize,
     current_name: String,
+    profile_id: i64,
+    profile_name: String,
+    use_cache: bool,
+    disable_strategy: DisableStrategy,
+    /// Only download and extract mods into the cache, without installing them.
+    cache_only: bool,
 
     start_time: Instant,
     total_mods: usize,
     total_bytes: u64,
     completed_bytes: u64,
+    summary: InstallSummary,
 
     app: &'a AppHandle,
 }
 
 enum InstallMethod {
     Cached,
-    Download { url: String, file_size: u64 },
+    Download {
+        url: String,
+        file_size: u64,
+    },
+    Repack {
+        path: PathBuf,
+        url: String,
+        file_size: u64,
+    },
 }
 
 #[derive(Debug, Error)]
@@ -52,17 +74,49 @@ enum InstallError {
     Error(#[from] eyre::Error),
 }
 
+impl InstallError {
+    fn from_http(err: reqwest::Error) -> Self {
+        let is_timeout = err.is_timeout();
+        let err = eyre::Error::from(err);
+
+        match is_timeout {
+            true => Self::Error(err.wrap_err(
+                "download timed out, check your connection or increase the timeout in settings",
+            )),
+            false => Self::Error(err),
+        }
+    }
+}
+
 type InstallResult<T> = std::result::Result<T, InstallError>;
 
 impl<'a> Installer<'a> {
     pub fn create(options: InstallOptions, app: &'a AppHandle) -> Result<Self> {
+        let prefs = app.lock_prefs()?;
+        let manager = app.lock_manager()?;
+        let use_cache = !options.bypass_cache && prefs.enable_mod_cache();
+
+        let mut options = options;
+        options
+            .archive_sources
+            .extend_from_slice(prefs.extra_cache_dirs());
+
+        let profile_id = options.resolve_profile(&manager);
+        let profile_name = manager.active_game().find_profile(profile_id)?.name.clone();
+
         Ok(Self {
             options,
+            profile_id,
+            profile_name,
+            use_cache,
+            disable_strategy: prefs.disable_strategy,
+            cache_only: false,
             index: 0,
             app,
             total_mods: 0,
             total_bytes: 0,
             completed_bytes: 0,
+            summary: InstallSummary::default(),
             current_name: String::new(),
             start_time: Instant::now(),
         })
@@ -94,6 +148,8 @@ impl<'a> Installer<'a> {
             total_mods: self.total_mods,
             can_cancel: self.options.can_cancel,
             current_name: &self.current_name,
+            profile_id: self.profile_id,
+            profile_name: &self.profile_name,
             duration_secs: self.start_time.elapsed().as_secs_f32(),
         };
 
@@ -107,28 +163,82 @@ impl<'a> Installer<'a> {
     }
 
     fn try_cache_install(&mut self, data: &ModInstall) -> Result<InstallMethod> {
-        let prefs = self.app.lock_prefs();
-        let mut manager = self.app.lock_manager();
-        let thunderstore = self.app.lock_thunderstore();
+        let prefs = self.app.lock_prefs()?;
+        let mut manager = self.app.lock_manager()?;
+        let thunderstore = self.app.lock_thunderstore()?;
 
         let version = data.id.borrow(&thunderstore)?.version;
         let cache_path = cache::path(&version.ident, &prefs);
 
         self.current_name = version.name().to_owned();
 
-        if cache_path.exists() {
+        if let Some(local_path) = self.local_path(&version.ident) {
             self.update(InstallTask::Installing);
 
+            let profile = manager
+                .active_game_mut()
+                .find_profile_mut(self.profile_id)?;
+
             if let Some(callback) = &self.options.before_install {
-                callback(data, &mut manager, &thunderstore)?;
+                callback(data, profile, &thunderstore)?;
             }
 
-            cache_install(data, &cache_path, &mut manager, &thunderstore)?;
+            cache_install(
+                data,
+                &local_path,
+                self.disable_strategy,
+                profile,
+                &thunderstore,
+            )?;
 
             self.completed_bytes += version.file_size;
-            manager.active_profile().save(self.app.db())?;
+            profile.save(self.app.db())?;
 
             Ok(InstallMethod::Cached)
+        } else if self.use_cache
+            && cache_path.exists()
+            && cache::check_version(
+                &version.ident,
+                &cache_path,
+                &prefs.cache_dir(),
+                self.app.db(),
+            )?
+        {
+            self.touch_cache(&version.ident);
+
+            if self.cache_only {
+                self.completed_bytes += version.file_size;
+                return Ok(InstallMethod::Cached);
+            }
+
+            self.update(InstallTask::Installing);
+
+            let profile = manager
+                .active_game_mut()
+                .find_profile_mut(self.profile_id)?;
+
+            if let Some(callback) = &self.options.before_install {
+                callback(data, profile, &thunderstore)?;
+            }
+
+            cache_install(
+                data,
+                &cache_path,
+                self.disable_strategy,
+                profile,
+                &thunderstore,
+            )?;
+
+            self.completed_bytes += version.file_size;
+            profile.save(self.app.db())?;
+
+            Ok(InstallMethod::Cached)
+        } else if let Some(path) = self.archive_path(&version.ident) {
+            Ok(InstallMethod::Repack {
+                path,
+                url: version.download_url(),
+                file_size: version.file_size,
+            })
         } else {
             Ok(InstallMethod::Download {
                 url: version.download_url(),
@@ -137,55 +247,138 @@ impl<'a> Installer<'a> {
         }
     }
 
-    async fn download(&mut self, url: &str, file_size: u64) -> InstallResult<Vec<u8>> {
+    /// Marks a cached version as recently used, so it's evicted last.
+    fn touch_cache(&self, ident: &VersionIdent) {
+        if let Err(err) = self.app.db().touch_cache_entry(ident, Utc::now()) {
+            warn!("failed to record cache access of {}: {:#}", ident, err);
+        }
+    }
+
+    /// Finds a package in [`InstallOptions::local_source`], if one is set.
+    fn local_path(&self, ident: &VersionIdent) -> Option<PathBuf> {
+        if self.cache_only {
+            return None;
+        }
+
+        let mut path = self.options.local_source.clone()?;
+        path.push(ident.full_name());
+        path.push(ident.version());
+
+        path.exists().then_some(path)
+    }
+
+    /// Finds a package in one of the [`InstallOptions::archive_source`] directories.
+    ///
+    /// Entries whose manifest doesn't match the package are skipped.
+    fn archive_path(&self, ident: &VersionIdent) -> Option<PathBuf> {
+        self.options
+            .archive_sources
+            .iter()
+            .map(|dir| dir.join(ident.full_name()).join(ident.version()))
+            .filter(|path| path.exists())
+            .find(|path| match check_archive_entry(path, ident) {
+                Ok(()) => true,
+                Err(err) => {
+                    warn!(
+                        "skipping malformed cache entry at {}: {:#}",
+                        path.display(),
+                        err
+                    );
+                    false
+                }
+            })
+    }
+
+    /// Packs an extracted package from [`InstallOptions::archive_source`]
+    /// back into an archive, as if it had been downloaded.
+    fn repack(&mut self, path: &Path, file_size: u64) -> Result<PackageSource> {
+        self.update(InstallTask::Extracting);
+
+        let data = util::zip::pack_dir(path)?;
+        self.completed_bytes += file_size;
+
+        Ok(data.into())
+    }
+
+    /// Downloads an archive into an anonymous file in the temp directory,
+    /// which is deleted as soon as it's closed.
+    ///
+    /// Only one chunk of the response is held in memory at a time.
+    async fn download(&mut self, url: &str, file_size: u64) -> InstallResult<PackageSource> {
         self.update(InstallTask::Downloading {
             total: file_size,
             downloaded: 0,
         });
 
-        let mut stream = self
+        let (timeout, temp_dir) = {
+            let prefs = self.app.lock_prefs()?;
+            (prefs.download_timeout(), prefs.temp_dir())
+        };
+
+        let response = self
             .app
             .http()
             .get(url)
+            .timeout(timeout)
             .send()
             .await
             .and_then(|response| response.error_for_status())
-            .map_err(|err| InstallError::Error(err.into()))?
-            .bytes_stream();
+            .map_err(InstallError::from_http)?;
 
         let mut last_update = Instant::now();
-        let mut response = Vec::with_capacity(file_size as usize);
-
-        while let Some(item) = stream.next().await {
-            let item = item.map_err(|err| InstallError::Error(err.into()))?;
+        let mut downloaded = 0;
 
-            self.completed_bytes += item.len() as u64;
-            response.extend_from_slice(&item);
+        let file = stream_to_temp_file(response, &temp_dir, |len| {
+            self.completed_bytes += len;
+            downloaded += len;
 
             if last_update.elapsed() >= DOWNLOAD_UPDATE_INTERVAL {
                 self.update(InstallTask::Downloading {
                     total: file_size,
-                    downloaded: response.len() as u64,
+                    downloaded,
                 });
 
                 last_update = Instant::now();
 
                 self.check_cancel()?;
             };
-        }
 
-        Ok(response)
+            Ok(())
+        })
+        .await?;
+
+        let game = self.app.lock_manager()?.active_game;
+        super::record_network_usage(
+            game,
+            &self.current_name,
+            downloaded,
+            UsageKind::Package,
+            self.app,
+        );
+
+        Ok(file.into())
     }
 
-    fn install_from_download(&mut self, data: Vec<u8>, install: &ModInstall) -> InstallResult<()> {
-        let prefs = self.app.lock_prefs();
-        let mut manager = self.app.lock_manager();
-        let thunderstore = self.app.lock_thunderstore();
+    fn install_from_download(
+        &mut self,
+        data: PackageSource,
+        install: &ModInstall,
+    ) -> InstallResult<()> {
+        let prefs = self.app.lock_prefs()?;
+        let mut manager = self.app.lock_manager()?;
+        let thunderstore = self.app.lock_thunderstore()?;
 
         let version = install.id.borrow(&thunderstore)?.version;
-        let cache_path = cache::path(&version.ident, &prefs);
 
-        fs::create_dir_all(&cache_path).fs_context("creating mod cache dir", &cache_path)?;
+        let (extract_path, _staging) = if self.use_cache {
+            cache::ensure_format(&prefs.cache_dir(), self.app.db())?;
+            (cache::path(&version.ident, &prefs), None)
+        } else {
+            let staging = cache::staging_dir(&prefs)?;
+            (staging.path().to_path_buf(), Some(staging))
+        };
+
+        fs::create_dir_all(&extract_path).fs_context("creating extraction dir", &extract_path)?;
 
         self.check_cancel()?;
         self.update(InstallTask::Extracting);
@@ -195,13 +388,13 @@ impl<'a> Installer<'a> {
             .mod_loader
             .installer_for(version.full_name());
 
-        let archive = ZipArchive::new(Cursor::new(data)).context("failed to open archive")?;
+        let archive = ZipArchive::new(data).context("failed to open archive")?;
 
         installer
-            .extract(archive, version.full_name(), cache_path.clone())
+            .extract(archive, version.full_name(), extract_path.clone())
             .inspect_err(|_| {
                 // the cached mod is probably in an invalid state
-                fs::remove_dir_all(&cache_path).unwrap_or_else(|err| {
+                fs::remove_dir_all(&extract_path).unwrap_or_else(|err| {
                     warn!(
                         "failed to clean up after failed extraction of {}: {:#}",
                         self.current_name, err
@@ -210,30 +403,88 @@ impl<'a> Installer<'a> {
             })
             .context("error while extracting")?;
 
+        if self.use_cache {
+            self.touch_cache(&version.ident);
+
+            // compact before installing, so the profile links to the stored files
+            if let Err(err) = cache::compact_version(
+                &version.ident,
+                &extract_path,
+                &prefs.cache_dir(),
+                self.app.db(),
+            ) {
+                warn!("{:#}", err);
+            }
+        }
+
         self.check_cancel()?;
+
+        if self.cache_only {
+            return Ok(());
+        }
+
         self.update(InstallTask::Installing);
 
+        let profile = manager
+            .active_game_mut()
+            .find_profile_mut(self.profile_id)?;
+
         if let Some(callback) = &self.options.before_install {
-            callback(install, &mut manager, &thunderstore)?;
+            callback(install, profile, &thunderstore)?;
         }
 
-        cache_install(install, &cache_path, &mut manager, &thunderstore)?;
+        cache_install(
+            install,
+            &extract_path,
+            self.disable_strategy,
+            profile,
+            &thunderstore,
+        )?;
 
-        manager.active_profile().save(self.app.db())?;
+        profile.save(self.app.db())?;
 
         Ok(())
     }
 
     async fn install(&mut self, data: &ModInstall) -> InstallResult<()> {
-        if let InstallMethod::Download { url, file_size } = self.try_cache_install(data)? {
-            let response = self.download(&url, file_size).await?;
-            self.install_from_download(response, data)
-        } else {
-            Ok(())
-        }
+        let (response, source) = match self.try_cache_install(data)? {
+            InstallMethod::Cached => {
+                self.summary
+                    .record(data.uuid(), &self.current_name, InstallSource::Cache);
+                return Ok(());
+            }
+            InstallMethod::Download { url, file_size } => (
+                self.download(&url, file_size).await?,
+                InstallSource::Download,
+            ),
+            InstallMethod::Repack {
+                path,
+                url,
+                file_size,
+            } => match self.repack(&path, file_size) {
+                Ok(response) => (response, InstallSource::Archive),
+                Err(err) => {
+                    warn!(
+                        "failed to repack {} from {}, downloading instead: {:#}",
+                        self.current_name,
+                        path.display(),
+                        err
+                    );
+                    (
+                        self.download(&url, file_size).await?,
+                        InstallSource::Download,
+                    )
+                }
+            },
+        };
+
+        self.install_from_download(response, data)?;
+        self.summary.record(data.uuid(), &self.current_name, source);
+
+        Ok(())
     }
 
-    pub async fn install_all(&mut self, mods: Vec<ModInstall>) -> Result<()> {
+    pub async fn install_all(&mut self, mods: Vec<ModInstall>) -> Result<InstallSummary> {
         self.app
             .app_state()
             .cancel_install_flag
@@ -251,9 +502,15 @@ impl<'a> Installer<'a> {
                 Err(InstallError::Cancelled) => {
                     self.update(InstallTask::Error);
 
-                    let mut manager = self.app.lock_manager();
+                    if self.cache_only {
+                        return Ok(mem::take(&mut self.summary));
+                    }
+
+                    let mut manager = self.app.lock_manager()?;
 
-                    let profile = manager.active_profile_mut();
+                    let profile = manager
+                        .active_game_mut()
+                        .find_profile_mut(self.profile_id)?;
 
                     for install in mods.iter().take(i) {
                         profile
@@ -261,12 +518,12 @@ impl<'a> Installer<'a> {
                             .context("failed to clean up after cancellation")?;
                     }
 
-                    return Ok(());
+                    return Ok(mem::take(&mut self.summary));
                 }
                 Err(InstallError::Error(err)) => {
                     self.update(InstallTask::Error);
 
-                    let thunderstore = self.app.lock_thunderstore();
+                    let thunderstore = self.app.lock_thunderstore()?;
 
                     let borrowed = data.id.borrow(&thunderstore)?;
                     let name = &borrowed.package.ident;
@@ -276,18 +533,65 @@ impl<'a> Installer<'a> {
             }
         }
 
-        self.update(InstallTask::Done);
+        self.update(InstallTask::Done {
+            cached: self.summary.cached,
+            reused: self.summary.reused,
+            downloaded: self.summary.downloaded,
+        });
 
-        let manager = self.app.lock_manager();
-        let thunderstore = self.app.lock_thunderstore();
+        if self.use_cache {
+            let app = self.app.clone();
+            tauri::async_runtime::spawn_blocking(move || {
+                if let Err(err) = cache::evict(&app) {
+                    warn!("failed to evict mods from cache: {:#}", err);
+                }
+            });
+        }
 
-        manager.cache_mods(&thunderstore).ok();
+        if self.cache_only {
+            return Ok(mem::take(&mut self.summary));
+        }
+
+        let game = {
+            let manager = self.app.lock_manager()?;
+            let thunderstore = self.app.lock_thunderstore()?;
+
+            manager.cache_mods(&thunderstore).ok();
+            manager.active_game
+        };
+
+        thunderstore::metadata::update(game, self.app);
+
+        // nothing is copied to the game directory until the user allows it,
+        // which the caller asks for after the install
+        self.summary.needs_game_dir_consent = self
+            .app
+            .lock_manager()?
+            .active_game()
+            .find_profile(self.profile_id)?
+            .needs_game_dir_consent();
+
+        if !self.summary.needs_game_dir_consent {
+            launch::sync_game_dir(self.app)?;
+        }
+
+        Ok(mem::take(&mut self.summary))
+    }
+
+    /// Downloads and extracts mods into the cache without installing them.
+    ///
+    /// Mods that are already cached are skipped.
+    pub async fn cache_all(&mut self, mods: Vec<ModInstall>) -> Result<()> {
+        ensure!(self.use_cache, "the mod cache is disabled");
+
+        self.cache_only = true;
+        self.install_all(mods).await?;
 
         Ok(())
     }
 
     fn count_total_bytes(&mut self, mods: &Vec<ModInstall>) -> Result<()> {
-        let thunderstore = self.app.lock_thunderstore();
+        let thunderstore = self.app.lock_thunderstore()?;
         for install in mods {
             let borrowed = install.id.borrow(&thunderstore)?;
             self.total_bytes += borrowed.version.file_size;
@@ -297,29 +601,186 @@ impl<'a> Installer<'a> {
     }
 }
 
+/// Streams `response` into an anonymous file in `temp_dir`, which is deleted
+/// as soon as it's closed.
+///
+/// `on_chunk` is called with the length of each chunk once it's written, and
+/// an error from it aborts the download. The returned file is rewound to the
+/// start, ready to be read.
+async fn stream_to_temp_file(
+    response: reqwest::Response,
+    temp_dir: &Path,
+    mut on_chunk: impl FnMut(u64) -> InstallResult<()>,
+) -> InstallResult<File> {
+    fs::create_dir_all(temp_dir).fs_context("creating temp dir", temp_dir)?;
+    let file = tempfile::tempfile_in(temp_dir).fs_context("creating temp file", temp_dir)?;
+    let mut writer = BufWriter::new(file);
+
+    let mut stream = response.bytes_stream();
+
+    while let Some(item) = stream.next().await {
+        let item = item.map_err(InstallError::from_http)?;
+
+        writer
+            .write_all(&item)
+            .context("failed to write download to disk")?;
+
+        on_chunk(item.len() as u64)?;
+    }
+
+    let mut file = writer
+        .into_inner()
+        .map_err(|err| err.into_error())
+        .context("failed to write download to disk")?;
+    file.rewind().context("failed to rewind downloaded file")?;
+
+    Ok(file)
+}
+
+/// The largest archive [`download_archive`] accepts.
+const MAX_ARCHIVE_SIZE: u64 = 1024 * 1024 * 1024;
+
+/// Content types servers use for zip archives. GitHub release assets, for
+/// example, are served as `application/octet-stream`.
+const ARCHIVE_CONTENT_TYPES: &[&str] = &[
+    "application/zip",
+    "application/x-zip",
+    "application/x-zip-compressed",
+    "application/octet-stream",
+    "binary/octet-stream",
+];
+
+/// Whether a `Content-Type` header could be a zip archive. A missing
+/// header is given the benefit of the doubt.
+pub(super) fn is_archive_content_type(content_type: Option<&str>) -> bool {
+    let Some(content_type) = content_type else {
+        return true;
+    };
+
+    let essence = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+
+    ARCHIVE_CONTENT_TYPES.contains(&essence.as_str())
+}
+
+/// Downloads the zip archive at `url` outside of a regular install, for
+/// mods that aren't on Thunderstore.
+///
+/// Fails if the server doesn't send an archive, or if it's larger than
+/// [`MAX_ARCHIVE_SIZE`]. The download counts towards `name`'s network usage.
+pub async fn download_archive(url: &str, name: &str, app: &AppHandle) -> Result<File> {
+    let (timeout, temp_dir) = {
+        let prefs = app.lock_prefs()?;
+        (prefs.download_timeout(), prefs.temp_dir())
+    };
+
+    let response = app
+        .http()
+        .get(url)
+        .timeout(timeout)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(InstallError::from_http)?;
+
+    let content_type = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok());
+
+    ensure!(
+        is_archive_content_type(content_type),
+        "the URL doesn't point to a zip archive (got {})",
+        content_type.unwrap_or_default()
+    );
+
+    let too_large = || {
+        eyre!(
+            "the archive is larger than the limit of {} MB",
+            MAX_ARCHIVE_SIZE / 1024 / 1024
+        )
+    };
+
+    if response
+        .content_length()
+        .is_some_and(|len| len > MAX_ARCHIVE_SIZE)
+    {
+        return Err(too_large());
+    }
+
+    // the content length can be missing or wrong, so check as it downloads too
+    let mut downloaded = 0;
+    let file = stream_to_temp_file(response, &temp_dir, |len| {
+        downloaded += len;
+
+        match downloaded > MAX_ARCHIVE_SIZE {
+            true => Err(too_large().into()),
+            false => Ok(()),
+        }
+    })
+    .await?;
+
+    let game = app.lock_manager()?.active_game;
+    super::record_network_usage(game, name, downloaded, UsageKind::Package, app);
+
+    Ok(file)
+}
+
+/// Checks that the manifest in an extracted package matches `ident`.
+pub(super) fn check_archive_entry(path: &Path, ident: &VersionIdent) -> Result<()> {
+    #[derive(Deserialize)]
+    struct Manifest {
+        name: String,
+        version_number: String,
+    }
+
+    let manifest_path = path.join("manifest.json");
+    let text = fs::read_to_string(&manifest_path).fs_context("reading manifest", &manifest_path)?;
+
+    let manifest: Manifest = serde_json::from_str(text.trim_start_matches('\u{feff}'))
+        .context("failed to parse manifest")?;
+
+    ensure!(
+        manifest.name == ident.name() && manifest.version_number == ident.version(),
+        "manifest is for {}-{} instead",
+        manifest.name,
+        manifest.version_number
+    );
+
+    Ok(())
+}
+
+/// Installs an extracted package at `src`, either from the
+/// cache or a staging directory, into `profile`.
 fn cache_install(
     data: &ModInstall,
     src: &Path,
-    manager: &mut ModManager,
+    disable_strategy: DisableStrategy,
+    profile: &mut Profile,
     thunderstore: &Thunderstore,
 ) -> Result<()> {
     let borrowed = data.id.borrow(thunderstore)?;
     let package_name = borrowed.ident().full_name();
 
-    let mut installer = manager.active_game.mod_loader.installer_for(package_name);
-    let profile = manager.active_profile_mut();
+    let mut installer = profile.game.mod_loader.installer_for(package_name);
 
     installer.install(src, package_name, profile)?;
 
     let install_time = data.install_time.unwrap_or_else(Utc::now);
 
-    let profile_mod = ProfileMod::new_at(
+    let mut profile_mod = ProfileMod::new_at(
         install_time,
         ProfileModKind::Thunderstore(ThunderstoreMod {
             ident: borrowed.ident().clone(),
             id: borrowed.into(),
         }),
     );
+    profile_mod.side = data.side;
+    profile_mod.origin = data.origin;
 
     match data.index {
         Some(index) if index < profile.mods.len() => {
@@ -331,7 +792,7 @@ fn cache_install(
     };
 
     if !data.enabled {
-        profile.force_toggle_mod(borrowed.package.uuid)?;
+        profile.force_toggle_mod(borrowed.package.uuid, disable_strategy)?;
     }
 
     Ok(())
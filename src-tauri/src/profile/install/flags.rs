@@ -0,0 +1,77 @@
+//! Packages that should be confirmed before they're installed, because
+//! they're deprecated or on the community [`blocklist`].
+//!
+//! [`blocklist`]: crate::thunderstore::blocklist
+
+use std::fmt::{self, Display};
+
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::thunderstore::{BorrowedMod, Thunderstore};
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum PackageFlag {
+    Deprecated,
+    Blocklisted { reason: Option<String> },
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FlaggedPackage {
+    pub full_name: String,
+    pub version: String,
+    pub flags: Vec<PackageFlag>,
+}
+
+/// Returned by [`super::install_with_deps`] with [`InstallOptions::confirm_flagged`]
+/// when flagged packages would be installed.
+///
+/// [`InstallOptions::confirm_flagged`]: super::InstallOptions::confirm_flagged
+#[derive(Debug)]
+pub struct NeedsConfirmation(pub Vec<FlaggedPackage>);
+
+impl Display for NeedsConfirmation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "installing flagged packages needs confirmation: {}",
+            self.0.iter().map(|package| &package.full_name).join(", ")
+        )
+    }
+}
+
+impl std::error::Error for NeedsConfirmation {}
+
+/// Finds the flagged packages among `mods`.
+pub fn find<'a>(
+    mods: impl IntoIterator<Item = BorrowedMod<'a>>,
+    thunderstore: &Thunderstore,
+) -> Vec<FlaggedPackage> {
+    mods.into_iter()
+        .filter_map(|borrowed| {
+            let mut flags = Vec::new();
+
+            if borrowed.package.is_deprecated {
+                flags.push(PackageFlag::Deprecated);
+            }
+
+            if let Some(entry) = thunderstore.blocklist().find(borrowed.ident()) {
+                flags.push(PackageFlag::Blocklisted {
+                    reason: entry.reason.clone(),
+                });
+            }
+
+            if flags.is_empty() {
+                return None;
+            }
+
+            Some(FlaggedPackage {
+                full_name: borrowed.package.full_name().to_owned(),
+                version: borrowed.version.version().to_owned(),
+                flags,
+            })
+        })
+        .collect()
+}
@@ -7,6 +7,7 @@ use std::{
 
 use eyre::{Context, Result};
 use log::warn;
+use serde::{Deserialize, Serialize};
 use walkdir::WalkDir;
 use zip::ZipArchive;
 
@@ -155,38 +156,94 @@ where
     Ok(())
 }
 
+/// Where [`DisableStrategy::Stage`] moves the files of disabled mods, relative to the profile.
+///
+/// The layout of the profile is mirrored within it.
+pub const DISABLED_STAGING: &str = "_disabled";
+
+/// How the files of disabled mods are kept away from the mod loader.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum DisableStrategy {
+    /// Add a `.old` extension to the files, which most loaders ignore.
+    #[default]
+    Rename,
+    /// Move the files to [`DISABLED_STAGING`], out of every directory the loader scans.
+    Stage,
+}
+
+/// Where `path` is kept while staged, or `None` if it isn't in the profile.
+fn staged_path(path: &Path, profile: &Profile) -> Option<PathBuf> {
+    let relative = path.strip_prefix(&profile.path).ok()?;
+
+    if relative.starts_with(DISABLED_STAGING) {
+        return None;
+    }
+
+    Some(profile.path.join(DISABLED_STAGING).join(relative))
+}
+
+/// Where the staged file at `path` belongs, or `None` if it isn't staged.
+fn unstaged_path(path: &Path, profile: &Profile) -> Option<PathBuf> {
+    let relative = path
+        .strip_prefix(profile.path.join(DISABLED_STAGING))
+        .ok()?;
+
+    Some(profile.path.join(relative))
+}
+
+/// Returns the staging mirror of `dir`, which is where the loader looks for its files.
+///
+/// This is for installers that find files by listing a directory, since the
+/// files of a staged mod aren't in the directory anymore.
+pub(super) fn staged_dir(dir: &Path, profile: &Profile) -> Option<PathBuf> {
+    staged_path(dir, profile).filter(|path| path.is_dir())
+}
+
 /// Removes either a directory or file at `path`. Also accounts for any
-/// `.old` extensions that may exist.
-pub(super) fn uninstall_any(path: impl AsRef<Path>) -> Result<()> {
+/// `.old` extensions or staged copies that may exist.
+pub(super) fn uninstall_any(path: impl AsRef<Path>, profile: &Profile) -> Result<()> {
     for_any(
         path.as_ref(),
+        profile,
         |path| fs::remove_dir_all(path).map_err(|err| err.into()),
         |path| fs::remove_file(path).map_err(|err| err.into()),
     )
 }
 
 /// Toggles either a directory or file at `path`.
-pub(super) fn toggle_any(path: impl AsRef<Path>, enabled: bool) -> Result<()> {
+pub(super) fn toggle_any(
+    path: impl AsRef<Path>,
+    enabled: bool,
+    strategy: DisableStrategy,
+    profile: &Profile,
+) -> Result<()> {
     for_any(
         path.as_ref(),
-        |path| toggle_dir(path, enabled),
-        |path| toggle_file(path, enabled),
+        profile,
+        |path| toggle_dir(path, enabled, strategy, profile),
+        |path| toggle_file(path, enabled, strategy, profile),
     )
 }
 
-fn for_any<F, G>(path: &Path, for_dir: F, for_file: G) -> Result<()>
+pub(super) fn for_any<F, G>(path: &Path, profile: &Profile, for_dir: F, for_file: G) -> Result<()>
 where
     F: FnOnce(&Path) -> Result<()>,
     G: FnOnce(&Path) -> Result<()>,
 {
+    let path = match staged_path(path, profile) {
+        Some(staged) if !path.exists() && staged.exists() => Cow::Owned(staged),
+        _ => Cow::Borrowed(path),
+    };
+
     if let Ok(metadata) = path.metadata() {
         if metadata.is_dir() {
-            for_dir(path)
+            for_dir(&path)
         } else {
-            for_file(path)
+            for_file(&path)
         }
     } else {
-        let mut path = path.to_path_buf();
+        let mut path = path.into_owned();
         path.add_ext("old");
 
         if path.exists() {
@@ -197,18 +254,42 @@ where
     }
 }
 
-/// Toggles a file by adding/removing a `.old` extension to it.
-pub(super) fn toggle_file(path: impl AsRef<Path>, enabled: bool) -> Result<()> {
+/// Toggles a file, depending on `strategy` when disabling:
+///
+/// - [`DisableStrategy::Rename`] adds a `.old` extension to it.
+/// - [`DisableStrategy::Stage`] moves it to [`DISABLED_STAGING`].
+///
+/// When enabling, the file is restored from either.
+pub(super) fn toggle_file(
+    path: impl AsRef<Path>,
+    enabled: bool,
+    strategy: DisableStrategy,
+    profile: &Profile,
+) -> Result<()> {
     let path = path.as_ref();
+
+    if let Some(mut target) = unstaged_path(path, profile) {
+        if enabled {
+            // already out of the way
+            return Ok(());
+        }
+
+        strip_old_ext(&mut target);
+        return move_file(path, &target);
+    }
+
+    if enabled && strategy == DisableStrategy::Stage {
+        if let Some(target) = staged_path(path, profile) {
+            return move_file(path, &target);
+        }
+    }
+
     let mut new_path = path.to_path_buf();
 
     if enabled {
         new_path.add_ext("old");
     } else {
-        // remove all old extensions if multiple got added somehow
-        while let Some("old") = new_path.extension().and_then(|ext| ext.to_str()) {
-            new_path.set_extension("");
-        }
+        strip_old_ext(&mut new_path);
     }
 
     fs::rename(path, &new_path).fs_context("renaming file", path)?;
@@ -216,19 +297,68 @@ pub(super) fn toggle_file(path: impl AsRef<Path>, enabled: bool) -> Result<()> {
     Ok(())
 }
 
-/// Toggles a directory by recursively adding/removing a `.old` extension to all files within it.
-pub(super) fn toggle_dir(path: impl AsRef<Path>, enabled: bool) -> Result<()> {
+fn strip_old_ext(path: &mut PathBuf) {
+    // remove all old extensions if multiple got added somehow
+    while let Some("old") = path.extension().and_then(|ext| ext.to_str()) {
+        path.set_extension("");
+    }
+}
+
+fn move_file(from: &Path, to: &Path) -> Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent).fs_context("creating directory", parent)?;
+    }
+
+    fs::rename(from, to).fs_context("moving file", from)?;
+
+    Ok(())
+}
+
+/// Toggles a directory by recursively toggling all files within it, see [`toggle_file`].
+///
+/// If the files were moved, the empty directories left behind are removed.
+pub(super) fn toggle_dir(
+    path: impl AsRef<Path>,
+    enabled: bool,
+    strategy: DisableStrategy,
+    profile: &Profile,
+) -> Result<()> {
+    let path = path.as_ref();
+
     let files = WalkDir::new(path)
         .into_iter()
         .filter_map(Result::ok)
         .filter(|entry| {
             let file_type = entry.file_type();
             file_type.is_file() || file_type.is_symlink()
-        });
+        })
+        .map(|entry| entry.into_path())
+        .collect::<Vec<_>>();
 
     for file in files {
-        toggle_file(file.path(), enabled)?;
+        toggle_file(file, enabled, strategy, profile)?;
+    }
+
+    let moved =
+        unstaged_path(path, profile).is_some() || (enabled && strategy == DisableStrategy::Stage);
+
+    if moved {
+        remove_empty_dirs(path);
     }
 
     Ok(())
 }
+
+/// Removes `path` and every directory within it, as long as they're empty.
+fn remove_empty_dirs(path: &Path) {
+    let dirs = WalkDir::new(path)
+        .contents_first(true)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_dir());
+
+    for dir in dirs {
+        // fails if the directory isn't empty, which is fine
+        fs::remove_dir(dir.path()).ok();
+    }
+}
@@ -6,7 +6,7 @@ use std::{
 
 use eyre::Result;
 
-use super::{FileInstallMethod, PackageInstaller, PackageZip};
+use super::{DisableStrategy, FileInstallMethod, PackageInstaller, PackageZip};
 use crate::profile::{
     install::{self, fs::ConflictResolution},
     Profile, ProfileMod,
@@ -14,14 +14,27 @@ use crate::profile::{
 
 pub struct BepinexInstaller;
 
-fn scan(profile: &Profile) -> Result<impl Iterator<Item = PathBuf>> {
-    Ok(profile
-        .path
-        .join("BepInEx/core")
-        .read_dir()?
-        .filter_map(Result::ok)
-        .filter(|entry| entry.file_type().is_ok_and(|ty| ty.is_file()))
-        .map(|entry| entry.path()))
+/// Lists the files in `BepInEx/core`, including any that are staged.
+fn scan(profile: &Profile) -> Result<Vec<PathBuf>> {
+    let dir = profile.path.join("BepInEx/core");
+    let staged = install::fs::staged_dir(&dir, profile);
+
+    let mut files = Vec::new();
+
+    for dir in [Some(dir), staged].into_iter().flatten() {
+        if !dir.exists() {
+            continue;
+        }
+
+        files.extend(
+            dir.read_dir()?
+                .filter_map(Result::ok)
+                .filter(|entry| entry.file_type().is_ok_and(|ty| ty.is_file()))
+                .map(|entry| entry.path()),
+        );
+    }
+
+    Ok(files)
 }
 
 impl PackageInstaller for BepinexInstaller {
@@ -53,11 +66,12 @@ impl PackageInstaller for BepinexInstaller {
     fn toggle(
         &mut self,
         enabled: bool,
+        strategy: DisableStrategy,
         _profile_mod: &ProfileMod,
         profile: &Profile,
     ) -> Result<()> {
         for file in scan(profile)? {
-            install::fs::toggle_file(file, enabled)?;
+            install::fs::toggle_file(file, enabled, strategy, profile)?;
         }
 
         Ok(())
@@ -2,7 +2,7 @@ use std::{borrow::Cow, path::PathBuf};
 
 use eyre::Result;
 
-use super::{PackageInstaller, PackageZip};
+use super::{DisableStrategy, PackageInstaller, PackageZip};
 use crate::profile::{install, Profile, ProfileMod};
 
 pub enum FlattenTopLevel {
@@ -54,11 +54,12 @@ impl PackageInstaller for ExtractInstaller<'_> {
     fn toggle(
         &mut self,
         enabled: bool,
+        strategy: DisableStrategy,
         _profile_mod: &ProfileMod,
         profile: &Profile,
     ) -> Result<()> {
         for path in self.scan_mod(profile) {
-            install::fs::toggle_any(path, enabled)?;
+            install::fs::toggle_any(path, enabled, strategy, profile)?;
         }
 
         Ok(())
@@ -66,7 +67,7 @@ impl PackageInstaller for ExtractInstaller<'_> {
 
     fn uninstall(&mut self, _profile_mod: &ProfileMod, profile: &Profile) -> Result<()> {
         for path in self.scan_mod(profile) {
-            install::fs::uninstall_any(path)?;
+            install::fs::uninstall_any(path, profile)?;
         }
 
         Ok(())
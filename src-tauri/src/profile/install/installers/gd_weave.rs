@@ -1,12 +1,11 @@
 use std::{
     borrow::Cow,
-    fs,
     path::{self, PathBuf},
 };
 
 use eyre::{bail, Result};
 
-use super::{PackageInstaller, PackageZip};
+use super::{DisableStrategy, PackageInstaller, PackageZip};
 use crate::profile::{
     install::{self},
     Profile, ProfileMod,
@@ -70,16 +69,26 @@ impl PackageInstaller for GDWeaveModInstaller {
         })
     }
 
-    fn toggle(&mut self, enabled: bool, profile_mod: &ProfileMod, profile: &Profile) -> Result<()> {
-        install::fs::toggle_dir(profile_mod_dir(&profile_mod.full_name(), profile), enabled)
+    fn toggle(
+        &mut self,
+        enabled: bool,
+        strategy: DisableStrategy,
+        profile_mod: &ProfileMod,
+        profile: &Profile,
+    ) -> Result<()> {
+        let dir = profile_mod_dir(&profile_mod.full_name(), profile);
+        install::fs::toggle_any(dir, enabled, strategy, profile)
     }
 
     fn uninstall(&mut self, profile_mod: &ProfileMod, profile: &Profile) -> Result<()> {
-        fs::remove_dir_all(profile_mod_dir(&profile_mod.full_name(), profile))?;
-        Ok(())
+        install::fs::uninstall_any(profile_mod_dir(&profile_mod.full_name(), profile), profile)
     }
 
     fn mod_dir(&self, package_name: &str, profile: &Profile) -> Option<PathBuf> {
         Some(profile_mod_dir(package_name, profile))
     }
+
+    fn separated_dirs(&self) -> Vec<PathBuf> {
+        vec![["GDWeave", "mods"].iter().collect()]
+    }
 }
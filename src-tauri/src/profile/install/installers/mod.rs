@@ -1,12 +1,13 @@
 use std::{
-    io::Cursor,
+    fs::File,
+    io::{self, BufReader, Cursor, Read, Seek, SeekFrom},
     path::{Path, PathBuf},
 };
 
 use eyre::Result;
 use zip::ZipArchive;
 
-use super::fs::{ConflictResolution, FileInstallMethod};
+use super::fs::{ConflictResolution, DisableStrategy, FileInstallMethod};
 use crate::profile::{Profile, ProfileMod};
 
 mod bepinex;
@@ -20,10 +21,48 @@ pub use self::{
     extract::{ExtractInstaller, FlattenTopLevel},
     gd_weave::GDWeaveModInstaller,
     shimloader::ShimloaderInstaller,
-    subdir::{Subdir, SubdirInstaller},
+    subdir::{Subdir, SubdirFiles, SubdirInstaller, GAME_DIR_STAGING},
 };
 
-pub type PackageZip = ZipArchive<Cursor<Vec<u8>>>;
+pub type PackageZip = ZipArchive<PackageSource>;
+
+/// The data of a package archive, either held in memory or read from a file on disk.
+///
+/// Downloads are spooled to a file, so large packages don't have to fit in memory.
+pub enum PackageSource {
+    Memory(Cursor<Vec<u8>>),
+    File(BufReader<File>),
+}
+
+impl From<Vec<u8>> for PackageSource {
+    fn from(value: Vec<u8>) -> Self {
+        Self::Memory(Cursor::new(value))
+    }
+}
+
+impl From<File> for PackageSource {
+    fn from(value: File) -> Self {
+        Self::File(BufReader::new(value))
+    }
+}
+
+impl Read for PackageSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            PackageSource::Memory(cursor) => cursor.read(buf),
+            PackageSource::File(reader) => reader.read(buf),
+        }
+    }
+}
+
+impl Seek for PackageSource {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            PackageSource::Memory(cursor) => cursor.seek(pos),
+            PackageSource::File(reader) => reader.seek(pos),
+        }
+    }
+}
 
 pub trait PackageInstaller {
     fn extract(&mut self, archive: PackageZip, package_name: &str, dest: PathBuf) -> Result<()>;
@@ -34,10 +73,31 @@ pub trait PackageInstaller {
         })
     }
 
-    fn toggle(&mut self, enabled: bool, profile_mod: &ProfileMod, profile: &Profile) -> Result<()>;
+    /// Enables or disables the files of a mod, `enabled` being its current state.
+    fn toggle(
+        &mut self,
+        enabled: bool,
+        strategy: DisableStrategy,
+        profile_mod: &ProfileMod,
+        profile: &Profile,
+    ) -> Result<()>;
     fn uninstall(&mut self, profile_mod: &ProfileMod, profile: &Profile) -> Result<()>;
 
     fn mod_dir(&self, _package_name: &str, _profile: &Profile) -> Option<PathBuf> {
         None
     }
+
+    /// Directories, relative to the profile, where each mod
+    /// gets its own directory named after the package.
+    fn separated_dirs(&self) -> Vec<PathBuf> {
+        Vec::new()
+    }
+
+    /// Lists the subdirs the files of an installed mod are in.
+    ///
+    /// Files in subdirs that aren't separated or tracked can't be traced
+    /// back to a mod and are left out.
+    fn subdir_files(&self, _profile_mod: &ProfileMod, _profile: &Profile) -> Vec<SubdirFiles> {
+        Vec::new()
+    }
 }
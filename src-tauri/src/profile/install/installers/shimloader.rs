@@ -6,7 +6,7 @@ use std::{
 
 use eyre::{Context, Result};
 
-use super::{PackageInstaller, PackageZip};
+use super::{DisableStrategy, PackageInstaller, PackageZip};
 use crate::profile::{
     install::{self},
     Profile, ProfileMod,
@@ -59,11 +59,12 @@ impl PackageInstaller for ShimloaderInstaller {
     fn toggle(
         &mut self,
         enabled: bool,
+        strategy: DisableStrategy,
         _profile_mod: &ProfileMod,
         profile: &Profile,
     ) -> Result<()> {
         for file in ["dwmapi.dll", "UE4SS.dll", "UE4SS-settings.ini"] {
-            install::fs::toggle_file(profile.path.join(file), enabled)?;
+            install::fs::toggle_any(profile.path.join(file), enabled, strategy, profile)?;
         }
 
         Ok(())
@@ -71,7 +72,7 @@ impl PackageInstaller for ShimloaderInstaller {
 
     fn uninstall(&mut self, _profile_mod: &ProfileMod, profile: &Profile) -> Result<()> {
         for file in ["dwmapi.dll", "UE4SS.dll", "UE4SS-settings.ini"] {
-            fs::remove_file(profile.path.join(file))?;
+            install::fs::uninstall_any(profile.path.join(file), profile)?;
         }
 
         Ok(())
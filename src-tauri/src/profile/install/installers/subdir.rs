@@ -6,10 +6,12 @@ use std::{
 };
 
 use eyre::{Context, OptionExt, Result};
+use itertools::Itertools;
 use log::warn;
 use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
 
-use super::{PackageInstaller, PackageZip};
+use super::{DisableStrategy, PackageInstaller, PackageZip};
 use crate::{
     profile::{
         install::{
@@ -21,6 +23,10 @@ use crate::{
     util::{self, fs::JsonStyle},
 };
 
+/// Where files of [`Subdir::game_dir`] subdirs are kept in the profile,
+/// until they're deployed to the game directory.
+pub const GAME_DIR_STAGING: &str = "_game";
+
 pub struct SubdirInstaller<'a> {
     subdirs: &'a [Subdir<'a>],
     default_subdir: Option<usize>,
@@ -49,6 +55,25 @@ pub struct Subdir<'a> {
     /// Multiple extensions are separated by a comma.
     #[serde(default)]
     pub extension: Option<&'a str>,
+    /// Whether the files belong in the game directory instead of the profile,
+    /// in which case [`Subdir::target`] is relative to the game directory.
+    ///
+    /// The files are kept in [`GAME_DIR_STAGING`] and only copied to the game
+    /// directory for profiles that allow it. This is for the few games where
+    /// mods like script hooks can't be loaded from anywhere else.
+    #[serde(default)]
+    pub game_dir: bool,
+}
+
+/// The files a mod has in one subdir.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubdirFiles {
+    pub name: String,
+    /// The directory of the files, relative to the profile.
+    pub path: PathBuf,
+    pub mode: SubdirMode,
+    pub file_count: usize,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
@@ -75,6 +100,7 @@ impl<'a> Subdir<'a> {
             mode,
             mutable: false,
             extension: None,
+            game_dir: false,
         }
     }
 
@@ -103,6 +129,19 @@ impl<'a> Subdir<'a> {
         self.extension = Some(ext);
         self
     }
+
+    pub const fn game_dir(mut self) -> Self {
+        self.game_dir = true;
+        self
+    }
+
+    /// The path of the subdir relative to the profile.
+    fn profile_target(&self) -> PathBuf {
+        match self.game_dir {
+            true => Path::new(GAME_DIR_STAGING).join(self.target),
+            false => PathBuf::from(self.target),
+        }
+    }
 }
 
 impl<'a> SubdirInstaller<'a> {
@@ -146,6 +185,14 @@ impl<'a> SubdirInstaller<'a> {
         })
     }
 
+    /// Finds the subdir with the longest target that contains `relative_path`,
+    /// since targets can be nested in each other.
+    fn innermost_subdir(&self, relative_path: &Path) -> Option<&Subdir<'_>> {
+        self.subdirs()
+            .filter(|subdir| relative_path.starts_with(subdir.profile_target()))
+            .max_by_key(|subdir| subdir.profile_target().components().count())
+    }
+
     fn map_file<'p>(
         &self,
         relative_path: &'p Path,
@@ -186,7 +233,7 @@ impl<'a> SubdirInstaller<'a> {
             }
         };
 
-        let mut target = PathBuf::from(subdir.target);
+        let mut target = subdir.profile_target();
 
         let separate = matches!(
             subdir.mode,
@@ -235,8 +282,7 @@ impl<'a> SubdirInstaller<'a> {
         for subdir in self.subdirs() {
             match subdir.mode {
                 SubdirMode::Separate | SubdirMode::SeparateFlatten => {
-                    let mut path = profile.path.to_path_buf();
-                    path.push(subdir.target);
+                    let mut path = profile.path.join(subdir.profile_target());
                     path.push(&*package_name);
 
                     scan(&path)?;
@@ -347,7 +393,7 @@ impl PackageInstaller for SubdirInstaller<'_> {
         install::fs::install(src, profile, |relative_path, exists| {
             let subdir = self
                 .subdirs()
-                .find(|subdir| relative_path.starts_with(subdir.target))
+                .find(|subdir| relative_path.starts_with(subdir.profile_target()))
                 .expect("file should be in a subdir");
 
             let method = if subdir.mutable {
@@ -399,9 +445,15 @@ impl PackageInstaller for SubdirInstaller<'_> {
         Ok(())
     }
 
-    fn toggle(&mut self, enabled: bool, profile_mod: &ProfileMod, profile: &Profile) -> Result<()> {
+    fn toggle(
+        &mut self,
+        enabled: bool,
+        strategy: DisableStrategy,
+        profile_mod: &ProfileMod,
+        profile: &Profile,
+    ) -> Result<()> {
         self.scan_mod(profile_mod, profile, |path| {
-            install::fs::toggle_any(path, enabled)
+            install::fs::toggle_any(path, enabled, strategy, profile)
         })?;
 
         Ok(())
@@ -409,7 +461,7 @@ impl PackageInstaller for SubdirInstaller<'_> {
 
     fn uninstall(&mut self, profile_mod: &ProfileMod, profile: &Profile) -> Result<()> {
         let has_tracked_files = self.scan_mod(profile_mod, profile, |path| {
-            install::fs::uninstall_any(path)
+            install::fs::uninstall_any(path, profile)
         })?;
 
         if has_tracked_files {
@@ -435,12 +487,90 @@ impl PackageInstaller for SubdirInstaller<'_> {
 
     fn mod_dir(&self, package_name: &str, profile: &Profile) -> Option<PathBuf> {
         self.default_subdir.map(|index| {
-            let mut path = profile.path.to_path_buf();
-
-            path.push(self.subdirs[index].target);
+            let mut path = profile.path.join(self.subdirs[index].profile_target());
             path.push(package_name);
 
             path
         })
     }
+
+    fn separated_dirs(&self) -> Vec<PathBuf> {
+        self.subdirs()
+            .filter(|subdir| {
+                matches!(
+                    subdir.mode,
+                    SubdirMode::Separate | SubdirMode::SeparateFlatten
+                )
+            })
+            .map(Subdir::profile_target)
+            .collect()
+    }
+
+    fn subdir_files(&self, profile_mod: &ProfileMod, profile: &Profile) -> Vec<SubdirFiles> {
+        let mut dirs = Vec::new();
+        let mut tracked_files = Vec::new();
+
+        let scanned = self.scan_mod(profile_mod, profile, |path| {
+            install::fs::for_any(
+                path,
+                profile,
+                |dir| {
+                    dirs.push(dir.to_owned());
+                    Ok(())
+                },
+                |file| {
+                    tracked_files.push(file.strip_prefix(&profile.path)?.to_owned());
+                    Ok(())
+                },
+            )
+        });
+
+        if let Err(err) = scanned {
+            warn!(
+                "failed to scan files of {}: {:#}",
+                profile_mod.full_name(),
+                err
+            );
+        }
+
+        let mut result = dirs
+            .into_iter()
+            .filter_map(|dir| {
+                let relative = dir.strip_prefix(&profile.path).ok()?.to_owned();
+                let subdir = self.innermost_subdir(&relative)?;
+
+                let file_count = WalkDir::new(&dir)
+                    .into_iter()
+                    .filter_map(Result::ok)
+                    .filter(|entry| entry.file_type().is_file())
+                    .count();
+
+                Some(SubdirFiles {
+                    name: subdir.name.to_owned(),
+                    path: relative,
+                    mode: subdir.mode,
+                    file_count,
+                })
+            })
+            .collect_vec();
+
+        // tracked files are mixed with other mods', so group them by subdir
+        let counts = tracked_files
+            .iter()
+            .filter_map(|file| self.innermost_subdir(file))
+            .counts_by(|subdir| subdir.name);
+
+        for subdir in self.subdirs() {
+            if let Some(&file_count) = counts.get(subdir.name) {
+                result.push(SubdirFiles {
+                    name: subdir.name.to_owned(),
+                    path: subdir.profile_target(),
+                    mode: subdir.mode,
+                    file_count,
+                });
+            }
+        }
+
+        result
+    }
 }
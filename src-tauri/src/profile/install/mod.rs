@@ -1,26 +1,46 @@
-use std::iter;
+use std::{
+    collections::HashSet,
+    iter,
+    path::{Path, PathBuf},
+};
 
 use chrono::{DateTime, Utc};
 use eyre::{bail, Context, Result};
 use itertools::Itertools;
+use log::info;
 use serde::{Deserialize, Serialize};
 use strum_macros::Display;
 use tauri::AppHandle;
 use uuid::Uuid;
 
-use super::{ModManager, Profile};
+use super::{
+    compat::{CompatRules, CompatWarning},
+    InstallOrigin, ModManager, ModSide, Profile,
+};
 use crate::{
+    db::Db,
     prefs::Prefs,
     state::ManagerExt,
-    thunderstore::{BorrowedMod, ModId, Thunderstore},
+    thunderstore::{BorrowedMod, ModId, Thunderstore, VersionIdent},
+    util::{cmd::UserError, temp},
 };
 
 mod cache;
 pub mod commands;
 mod download;
+mod flags;
 mod fs;
 mod installers;
+mod network;
+mod store;
+pub use download::download_archive;
+pub use flags::{FlaggedPackage, NeedsConfirmation};
+pub use fs::{DisableStrategy, DISABLED_STAGING};
 pub use installers::*;
+pub use network::record_usage as record_network_usage;
+
+#[cfg(test)]
+pub(crate) mod tests;
 
 #[derive(Serialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -31,6 +51,9 @@ pub struct InstallProgress<'a> {
     pub installed_mods: usize,
     pub total_mods: usize,
     pub current_name: &'a str,
+    /// The profile the mods are being installed into.
+    pub profile_id: i64,
+    pub profile_name: &'a str,
     pub can_cancel: bool,
     pub task: InstallTask,
 }
@@ -39,20 +62,85 @@ pub struct InstallProgress<'a> {
 #[serde(rename_all = "camelCase", tag = "kind", content = "payload")]
 #[strum(serialize_all = "camelCase")]
 pub enum InstallTask {
-    Done,
+    /// All mods have been installed, with the counts of the [`InstallSummary`].
+    Done {
+        cached: usize,
+        reused: usize,
+        downloaded: usize,
+    },
     Error,
-    Downloading { total: u64, downloaded: u64 },
+    Downloading {
+        total: u64,
+        downloaded: u64,
+    },
     Extracting,
     Installing,
 }
 
+/// Where the mods of an install came from.
+#[derive(Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallSummary {
+    /// Installed from the cache or [`InstallOptions::local_source`].
+    pub cached: usize,
+    /// Repackaged from one of the [`InstallOptions::archive_source`] directories.
+    pub reused: usize,
+    pub downloaded: usize,
+    /// Where each mod came from, in the order they were installed.
+    pub sources: Vec<ModSource>,
+    /// The profile has game directory files which weren't copied, because
+    /// the user hasn't allowed it yet. See [`Profile::needs_game_dir_consent`].
+    pub needs_game_dir_consent: bool,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ModSource {
+    pub uuid: Uuid,
+    pub name: String,
+    pub source: InstallSource,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum InstallSource {
+    /// See [`InstallSummary::cached`].
+    Cache,
+    /// See [`InstallSummary::reused`].
+    Archive,
+    Download,
+}
+
+impl InstallSummary {
+    fn record(&mut self, uuid: Uuid, name: &str, source: InstallSource) {
+        match source {
+            InstallSource::Cache => self.cached += 1,
+            InstallSource::Archive => self.reused += 1,
+            InstallSource::Download => self.downloaded += 1,
+        }
+
+        self.sources.push(ModSource {
+            uuid,
+            name: name.to_owned(),
+            source,
+        });
+    }
+}
+
 type ProgressHandler = Box<dyn Fn(&InstallProgress, &AppHandle) + 'static + Send>;
 type EventHandler =
-    Box<dyn Fn(&ModInstall, &mut ModManager, &Thunderstore) -> Result<()> + 'static + Send>;
+    Box<dyn Fn(&ModInstall, &mut Profile, &Thunderstore) -> Result<()> + 'static + Send>;
 
 pub struct InstallOptions {
     can_cancel: bool,
     send_progress: bool,
+    bypass_cache: bool,
+    local_source: Option<PathBuf>,
+    archive_sources: Vec<PathBuf>,
+    profile_id: Option<i64>,
+    idempotent: bool,
+    confirm_flagged: bool,
+    dependency_origin: InstallOrigin,
     on_progress: Option<ProgressHandler>,
     before_install: Option<EventHandler>,
 }
@@ -62,6 +150,13 @@ impl Default for InstallOptions {
         Self {
             can_cancel: true,
             send_progress: true,
+            bypass_cache: false,
+            local_source: None,
+            archive_sources: Vec::new(),
+            profile_id: None,
+            idempotent: false,
+            confirm_flagged: true,
+            dependency_origin: InstallOrigin::Dependency,
             on_progress: None,
             before_install: None,
         }
@@ -79,6 +174,77 @@ impl InstallOptions {
         self
     }
 
+    /// Extract packages straight into the profile (via a staging directory)
+    /// instead of going through the mod cache.
+    ///
+    /// This is always the case if the cache is disabled in [`Prefs`].
+    pub fn bypass_cache(mut self, bypass_cache: bool) -> Self {
+        self.bypass_cache = bypass_cache;
+        self
+    }
+
+    /// Install packages from `dir` when possible, instead of downloading them.
+    ///
+    /// `dir` has the same layout as the cache, i.e. `<full_name>/<version>`
+    /// containing the extracted package. Used to import offline archives.
+    pub fn local_source(mut self, dir: PathBuf) -> Self {
+        self.local_source = Some(dir);
+        self
+    }
+
+    /// Repackage packages from `dir` when possible, instead of downloading them.
+    ///
+    /// Unlike [`local_source`](Self::local_source), `dir` holds the unmodified
+    /// contents of each package's archive at `<full_name>/<version>`, like
+    /// r2modman's cache. Nothing in `dir` is modified.
+    ///
+    /// The directories in [`Prefs::extra_cache_dirs`] are always used as well.
+    pub fn archive_source(mut self, dir: PathBuf) -> Self {
+        self.archive_sources.push(dir);
+        self
+    }
+
+    /// Install into the profile with `profile_id` instead of the active one.
+    ///
+    /// The profile has to belong to the active game, since that's
+    /// the only one with a loaded package index.
+    pub fn target_profile(mut self, profile_id: i64) -> Self {
+        self.profile_id = Some(profile_id);
+        self
+    }
+
+    /// Skip mods whose exact version is already installed and enabled, and
+    /// replace other installed versions instead of failing, so the same
+    /// install can safely be repeated.
+    pub fn idempotent(mut self, idempotent: bool) -> Self {
+        self.idempotent = idempotent;
+        self
+    }
+
+    /// Fail with [`NeedsConfirmation`] instead of installing anything if any
+    /// of the mods or their dependencies are deprecated or blocklisted.
+    ///
+    /// This is on by default. Only turn it off if the user has already
+    /// confirmed the install, or picked the mods some other way.
+    pub fn confirm_flagged(mut self, confirm_flagged: bool) -> Self {
+        self.confirm_flagged = confirm_flagged;
+        self
+    }
+
+    /// How missing dependencies installed by [`install_with_deps`] are recorded,
+    /// [`InstallOrigin::Dependency`] by default.
+    pub fn dependency_origin(mut self, origin: InstallOrigin) -> Self {
+        self.dependency_origin = origin;
+        self
+    }
+
+    /// Fixes the target profile, so switching profiles during the install doesn't change it.
+    pub(super) fn resolve_profile(&mut self, manager: &ModManager) -> i64 {
+        *self
+            .profile_id
+            .get_or_insert_with(|| manager.active_profile().id)
+    }
+
     pub fn on_progress(mut self, on_progress: ProgressHandler) -> Self {
         self.on_progress = Some(on_progress);
         self
@@ -97,15 +263,20 @@ pub struct ModInstall {
     enabled: bool,
     index: Option<usize>,
     install_time: Option<DateTime<Utc>>,
+    side: Option<ModSide>,
+    origin: Option<InstallOrigin>,
 }
 
 impl ModInstall {
+    /// Creates an install of a mod the user asked for, see [`ModInstall::with_origin`].
     pub fn new(id: ModId) -> Self {
         Self {
             id,
             enabled: true,
             index: None,
             install_time: None,
+            side: None,
+            origin: Some(InstallOrigin::Explicit),
         }
     }
 
@@ -124,10 +295,25 @@ impl ModInstall {
         self
     }
 
+    pub fn with_side(mut self, side: Option<ModSide>) -> Self {
+        self.side = side;
+        self
+    }
+
+    /// Records how the mod was added, see [`ProfileMod::origin`].
+    pub fn with_origin(mut self, origin: Option<InstallOrigin>) -> Self {
+        self.origin = origin;
+        self
+    }
+
     /// The uuid the resulting `ProfileMod` will get after the mod is installed.
     pub fn uuid(&self) -> Uuid {
         self.id.package_uuid
     }
+
+    pub fn id(&self) -> &ModId {
+        &self.id
+    }
 }
 
 impl From<BorrowedMod<'_>> for ModInstall {
@@ -136,36 +322,111 @@ impl From<BorrowedMod<'_>> for ModInstall {
     }
 }
 
-/// Downloads and install mods on the active profile.
+/// Downloads and install mods on the target profile of `options`.
 ///
 /// Note that this does not check for duplicates, so make sure
 /// none of `mods` are already installed!
 pub async fn install_mods(
     mods: Vec<ModInstall>,
-    options: InstallOptions,
+    mut options: InstallOptions,
     app: &AppHandle,
-) -> Result<()> {
+) -> Result<InstallSummary> {
+    let _guard = lock_target_profile(&mut options, app).await?;
+
     download::Installer::create(options, app)?
         .install_all(mods)
         .await
 }
 
-/// Downloads and installs mods and their missing dependencies on the active profile.
+/// Waits for other operations on the target profile of `options` to
+/// finish and holds it until the returned guard is dropped.
+///
+/// Fails if the profile is locked.
+async fn lock_target_profile(
+    options: &mut InstallOptions,
+    app: &AppHandle,
+) -> Result<super::ops::OpGuard> {
+    let profile_id = {
+        let manager = app.lock_manager()?;
+        let profile_id = options.resolve_profile(&manager);
+        manager
+            .active_game()
+            .find_profile(profile_id)?
+            .ensure_unlocked()?;
+        profile_id
+    };
+
+    let guard = app
+        .profile_ops()
+        .acquire(profile_id, "installing mods")
+        .await;
+
+    // the profile could have been locked while we were waiting
+    app.lock_manager()?
+        .active_game()
+        .find_profile(profile_id)?
+        .ensure_unlocked()?;
+
+    Ok(guard)
+}
+
+/// Downloads and installs mods and their missing dependencies on the target profile of `options`.
 ///
 /// Dependencies are installed before each respective mod, sorted by descending depth.
+/// Packages in `exclude` are skipped, along with dependencies only they need.
+///
+/// Returns the versions that were installed, in the order they were installed in.
 pub async fn install_with_deps(
     mods: Vec<ModInstall>,
-    options: InstallOptions,
+    mut options: InstallOptions,
     allow_multiple: bool,
+    exclude: &HashSet<Uuid>,
     app: &tauri::AppHandle,
-) -> Result<()> {
-    let mods = {
-        let manager = app.lock_manager();
-        let thunderstore = app.lock_thunderstore();
-        let profile = manager.active_profile();
+) -> Result<Vec<VersionIdent>> {
+    // hold the profile while checking for missing dependencies,
+    // so they can't be installed by another operation in the meantime
+    let _guard = lock_target_profile(&mut options, app).await?;
+
+    let (mods, idents) = {
+        let manager = app.lock_manager()?;
+        let thunderstore = app.lock_thunderstore()?;
+        let profile = manager
+            .active_game()
+            .find_profile(options.resolve_profile(&manager))?;
+
+        let mut mods = mods;
+        if options.idempotent {
+            mods.retain_mut(|install| match profile.index_of(install.uuid()) {
+                Ok(index) => {
+                    let profile_mod = &profile.mods[index];
+
+                    let same_version = profile_mod.as_thunderstore().is_some_and(|(ts_mod, _)| {
+                        ts_mod.id.version_uuid == install.id.version_uuid
+                    });
+
+                    if same_version && profile_mod.enabled {
+                        info!("{} is already satisfied", profile_mod.ident());
+                        return false;
+                    }
+
+                    // change the version in place, like an update
+                    *install = install
+                        .clone()
+                        .with_index(index)
+                        .with_time(profile_mod.install_time)
+                        .with_side(install.side.or(profile_mod.side))
+                        .with_origin(profile_mod.origin.or(install.origin));
 
-        if !allow_multiple && mods.len() == 1 && profile.has_mod(mods[0].uuid()) {
-            bail!("mod already installed");
+                    true
+                }
+                Err(_) => true,
+            });
+        } else if !allow_multiple && mods.len() == 1 {
+            if let Ok(profile_mod) = profile.get_mod(mods[0].uuid()) {
+                bail!(UserError::AlreadyInstalled {
+                    name: profile_mod.full_name().into_owned()
+                });
+            }
         }
 
         let mods = mods
@@ -175,35 +436,383 @@ pub async fn install_with_deps(
 
                 Ok(iter::once(install).chain(
                     profile
-                        .missing_deps(borrowed.dependencies(), &thunderstore)
-                        .map(ModInstall::from),
+                        .missing_deps(borrowed.dependencies(), exclude, &thunderstore)
+                        .map(|dep| {
+                            ModInstall::from(dep).with_origin(Some(options.dependency_origin))
+                        }),
                 ))
             })
             .flatten_ok()
             .collect::<Result<Vec<_>>>()
             .context("failed to resolve dependencies")?;
 
-        mods.into_iter()
+        let mods = mods
+            .into_iter()
             .unique_by(|install| install.uuid())
             .rev() // install dependencies first
-            .collect()
+            .collect_vec();
+
+        let borrowed = mods
+            .iter()
+            .map(|install| install.id.borrow(&thunderstore))
+            .collect::<Result<Vec<_>>>()?;
+
+        if options.confirm_flagged {
+            let flagged = flags::find(borrowed.iter().copied(), &thunderstore);
+
+            if !flagged.is_empty() {
+                return Err(NeedsConfirmation(flagged).into());
+            }
+        }
+
+        let idents = borrowed
+            .iter()
+            .map(|borrowed| borrowed.ident().clone())
+            .collect_vec();
+
+        (mods, idents)
+    };
+
+    if options.idempotent {
+        let before_install = options.before_install.take();
+
+        options.before_install = Some(Box::new(move |install, profile, thunderstore| {
+            if profile.has_mod(install.uuid()) {
+                profile
+                    .force_remove_mod(install.uuid())
+                    .context("failed to remove existing version")?;
+            }
+
+            match &before_install {
+                Some(before_install) => before_install(install, profile, thunderstore),
+                None => Ok(()),
+            }
+        }));
+    }
+
+    download::Installer::create(options, app)?
+        .install_all(mods)
+        .await?;
+
+    Ok(idents)
+}
+
+/// Gets the path where the extracted files of a package are cached.
+pub fn cache_path(ident: &VersionIdent, prefs: &Prefs) -> PathBuf {
+    cache::path(ident, prefs)
+}
+
+/// Removes leftovers of interrupted operations from the temp directory
+/// that are older than [`Prefs::temp_cleanup_age`].
+pub fn clean_temp_dir(app: &AppHandle) -> Result<()> {
+    let (temp_dir, min_age) = {
+        let prefs = app.lock_prefs()?;
+        (prefs.temp_dir(), prefs.temp_cleanup_age())
+    };
+
+    let Some(min_age) = min_age else {
+        return Ok(());
+    };
+
+    let freed = temp::clean(&temp_dir, min_age)?;
+
+    if freed > 0 {
+        info!("removed {} bytes of leftovers from the temp dir", freed);
+    }
+
+    Ok(())
+}
+
+/// Gets the space the mod cache takes up on disk.
+pub fn cache_size(prefs: &Prefs, db: &Db) -> Result<u64> {
+    cache::disk_size(&prefs.cache_dir(), db)
+}
+
+/// Finds the Thunderstore mods in `profile` that are missing from the cache.
+///
+/// Local mods and mods which can't be found on Thunderstore are skipped.
+fn uncached_mods<'a>(
+    profile: &'a Profile,
+    prefs: &'a Prefs,
+    thunderstore: &'a Thunderstore,
+) -> impl Iterator<Item = BorrowedMod<'a>> + 'a {
+    profile
+        .thunderstore_mods()
+        .filter_map(|(ts_mod, _)| ts_mod.id.borrow(thunderstore).ok())
+        .filter(|borrowed| !cache::path(borrowed.ident(), prefs).exists())
+}
+
+/// Downloads every mod in a profile of the active game into the cache,
+/// so they can later be installed without an internet connection.
+///
+/// The profile itself is left untouched.
+pub async fn precache_profile(
+    profile_id: i64,
+    options: InstallOptions,
+    app: &AppHandle,
+) -> Result<()> {
+    let mods = {
+        let prefs = app.lock_prefs()?;
+        let manager = app.lock_manager()?;
+        let thunderstore = app.lock_thunderstore()?;
+
+        let profile = manager.active_game().find_profile(profile_id)?;
+
+        uncached_mods(profile, &prefs, &thunderstore)
+            .map(ModInstall::from)
+            .collect_vec()
     };
 
-    install_mods(mods, options, app).await
+    download::Installer::create(options.target_profile(profile_id), app)?
+        .cache_all(mods)
+        .await
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PrecacheInfo {
+    pub mod_count: usize,
+    pub download_size: u64,
+}
+
+/// What installing a mod would download, shown before the install starts.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallPreview {
+    pub download_size: u64,
+    /// Set if the download would go over the monthly download cap.
+    pub download_cap: Option<network::DownloadCapExceeded>,
+    /// Known conflicts with the mods already in the profile.
+    pub compatibility: Vec<CompatWarning>,
+    /// The mod and missing dependencies that are deprecated or blocklisted,
+    /// which have to be confirmed before installing.
+    pub flagged: Vec<FlaggedPackage>,
+}
+
+/// The result of [`commands::install_mod`].
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum InstallResponse {
+    /// Returns conflicts with mods that were already installed. These don't stop the install.
+    #[serde(rename_all = "camelCase")]
+    Installed {
+        warnings: Vec<CompatWarning>,
+        /// Dependencies that were left out because they were excluded,
+        /// so the mod may not work as intended.
+        skipped_dependencies: Vec<String>,
+        /// The mod has files for the game directory, which are only copied
+        /// once the user allows it with `set_game_dir_install`.
+        needs_game_dir_consent: bool,
+    },
+    AlreadyInstalled(AlreadyInstalled),
+    /// Nothing was installed, because these packages have to be confirmed first.
+    #[serde(rename_all = "camelCase")]
+    NeedsConfirmation {
+        flagged: Vec<FlaggedPackage>,
+    },
+    /// The exact version was already installed and enabled, see [`InstallOptions::idempotent`].
+    AlreadySatisfied,
+}
+
+/// The package was already in the profile, so nothing was installed.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AlreadyInstalled {
+    pub package_uuid: Uuid,
+    pub name: String,
+    /// The version in the profile, `None` for local mods.
+    pub installed: Option<String>,
+    pub requested: String,
+    /// What can be done instead. Leaving the mod as it is is always an option.
+    pub actions: Vec<AlreadyInstalledAction>,
+}
+
+/// Both of these are done by passing `mod_ref` to `change_mod_version`.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum AlreadyInstalledAction {
+    /// Switch to the requested version.
+    #[serde(rename_all = "camelCase")]
+    ChangeVersion { mod_ref: ModId },
+    /// Install the same version again, from the cache if `cached` is set.
+    #[serde(rename_all = "camelCase")]
+    Reinstall { mod_ref: ModId, cached: bool },
+}
+
+impl AlreadyInstalled {
+    /// Checks if the package of `requested` is already in `profile`.
+    fn check(
+        requested: BorrowedMod<'_>,
+        profile: &Profile,
+        prefs: &Prefs,
+        thunderstore: &Thunderstore,
+    ) -> Option<Self> {
+        let profile_mod = profile.get_mod(requested.package.uuid).ok()?;
+        let installed = profile_mod.as_thunderstore().map(|(ts_mod, _)| ts_mod);
+
+        let mut actions = Vec::new();
+
+        if let Some(installed) = installed {
+            if installed.id.version_uuid != requested.version.uuid {
+                actions.push(AlreadyInstalledAction::ChangeVersion {
+                    mod_ref: requested.into(),
+                });
+            }
+
+            // the installed version may have been delisted since
+            if installed.id.borrow(thunderstore).is_ok() {
+                actions.push(AlreadyInstalledAction::Reinstall {
+                    mod_ref: installed.id.clone(),
+                    cached: prefs.enable_mod_cache()
+                        && cache::path(&installed.ident, prefs).exists(),
+                });
+            }
+        }
+
+        Some(Self {
+            package_uuid: requested.package.uuid,
+            name: profile_mod.full_name().into_owned(),
+            installed: installed.map(|ts_mod| ts_mod.ident.version().to_owned()),
+            requested: requested.version.version().to_owned(),
+            actions,
+        })
+    }
+}
+
+/// A mod in an [`InstallPlan`].
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedMod {
+    pub full_name: String,
+    pub version: String,
+    pub file_size: u64,
+    /// Whether the mod is installed from the cache or a local source, instead of downloaded.
+    pub cached: bool,
+    /// Whether the package is already installed in the profile, in any version.
+    pub installed: bool,
+}
+
+/// What installing a set of mods would do, without installing anything.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallPlan {
+    pub mods: Vec<PlannedMod>,
+    pub download_size: u64,
+    /// Set if the download would go over the monthly download cap.
+    pub download_cap: Option<network::DownloadCapExceeded>,
+}
+
+/// Plans the installation of `mods` into `profile`.
+///
+/// `local_source` is the same as [`InstallOptions::local_source`].
+pub fn plan(
+    mods: &[ModInstall],
+    local_source: Option<&Path>,
+    profile: &Profile,
+    prefs: &Prefs,
+    thunderstore: &Thunderstore,
+    db: &Db,
+) -> Result<InstallPlan> {
+    let mods = mods
+        .iter()
+        .map(|install| {
+            let borrowed = install.id.borrow(thunderstore)?;
+            let ident = borrowed.ident();
+
+            let cached = (prefs.enable_mod_cache() && cache::path(ident, prefs).exists())
+                || local_source
+                    .is_some_and(|dir| dir.join(ident.full_name()).join(ident.version()).exists());
+
+            Ok(PlannedMod {
+                full_name: ident.full_name().to_owned(),
+                version: ident.version().to_owned(),
+                file_size: borrowed.version.file_size,
+                cached,
+                installed: profile.has_mod(borrowed.package.uuid),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let download_size = mods
+        .iter()
+        .filter(|planned| !planned.cached)
+        .map(|planned| planned.file_size)
+        .sum();
+
+    Ok(InstallPlan {
+        mods,
+        download_size,
+        download_cap: network::check_cap(download_size, prefs, db)?,
+    })
+}
+
+/// Gets the number of mods and bytes that [`precache_profile`] would download.
+fn precache_info(profile: &Profile, prefs: &Prefs, thunderstore: &Thunderstore) -> PrecacheInfo {
+    uncached_mods(profile, prefs, thunderstore).fold(
+        PrecacheInfo {
+            mod_count: 0,
+            download_size: 0,
+        },
+        |mut info, borrowed| {
+            info.mod_count += 1;
+            info.download_size += borrowed.version.file_size;
+            info
+        },
+    )
 }
 
 /// Gets the number of bytes to download the given mod and its
-/// missing dependencies (ignoring already cached mods).
+/// missing dependencies (ignoring already cached and excluded mods).
 fn total_download_size(
     borrowed: BorrowedMod<'_>,
+    exclude: &HashSet<Uuid>,
     profile: &Profile,
     prefs: &Prefs,
     thunderstore: &Thunderstore,
 ) -> u64 {
     profile
-        .missing_deps(borrowed.dependencies(), thunderstore)
+        .missing_deps(borrowed.dependencies(), exclude, thunderstore)
         .chain(iter::once(borrowed))
-        .filter(|borrowed| !cache::path(borrowed.ident(), prefs).exists())
+        .filter(|borrowed| {
+            !prefs.enable_mod_cache() || !cache::path(borrowed.ident(), prefs).exists()
+        })
         .map(|borrowed| borrowed.version.file_size)
         .sum()
 }
+
+/// Finds the dependencies of `borrowed` which are left out because of `exclude`.
+fn skipped_dependencies(
+    borrowed: BorrowedMod<'_>,
+    exclude: &HashSet<Uuid>,
+    thunderstore: &Thunderstore,
+) -> Vec<String> {
+    thunderstore
+        .dependencies(borrowed.dependencies())
+        .filter(|dep| exclude.contains(&dep.package.uuid))
+        .map(|dep| dep.package.full_name().to_owned())
+        .collect()
+}
+
+/// Checks `borrowed` and its missing dependencies against the compatibility rules.
+fn compat_warnings(
+    borrowed: BorrowedMod<'_>,
+    exclude: &HashSet<Uuid>,
+    profile: &Profile,
+    prefs: &Prefs,
+    thunderstore: &Thunderstore,
+) -> Vec<CompatWarning> {
+    let new = profile
+        .missing_deps(borrowed.dependencies(), exclude, thunderstore)
+        .chain(iter::once(borrowed))
+        .map(|borrowed| borrowed.ident().clone())
+        .collect_vec();
+
+    CompatRules::load(profile.game, prefs).check_install(
+        profile.mods.iter().filter_map(|profile_mod| {
+            profile_mod
+                .as_thunderstore()
+                .map(|(ts_mod, _)| &ts_mod.ident)
+        }),
+        &new,
+    )
+}
@@ -0,0 +1,100 @@
+use chrono::{Datelike, Duration, NaiveDate, Utc};
+use eyre::Result;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::{
+    db::{DailyUsage, Db, DownloadRecord, UsageKind},
+    game::Game,
+    prefs::Prefs,
+    state::ManagerExt,
+};
+
+const LARGEST_DOWNLOADS: u32 = 10;
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum NetworkStatsRange {
+    /// The last seven days, including today.
+    Week,
+    /// The current calendar month.
+    Month,
+    /// The last 365 days, including today.
+    Year,
+}
+
+impl NetworkStatsRange {
+    fn start(self, today: NaiveDate) -> NaiveDate {
+        match self {
+            NetworkStatsRange::Week => today - Duration::days(6),
+            NetworkStatsRange::Month => month_start(today),
+            NetworkStatsRange::Year => today - Duration::days(364),
+        }
+    }
+}
+
+fn month_start(today: NaiveDate) -> NaiveDate {
+    today
+        .with_day(1)
+        .expect("every month should have a first day")
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkStats {
+    pub total: u64,
+    pub days: Vec<DailyUsage>,
+    pub largest: Vec<DownloadRecord>,
+}
+
+pub(super) fn stats(range: NetworkStatsRange, db: &Db) -> Result<NetworkStats> {
+    let since = range.start(Utc::now().date_naive());
+    let days = db.network_usage(since)?;
+
+    Ok(NetworkStats {
+        total: days.iter().map(|day| day.bytes).sum(),
+        largest: db.largest_downloads(since, LARGEST_DOWNLOADS)?,
+        days,
+    })
+}
+
+/// Records a finished download towards the network usage stats.
+///
+/// Failures are only logged, since they shouldn't interrupt the download.
+pub fn record_usage(game: Game, name: &str, bytes: u64, kind: UsageKind, app: &AppHandle) {
+    if let Err(err) = app
+        .db()
+        .record_network_usage(&game.slug, name, bytes, kind, Utc::now())
+    {
+        warn!("failed to record network usage of {}: {:#}", name, err);
+    }
+}
+
+/// Returned when a download would go over [`Prefs::monthly_download_cap`].
+///
+/// Only package downloads count towards the cap, see [`UsageKind`].
+///
+/// The frontend should ask the user to confirm before installing.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadCapExceeded {
+    /// Bytes already downloaded this month.
+    pub used: u64,
+    pub cap: u64,
+}
+
+pub(super) fn check_cap(
+    download_size: u64,
+    prefs: &Prefs,
+    db: &Db,
+) -> Result<Option<DownloadCapExceeded>> {
+    let Some(cap) = prefs.monthly_download_cap() else {
+        return Ok(None);
+    };
+
+    let used = db.total_package_usage(month_start(Utc::now().date_naive()))?;
+    let exceeded = download_size > 0 && used + download_size > cap;
+
+    Ok(exceeded.then_some(DownloadCapExceeded { used, cap }))
+}
@@ -0,0 +1,160 @@
+//! Content-addressed storage for the mod cache.
+//!
+//! Files of cached versions are stored once under [`STORE_DIR`], keyed by
+//! their hash. A compacted version directory only contains hard links into
+//! the store, so identical files across versions take up space only once,
+//! and installers keep linking from the version directory as usual.
+
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use eyre::{Context, Result};
+use log::warn;
+use walkdir::WalkDir;
+
+use crate::{
+    db::CacheFile,
+    util::{self, error::IoResultExt},
+};
+
+pub(super) const STORE_DIR: &str = ".store";
+
+pub(super) fn store_dir(cache_dir: &Path) -> PathBuf {
+    cache_dir.join(STORE_DIR)
+}
+
+fn object_path(store: &Path, hash: &str) -> PathBuf {
+    let mut path = store.join(&hash[..2]);
+    path.push(hash);
+    path
+}
+
+/// The size of a stored file, or 0 if it's missing.
+pub(super) fn stored_size(store: &Path, hash: &str) -> u64 {
+    object_path(store, hash)
+        .metadata()
+        .map_or(0, |meta| meta.len())
+}
+
+/// A compacted version directory.
+pub(super) struct Manifest {
+    pub files: Vec<CacheFile>,
+    /// Bytes no longer taken up since an identical file was already stored.
+    pub saved: u64,
+}
+
+/// Moves the files of a version directory into the store, replacing them
+/// with hard links to the stored copies.
+pub(super) fn compact(version_dir: &Path, store: &Path) -> Result<Manifest> {
+    let mut files = Vec::new();
+    let mut saved = 0;
+
+    for entry in WalkDir::new(version_dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+    {
+        let path = entry.path();
+        let size = entry.metadata().map(|meta| meta.len()).unwrap_or(0);
+        let hash = util::fs::hash_file(path)?;
+        let object = object_path(store, &hash);
+
+        // a stored file can only have the wrong size if it was modified through
+        // one of its links, in which case it's replaced with this copy
+        let stored = object.metadata().is_ok_and(|meta| meta.len() == size);
+
+        if stored {
+            // link to a temporary path first, so the file is never missing
+            let mut temp = path.as_os_str().to_owned();
+            temp.push(".gale_link");
+            let temp = PathBuf::from(temp);
+
+            fs::hard_link(&object, &temp).fs_context("linking stored file", &temp)?;
+            fs::rename(&temp, path).fs_context("replacing file", path)?;
+
+            saved += size;
+        } else {
+            let parent = object.parent().expect("object path should have a parent");
+            fs::create_dir_all(parent).fs_context("creating store directory", parent)?;
+
+            if object.exists() {
+                warn!("stored file {} was modified, replacing it", hash);
+                fs::remove_file(&object).fs_context("removing stored file", &object)?;
+            }
+
+            fs::hard_link(path, &object).fs_context("storing file", &object)?;
+        }
+
+        let relative = path
+            .strip_prefix(version_dir)
+            .expect("WalkDir should only return full paths inside of the root");
+
+        files.push(CacheFile {
+            path: relative.to_string_lossy().into_owned(),
+            hash,
+            size: Some(size),
+        });
+    }
+
+    Ok(Manifest { files, saved })
+}
+
+/// Checks that every file in `manifest` is present in the version directory
+/// with its recorded size.
+///
+/// Sizes are compared instead of hashes, since hashing every file on each
+/// install would make cached installs much slower. An interrupted extraction
+/// leaves files missing or truncated, which this catches.
+pub(super) fn verify(version_dir: &Path, manifest: &[CacheFile]) -> bool {
+    manifest.iter().all(|file| {
+        let Ok(metadata) = version_dir.join(&file.path).metadata() else {
+            return false;
+        };
+
+        file.size.map_or(true, |size| metadata.len() == size)
+    })
+}
+
+/// Removes stored files that no cached version references anymore.
+///
+/// Profiles that installed a file by hard link keep their copy, since
+/// removing one link leaves the others intact.
+///
+/// Returns the number of bytes freed.
+pub(super) fn collect_garbage(store: &Path, referenced: &HashSet<String>) -> Result<u64> {
+    if !store.exists() {
+        return Ok(0);
+    }
+
+    let mut freed = 0;
+
+    for entry in WalkDir::new(store)
+        .min_depth(2)
+        .max_depth(2)
+        .into_iter()
+        .filter_map(Result::ok)
+    {
+        let hash = entry.file_name().to_string_lossy();
+
+        if referenced.contains(hash.as_ref()) {
+            continue;
+        }
+
+        let size = entry.metadata().map(|meta| meta.len()).unwrap_or(0);
+
+        match fs::remove_file(entry.path()) {
+            Ok(()) => freed += size,
+            Err(err) => warn!("failed to remove stored file {}: {}", hash, err),
+        }
+    }
+
+    // remove empty prefix directories
+    for entry in store.read_dir().context("failed to read store")?.flatten() {
+        fs::remove_dir(entry.path()).ok();
+    }
+
+    Ok(freed)
+}
@@ -0,0 +1,750 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::{Cursor, Seek, Write},
+    path::{Path, PathBuf},
+};
+
+use itertools::Itertools;
+use tempfile::TempDir;
+use walkdir::WalkDir;
+use zip::{write::SimpleFileOptions, ZipArchive, ZipWriter};
+
+use super::*;
+use crate::{
+    db::Db,
+    game,
+    profile::{LocalMod, ProfileMod, ProfileModKind, ThunderstoreMod},
+    thunderstore::{
+        blocklist::BlocklistEntry,
+        tests::{test_package, test_thunderstore},
+        ModId, Thunderstore, VersionIdent,
+    },
+};
+
+const PACKAGE_NAME: &str = "Kesomannen-TestMod";
+
+fn test_archive() -> PackageZip {
+    ZipArchive::new(test_archive_data().into()).unwrap()
+}
+
+fn test_archive_data() -> Vec<u8> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+
+    for (name, content) in [
+        ("manifest.json", "{}"),
+        ("icon.png", ""),
+        ("plugins/TestMod.dll", "dll"),
+        ("plugins/assets/bundle", "bundle"),
+        ("config/TestMod.cfg", "[General]"),
+    ] {
+        zip.start_file(name, SimpleFileOptions::default()).unwrap();
+        zip.write_all(content.as_bytes()).unwrap();
+    }
+
+    zip.finish().unwrap().into_inner()
+}
+
+fn test_prefs(root: &TempDir) -> Prefs {
+    Prefs {
+        data_dir: root.path().to_path_buf().into(),
+        ..Default::default()
+    }
+}
+
+fn test_profile(path: PathBuf) -> Profile {
+    fs::create_dir_all(&path).unwrap();
+
+    crate::profile::tests::test_profile(path)
+}
+
+fn relative_files(root: &Path) -> Vec<PathBuf> {
+    WalkDir::new(root)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.path().strip_prefix(root).unwrap().to_path_buf())
+        .sorted()
+        .collect()
+}
+
+/// Extracts the test archive to `extract_dir`, then installs it into a new profile at `profile_dir`.
+fn install_via(extract_dir: &Path, profile_dir: PathBuf) -> Profile {
+    let mut profile = test_profile(profile_dir);
+    install_into(&mut profile, extract_dir, PACKAGE_NAME);
+    profile
+}
+
+/// Extracts the test archive to `extract_dir`, then installs it into `profile` as `package_name`.
+pub(crate) fn install_into(profile: &mut Profile, extract_dir: &Path, package_name: &str) {
+    let mut installer = profile.game.mod_loader.installer_for(package_name);
+
+    installer
+        .extract(test_archive(), package_name, extract_dir.to_path_buf())
+        .unwrap();
+    installer
+        .install(extract_dir, package_name, profile)
+        .unwrap();
+
+    let (owner, name) = package_name.split_once('-').unwrap();
+    profile.mods.push(ProfileMod::new_local(LocalMod {
+        name: name.to_owned(),
+        author: Some(owner.to_owned()),
+        ..Default::default()
+    }));
+}
+
+#[test]
+fn archives_extract_the_same_from_disk() {
+    let root = TempDir::new().unwrap();
+
+    let mut file = tempfile::tempfile_in(root.path()).unwrap();
+    file.write_all(&test_archive_data()).unwrap();
+    file.rewind().unwrap();
+
+    let from_disk = ZipArchive::new(PackageSource::from(file)).unwrap();
+
+    let game = game::from_slug("lethal-company").unwrap();
+    let mut installer = game.mod_loader.installer_for(PACKAGE_NAME);
+
+    let memory_dir = root.path().join("memory");
+    let disk_dir = root.path().join("disk");
+
+    installer
+        .extract(test_archive(), PACKAGE_NAME, memory_dir.clone())
+        .unwrap();
+    installer
+        .extract(from_disk, PACKAGE_NAME, disk_dir.clone())
+        .unwrap();
+
+    assert_eq!(relative_files(&memory_dir), relative_files(&disk_dir));
+    assert!(!relative_files(&disk_dir).is_empty());
+}
+
+#[test]
+fn staging_dir_is_outside_cache() {
+    let root = tempfile::tempdir().unwrap();
+    let prefs = test_prefs(&root);
+
+    let staging = cache::staging_dir(&prefs).unwrap();
+
+    assert!(staging.path().starts_with(prefs.temp_dir()));
+    assert!(!staging.path().starts_with(prefs.cache_dir()));
+}
+
+#[test]
+fn failed_staged_install_leaves_nothing_behind() {
+    let root = tempfile::tempdir().unwrap();
+    let prefs = test_prefs(&root);
+    let mut profile = test_profile(root.path().join("profile"));
+    let mut staging_path = PathBuf::new();
+
+    let mut install = || -> Result<()> {
+        let staging = cache::staging_dir(&prefs)?;
+        staging_path = staging.path().to_path_buf();
+
+        install_into(&mut profile, staging.path(), PACKAGE_NAME);
+
+        bail!("simulated failure");
+    };
+
+    assert!(install().is_err());
+    assert!(staging_path.starts_with(prefs.temp_dir()));
+    assert!(!staging_path.exists());
+}
+
+#[test]
+fn cached_and_uncached_installs_match() {
+    let root = tempfile::tempdir().unwrap();
+    let prefs = test_prefs(&root);
+    let ident = VersionIdent::new("Kesomannen", "TestMod", "1.0.0");
+
+    let cache_dir = cache::path(&ident, &prefs);
+    let cached = install_via(&cache_dir, root.path().join("cached"));
+
+    let staging = cache::staging_dir(&prefs).unwrap();
+    let uncached = install_via(staging.path(), root.path().join("uncached"));
+    drop(staging);
+
+    assert!(cache_dir.exists());
+    assert_eq!(relative_files(&cached.path), relative_files(&uncached.path));
+    assert!(uncached
+        .path
+        .join("BepInEx/plugins")
+        .join(PACKAGE_NAME)
+        .join("TestMod.dll")
+        .exists());
+}
+
+#[test]
+fn uncached_install_can_be_toggled_and_removed() {
+    let root = tempfile::tempdir().unwrap();
+    let prefs = test_prefs(&root);
+
+    let staging = cache::staging_dir(&prefs).unwrap();
+    let mut profile = install_via(staging.path(), root.path().join("profile"));
+    drop(staging);
+
+    let plugin = profile
+        .path
+        .join("BepInEx/plugins")
+        .join(PACKAGE_NAME)
+        .join("TestMod.dll");
+
+    let uuid = profile.mods[0].uuid();
+
+    profile
+        .force_toggle_mod(uuid, DisableStrategy::Rename)
+        .unwrap();
+    assert!(!plugin.exists());
+    assert!(plugin.with_extension("dll.old").exists());
+
+    profile
+        .force_toggle_mod(uuid, DisableStrategy::Rename)
+        .unwrap();
+    assert!(plugin.exists());
+
+    profile.force_remove_mod(uuid).unwrap();
+    assert!(!plugin.exists());
+    assert!(profile.mods.is_empty());
+}
+
+#[test]
+fn mod_subdirs_count_installed_files() {
+    let root = tempfile::tempdir().unwrap();
+    let mut profile = install_via(&root.path().join("extract"), root.path().join("profile"));
+
+    let uuid = profile.mods[0].uuid();
+    let plugins = Path::new("BepInEx/plugins").join(PACKAGE_NAME);
+
+    // config is untracked, so it can't be traced back to the mod
+    let subdirs = profile.mod_subdirs(uuid).unwrap();
+    assert_eq!(subdirs.len(), 1);
+    assert_eq!(subdirs[0].name, "plugins");
+    assert_eq!(subdirs[0].path, plugins);
+    assert_eq!(subdirs[0].file_count, 2);
+
+    // disabled files are still counted
+    profile
+        .force_toggle_mod(uuid, DisableStrategy::Rename)
+        .unwrap();
+    assert_eq!(profile.mod_subdirs(uuid).unwrap()[0].file_count, 2);
+}
+
+#[test]
+fn staged_mod_is_moved_out_and_restored() {
+    let root = tempfile::tempdir().unwrap();
+    let prefs = test_prefs(&root);
+
+    let staging = cache::staging_dir(&prefs).unwrap();
+    let mut profile = install_via(staging.path(), root.path().join("profile"));
+    drop(staging);
+
+    let mod_dir = profile.path.join("BepInEx/plugins").join(PACKAGE_NAME);
+    let staged = profile
+        .path
+        .join(super::fs::DISABLED_STAGING)
+        .join("BepInEx/plugins")
+        .join(PACKAGE_NAME);
+
+    let uuid = profile.mods[0].uuid();
+
+    profile
+        .force_toggle_mod(uuid, DisableStrategy::Stage)
+        .unwrap();
+    assert!(!mod_dir.exists());
+    assert!(staged.join("TestMod.dll").exists());
+
+    // enabling doesn't depend on the current strategy
+    profile
+        .force_toggle_mod(uuid, DisableStrategy::Rename)
+        .unwrap();
+    assert!(mod_dir.join("TestMod.dll").exists());
+    assert!(!staged.exists());
+
+    profile
+        .force_toggle_mod(uuid, DisableStrategy::Stage)
+        .unwrap();
+    profile.force_remove_mod(uuid).unwrap();
+    assert!(!staged.exists());
+    assert!(!mod_dir.exists());
+}
+
+const BATCH_PACKAGES: [&str; 3] = ["Test-A", "Test-B", "Test-C"];
+
+/// A profile with each of [`BATCH_PACKAGES`] installed.
+fn batch_profile(root: &TempDir) -> Profile {
+    let mut profile = test_profile(root.path().join("profile"));
+
+    for name in BATCH_PACKAGES {
+        install_into(&mut profile, &root.path().join("extract").join(name), name);
+    }
+
+    profile
+}
+
+fn plugin_path(profile: &Profile, package_name: &str) -> PathBuf {
+    profile
+        .path
+        .join("BepInEx/plugins")
+        .join(package_name)
+        .join("TestMod.dll")
+}
+
+/// Stands in for a file held open by the game: a directory where the
+/// disabled plugin would be renamed to stops the mod from being toggled.
+fn block_toggle(profile: &Profile, package_name: &str) {
+    let blocker = plugin_path(profile, package_name).with_extension("dll.old");
+    fs::create_dir_all(&blocker).unwrap();
+    fs::write(blocker.join("blocker"), "").unwrap();
+}
+
+fn batch_uuids(profile: &Profile) -> Vec<Uuid> {
+    profile.mods.iter().map(ProfileMod::uuid).collect()
+}
+
+#[test]
+fn batch_toggle_continues_past_failures() {
+    let root = tempfile::tempdir().unwrap();
+    let mut profile = batch_profile(&root);
+    let uuids = batch_uuids(&profile);
+
+    block_toggle(&profile, "Test-B");
+
+    let report = profile
+        .try_toggle_mods(&uuids, false, DisableStrategy::Rename, |_| ())
+        .unwrap();
+
+    assert_eq!(report.succeeded, [uuids[0], uuids[2]]);
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(report.failed[0].name, "Test-B");
+    assert!(!report.reverted);
+
+    let enabled = profile.mods.iter().map(|m| m.enabled).collect_vec();
+    assert_eq!(enabled, [false, true, false]);
+    assert!(!plugin_path(&profile, "Test-A").exists());
+    assert!(!plugin_path(&profile, "Test-C").exists());
+}
+
+#[test]
+fn strict_batch_toggle_reverts() {
+    let root = tempfile::tempdir().unwrap();
+    let mut profile = batch_profile(&root);
+    let uuids = batch_uuids(&profile);
+
+    block_toggle(&profile, "Test-B");
+
+    let report = profile
+        .try_toggle_mods(&uuids, true, DisableStrategy::Rename, |_| ())
+        .unwrap();
+
+    assert!(report.succeeded.is_empty());
+    assert_eq!(report.failed.len(), 1);
+    assert!(report.reverted);
+
+    assert!(profile.mods.iter().all(|m| m.enabled));
+    assert!(plugin_path(&profile, "Test-A").exists());
+    assert!(plugin_path(&profile, "Test-C").exists());
+}
+
+/// Makes the mods of a [`batch_profile`] Thunderstore packages where
+/// each one depends on the next.
+fn chained_batch(profile: &mut Profile) -> Thunderstore {
+    let packages = [
+        test_package("A", &["B"]),
+        test_package("B", &["C"]),
+        test_package("C", &[]),
+    ];
+
+    for (profile_mod, package) in profile.mods.iter_mut().zip(&packages) {
+        profile_mod.kind = ProfileModKind::Thunderstore(ThunderstoreMod {
+            ident: package.latest().ident.clone(),
+            id: ModId {
+                package_uuid: package.uuid,
+                version_uuid: package.latest().uuid,
+            },
+        });
+    }
+
+    test_thunderstore(packages)
+}
+
+#[test]
+fn toggle_all_keeps_dependencies_consistent() {
+    let root = tempfile::tempdir().unwrap();
+    let mut profile = batch_profile(&root);
+    let thunderstore = chained_batch(&mut profile);
+    let uuids = batch_uuids(&profile);
+
+    block_toggle(&profile, "Test-B");
+
+    // dependants go first, and C is still needed by B
+    let report = profile
+        .toggle_all_mods(false, false, DisableStrategy::Rename, &thunderstore)
+        .unwrap();
+    assert_eq!(report.changed, [uuids[0]]);
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(report.failed[0].uuid, uuids[1]);
+    assert_eq!(report.skipped, [uuids[2]]);
+
+    let enabled = profile.mods.iter().map(|m| m.enabled).collect_vec();
+    assert_eq!(enabled, [false, true, true]);
+
+    // dependencies go first, so A still has B and C
+    let report = profile
+        .toggle_all_mods(true, false, DisableStrategy::Rename, &thunderstore)
+        .unwrap();
+    assert_eq!(report.changed, [uuids[0]]);
+    assert!(report.skipped.is_empty() && report.failed.is_empty());
+
+    fs::remove_dir_all(plugin_path(&profile, "Test-B").with_extension("dll.old")).unwrap();
+
+    let report = profile
+        .toggle_all_mods(false, false, DisableStrategy::Rename, &thunderstore)
+        .unwrap();
+    assert_eq!(report.changed, uuids);
+    assert!(profile.mods.iter().all(|m| !m.enabled));
+}
+
+#[test]
+fn batch_remove_continues_past_failures() {
+    let root = tempfile::tempdir().unwrap();
+    let mut profile = batch_profile(&root);
+    let uuids = batch_uuids(&profile);
+
+    // the mod's directory can't be removed as a file
+    let mod_dir = profile.path.join("BepInEx/plugins/Test-B");
+    fs::rename(&mod_dir, mod_dir.with_extension("old")).unwrap();
+
+    let report = profile.try_remove_mods(&uuids, false, |_| ()).unwrap();
+
+    assert_eq!(report.succeeded, [uuids[0], uuids[2]]);
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(report.failed[0].uuid, uuids[1]);
+
+    assert_eq!(batch_uuids(&profile), [uuids[1]]);
+    assert!(!plugin_path(&profile, "Test-A").exists());
+    assert!(!plugin_path(&profile, "Test-C").exists());
+}
+
+#[test]
+fn strict_batch_remove_leaves_profile_untouched() {
+    let root = tempfile::tempdir().unwrap();
+    let mut profile = batch_profile(&root);
+    let uuids = batch_uuids(&profile);
+
+    block_toggle(&profile, "Test-B");
+
+    let report = profile.try_remove_mods(&uuids, true, |_| ()).unwrap();
+
+    assert!(report.succeeded.is_empty());
+    assert_eq!(report.failed.len(), 1);
+    assert!(report.reverted);
+
+    assert_eq!(batch_uuids(&profile), uuids);
+    assert!(profile.mods.iter().all(|m| m.enabled));
+    assert!(plugin_path(&profile, "Test-A").exists());
+    assert!(plugin_path(&profile, "Test-C").exists());
+}
+
+#[test]
+fn summary_records_each_source() {
+    let mut summary = InstallSummary::default();
+    let uuids = (0..3).map(|_| Uuid::new_v4()).collect_vec();
+
+    summary.record(uuids[0], "A", InstallSource::Cache);
+    summary.record(uuids[1], "B", InstallSource::Download);
+    summary.record(uuids[2], "C", InstallSource::Cache);
+
+    assert_eq!(
+        (summary.cached, summary.reused, summary.downloaded),
+        (2, 0, 1)
+    );
+
+    let sources = summary
+        .sources
+        .iter()
+        .map(|s| (s.uuid, s.source))
+        .collect_vec();
+    assert_eq!(
+        sources,
+        [
+            (uuids[0], InstallSource::Cache),
+            (uuids[1], InstallSource::Download),
+            (uuids[2], InstallSource::Cache)
+        ]
+    );
+}
+
+#[test]
+fn lru_eviction_skips_installed_versions() {
+    use chrono::TimeZone;
+
+    let entry = |ident: &str, day: u32| cache::CacheEntry {
+        ident: ident.parse().unwrap(),
+        path: PathBuf::new(),
+        size: 100,
+        last_access: Some(Utc.with_ymd_and_hms(2024, 1, day, 0, 0, 0).unwrap()),
+    };
+
+    let entries = [
+        entry("A-Old-1.0.0", 1),
+        entry("B-Installed-1.0.0", 2),
+        entry("C-Recent-1.0.0", 4),
+        entry("D-Middle-1.0.0", 3),
+    ];
+
+    let in_use = HashSet::from(["B-Installed-1.0.0".parse::<VersionIdent>().unwrap()]);
+
+    let evicted = |max_size| {
+        cache::lru_evictions(&entries, &HashMap::new(), &in_use, max_size)
+            .0
+            .into_iter()
+            .map(|entry| entry.ident.as_str())
+            .collect_vec()
+    };
+
+    assert!(evicted(400).is_empty());
+    assert_eq!(evicted(250), ["A-Old-1.0.0", "D-Middle-1.0.0"]);
+    // the installed version is kept even if that leaves the cache over the limit
+    assert_eq!(
+        evicted(0),
+        ["A-Old-1.0.0", "D-Middle-1.0.0", "C-Recent-1.0.0"]
+    );
+}
+
+#[test]
+fn lru_eviction_counts_shared_files_once() {
+    use chrono::TimeZone;
+
+    let entry = |ident: &str, day: u32| cache::CacheEntry {
+        ident: ident.parse().unwrap(),
+        path: PathBuf::new(),
+        size: 150,
+        last_access: Some(Utc.with_ymd_and_hms(2024, 1, day, 0, 0, 0).unwrap()),
+    };
+
+    let entries = [
+        entry("A-Mod-1.0.0", 1),
+        entry("A-Mod-1.1.0", 2),
+        entry("B-Uncompacted-1.0.0", 3),
+    ];
+
+    // both versions link to the same 100 byte file
+    let files = HashMap::from([
+        (
+            "A-Mod-1.0.0".to_owned(),
+            vec![("shared".to_owned(), 100), ("old".to_owned(), 50)],
+        ),
+        (
+            "A-Mod-1.1.0".to_owned(),
+            vec![("shared".to_owned(), 100), ("new".to_owned(), 50)],
+        ),
+    ]);
+
+    let evicted = |max_size| {
+        let (evictions, freed) = cache::lru_evictions(&entries, &files, &HashSet::new(), max_size);
+
+        let idents = evictions
+            .into_iter()
+            .map(|entry| entry.ident.as_str())
+            .collect_vec();
+
+        (idents, freed)
+    };
+
+    // 300 bytes are actually taken up, not 400
+    assert_eq!(evicted(300), (vec![], 0));
+    assert_eq!(evicted(250), (vec!["A-Mod-1.0.0"], 50));
+    assert_eq!(evicted(200), (vec!["A-Mod-1.0.0", "A-Mod-1.1.0"], 200));
+}
+
+#[test]
+fn compacted_versions_share_identical_files() {
+    let root = tempfile::tempdir().unwrap();
+    let store_dir = store::store_dir(root.path());
+
+    let write_version = |version: &str, dll: &str| {
+        let path = root.path().join(PACKAGE_NAME).join(version);
+        fs::create_dir_all(path.join("plugins")).unwrap();
+        fs::write(path.join("plugins/TestMod.dll"), dll).unwrap();
+        fs::write(path.join("plugins/assets.bundle"), "large bundle").unwrap();
+        path
+    };
+
+    let old = write_version("1.0.0", "old dll");
+    let new = write_version("1.0.1", "new dll");
+
+    let first = store::compact(&old, &store_dir).unwrap();
+    assert_eq!(first.saved, 0);
+
+    let second = store::compact(&new, &store_dir).unwrap();
+    assert_eq!(second.saved, "large bundle".len() as u64);
+    assert_eq!(
+        fs::read_to_string(new.join("plugins/TestMod.dll")).unwrap(),
+        "new dll"
+    );
+
+    // remove the old version, keeping only what the new one references
+    fs::remove_dir_all(&old).unwrap();
+    let referenced = second
+        .files
+        .into_iter()
+        .map(|file| file.hash)
+        .collect::<HashSet<_>>();
+
+    let freed = store::collect_garbage(&store_dir, &referenced).unwrap();
+    assert_eq!(freed, "old dll".len() as u64);
+    assert_eq!(
+        fs::read_to_string(new.join("plugins/assets.bundle")).unwrap(),
+        "large bundle"
+    );
+}
+
+#[test]
+fn incomplete_cached_versions_are_removed() {
+    let root = tempfile::tempdir().unwrap();
+    let prefs = test_prefs(&root);
+    let db = Db::in_memory();
+    let ident = VersionIdent::new("Kesomannen", "TestMod", "1.0.0");
+
+    let cache_dir = prefs.cache_dir();
+    let path = cache::path(&ident, &prefs);
+    let profile = test_profile(root.path().join("profile"));
+
+    let extract = || {
+        cache::ensure_format(&cache_dir, &db).unwrap();
+
+        profile
+            .game
+            .mod_loader
+            .installer_for(PACKAGE_NAME)
+            .extract(test_archive(), PACKAGE_NAME, path.clone())
+            .unwrap();
+    };
+
+    // interrupted before the extraction finished
+    extract();
+    assert!(!cache::check_version(&ident, &path, &cache_dir, &db).unwrap());
+    assert!(!path.exists());
+
+    extract();
+    cache::compact_version(&ident, &path, &cache_dir, &db).unwrap();
+    assert!(cache::check_version(&ident, &path, &cache_dir, &db).unwrap());
+
+    // truncated afterwards
+    let files = relative_files(&path);
+    let truncated = files
+        .iter()
+        .find(|file| fs::metadata(path.join(file)).unwrap().len() > 0)
+        .unwrap();
+    fs::write(path.join(truncated), "").unwrap();
+    assert!(!cache::check_version(&ident, &path, &cache_dir, &db).unwrap());
+    assert!(!path.exists());
+
+    // the next download recovers, without reusing the truncated stored file
+    extract();
+    cache::compact_version(&ident, &path, &cache_dir, &db).unwrap();
+    assert!(cache::check_version(&ident, &path, &cache_dir, &db).unwrap());
+    assert_eq!(relative_files(&path), files);
+    assert!(fs::metadata(path.join(truncated)).unwrap().len() > 0);
+}
+
+#[test]
+fn versions_cached_before_verification_are_kept() {
+    let root = tempfile::tempdir().unwrap();
+    let prefs = test_prefs(&root);
+    let db = Db::in_memory();
+    let ident = VersionIdent::new("Kesomannen", "TestMod", "1.0.0");
+
+    let cache_dir = prefs.cache_dir();
+    let path = cache::path(&ident, &prefs);
+    let profile = test_profile(root.path().join("profile"));
+
+    // written by an older version, without a manifest
+    profile
+        .game
+        .mod_loader
+        .installer_for(PACKAGE_NAME)
+        .extract(test_archive(), PACKAGE_NAME, path.clone())
+        .unwrap();
+
+    assert!(cache::check_version(&ident, &path, &cache_dir, &db).unwrap());
+    assert!(db.cache_manifest(&ident).unwrap().is_some());
+}
+
+#[test]
+fn malformed_archive_entries_are_rejected() {
+    let temp = TempDir::new().unwrap();
+    let ident = VersionIdent::new("Kesomannen", "TestMod", "1.0.0");
+
+    let write_manifest = |content: &str| {
+        fs::write(temp.path().join("manifest.json"), content).unwrap();
+    };
+
+    assert!(download::check_archive_entry(temp.path(), &ident).is_err());
+
+    write_manifest("\u{feff}{\"name\": \"TestMod\", \"version_number\": \"1.0.0\"}");
+    assert!(download::check_archive_entry(temp.path(), &ident).is_ok());
+
+    write_manifest("{\"name\": \"TestMod\", \"version_number\": \"1.0.1\"}");
+    assert!(download::check_archive_entry(temp.path(), &ident).is_err());
+
+    write_manifest("not json");
+    assert!(download::check_archive_entry(temp.path(), &ident).is_err());
+}
+
+#[test]
+fn deprecated_and_blocklisted_packages_are_flagged() {
+    let mut deprecated = test_package("Old", &[]);
+    deprecated.is_deprecated = true;
+
+    let mut thunderstore = test_thunderstore([
+        test_package("Fine", &[]),
+        deprecated,
+        test_package("Bad", &[]),
+    ]);
+    thunderstore.set_blocklist(vec![BlocklistEntry {
+        package: "Test-Bad".to_owned(),
+        versions: Vec::new(),
+        reason: Some("malware".to_owned()),
+    }]);
+
+    let flagged = flags::find(thunderstore.latest(), &thunderstore)
+        .into_iter()
+        .map(|package| (package.full_name, package.flags))
+        .collect_vec();
+
+    assert_eq!(
+        flagged,
+        [
+            ("Test-Old".to_owned(), vec![flags::PackageFlag::Deprecated]),
+            (
+                "Test-Bad".to_owned(),
+                vec![flags::PackageFlag::Blocklisted {
+                    reason: Some("malware".to_owned())
+                }]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn archive_content_types() {
+    for content_type in [
+        None,
+        Some("application/zip"),
+        Some("application/octet-stream"),
+        Some("Application/X-Zip-Compressed; charset=binary"),
+    ] {
+        assert!(
+            download::is_archive_content_type(content_type),
+            "{:?}",
+            content_type
+        );
+    }
+
+    for content_type in ["text/html; charset=utf-8", "application/json"] {
+        assert!(!download::is_archive_content_type(Some(content_type)));
+    }
+}
@@ -0,0 +1,174 @@
+//! Notices when a game has been updated since it was last launched,
+//! since game updates are a common reason for mods to break.
+
+use std::{fs, path::Path};
+
+use chrono::{DateTime, Utc};
+use eyre::Result;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use super::platform;
+use crate::{db::Db, game::Game, prefs::Prefs, state::ManagerExt};
+
+/// Identifies the installed version of a game.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", tag = "type", content = "value")]
+pub enum GameBuild {
+    /// The build id from Steam's app manifest.
+    Steam(String),
+    /// When the game's executable was last modified, for games that
+    /// aren't installed through Steam.
+    ExeModified(DateTime<Utc>),
+}
+
+impl GameBuild {
+    /// Whether `self` is a different build than `previous`.
+    ///
+    /// Builds found in different ways can't be compared, for example
+    /// after switching platforms, so they never count as an update.
+    fn is_update_of(&self, previous: &GameBuild) -> bool {
+        match (self, previous) {
+            (GameBuild::Steam(current), GameBuild::Steam(previous)) => current != previous,
+            (GameBuild::ExeModified(current), GameBuild::ExeModified(previous)) => {
+                current != previous
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Sent to the frontend as the `game_updated` event.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GameUpdated {
+    pub game_name: String,
+    pub previous: GameBuild,
+    pub current: GameBuild,
+}
+
+/// Finds the current build of `game`, installed at `game_dir`.
+pub(super) fn current(game: Game, game_dir: &Path, prefs: &Prefs) -> Option<GameBuild> {
+    steam_build(game, game_dir, prefs).or_else(|| exe_build(game_dir))
+}
+
+fn steam_build(game: Game, game_dir: &Path, prefs: &Prefs) -> Option<GameBuild> {
+    let steam = game.platforms.steam.as_ref()?;
+    let library = platform::steam_library_dir(steam.id as u64, prefs).ok()?;
+
+    // an overridden game directory might not be the Steam install
+    if !game_dir.starts_with(&library) {
+        return None;
+    }
+
+    let manifest_path = library
+        .join("steamapps")
+        .join(format!("appmanifest_{}.acf", steam.id));
+    let manifest = fs::read_to_string(manifest_path).ok()?;
+
+    parse_build_id(&manifest).map(|id| GameBuild::Steam(id.to_owned()))
+}
+
+/// Finds the `"buildid"` value in a Steam app manifest.
+fn parse_build_id(manifest: &str) -> Option<&str> {
+    manifest.lines().find_map(|line| {
+        let mut parts = line.split('"').filter(|part| !part.trim().is_empty());
+
+        match parts.next()? {
+            "buildid" => parts.next(),
+            _ => None,
+        }
+    })
+}
+
+fn exe_build(game_dir: &Path) -> Option<GameBuild> {
+    let modified = super::exe_path(game_dir)
+        .ok()?
+        .metadata()
+        .and_then(|metadata| metadata.modified())
+        .ok()?;
+
+    Some(GameBuild::ExeModified(modified.into()))
+}
+
+/// Compares `current` with the build recorded at the last launch, without recording it.
+pub(super) fn check(game: Game, current: &GameBuild, db: &Db) -> Result<Option<GameUpdated>> {
+    let update = db
+        .game_build(&game.slug)?
+        .filter(|previous| current.is_update_of(previous))
+        .map(|previous| GameUpdated {
+            game_name: game.name.to_owned(),
+            previous,
+            current: current.clone(),
+        });
+
+    Ok(update)
+}
+
+/// Records the current build of `game` as it's launched, and emits
+/// `game_updated` if it has changed since the last launch.
+pub(super) fn record_launch(game: Game, game_dir: &Path, prefs: &Prefs, app: &AppHandle) {
+    let Some(current) = current(game, game_dir, prefs) else {
+        return;
+    };
+
+    let result = check(game, &current, app.db()).and_then(|update| {
+        app.db().save_game_build(&game.slug, &current)?;
+        Ok(update)
+    });
+
+    match result {
+        Ok(Some(update)) => {
+            info!(
+                "{} was updated since its last launch ({:?} -> {:?})",
+                game.slug, update.previous, update.current
+            );
+            app.emit("game_updated", &update).ok();
+        }
+        Ok(None) => (),
+        Err(err) => warn!("failed to check for game updates: {:#}", err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game;
+
+    #[test]
+    fn build_id_is_parsed() {
+        let manifest = r#""AppState"
+{
+	"appid"		"1966720"
+	"name"		"Lethal Company"
+	"buildid"		"16184279"
+	"LastOwner"		"76561198000000000"
+}"#;
+
+        assert_eq!(parse_build_id(manifest), Some("16184279"));
+        assert_eq!(parse_build_id("\"AppState\"\n{\n}"), None);
+    }
+
+    #[test]
+    fn only_changed_builds_are_updates() {
+        let db = Db::in_memory();
+        let game = game::from_slug("lethal-company").unwrap();
+
+        let old = GameBuild::Steam("1".to_owned());
+        let new = GameBuild::Steam("2".to_owned());
+
+        assert!(check(game, &old, &db).unwrap().is_none());
+        db.save_game_build(&game.slug, &old).unwrap();
+
+        assert!(check(game, &old, &db).unwrap().is_none());
+
+        let update = check(game, &new, &db).unwrap().unwrap();
+        assert_eq!(update.previous, old);
+        assert_eq!(update.current, new);
+
+        // switching platforms isn't an update
+        let exe = GameBuild::ExeModified(Utc::now());
+        assert!(check(game, &exe, &db).unwrap().is_none());
+    }
+}
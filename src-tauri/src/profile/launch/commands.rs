@@ -2,25 +2,126 @@ use eyre::Context;
 use itertools::Itertools;
 use tauri::{command, AppHandle};
 
-use crate::{state::ManagerExt, util::cmd::Result};
+use super::{
+    build, deploy::DeploymentStatus, game_files::GameDirInstall, repair::LoaderRepair,
+    DoorstopSettings, GameUpdated, LaunchCommand,
+};
+use crate::{db::LaunchRecord, state::ManagerExt, util::cmd::Result};
 
 #[command]
 pub fn launch_game(app: AppHandle) -> Result<()> {
-    let prefs = app.lock_prefs();
-    let manager = app.lock_manager();
+    super::launch_active(&app)?;
 
-    manager.active_game().launch(&prefs, &app)?;
+    Ok(())
+}
+
+/// Returns the latest launches of the active game, newest first.
+#[command]
+pub fn get_launch_history(limit: Option<u32>, app: AppHandle) -> Result<Vec<LaunchRecord>> {
+    let slug = &app.lock_manager()?.active_game.slug;
+    let history = app.db().launch_history(slug, limit.unwrap_or(50))?;
+
+    Ok(history)
+}
+
+/// Checks whether the active game has been updated since it was last launched,
+/// which often breaks mods.
+#[command]
+pub fn check_game_update(app: AppHandle) -> Result<Option<GameUpdated>> {
+    let prefs = app.lock_prefs()?;
+    let game = app.lock_manager()?.active_game;
+
+    let game_dir = super::game_dir(game, &prefs)?;
+    let Some(current) = build::current(game, &game_dir, &prefs) else {
+        return Ok(None);
+    };
+
+    Ok(build::check(game, &current, app.db())?)
+}
+
+#[command]
+pub fn get_game_dir_install_info(app: AppHandle) -> Result<GameDirInstall> {
+    let prefs = app.lock_prefs()?;
+    let manager = app.lock_manager()?;
+
+    Ok(manager.active_game().game_dir_install_info(&prefs)?)
+}
+
+#[command]
+pub fn set_game_dir_install(allowed: bool, app: AppHandle) -> Result<()> {
+    let prefs = app.lock_prefs()?;
+    let mut manager = app.lock_manager()?;
+
+    let game = manager.active_game_mut();
+    if allowed {
+        // make sure the game directory is usable before saving the consent
+        game.game_dir_install_info(&prefs)?;
+    }
+
+    let profile = game.active_profile_mut();
+    profile.game_dir_install = allowed;
+    profile.save(app.db())?;
+
+    game.sync_game_dir(&prefs, app.db())?;
 
     Ok(())
 }
 
+#[command]
+pub fn get_doorstop_settings(app: AppHandle) -> Result<DoorstopSettings> {
+    let manager = app.lock_manager()?;
+
+    Ok(manager.active_profile().doorstop.clone())
+}
+
+#[command]
+pub fn set_doorstop_settings(settings: DoorstopSettings, app: AppHandle) -> Result<()> {
+    let prefs = app.lock_prefs()?;
+    let mut manager = app.lock_manager()?;
+
+    let game = manager.active_game_mut();
+    let launch_mode = prefs
+        .game_prefs
+        .get(&*game.game.slug)
+        .map(|prefs| prefs.launch_mode.clone())
+        .unwrap_or_default();
+
+    let profile = game.active_profile_mut();
+    profile.ensure_unlocked()?;
+    settings.validate(profile.game, &profile.path, &launch_mode)?;
+
+    profile.doorstop = settings;
+    profile.save(app.db())?;
+
+    Ok(())
+}
+
+#[command]
+pub fn get_deployment_status(app: AppHandle) -> Result<Option<DeploymentStatus>> {
+    let manager = app.lock_manager()?;
+
+    Ok(manager.active_game().deployment_status(app.db())?)
+}
+
+#[command]
+pub fn repair_loader(app: AppHandle) -> Result<LoaderRepair> {
+    let prefs = app.lock_prefs()?;
+    let manager = app.lock_manager()?;
+
+    let game_dir = super::game_dir(manager.active_game, &prefs)?;
+    let repair = manager.active_game().repair_loader(&game_dir, &prefs)?;
+
+    Ok(repair)
+}
+
 #[command]
 pub fn get_launch_args(app: AppHandle) -> Result<String> {
-    let prefs = app.lock_prefs();
-    let manager = app.lock_manager();
+    let prefs = app.lock_prefs()?;
+    let manager = app.lock_manager()?;
 
     let game_dir = super::game_dir(manager.active_game, &prefs)?;
-    let (_, command) = manager.active_game().launch_command(&game_dir, &prefs)?;
+    let game = manager.active_game();
+    let (_, command) = game.launch_command(game.active_profile(), &game_dir, &prefs)?;
     let text = command
         .get_args()
         .map(|arg| format!("\"{}\"", arg.to_string_lossy()))
@@ -29,10 +130,22 @@ pub fn get_launch_args(app: AppHandle) -> Result<String> {
     Ok(text)
 }
 
+/// Returns what launching a profile would run, for diagnosing failed launches.
+#[command]
+pub fn get_launch_command(profile_id: i64, app: AppHandle) -> Result<LaunchCommand> {
+    let prefs = app.lock_prefs()?;
+    let manager = app.lock_manager()?;
+
+    let game = manager.active_game();
+    let profile = game.find_profile(profile_id)?;
+
+    Ok(game.resolved_launch_command(profile, &prefs)?)
+}
+
 #[command]
 pub fn open_game_dir(app: AppHandle) -> Result<()> {
-    let prefs = app.lock_prefs();
-    let manager = app.lock_manager();
+    let prefs = app.lock_prefs()?;
+    let manager = app.lock_manager()?;
 
     let path = super::game_dir(manager.active_game, &prefs)?;
     open::that(path).context("failed to open directory")?;
@@ -0,0 +1,457 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use eyre::{Context, Result};
+use itertools::Itertools;
+use log::{info, warn};
+use serde::Serialize;
+use tauri::AppHandle;
+use walkdir::WalkDir;
+
+use super::game_dir;
+use crate::{
+    db::{Db, DeployMethod, Deployment},
+    prefs::Prefs,
+    profile::ManagedGame,
+    state::ManagerExt,
+    util::{cmd::UserError, error::IoResultExt},
+};
+
+/// Which profile is deployed to the game directory, for games whose
+/// mod loader only loads mods from one location.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DeploymentStatus {
+    pub profile_id: i64,
+    /// `None` if the profile has since been deleted.
+    pub profile_name: Option<String>,
+    pub target: PathBuf,
+    pub method: DeployMethod,
+    /// Whether the deployed profile is the active one.
+    pub active: bool,
+    /// `false` if Gale was interrupted while deploying. The deployment
+    /// is repaired the next time a profile is selected or launched.
+    pub complete: bool,
+}
+
+impl ManagedGame {
+    pub fn deployment_status(&self, db: &Db) -> Result<Option<DeploymentStatus>> {
+        let Some(deployment) = db.deployment(&self.game.slug)? else {
+            return Ok(None);
+        };
+
+        let profile_name = self
+            .profiles
+            .iter()
+            .find(|profile| profile.id == deployment.profile_id)
+            .map(|profile| profile.name.clone());
+
+        Ok(Some(DeploymentStatus {
+            active: deployment.profile_id == self.active_profile_id,
+            profile_id: deployment.profile_id,
+            profile_name,
+            target: deployment.target,
+            method: deployment.method,
+            complete: deployment.complete,
+        }))
+    }
+
+    /// Deploys the active profile to the directory the mod loader loads mods
+    /// from, after removing the previous deployment.
+    ///
+    /// The profile's directory is symlinked if possible, and copied otherwise.
+    /// Links to the active profile are left as is, while copies are redone
+    /// every time to pick up changes to the profile.
+    pub fn deploy_active_profile(&self, prefs: &Prefs, db: &Db) -> Result<()> {
+        let slug = &*self.game.slug;
+        let previous = db.deployment(slug)?;
+
+        let Some(deploy_dir) = self.game.mod_loader.deploy_dir else {
+            if let Some(previous) = previous {
+                undeploy(&previous)?;
+                db.remove_deployment(slug)?;
+            }
+
+            return Ok(());
+        };
+
+        let profile = self.active_profile();
+        let source = profile.path.join(deploy_dir);
+        let target = game_dir(self.game, prefs)?.join(deploy_dir);
+
+        // files of an earlier copy which couldn't be removed may be overwritten
+        let mut owned = Vec::new();
+
+        if let Some(previous) = previous {
+            let up_to_date = previous.complete
+                && previous.method == DeployMethod::Link
+                && previous.profile_id == profile.id
+                && previous.target == target
+                && read_link(&target).is_ok_and(|dest| dest == source);
+
+            if up_to_date {
+                return Ok(());
+            }
+
+            if !previous.complete {
+                warn!(
+                    "repairing interrupted deployment at {}",
+                    previous.target.display()
+                );
+            }
+
+            undeploy(&previous)?;
+            db.remove_deployment(slug)?;
+
+            if previous.target == target {
+                owned = previous.files;
+            }
+        }
+
+        let sources = self
+            .profiles
+            .iter()
+            .map(|profile| profile.path.join(deploy_dir))
+            .collect::<Vec<_>>();
+
+        ensure_vacant(&target, deploy_dir, &sources, &owned)?;
+
+        fs::create_dir_all(&source).fs_context("creating directory", &source)?;
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).fs_context("creating directory", parent)?;
+        }
+
+        let mut deployment = Deployment {
+            profile_id: profile.id,
+            target: target.clone(),
+            method: DeployMethod::Link,
+            files: Vec::new(),
+            complete: false,
+        };
+
+        // record the deployment first, so it can be cleaned up if we're interrupted
+        db.save_deployment(slug, &deployment)?;
+
+        if let Err(err) = link_dir(&source, &target) {
+            info!(
+                "failed to link {}, copying files instead: {}",
+                target.display(),
+                err
+            );
+
+            deployment.method = DeployMethod::Copy;
+            deployment.files = files_in(&source);
+            db.save_deployment(slug, &deployment)?;
+
+            for file in &deployment.files {
+                let dest = target.join(file);
+
+                if let Some(parent) = dest.parent() {
+                    fs::create_dir_all(parent).fs_context("creating directory", parent)?;
+                }
+
+                fs::copy(source.join(file), &dest).fs_context("copying file", &dest)?;
+            }
+        }
+
+        deployment.complete = true;
+        db.save_deployment(slug, &deployment)?;
+
+        info!(
+            "deployed {} to {} ({:?})",
+            profile.name,
+            target.display(),
+            deployment.method
+        );
+
+        Ok(())
+    }
+}
+
+/// Deploys the active profile of the active game.
+pub fn deploy(app: &AppHandle) -> Result<()> {
+    let prefs = app.lock_prefs()?;
+    let manager = app.lock_manager()?;
+
+    manager
+        .active_game()
+        .deploy_active_profile(&prefs, app.db())
+        .map_err(|err| super::blame_running_game(err, manager.active_game().game, &prefs))
+        .context("failed to deploy profile to the game directory")
+}
+
+/// Clears the way for a deployment to `target`, or fails with
+/// [`UserError::GameDirFileConflict`] if something Gale didn't deploy is in
+/// the way, without removing anything.
+///
+/// Gale owns links to any of the profile directories in `sources` and the
+/// `owned` files, relative to `target`. Those are removed, along with
+/// directories left empty.
+fn ensure_vacant(
+    target: &Path,
+    deploy_dir: &str,
+    sources: &[PathBuf],
+    owned: &[PathBuf],
+) -> Result<()> {
+    let Ok(metadata) = fs::symlink_metadata(target) else {
+        return Ok(());
+    };
+
+    if metadata.is_symlink() {
+        let is_owned = read_link(target).is_ok_and(|dest| sources.contains(&dest));
+
+        if !is_owned {
+            return Err(UserError::GameDirFileConflict {
+                path: deploy_dir.to_owned(),
+            }
+            .into());
+        }
+
+        remove_link(target).fs_context("removing link", target)?;
+        return Ok(());
+    }
+
+    if !metadata.is_dir() {
+        return Err(UserError::GameDirFileConflict {
+            path: deploy_dir.to_owned(),
+        }
+        .into());
+    }
+
+    let conflicts = files_in(target)
+        .into_iter()
+        .filter(|file| !owned.contains(file))
+        .collect::<Vec<_>>();
+
+    if let Some(first) = conflicts.first() {
+        warn!(
+            "files not deployed by Gale are in the way at {}: {}",
+            target.display(),
+            conflicts.iter().map(|file| file.display()).join(", ")
+        );
+
+        let mut path = Path::new(deploy_dir).join(first).display().to_string();
+        match conflicts.len() - 1 {
+            0 => (),
+            1 => path += " (and 1 other file)",
+            n => path += &format!(" (and {} other files)", n),
+        }
+
+        return Err(UserError::GameDirFileConflict { path }.into());
+    }
+
+    for file in owned {
+        let path = target.join(file);
+
+        match fs::remove_file(&path) {
+            Ok(()) => (),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+            Err(err) => return Err(err).fs_context("removing file", &path),
+        }
+    }
+
+    remove_empty_dirs(target);
+
+    Ok(())
+}
+
+/// Removes the empty directories in `dir`, including `dir` itself.
+fn remove_empty_dirs(dir: &Path) {
+    WalkDir::new(dir)
+        .contents_first(true)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_dir())
+        .for_each(|entry| {
+            fs::remove_dir(entry.path()).ok();
+        });
+}
+
+fn undeploy(deployment: &Deployment) -> Result<()> {
+    let target = &deployment.target;
+
+    match deployment.method {
+        DeployMethod::Link => match fs::symlink_metadata(target) {
+            Ok(metadata) if metadata.is_symlink() => {
+                remove_link(target).fs_context("removing link", target)?;
+            }
+            Ok(_) => warn!(
+                "{} is no longer a link, leaving it in place",
+                target.display()
+            ),
+            Err(_) => (),
+        },
+        DeployMethod::Copy => {
+            for file in &deployment.files {
+                let path = target.join(file);
+
+                match fs::remove_file(&path) {
+                    Ok(()) => (),
+                    Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+                    Err(err) => warn!("failed to remove {}: {}", path.display(), err),
+                }
+            }
+
+            remove_empty_dirs(target);
+        }
+    }
+
+    info!("removed deployment at {}", target.display());
+
+    Ok(())
+}
+
+fn files_in(dir: &Path) -> Vec<PathBuf> {
+    WalkDir::new(dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| entry.path().strip_prefix(dir).ok().map(Path::to_path_buf))
+        .collect()
+}
+
+#[cfg(unix)]
+fn link_dir(source: &Path, target: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(source, target)
+}
+
+/// Directory symlinks on Windows need developer mode or admin rights, so
+/// this falls back to a junction, which doesn't. If that fails as well,
+/// the files are copied instead.
+#[cfg(windows)]
+fn link_dir(source: &Path, target: &Path) -> io::Result<()> {
+    std::os::windows::fs::symlink_dir(source, target).or_else(|err| {
+        info!(
+            "failed to symlink {}, trying a junction instead: {}",
+            target.display(),
+            err
+        );
+
+        create_junction(source, target)
+    })
+}
+
+#[cfg(windows)]
+fn create_junction(source: &Path, target: &Path) -> io::Result<()> {
+    use std::{os::windows::process::CommandExt, process::Command};
+
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+    let output = Command::new("cmd")
+        .args(["/C", "mklink", "/J"])
+        .arg(target)
+        .arg(source)
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()?;
+
+    match output.status.success() {
+        true => Ok(()),
+        false => Err(io::Error::other(format!(
+            "mklink failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))),
+    }
+}
+
+/// Reads the destination of a link. Junctions are read back with a `\\?\`
+/// prefix, which is stripped so they compare equal to the path they were
+/// created with.
+fn read_link(path: &Path) -> io::Result<PathBuf> {
+    let dest = fs::read_link(path)?;
+
+    #[cfg(windows)]
+    if let Some(stripped) = dest.to_str().and_then(|dest| dest.strip_prefix(r"\\?\")) {
+        return Ok(PathBuf::from(stripped));
+    }
+
+    Ok(dest)
+}
+
+#[cfg(unix)]
+fn remove_link(path: &Path) -> io::Result<()> {
+    fs::remove_file(path)
+}
+
+#[cfg(windows)]
+fn remove_link(path: &Path) -> io::Result<()> {
+    fs::remove_dir(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEPLOY_DIR: &str = "Mods";
+
+    fn write(path: &Path) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "").unwrap();
+    }
+
+    fn conflict(result: Result<()>) -> Option<String> {
+        match result.unwrap_err().downcast::<UserError>() {
+            Ok(UserError::GameDirFileConflict { path }) => Some(path),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn vacant_targets_are_left_empty() {
+        let temp = tempfile::tempdir().unwrap();
+        let target = temp.path().join(DEPLOY_DIR);
+
+        ensure_vacant(&target, DEPLOY_DIR, &[], &[]).unwrap();
+        assert!(!target.exists());
+
+        fs::create_dir_all(target.join("Empty")).unwrap();
+        ensure_vacant(&target, DEPLOY_DIR, &[], &[]).unwrap();
+        assert!(!target.exists());
+    }
+
+    #[test]
+    fn owned_files_are_removed() {
+        let temp = tempfile::tempdir().unwrap();
+        let target = temp.path().join(DEPLOY_DIR);
+
+        write(&target.join("Mod/plugin.dll"));
+        let owned = [PathBuf::from("Mod/plugin.dll")];
+
+        ensure_vacant(&target, DEPLOY_DIR, &[], &owned).unwrap();
+        assert!(!target.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn owned_links_are_removed() {
+        let temp = tempfile::tempdir().unwrap();
+        let target = temp.path().join(DEPLOY_DIR);
+        let source = temp.path().join("profile").join(DEPLOY_DIR);
+
+        fs::create_dir_all(&source).unwrap();
+        link_dir(&source, &target).unwrap();
+
+        ensure_vacant(&target, DEPLOY_DIR, std::slice::from_ref(&source), &[]).unwrap();
+        assert!(!target.exists());
+        assert!(source.exists());
+    }
+
+    #[test]
+    fn foreign_files_are_reported_and_kept() {
+        let temp = tempfile::tempdir().unwrap();
+        let target = temp.path().join(DEPLOY_DIR);
+
+        write(&target.join("Mod/plugin.dll"));
+        write(&target.join("Other/a.dll"));
+        write(&target.join("Other/b.dll"));
+        let owned = [PathBuf::from("Mod/plugin.dll")];
+
+        let path = conflict(ensure_vacant(&target, DEPLOY_DIR, &[], &owned)).unwrap();
+        assert!(path.ends_with("(and 1 other file)"), "{}", path);
+
+        // nothing is removed if there's a conflict
+        assert!(target.join("Mod/plugin.dll").exists());
+        assert!(target.join("Other/a.dll").exists());
+    }
+}
@@ -0,0 +1,239 @@
+//! Per-profile settings for Unity Doorstop, which BepInEx uses to
+//! load its preloader into the game.
+//!
+//! The default settings launch the game the same way as without any.
+
+use std::{path::Path, process::Command};
+
+use eyre::{bail, ensure, Result};
+use serde::{Deserialize, Serialize};
+
+use super::{mod_loader, LaunchMode};
+use crate::game::{Game, ModLoaderKind};
+
+const ENABLE_SWITCHES: &[&str] = &["--doorstop-enable", "--doorstop-enabled"];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DoorstopSettings {
+    /// Directory Mono looks in first for its assemblies, for games
+    /// that need a custom Mono build.
+    pub mono_dll_search_path: Option<String>,
+    /// Drops `--doorstop-enable false` and similar from the game's custom
+    /// arguments, which are often left over from launching it without mods.
+    pub ignore_disable_switch: bool,
+    /// Passes the settings as `DOORSTOP_*` environment variables instead
+    /// of arguments. Only doorstop 4 reads these.
+    pub use_env_vars: bool,
+}
+
+impl DoorstopSettings {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Checks that the settings can be used to launch `game` from `profile_dir`,
+    /// explaining what's wrong if not.
+    pub fn validate(&self, game: Game, profile_dir: &Path, launch_mode: &LaunchMode) -> Result<()> {
+        if self.is_default() {
+            return Ok(());
+        }
+
+        ensure!(
+            matches!(game.mod_loader.kind, ModLoaderKind::BepInEx { .. }),
+            "{} doesn't use doorstop, only BepInEx games do",
+            game.name
+        );
+
+        if let Some(path) = &self.mono_dll_search_path {
+            ensure!(
+                !path.trim().is_empty(),
+                "the Mono search path can't be empty, leave it unset to use the default"
+            );
+            ensure!(
+                !path.contains(['\n', '\r', '\0']),
+                "the Mono search path can't contain line breaks"
+            );
+        }
+
+        if self.use_env_vars {
+            let version = mod_loader::doorstop_version(profile_dir)?;
+
+            ensure!(
+                version >= 4,
+                "environment variables are only read by doorstop 4, but this profile has doorstop {}",
+                version
+            );
+
+            ensure!(
+                matches!(launch_mode, LaunchMode::Direct { .. }),
+                "environment variables don't reach the game when it's started through a launcher, \
+                 switch the launch mode to direct"
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Adds the doorstop arguments or environment variables to `command`.
+    pub(super) fn apply(&self, command: &mut Command, version: u32, target: &Path) -> Result<()> {
+        if self.use_env_vars {
+            if version < 4 {
+                bail!("doorstop {} doesn't read environment variables", version);
+            }
+
+            command
+                .env("DOORSTOP_ENABLED", "1")
+                .env("DOORSTOP_TARGET_ASSEMBLY", target);
+
+            if let Some(path) = &self.mono_dll_search_path {
+                command.env("DOORSTOP_MONO_DLL_SEARCH_PATH_OVERRIDE", path);
+            }
+
+            return Ok(());
+        }
+
+        let (enable, target_arg, search_path) = match version {
+            3 => (
+                "--doorstop-enable",
+                "--doorstop-target",
+                "--mono-dll-search-path-override",
+            ),
+            4 => (
+                "--doorstop-enabled",
+                "--doorstop-target-assembly",
+                "--doorstop-mono-dll-search-path-override",
+            ),
+            vers => bail!("unsupported doorstop version: {}", vers),
+        };
+
+        command.args([enable, "true", target_arg]).arg(target);
+
+        if let Some(path) = &self.mono_dll_search_path {
+            command.arg(search_path).arg(path);
+        }
+
+        Ok(())
+    }
+
+    /// Filters the custom arguments of a game according to
+    /// [`DoorstopSettings::ignore_disable_switch`].
+    pub(super) fn filter_custom_args<'a>(&self, args: &'a [String]) -> Vec<&'a str> {
+        let mut result = Vec::with_capacity(args.len());
+        let mut iter = args.iter().map(String::as_str).peekable();
+
+        while let Some(arg) = iter.next() {
+            if self.ignore_disable_switch
+                && ENABLE_SWITCHES.contains(&arg)
+                && iter
+                    .next_if(|value| value.eq_ignore_ascii_case("false"))
+                    .is_some()
+            {
+                continue;
+            }
+
+            result.push(arg);
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::game;
+
+    fn args(command: &Command) -> Vec<String> {
+        command
+            .get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn default_settings_match_plain_launch() {
+        let mut command = Command::new("game");
+        DoorstopSettings::default()
+            .apply(&mut command, 4, Path::new("preloader.dll"))
+            .unwrap();
+
+        assert_eq!(
+            args(&command),
+            [
+                "--doorstop-enabled",
+                "true",
+                "--doorstop-target-assembly",
+                "preloader.dll"
+            ]
+        );
+        assert_eq!(command.get_envs().count(), 0);
+    }
+
+    #[test]
+    fn env_vars_replace_args() {
+        let settings = DoorstopSettings {
+            mono_dll_search_path: Some("mono".to_owned()),
+            use_env_vars: true,
+            ..Default::default()
+        };
+
+        let mut command = Command::new("game");
+        settings
+            .apply(&mut command, 4, Path::new("preloader.dll"))
+            .unwrap();
+
+        assert!(args(&command).is_empty());
+        assert_eq!(command.get_envs().count(), 3);
+
+        assert!(settings
+            .apply(&mut Command::new("game"), 3, Path::new("preloader.dll"))
+            .is_err());
+    }
+
+    #[test]
+    fn disable_switch_is_filtered() {
+        let custom = ["--doorstop-enable", "false", "-screen-width", "800"].map(String::from);
+
+        let mut settings = DoorstopSettings::default();
+        assert_eq!(settings.filter_custom_args(&custom), custom);
+
+        settings.ignore_disable_switch = true;
+        assert_eq!(
+            settings.filter_custom_args(&custom),
+            ["-screen-width", "800"]
+        );
+    }
+
+    #[test]
+    fn invalid_combinations_are_rejected() {
+        let temp = tempfile::tempdir().unwrap();
+        let game = game::from_slug("lethal-company").unwrap();
+        let direct = LaunchMode::Direct {
+            instances: 1,
+            interval_secs: 0.0,
+        };
+
+        let env_vars = DoorstopSettings {
+            use_env_vars: true,
+            ..Default::default()
+        };
+
+        // no doorstop version file, so it's assumed to be 3
+        assert!(env_vars.validate(game, temp.path(), &direct).is_err());
+
+        fs::write(temp.path().join(".doorstop_version"), "4.0.0").unwrap();
+        assert!(env_vars.validate(game, temp.path(), &direct).is_ok());
+        assert!(env_vars
+            .validate(game, temp.path(), &LaunchMode::Launcher)
+            .is_err());
+
+        let empty_path = DoorstopSettings {
+            mono_dll_search_path: Some(" ".to_owned()),
+            ..Default::default()
+        };
+        assert!(empty_path.validate(game, temp.path(), &direct).is_err());
+    }
+}
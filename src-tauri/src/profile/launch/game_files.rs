@@ -0,0 +1,320 @@
+use std::{
+    collections::HashSet,
+    ffi::OsStr,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use eyre::{Context, Result};
+use log::{info, warn};
+use serde::Serialize;
+use tauri::AppHandle;
+use walkdir::WalkDir;
+
+use super::game_dir;
+use crate::{
+    db::{Db, GameDirFile},
+    prefs::Prefs,
+    profile::{install::GAME_DIR_STAGING, ManagedGame, Profile},
+    state::ManagerExt,
+    util::{cmd::UserError, error::IoResultExt},
+};
+
+const RISKS: &[&str] = &[
+    "Files are copied into the game's installation, outside of Gale's profiles.",
+    "Verifying or updating the game through its launcher may remove or replace them.",
+    "Other mod managers or manually installed mods may conflict with them.",
+    "Gale only removes the files it copied itself, when the mods are uninstalled \
+    or another profile is selected.",
+];
+
+/// What enabling installs into the game directory for a profile would do.
+///
+/// Show this to the user to confirm before calling `set_game_dir_install`.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GameDirInstall {
+    pub game_dir: PathBuf,
+    /// Files currently waiting to be copied, relative to `game_dir`.
+    pub files: Vec<PathBuf>,
+    pub risks: &'static [&'static str],
+}
+
+impl Profile {
+    /// Finds the enabled files that should be copied to the game directory,
+    /// relative to the game directory.
+    fn staged_game_files(&self) -> Vec<PathBuf> {
+        let staging = self.path.join(GAME_DIR_STAGING);
+
+        WalkDir::new(&staging)
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_type().is_file())
+            .filter(|entry| entry.path().extension() != Some(OsStr::new("old")))
+            .filter_map(|entry| {
+                entry
+                    .path()
+                    .strip_prefix(&staging)
+                    .ok()
+                    .map(Path::to_path_buf)
+            })
+            .collect()
+    }
+
+    /// Whether the profile has mods with game directory files, but the
+    /// user hasn't allowed them to be copied yet.
+    pub fn needs_game_dir_consent(&self) -> bool {
+        !self.game_dir_install && !self.staged_game_files().is_empty()
+    }
+}
+
+impl ManagedGame {
+    pub fn game_dir_install_info(&self, prefs: &Prefs) -> Result<GameDirInstall> {
+        let game_dir = game_dir(self.game, prefs)?;
+        ensure_writable(&game_dir)?;
+
+        Ok(GameDirInstall {
+            files: self.active_profile().staged_game_files(),
+            game_dir,
+            risks: RISKS,
+        })
+    }
+
+    /// Makes the game directory contain the game directory files of the
+    /// active profile, and nothing from other profiles.
+    ///
+    /// Only files recorded in the database are ever removed, and existing
+    /// files that Gale didn't create are never overwritten.
+    pub fn sync_game_dir(&self, prefs: &Prefs, db: &Db) -> Result<()> {
+        let deployed = db.game_dir_files(&self.game.slug)?;
+        let profile = self.active_profile();
+
+        let files = match profile.game_dir_install {
+            true => profile.staged_game_files(),
+            false => Vec::new(),
+        };
+
+        if files.is_empty() && deployed.is_empty() {
+            return Ok(());
+        }
+
+        if files.is_empty() {
+            remove_files(&deployed);
+            db.set_game_dir_files(&self.game.slug, &[])?;
+            return Ok(());
+        }
+
+        let game_dir = game_dir(self.game, prefs)?;
+        ensure_writable(&game_dir)?;
+
+        let owned = deployed
+            .iter()
+            .filter(|file| file.game_dir == game_dir)
+            .map(|file| file.path.as_path())
+            .collect::<HashSet<_>>();
+
+        if let Some(conflict) = files
+            .iter()
+            .find(|file| !owned.contains(file.as_path()) && game_dir.join(file).exists())
+        {
+            return Err(UserError::GameDirFileConflict {
+                path: conflict.display().to_string(),
+            }
+            .into());
+        }
+
+        let wanted = files.iter().map(PathBuf::as_path).collect::<HashSet<_>>();
+        let stale = deployed
+            .into_iter()
+            .filter(|file| file.game_dir != game_dir || !wanted.contains(file.path.as_path()))
+            .collect::<Vec<_>>();
+
+        remove_files(&stale);
+
+        let records = files
+            .iter()
+            .map(|path| GameDirFile {
+                game_dir: game_dir.clone(),
+                path: path.clone(),
+                profile_id: profile.id,
+            })
+            .collect::<Vec<_>>();
+
+        // record the files first, so they can be cleaned up if copying fails halfway
+        db.set_game_dir_files(&self.game.slug, &records)?;
+
+        let staging = profile.path.join(GAME_DIR_STAGING);
+        for file in &files {
+            let target = game_dir.join(file);
+
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent).fs_context("creating directory", parent)?;
+            }
+
+            fs::copy(staging.join(file), &target).fs_context("copying file", &target)?;
+        }
+
+        info!(
+            "copied {} files from {} to the game directory",
+            files.len(),
+            profile.name
+        );
+
+        Ok(())
+    }
+}
+
+/// Syncs the game directory of the active game.
+pub fn sync(app: &AppHandle) -> Result<()> {
+    let prefs = app.lock_prefs()?;
+    let manager = app.lock_manager()?;
+
+    manager
+        .active_game()
+        .sync_game_dir(&prefs, app.db())
+        .map_err(|err| super::blame_running_game(err, manager.active_game().game, &prefs))
+        .context("failed to update files in the game directory")
+}
+
+fn ensure_writable(game_dir: &Path) -> Result<()> {
+    let probe = game_dir.join(".gale_write_test");
+
+    match fs::write(&probe, []) {
+        Ok(()) => {
+            fs::remove_file(&probe).ok();
+            Ok(())
+        }
+        Err(_) => Err(UserError::GameDirNotWritable {
+            path: game_dir.display().to_string(),
+        }
+        .into()),
+    }
+}
+
+fn remove_files(files: &[GameDirFile]) {
+    for file in files {
+        let path = file.game_dir.join(&file.path);
+
+        match fs::remove_file(&path) {
+            Ok(()) => (),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+            Err(err) => warn!("failed to remove {}: {}", path.display(), err),
+        }
+    }
+
+    info!("removed {} files from the game directory", files.len());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prefs::GamePrefs, profile::tests::test_profile};
+
+    fn stage(profile: &Profile, file: &str, contents: &str) {
+        let path = profile.path.join(GAME_DIR_STAGING).join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn game_dir_follows_the_active_profile() {
+        let temp = tempfile::tempdir().unwrap();
+        let game_dir = temp.path().join("game");
+        fs::create_dir_all(&game_dir).unwrap();
+
+        let db = Db::in_memory();
+
+        let mut first = test_profile(temp.path().join("first"));
+        first.game_dir_install = true;
+        stage(&first, "scripts/hook.dll", "first");
+
+        let mut second = test_profile(temp.path().join("second"));
+        second.id = 2;
+        second.game_dir_install = true;
+        stage(&second, "hook.ini", "second");
+
+        let game = first.game;
+        let mut prefs = Prefs::default();
+        prefs.game_prefs.insert(
+            game.slug.to_string(),
+            GamePrefs {
+                dir_override: Some(game_dir.clone()),
+                ..Default::default()
+            },
+        );
+
+        let mut managed = ManagedGame {
+            id: 1,
+            game,
+            path: temp.path().to_path_buf(),
+            profiles: vec![first, second],
+            favorite: false,
+            active_profile_id: 1,
+            last_played: None,
+        };
+
+        managed.sync_game_dir(&prefs, &db).unwrap();
+        assert_eq!(
+            fs::read_to_string(game_dir.join("scripts/hook.dll")).unwrap(),
+            "first"
+        );
+
+        managed.active_profile_id = 2;
+        managed.sync_game_dir(&prefs, &db).unwrap();
+        assert!(!game_dir.join("scripts/hook.dll").exists());
+        assert_eq!(
+            fs::read_to_string(game_dir.join("hook.ini")).unwrap(),
+            "second"
+        );
+
+        // without consent nothing is copied, and earlier copies are removed
+        managed.active_profile_mut().game_dir_install = false;
+        assert!(managed.active_profile().needs_game_dir_consent());
+
+        managed.sync_game_dir(&prefs, &db).unwrap();
+        assert!(!game_dir.join("hook.ini").exists());
+        assert!(db.game_dir_files(&game.slug).unwrap().is_empty());
+    }
+
+    #[test]
+    fn files_gale_did_not_copy_are_not_overwritten() {
+        let temp = tempfile::tempdir().unwrap();
+        let game_dir = temp.path().join("game");
+        fs::create_dir_all(&game_dir).unwrap();
+        fs::write(game_dir.join("hook.ini"), "user").unwrap();
+
+        let mut profile = test_profile(temp.path().join("profile"));
+        profile.game_dir_install = true;
+        stage(&profile, "hook.ini", "mod");
+
+        let game = profile.game;
+        let mut prefs = Prefs::default();
+        prefs.game_prefs.insert(
+            game.slug.to_string(),
+            GamePrefs {
+                dir_override: Some(game_dir.clone()),
+                ..Default::default()
+            },
+        );
+
+        let managed = ManagedGame {
+            id: 1,
+            game,
+            path: temp.path().to_path_buf(),
+            profiles: vec![profile],
+            favorite: false,
+            active_profile_id: 1,
+            last_played: None,
+        };
+
+        let err = managed.sync_game_dir(&prefs, &Db::in_memory()).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<UserError>(),
+            Some(UserError::GameDirFileConflict { .. })
+        ));
+        assert_eq!(
+            fs::read_to_string(game_dir.join("hook.ini")).unwrap(),
+            "user"
+        );
+    }
+}
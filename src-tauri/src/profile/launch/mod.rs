@@ -1,34 +1,62 @@
 use core::str;
 use std::{
-    fs,
+    ffi::OsStr,
+    fs, io, iter,
     path::{Path, PathBuf},
-    process::Command,
+    process::{Child, Command},
 };
 
+use chrono::Utc;
 use eyre::{bail, ensure, eyre, OptionExt, Result};
+use itertools::Itertools;
 use log::{info, warn};
 use serde::{Deserialize, Serialize};
-use tauri::AppHandle;
+use tauri::{AppHandle, Emitter};
 use tokio::time::Duration;
 
-use super::ManagedGame;
+use super::{ManagedGame, Profile};
 use crate::{
     game::Game,
     logger::log_webview_err,
     prefs::{GamePrefs, Prefs},
+    state::ManagerExt,
     util::{
         self,
+        cmd::UserError,
         fs::{Overwrite, UseLinks},
     },
 };
 
+mod build;
+mod deploy;
+mod doorstop;
+mod game_files;
 #[cfg(target_os = "linux")]
 mod linux;
 mod mod_loader;
 mod platform;
+mod repair;
+mod watch;
 
 pub mod commands;
 
+pub use build::{GameBuild, GameUpdated};
+pub use deploy::deploy as deploy_active_profile;
+pub use doorstop::DoorstopSettings;
+pub use game_files::sync as sync_game_dir;
+pub use platform::installed_steam_games;
+
+/// The program and arguments a launch runs.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LaunchCommand {
+    pub program: String,
+    pub args: Vec<String>,
+    pub mode: LaunchMode,
+    /// The program and arguments quoted and joined, ready to paste into a terminal.
+    pub command_line: String,
+}
+
 #[derive(Serialize, Deserialize, Default, Debug, Clone)]
 #[serde(rename_all = "camelCase", tag = "type", content = "content")]
 pub enum LaunchMode {
@@ -39,21 +67,141 @@ pub enum LaunchMode {
     Direct { instances: u32, interval_secs: f32 },
 }
 
+/// Launches the active game with its active profile.
+pub fn launch_active(app: &AppHandle) -> Result<()> {
+    let prefs = app.lock_prefs()?;
+    let mut manager = app.lock_manager()?;
+
+    let game = manager.active_game_mut();
+    game.launch(&prefs, app)?;
+    game.active_profile_mut().config_cache.mark_stale();
+
+    game.last_played = Some(Utc::now());
+    app.db().save_game(game)?;
+
+    Ok(())
+}
+
+/// Whether `game` is running, or `None` if it can't be determined.
+pub fn is_game_running(game: Game, prefs: &Prefs) -> Option<bool> {
+    let exe_path = game_dir(game, prefs).and_then(|dir| exe_path(&dir)).ok()?;
+    let name = exe_path.file_name()?.to_string_lossy();
+
+    watch::is_running(&name)
+}
+
+/// Turns an error from files being in use into [`UserError::GameRunning`]
+/// if `game` is running, since it's the most likely culprit.
+pub fn blame_running_game(err: eyre::Report, game: Game, prefs: &Prefs) -> eyre::Report {
+    let in_use = err.chain().any(|err| {
+        matches!(
+            err.downcast_ref::<UserError>(),
+            Some(UserError::FilesInUse { .. })
+        ) || err
+            .downcast_ref::<io::Error>()
+            .is_some_and(util::fs::is_in_use)
+    });
+
+    if in_use && is_game_running(game, prefs) == Some(true) {
+        UserError::GameRunning {
+            game: game.name.to_owned(),
+        }
+        .into()
+    } else {
+        err
+    }
+}
+
 impl ManagedGame {
     pub fn launch(&self, prefs: &Prefs, app: &AppHandle) -> Result<()> {
         let game_dir = game_dir(self.game, prefs)?;
+
+        build::record_launch(self.game, &game_dir, prefs, app);
+
+        match self.repair_loader(&game_dir, prefs) {
+            Ok(repair) if !repair.is_empty() => {
+                info!("repaired mod loader: {:?}", repair);
+                app.emit("loader_repaired", &repair).ok();
+            }
+            Ok(_) => (),
+            Err(err) => warn!("failed to check mod loader: {:#}", err),
+        }
+
+        self.sync_game_dir(prefs, app.db())
+            .map_err(|err| blame_running_game(err, self.game, prefs))?;
+        self.deploy_active_profile(prefs, app.db())
+            .map_err(|err| blame_running_game(err, self.game, prefs))?;
+
         if let Err(err) = self.link_files(&game_dir) {
             warn!("failed to link files: {:#}", err);
         }
 
-        let (launch_mode, command) = self.launch_command(&game_dir, prefs)?;
+        let profile = self.active_profile();
+        let (launch_mode, command) = self.launch_command(profile, &game_dir, prefs)?;
         info!("launching {} with command {:?}", self.game.slug, command);
-        do_launch(command, app, launch_mode)?;
+
+        let exe_path = exe_path(&game_dir).ok();
+        let is_direct = exe_path
+            .as_ref()
+            .is_some_and(|path| command.get_program() == path.as_os_str());
+
+        let child = do_launch(command, app, launch_mode)?;
+
+        let process = match (is_direct, exe_path) {
+            (true, _) => Some(watch::GameProcess::Child(child)),
+            (false, Some(path)) => path
+                .file_name()
+                .map(|name| watch::GameProcess::Named(name.to_string_lossy().into_owned())),
+            (false, None) => None,
+        };
+
+        if let Some(process) = process {
+            let launch = watch::Launch {
+                id: app
+                    .db()
+                    .record_launch(&self.game.slug, profile.id, Utc::now())?,
+                profile_id: profile.id,
+                log_path: profile.log_path().ok(),
+            };
+
+            watch::spawn(process, launch, app.clone());
+        }
 
         Ok(())
     }
 
-    fn launch_command(&self, game_dir: &Path, prefs: &Prefs) -> Result<(LaunchMode, Command)> {
+    /// Returns the command that launching `profile` would run, without running it
+    /// or preparing the game directory first.
+    pub fn resolved_launch_command(
+        &self,
+        profile: &Profile,
+        prefs: &Prefs,
+    ) -> Result<LaunchCommand> {
+        let game_dir = game_dir(self.game, prefs)?;
+        let (mode, command) = self.launch_command(profile, &game_dir, prefs)?;
+
+        let program = resolve_path(command.get_program());
+        let args = command.get_args().map(resolve_path).collect_vec();
+
+        let command_line = iter::once(&program)
+            .chain(&args)
+            .map(|part| format!("\"{}\"", part))
+            .join(" ");
+
+        Ok(LaunchCommand {
+            program,
+            args,
+            mode,
+            command_line,
+        })
+    }
+
+    fn launch_command(
+        &self,
+        profile: &Profile,
+        game_dir: &Path,
+        prefs: &Prefs,
+    ) -> Result<(LaunchMode, Command)> {
         let (launch_mode, mut platform, custom_args) = prefs
             .game_prefs
             .get(&*self.game.slug)
@@ -80,12 +228,15 @@ impl ManagedGame {
         }
         .unwrap_or_else(|| exe_path(game_dir).map(Command::new))?;
 
-        let profile = self.active_profile();
-
-        mod_loader::add_args(&mut command, &profile.path, &self.game.mod_loader)?;
+        mod_loader::add_args(
+            &mut command,
+            &profile.path,
+            &self.game.mod_loader,
+            &profile.doorstop,
+        )?;
 
         if let Some(custom_args) = custom_args {
-            command.args(custom_args);
+            command.args(profile.doorstop.filter_custom_args(custom_args));
         }
 
         if self.game.server {
@@ -136,19 +287,21 @@ impl ManagedGame {
     }
 }
 
-fn do_launch(mut command: Command, app: &AppHandle, mode: LaunchMode) -> Result<()> {
+/// Spawns the command, returning the first process.
+fn do_launch(mut command: Command, app: &AppHandle, mode: LaunchMode) -> Result<Child> {
     match mode {
-        LaunchMode::Launcher | LaunchMode::Direct { instances: 1, .. } => {
-            command.spawn()?;
-        }
+        LaunchMode::Launcher | LaunchMode::Direct { instances: 1, .. } => Ok(command.spawn()?),
         LaunchMode::Direct { instances: 0, .. } => bail!("instances must be greater than 0"),
         LaunchMode::Direct {
             instances,
             interval_secs,
         } => {
+            let first = command.spawn()?;
+
             let app = app.clone();
             tauri::async_runtime::spawn(async move {
-                for i in 0..instances {
+                for i in 1..instances {
+                    tokio::time::sleep(Duration::from_secs_f32(interval_secs)).await;
                     if let Err(err) = command.spawn() {
                         log_webview_err(
                             "Failed to launch game",
@@ -156,13 +309,12 @@ fn do_launch(mut command: Command, app: &AppHandle, mode: LaunchMode) -> Result<
                             &app,
                         );
                     }
-                    tokio::time::sleep(Duration::from_secs_f32(interval_secs)).await;
                 }
             });
-        }
-    };
 
-    Ok(())
+            Ok(first)
+        }
+    }
 }
 
 fn game_dir(game: Game, prefs: &Prefs) -> Result<PathBuf> {
@@ -192,6 +344,19 @@ fn game_dir(game: Game, prefs: &Prefs) -> Result<PathBuf> {
     Ok(path)
 }
 
+/// Makes existing paths absolute, leaving other arguments as they are.
+fn resolve_path(arg: &OsStr) -> String {
+    let path = Path::new(arg);
+
+    match path.exists() {
+        true => std::path::absolute(path)
+            .unwrap_or_else(|_| path.to_path_buf())
+            .to_string_lossy()
+            .into_owned(),
+        false => arg.to_string_lossy().into_owned(),
+    }
+}
+
 fn exe_path(game_dir: &Path) -> Result<PathBuf> {
     game_dir
         .read_dir()?
@@ -4,17 +4,23 @@ use std::{
     process::Command,
 };
 
-use eyre::{bail, Context, OptionExt, Result};
+use eyre::{Context, OptionExt, Result};
 use log::{info, warn};
 
+use super::doorstop::DoorstopSettings;
 use crate::{
     game::{ModLoader, ModLoaderKind},
     util::error::IoResultExt,
 };
 
-pub fn add_args(command: &mut Command, profile_dir: &Path, mod_loader: &ModLoader) -> Result<()> {
+pub fn add_args(
+    command: &mut Command,
+    profile_dir: &Path,
+    mod_loader: &ModLoader,
+    doorstop: &DoorstopSettings,
+) -> Result<()> {
     match &mod_loader.kind {
-        ModLoaderKind::BepInEx { .. } => add_bepinex_args(command, profile_dir),
+        ModLoaderKind::BepInEx { .. } => add_bepinex_args(command, profile_dir, doorstop),
         ModLoaderKind::MelonLoader { .. } => add_melon_loader_args(command, profile_dir),
         ModLoaderKind::Northstar {} => add_northstar_args(command, profile_dir),
         ModLoaderKind::GDWeave {} => add_gd_weave_args(command, profile_dir),
@@ -24,18 +30,18 @@ pub fn add_args(command: &mut Command, profile_dir: &Path, mod_loader: &ModLoade
     }
 }
 
-fn add_bepinex_args(command: &mut Command, profile_dir: &Path) -> Result<()> {
-    let (enable_prefix, target_prefix) = doorstop_args(profile_dir)?;
+fn add_bepinex_args(
+    command: &mut Command,
+    profile_dir: &Path,
+    doorstop: &DoorstopSettings,
+) -> Result<()> {
+    let version = doorstop_version(profile_dir)?;
     let preloader_path = bepinex_preloader_path(profile_dir)?;
 
-    command
-        .args([enable_prefix, "true", target_prefix])
-        .arg(preloader_path);
-
-    Ok(())
+    doorstop.apply(command, version, &preloader_path)
 }
 
-fn bepinex_preloader_path(profile_dir: &Path) -> Result<PathBuf> {
+pub fn bepinex_preloader_path(profile_dir: &Path) -> Result<PathBuf> {
     let mut core_dir = profile_dir.to_path_buf();
 
     core_dir.push("BepInEx");
@@ -62,29 +68,50 @@ fn bepinex_preloader_path(profile_dir: &Path) -> Result<PathBuf> {
     Ok(result)
 }
 
-fn doorstop_args(profile_dir: &Path) -> Result<(&'static str, &'static str)> {
+/// Finds the major version of the doorstop installed in the profile.
+///
+/// BepInEx packages usually include a `.doorstop_version` file. If there's
+/// none, the version is guessed from the format of `doorstop_config.ini`,
+/// which changed between version 3 and 4.
+pub(super) fn doorstop_version(profile_dir: &Path) -> Result<u32> {
     let path = profile_dir.join(".doorstop_version");
 
-    let version = if path.exists() {
+    if path.exists() {
         let version = fs::read_to_string(&path)
             .fs_context("reading version file", &path)?
+            .trim()
             .split('.') // read only the major version number
             .next()
             .and_then(|str| str.parse().ok())
             .ok_or_eyre("invalid version format")?;
 
         info!("doorstop version read: {}", version);
-        version
-    } else {
-        warn!(".doorstop_version file is missing, defaulting to 3");
-        3
-    };
-
-    match version {
-        3 => Ok(("--doorstop-enable", "--doorstop-target")),
-        4 => Ok(("--doorstop-enabled", "--doorstop-target-assembly")),
-        vers => bail!("unsupported doorstop version: {}", vers),
+        return Ok(version);
+    }
+
+    let path = profile_dir.join("doorstop_config.ini");
+
+    if let Ok(config) = fs::read_to_string(&path) {
+        let has_key = |key: &str| {
+            config
+                .lines()
+                .filter_map(|line| line.split_once('='))
+                .any(|(name, _)| name.trim() == key)
+        };
+
+        if has_key("target_assembly") {
+            info!("doorstop version detected from config: 4");
+            return Ok(4);
+        }
+
+        if has_key("targetAssembly") {
+            info!("doorstop version detected from config: 3");
+            return Ok(3);
+        }
     }
+
+    warn!("could not determine doorstop version, defaulting to 3");
+    Ok(3)
 }
 
 fn add_melon_loader_args(command: &mut Command, profile_dir: &Path) -> Result<()> {
@@ -152,3 +179,34 @@ fn add_return_of_modding_args(command: &mut Command, profile_dir: &Path) -> Resu
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn doorstop_version_from_config() {
+        let temp = tempfile::tempdir().unwrap();
+        let config = temp.path().join("doorstop_config.ini");
+
+        assert_eq!(doorstop_version(temp.path()).unwrap(), 3);
+
+        fs::write(
+            &config,
+            "[General]\nenabled = true\ntarget_assembly = BepInEx\\core\\BepInEx.Preloader.dll\n",
+        )
+        .unwrap();
+        assert_eq!(doorstop_version(temp.path()).unwrap(), 4);
+
+        fs::write(
+            &config,
+            "[UnityDoorstop]\nenabled=true\ntargetAssembly=BepInEx\\core\\BepInEx.Preloader.dll\n",
+        )
+        .unwrap();
+        assert_eq!(doorstop_version(temp.path()).unwrap(), 3);
+
+        // the version file takes precedence
+        fs::write(temp.path().join(".doorstop_version"), "4.0.0\n").unwrap();
+        assert_eq!(doorstop_version(temp.path()).unwrap(), 4);
+    }
+}
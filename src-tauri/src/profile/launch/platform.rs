@@ -12,7 +12,7 @@ use log::{debug, info};
 use serde::Deserialize;
 
 use crate::{
-    game::{Game, Platform},
+    game::{self, Game, Platform},
     prefs::Prefs,
 };
 
@@ -118,15 +118,40 @@ fn steam_game_dir(game: Game, prefs: &Prefs) -> Result<PathBuf> {
 }
 
 pub fn steam_library_dir(steam_id: u64, prefs: &Prefs) -> Result<PathBuf> {
-    #[derive(Deserialize, Debug)]
-    struct LibraryFolders {
-        libraries: Vec<Library>,
-    }
+    steam_libraries(prefs)?
+        .into_iter()
+        .find(|lib| lib.apps.contains_key(&steam_id))
+        .map(|lib| lib.path)
+        .ok_or_eyre("game is not installed")
+}
 
+/// Finds the supported games that are installed in any Steam library.
+pub fn installed_steam_games(prefs: &Prefs) -> Result<Vec<Game>> {
+    let libraries = steam_libraries(prefs)?;
+
+    let games = game::all()
+        .filter(|game| {
+            game.platforms.steam.as_ref().is_some_and(|steam| {
+                libraries
+                    .iter()
+                    .any(|lib| lib.apps.contains_key(&(steam.id as u64)))
+            })
+        })
+        .collect();
+
+    Ok(games)
+}
+
+#[derive(Deserialize, Debug)]
+struct SteamLibrary {
+    path: PathBuf,
+    apps: HashMap<u64, u64>,
+}
+
+fn steam_libraries(prefs: &Prefs) -> Result<Vec<SteamLibrary>> {
     #[derive(Deserialize, Debug)]
-    struct Library {
-        path: PathBuf,
-        apps: HashMap<u64, u64>,
+    struct LibraryFolders {
+        libraries: Vec<SteamLibrary>,
     }
 
     // we should always base this off the .exe location, since this should have the config folder
@@ -154,12 +179,7 @@ pub fn steam_library_dir(steam_id: u64, prefs: &Prefs) -> Result<PathBuf> {
 
     let folders: LibraryFolders = keyvalues_serde::from_vdf(vdf)?;
 
-    folders
-        .libraries
-        .into_iter()
-        .find(|lib| lib.apps.contains_key(&steam_id))
-        .map(|lib| lib.path)
-        .ok_or_eyre("game is not installed")
+    Ok(folders.libraries)
 }
 
 pub fn default_steam_library_dir(exe_path: Option<&Path>) -> Option<PathBuf> {
@@ -0,0 +1,135 @@
+use std::{fs, path::Path};
+
+use eyre::{bail, Result};
+use log::{info, warn};
+use serde::Serialize;
+
+use super::mod_loader;
+use crate::{
+    game::ModLoaderKind,
+    prefs::Prefs,
+    profile::{install, ManagedGame},
+    util::error::IoResultExt,
+};
+
+/// Describes what was fixed by [`ManagedGame::repair_loader`].
+#[derive(Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LoaderRepair {
+    /// The name of the mod loader package.
+    pub package: String,
+    /// Files that were missing from the profile and reinstalled from the cache.
+    pub profile_files: Vec<String>,
+    /// Files that were missing from the game directory and copied back from the profile.
+    pub game_files: Vec<String>,
+}
+
+impl LoaderRepair {
+    pub fn is_empty(&self) -> bool {
+        self.profile_files.is_empty() && self.game_files.is_empty()
+    }
+}
+
+impl ManagedGame {
+    /// Checks that the mod loader is properly set up in the active profile
+    /// and the game directory, and restores any missing files.
+    ///
+    /// Game updates, especially through Steam, sometimes delete the proxy dll
+    /// (e.g. `winhttp.dll`) and doorstop config from the game directory.
+    pub fn repair_loader(&self, game_dir: &Path, prefs: &Prefs) -> Result<LoaderRepair> {
+        let profile = self.active_profile();
+        let mod_loader = &self.game.mod_loader;
+
+        let Some((loader, enabled)) = profile
+            .mods
+            .iter()
+            .filter_map(|profile_mod| profile_mod.as_thunderstore())
+            .find(|(ts_mod, _)| mod_loader.is_loader_package(ts_mod.ident.full_name()))
+        else {
+            info!("no mod loader package installed, skipping integrity check");
+            return Ok(LoaderRepair::default());
+        };
+
+        let package_name = loader.ident.full_name();
+
+        let mut repair = LoaderRepair {
+            package: package_name.to_owned(),
+            ..Default::default()
+        };
+
+        if !enabled {
+            info!("mod loader package is disabled, skipping integrity check");
+            return Ok(repair);
+        }
+
+        let cache_path = install::cache_path(&loader.ident, prefs);
+        let files = if cache_path.exists() {
+            top_level_files(&cache_path)?
+        } else {
+            warn!(
+                "{} is not in the cache, cannot verify its files",
+                package_name
+            );
+            Vec::new()
+        };
+
+        repair.profile_files = files
+            .iter()
+            .filter(|name| !profile.path.join(name).exists())
+            .cloned()
+            .collect();
+
+        if matches!(mod_loader.kind, ModLoaderKind::BepInEx { .. })
+            && mod_loader::bepinex_preloader_path(&profile.path).is_err()
+        {
+            repair.profile_files.push("BepInEx/core".to_owned());
+        }
+
+        if !repair.profile_files.is_empty() {
+            if !cache_path.exists() {
+                bail!(
+                    "{} is missing files and is not in the cache, please reinstall it",
+                    package_name
+                );
+            }
+
+            info!(
+                "reinstalling {} from cache, missing files: {:?}",
+                package_name, repair.profile_files
+            );
+
+            let mut installer = mod_loader.installer_for(package_name);
+            installer.install(&cache_path, package_name, profile)?;
+        }
+
+        for name in &files {
+            let target = game_dir.join(name);
+            if target.exists() {
+                continue;
+            }
+
+            let source = profile.path.join(name);
+            info!("restoring {} to game directory", name);
+            fs::copy(&source, &target).fs_context("copying loader file", &target)?;
+
+            repair.game_files.push(name.clone());
+        }
+
+        Ok(repair)
+    }
+}
+
+/// Lists the files at the root of an extracted loader package.
+///
+/// These are the files that get copied into the game directory on launch.
+fn top_level_files(path: &Path) -> Result<Vec<String>> {
+    let files = path
+        .read_dir()
+        .fs_context("reading cached package", path)?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_ok_and(|ty| ty.is_file()))
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect();
+
+    Ok(files)
+}
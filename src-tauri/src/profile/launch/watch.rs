@@ -0,0 +1,215 @@
+//! Watches the game after it's launched, to tell the user when it crashes.
+//!
+//! Direct launches keep a handle on the spawned process. Launches through a
+//! launcher like Steam only spawn the launcher, so the game process is found
+//! by the name of its executable instead.
+
+use std::{
+    fs,
+    path::PathBuf,
+    process::Child,
+    thread,
+    time::{Duration, Instant},
+};
+
+use log::{info, warn};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::state::ManagerExt;
+
+/// Games that exit sooner than this after launching are assumed to have crashed.
+const CRASH_THRESHOLD: Duration = Duration::from_secs(30);
+/// How long to wait for a launcher to start the game.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(90);
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+const LOG_TAIL_LINES: usize = 100;
+
+pub enum GameProcess {
+    Child(Child),
+    /// The file name of the game's executable.
+    Named(String),
+}
+
+/// The payload of the `game-crashed` event.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GameCrash {
+    pub profile_id: i64,
+    /// Only known for games launched directly.
+    pub exit_code: Option<i32>,
+    pub duration_secs: f64,
+    /// The last lines of the mod loader's log, if there is one.
+    pub log_tail: Vec<String>,
+}
+
+pub struct Launch {
+    pub id: i64,
+    pub profile_id: i64,
+    pub log_path: Option<PathBuf>,
+}
+
+/// Waits for the game to exit in the background, then records how it went
+/// and emits `game-crashed` if it looks like a crash.
+pub fn spawn(process: GameProcess, launch: Launch, app: AppHandle) {
+    thread::spawn(move || {
+        let (exit_code, duration) = match process {
+            GameProcess::Child(mut child) => {
+                let start = Instant::now();
+
+                match child.wait() {
+                    Ok(status) => (status.code(), start.elapsed()),
+                    Err(err) => {
+                        warn!("failed to wait for game process: {}", err);
+                        return;
+                    }
+                }
+            }
+            GameProcess::Named(name) => match wait_for_named(&name) {
+                Some(duration) => (None, duration),
+                None => {
+                    info!("{} was not found after launching, not watching it", name);
+                    return;
+                }
+            },
+        };
+
+        let crashed = is_crash(exit_code, duration);
+
+        info!(
+            "game exited after {:.1}s with code {:?}",
+            duration.as_secs_f64(),
+            exit_code
+        );
+
+        if let Err(err) = app
+            .db()
+            .finish_launch(launch.id, duration, exit_code, crashed)
+        {
+            warn!("failed to record launch: {:#}", err);
+        }
+
+        if crashed {
+            let payload = GameCrash {
+                profile_id: launch.profile_id,
+                exit_code,
+                duration_secs: duration.as_secs_f64(),
+                log_tail: launch.log_path.map(log_tail).unwrap_or_default(),
+            };
+
+            app.emit("game-crashed", payload).ok();
+        }
+    });
+}
+
+fn is_crash(exit_code: Option<i32>, duration: Duration) -> bool {
+    exit_code.is_some_and(|code| code != 0) || duration < CRASH_THRESHOLD
+}
+
+fn log_tail(path: PathBuf) -> Vec<String> {
+    let Ok(bytes) = fs::read(&path) else {
+        return Vec::new();
+    };
+
+    let text = String::from_utf8_lossy(&bytes);
+    let lines = text.lines().collect::<Vec<_>>();
+
+    lines[lines.len().saturating_sub(LOG_TAIL_LINES)..]
+        .iter()
+        .map(|line| line.to_string())
+        .collect()
+}
+
+/// Waits for a process named `name` to start and then exit, and returns how
+/// long it ran for. The time the launcher takes to start it isn't included.
+///
+/// Returns `None` if it never started, or processes can't be listed.
+fn wait_for_named(name: &str) -> Option<Duration> {
+    let launched = Instant::now();
+
+    loop {
+        match is_running(name) {
+            Some(true) => break,
+            Some(false) if launched.elapsed() < STARTUP_TIMEOUT => thread::sleep(POLL_INTERVAL),
+            _ => return None,
+        }
+    }
+
+    let start = Instant::now();
+
+    while is_running(name).unwrap_or(false) {
+        thread::sleep(POLL_INTERVAL);
+    }
+
+    Some(start.elapsed())
+}
+
+/// Checks if a process with the given executable name is running,
+/// or returns `None` if processes can't be listed on this platform.
+#[cfg(target_os = "linux")]
+pub(super) fn is_running(name: &str) -> Option<bool> {
+    let entries = fs::read_dir("/proc").ok()?;
+
+    // games running through proton show up as wine processes,
+    // with the windows path of the executable in their arguments
+    let matches = |arg: &str| {
+        arg.rsplit(['/', '\\'])
+            .next()
+            .is_some_and(|file_name| file_name.eq_ignore_ascii_case(name))
+    };
+
+    let running = entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_name().to_string_lossy().parse::<u32>().is_ok())
+        .filter_map(|entry| fs::read(entry.path().join("cmdline")).ok())
+        .any(|cmdline| {
+            cmdline
+                .split(|byte| *byte == 0)
+                .any(|arg| matches(&String::from_utf8_lossy(arg)))
+        });
+
+    Some(running)
+}
+
+#[cfg(windows)]
+pub(super) fn is_running(name: &str) -> Option<bool> {
+    use std::{os::windows::process::CommandExt, process::Command};
+
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+    let output = Command::new("tasklist")
+        .args([
+            "/FI",
+            &format!("IMAGENAME eq {}", name),
+            "/NH",
+            "/FO",
+            "CSV",
+        ])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .ok()?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout).to_ascii_lowercase();
+    Some(stdout.contains(&format!("\"{}\"", name.to_ascii_lowercase())))
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+pub(super) fn is_running(_name: &str) -> Option<bool> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quick_or_failed_exits_are_crashes() {
+        let long = CRASH_THRESHOLD * 10;
+
+        assert!(is_crash(Some(1), long));
+        assert!(is_crash(None, Duration::from_secs(3)));
+        assert!(is_crash(Some(0), Duration::from_secs(3)));
+        assert!(!is_crash(Some(0), long));
+        assert!(!is_crash(None, long));
+    }
+}
@@ -8,6 +8,7 @@ use chrono::{DateTime, Utc};
 use export::modpack::ModpackArgs;
 use eyre::{anyhow, ensure, Context, ContextCompat, OptionExt, Result};
 use itertools::Itertools;
+use launch::DoorstopSettings;
 use log::{info, warn};
 use serde::{Deserialize, Serialize};
 use tauri::AppHandle;
@@ -20,7 +21,7 @@ use crate::{
     prefs::Prefs,
     state::ManagerExt,
     thunderstore::{self, BorrowedMod, ModId, Thunderstore, VersionIdent},
-    util::fs::PathExt,
+    util::{cmd::UserError, fs::PathExt},
 };
 
 pub mod commands;
@@ -31,7 +32,30 @@ pub mod launch;
 pub mod update;
 
 mod actions;
+mod bisect;
+mod compat;
+mod dependants;
+mod explain;
+mod game_log;
+mod health;
+pub(crate) mod name;
+mod ops;
+mod overview;
 mod query;
+mod side;
+
+#[cfg(test)]
+pub(crate) mod tests;
+
+pub use bisect::{BisectResult, BisectSession, BisectStatus};
+pub use dependants::DependantCache;
+pub use explain::ModExplanation;
+pub use game_log::LogEntry;
+pub use health::{DependencyCheck, OrphanedDir};
+pub use name::{InvalidNameReason, NameValidation};
+pub use ops::ProfileOps;
+pub use overview::{AppStats, GameOverview};
+pub use side::{ExportTarget, ModSide};
 
 pub fn setup(data: db::SaveData, prefs: &Prefs, db: &Db, app: &AppHandle) -> Result<ModManager> {
     actions::setup(app)?;
@@ -59,6 +83,7 @@ pub struct ManagedGame {
     pub profiles: Vec<Profile>,
     pub favorite: bool,
     pub active_profile_id: i64,
+    pub last_played: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug)]
@@ -72,6 +97,12 @@ pub struct Profile {
     pub config_cache: ConfigCache,
     pub linked_config: HashMap<Uuid, PathBuf>,
     pub modpack: Option<ModpackArgs>,
+    /// Locked profiles can't be modified until they are unlocked.
+    pub locked: bool,
+    /// Whether the user allowed files to be installed into the game directory.
+    pub game_dir_install: bool,
+    pub doorstop: DoorstopSettings,
+    pub dependant_cache: DependantCache,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -84,6 +115,29 @@ pub struct ProfileMod {
 
     #[serde(flatten)]
     pub kind: ProfileModKind,
+
+    /// Overrides the guessed side of the mod, see [`ProfileMod::side`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub side: Option<ModSide>,
+
+    /// How the mod was added to the profile. This is kept when the mod is updated,
+    /// and `None` for mods installed before it was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<InstallOrigin>,
+}
+
+/// How a mod was added to a profile, see [`ProfileMod::origin`].
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum InstallOrigin {
+    /// Installed by the user.
+    Explicit,
+    /// Installed because another mod depends on it.
+    Dependency,
+    /// Installed along with the rest of an imported profile.
+    Import,
+    /// Installed because an updated mod started depending on it.
+    Update,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -110,6 +164,8 @@ impl ProfileMod {
             kind,
             install_time: Utc::now(),
             enabled: true,
+            side: None,
+            origin: None,
         }
     }
 
@@ -202,49 +258,63 @@ impl ProfileModKind {
         &'a self,
         thunderstore: &'a Thunderstore,
     ) -> impl Iterator<Item = BorrowedMod<'a>> {
-        let idents = match self {
-            ProfileModKind::Local(local_mod) => local_mod.dependencies.as_ref(),
+        thunderstore.dependencies(self.direct_dependencies(thunderstore))
+    }
+
+    /// The dependency strings declared by this mod, without resolving them.
+    fn direct_dependencies<'a>(&'a self, thunderstore: &'a Thunderstore) -> &'a [VersionIdent] {
+        match self {
+            ProfileModKind::Local(local_mod) => {
+                local_mod.dependencies.as_deref().unwrap_or_default()
+            }
             ProfileModKind::Thunderstore(ts_mod) => ts_mod
                 .id
                 .borrow(thunderstore)
-                .map(|borrowed| &borrowed.version.dependencies)
-                .ok(),
-        };
-
-        idents
-            .into_iter()
-            .flat_map(|deps| thunderstore.dependencies(deps))
+                .map(|borrowed| borrowed.version.dependencies.as_slice())
+                .unwrap_or_default(),
+        }
     }
 }
 
 impl Profile {
-    fn is_valid_name(name: &str) -> bool {
-        const FORBIDDEN: &[char] = &['\\', '/', ':', '*', '?', '"', '<', '>', '|'];
-
-        !name.is_empty()
-            && !name.chars().all(char::is_whitespace)
-            && name.chars().all(|c| !FORBIDDEN.contains(&c))
+    /// Creates an empty profile, without touching the filesystem.
+    pub(crate) fn new(id: i64, name: String, path: PathBuf, game: Game) -> Self {
+        Self {
+            id,
+            name,
+            path,
+            mods: Vec::new(),
+            game,
+            ignored_updates: HashSet::new(),
+            config_cache: ConfigCache::default(),
+            linked_config: HashMap::new(),
+            modpack: None,
+            locked: false,
+            game_dir_install: false,
+            doorstop: Default::default(),
+            dependant_cache: DependantCache::default(),
+        }
     }
 
     fn index_of(&self, uuid: Uuid) -> Result<usize> {
         self.mods
             .iter()
             .position(|p| p.uuid() == uuid)
-            .ok_or_eyre("mod not found in profile")
+            .ok_or_else(|| UserError::ModNotFound.into())
     }
 
-    fn get_mod(&self, uuid: Uuid) -> Result<&ProfileMod> {
+    pub fn get_mod(&self, uuid: Uuid) -> Result<&ProfileMod> {
         self.mods
             .iter()
             .find(|p| p.uuid() == uuid)
-            .ok_or_eyre("mod not found in profile")
+            .ok_or_else(|| UserError::ModNotFound.into())
     }
 
     fn get_mod_mut(&mut self, uuid: Uuid) -> Result<&mut ProfileMod> {
         self.mods
             .iter_mut()
             .find(|p| p.uuid() == uuid)
-            .ok_or_eyre("mod not found in profile")
+            .ok_or_else(|| UserError::ModNotFound.into())
     }
 
     pub fn has_mod(&self, uuid: Uuid) -> bool {
@@ -259,33 +329,19 @@ impl Profile {
         self.mods.iter().filter_map(ProfileMod::as_local)
     }
 
-    /// Finds all the dependants of a mod in this profile.
-    ///
-    /// This includes both direct and indirect dependencies.
-    fn dependants<'a>(
-        &'a self,
-        uuid: Uuid,
-        thunderstore: &'a Thunderstore,
-    ) -> impl Iterator<Item = &'a ProfileMod> + 'a {
-        self.mods
-            .iter()
-            .filter(move |other| other.uuid() != uuid)
-            .filter(move |other| {
-                other
-                    .dependencies(thunderstore)
-                    .any(|dep| dep.package.uuid == uuid)
-            })
-    }
-
     /// Recursively finds the dependencies of the given mods and filters
     /// out those already installed.
+    ///
+    /// Packages in `exclude` are skipped, see [`Dependencies::exclude`](crate::thunderstore::Dependencies::exclude).
     fn missing_deps<'a>(
         &'a self,
         idents: impl IntoIterator<Item = &'a VersionIdent>,
+        exclude: &HashSet<Uuid>,
         thunderstore: &'a Thunderstore,
     ) -> impl Iterator<Item = BorrowedMod<'a>> + 'a {
         thunderstore
             .dependencies(idents)
+            .exclude(exclude.iter().copied())
             .filter(|dep| !self.has_mod(dep.package.uuid))
     }
 
@@ -299,6 +355,20 @@ impl Profile {
     pub fn save(&self, db: &Db) -> Result<()> {
         db.save_profile(self)
     }
+
+    /// Fails with [`UserError::ProfileLocked`] if the profile is locked.
+    ///
+    /// Call this before anything that changes the profile's mods or config.
+    pub fn ensure_unlocked(&self) -> Result<()> {
+        ensure!(
+            !self.locked,
+            UserError::ProfileLocked {
+                name: self.name.clone()
+            }
+        );
+
+        Ok(())
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
@@ -366,13 +436,20 @@ impl ManagedGame {
             .ok_or_else(|| anyhow!("profile index {} is out of bounds", index))
     }
 
-    fn find_profile(&self, id: i64) -> Result<&Profile> {
+    pub(crate) fn find_profile(&self, id: i64) -> Result<&Profile> {
         self.profiles
             .iter()
             .find(|profile| profile.id == id)
             .with_context(|| format!("profile with id {} not found", id))
     }
 
+    fn find_profile_mut(&mut self, id: i64) -> Result<&mut Profile> {
+        self.profiles
+            .iter_mut()
+            .find(|profile| profile.id == id)
+            .with_context(|| format!("profile with id {} not found", id))
+    }
+
     fn active_profile(&self) -> &Profile {
         self.find_profile(self.active_profile_id).unwrap()
     }
@@ -423,24 +500,31 @@ impl ModManager {
             manager,
             games,
             profiles,
+            ..
         } = data;
 
         let path = prefs.data_dir.to_path_buf();
 
         let mut games = games
             .into_iter()
-            .map(|saved_game| {
-                let game = game::from_slug(&saved_game.slug).unwrap();
+            .filter_map(|saved_game| {
+                // the rows are kept, so they come back if the slug is remapped
+                let Some(game) = game::from_slug(&saved_game.slug) else {
+                    warn!("skipping unknown game {}", saved_game.slug);
+                    return None;
+                };
+
                 let managed_game = ManagedGame {
                     id: saved_game.id,
                     game,
                     profiles: Vec::new(),
                     favorite: saved_game.favorite,
                     active_profile_id: saved_game.active_profile_id,
+                    last_played: saved_game.last_played,
                     path: path.join(&*game.slug),
                 };
 
-                (game, managed_game)
+                Some((game, managed_game))
             })
             .collect::<HashMap<_, _>>();
 
@@ -459,7 +543,13 @@ impl ModManager {
                 continue;
             }
 
-            let game = game::from_slug(&saved_profile.game_slug).unwrap();
+            let Some(game) = game::from_slug(&saved_profile.game_slug) else {
+                warn!(
+                    "skipping profile {} of unknown game {}",
+                    saved_profile.name, saved_profile.game_slug
+                );
+                continue;
+            };
 
             let profile = Profile {
                 path,
@@ -471,6 +561,10 @@ impl ModManager {
                 ignored_updates: saved_profile.ignored_updates.unwrap_or_default(),
                 config_cache: ConfigCache::default(),
                 linked_config: HashMap::new(),
+                locked: saved_profile.locked,
+                game_dir_install: saved_profile.game_dir_install,
+                doorstop: saved_profile.doorstop.unwrap_or_default(),
+                dependant_cache: DependantCache::default(),
             };
 
             games.get_mut(game).unwrap().profiles.push(profile);
@@ -483,12 +577,51 @@ impl ModManager {
 
         let mut manager = Self { games, active_game };
 
+        // moves directories left over from an older version or naming
+        for game in manager.games.values_mut() {
+            game.apply_dir_naming(db);
+        }
+
         manager.ensure_game(manager.active_game, prefs, db)?;
         manager.save_all(db)?;
 
         Ok(manager)
     }
 
+    /// Checks the invariants that the rest of the app relies on, namely that
+    /// the active game and each game's active profile exist.
+    ///
+    /// Used to decide if the manager can still be used after a panic.
+    pub fn validate(&self) -> Result<()> {
+        let active_game = self
+            .games
+            .get(&self.active_game)
+            .ok_or_eyre("active game is not managed")?;
+
+        ensure!(
+            active_game
+                .profiles
+                .iter()
+                .any(|profile| profile.id == active_game.active_profile_id),
+            "active profile of {} is missing",
+            self.active_game.slug
+        );
+
+        for game in self.games.values() {
+            ensure!(
+                game.profiles.is_empty()
+                    || game
+                        .profiles
+                        .iter()
+                        .any(|profile| profile.id == game.active_profile_id),
+                "active profile of {} is missing",
+                game.game.slug
+            );
+        }
+
+        Ok(())
+    }
+
     pub fn active_mod_loader(&self) -> &'static ModLoader<'static> {
         &self.active_game.mod_loader
     }
@@ -514,12 +647,12 @@ impl ModManager {
     }
 
     pub fn set_active_game(&mut self, game: Game, app: &AppHandle) -> Result<()> {
-        self.ensure_game(game, &app.lock_prefs(), app.db())?;
+        self.ensure_game(game, &*app.lock_prefs()?, app.db())?;
 
         if self.active_game != game {
             self.active_game = game;
 
-            let mut thunderstore = app.lock_thunderstore();
+            let mut thunderstore = app.lock_thunderstore()?;
             thunderstore.switch_game(game, app.clone());
         }
 
@@ -538,7 +671,13 @@ impl ModManager {
             info!("managing new game: {}", game.slug);
 
             let path = prefs.data_dir.join(&*game.slug);
-            let id = self.games.values().map(|game| game.id).max().unwrap_or(0) + 1;
+            let id = self
+                .games
+                .values()
+                .map(|game| game.id + 1)
+                .max()
+                .unwrap_or(1)
+                .max(db.next_game_id()?);
 
             let managed_game = ManagedGame {
                 id,
@@ -547,6 +686,7 @@ impl ModManager {
                 profiles: Vec::new(),
                 favorite: false,
                 active_profile_id: 0,
+                last_played: None,
             };
 
             self.games.insert(game, managed_game);
@@ -561,6 +701,10 @@ impl ModManager {
                 .create_profile(DEFAULT_PROFILE_NAME.to_owned(), None, db)
                 .context("failed to create default profile")?;
 
+            if let Err(err) = default_profile.apply_game_defaults() {
+                warn!("failed to apply default config: {:#}", err);
+            }
+
             managed.active_profile_id = default_profile.id;
         } else if managed.find_profile(managed.active_profile_id).is_err() {
             warn!("active profile was out of bounds, adjusting...");
@@ -0,0 +1,413 @@
+//! Rules for profile names, and how the directories of profiles are named.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+
+use super::ManagedGame;
+use crate::util::cmd::UserError;
+
+/// Long names quickly push paths of nested mod files over
+/// the 260 character limit on Windows.
+const MAX_LEN: usize = 64;
+
+const ILLEGAL_CHARS: &[char] = &['\\', '/', ':', '*', '?', '"', '<', '>', '|'];
+
+/// Device names which Windows doesn't allow as file names, even with an extension.
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+const FALLBACK_NAME: &str = "Imported profile";
+
+/// Why a profile name was rejected.
+#[derive(thiserror::Error, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum InvalidNameReason {
+    #[error("the name is empty")]
+    Empty,
+    #[error("the name is longer than {MAX_LEN} characters")]
+    TooLong,
+    #[error("the name is reserved by the system")]
+    Reserved,
+    #[error("the name contains characters that are not allowed in file names")]
+    IllegalChar,
+    #[error("the name ends with a dot or space")]
+    TrailingDotOrSpace,
+    #[error("a profile with the same name already exists")]
+    Duplicate,
+    #[error("a profile with the same name in different casing already exists")]
+    DuplicateCase,
+}
+
+/// Feedback on a profile name, see [`ManagedGame::validate_profile_name`].
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct NameValidation {
+    /// Why the name can't be used, or `None` if it can.
+    pub reason: Option<InvalidNameReason>,
+    pub message: Option<String>,
+    /// A similar name which can be used instead.
+    pub suggestion: Option<String>,
+}
+
+fn is_illegal(c: char) -> bool {
+    c.is_control() || ILLEGAL_CHARS.contains(&c)
+}
+
+fn is_reserved(name: &str) -> bool {
+    let stem = name.split('.').next().unwrap_or(name).trim_end();
+
+    RESERVED_NAMES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(stem))
+}
+
+/// Checks a name against the rules of every platform, so profiles can be
+/// shared and synced between them.
+pub fn check(name: &str) -> Result<(), InvalidNameReason> {
+    if name.trim().is_empty() {
+        Err(InvalidNameReason::Empty)
+    } else if name.chars().count() > MAX_LEN {
+        Err(InvalidNameReason::TooLong)
+    } else if name.chars().any(is_illegal) {
+        Err(InvalidNameReason::IllegalChar)
+    } else if name.ends_with(['.', ' ']) {
+        Err(InvalidNameReason::TrailingDotOrSpace)
+    } else if is_reserved(name) {
+        Err(InvalidNameReason::Reserved)
+    } else {
+        Ok(())
+    }
+}
+
+/// Fails with [`UserError::InvalidProfileName`] if the name breaks one of the rules in [`check`].
+pub fn ensure_valid(name: &str) -> Result<()> {
+    check(name).map_err(|reason| {
+        UserError::InvalidProfileName {
+            name: name.to_owned(),
+            reason,
+        }
+        .into()
+    })
+}
+
+/// Turns a name from elsewhere into a valid one, for example
+/// when importing a profile from another mod manager.
+pub fn sanitize(name: &str) -> String {
+    let mut name = name
+        .chars()
+        .map(|c| if is_illegal(c) { '_' } else { c })
+        .take(MAX_LEN)
+        .collect::<String>();
+
+    if is_reserved(&name) {
+        name.insert(0, '_');
+        // truncate by characters, since the name may contain multibyte characters
+        name = name.chars().take(MAX_LEN).collect();
+    }
+
+    name.truncate(name.trim_end_matches(['.', ' ']).len());
+
+    match name.trim().is_empty() {
+        true => FALLBACK_NAME.to_owned(),
+        false => name,
+    }
+}
+
+/// How the directories of profiles are named.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum DirNaming {
+    /// Name directories after the id of the profile, so renaming
+    /// a profile doesn't touch its files.
+    #[default]
+    Id,
+    /// Name directories after the profile, which is easier to find by hand.
+    Name,
+}
+
+static NAME_DIRS: AtomicBool = AtomicBool::new(false);
+
+/// Sets how new profile directories are named, see [`Prefs::profile_dir_naming`].
+///
+/// Existing directories are moved by [`ManagedGame::apply_dir_naming`].
+///
+/// [`Prefs::profile_dir_naming`]: crate::prefs::Prefs::profile_dir_naming
+pub fn set_dir_naming(naming: DirNaming) {
+    NAME_DIRS.store(naming == DirNaming::Name, Ordering::Relaxed);
+}
+
+pub fn dir_naming() -> DirNaming {
+    match NAME_DIRS.load(Ordering::Relaxed) {
+        true => DirNaming::Name,
+        false => DirNaming::Id,
+    }
+}
+
+/// The name of the directory of a profile, according to [`dir_naming`].
+pub fn dir_name(id: i64, name: &str) -> String {
+    match dir_naming() {
+        DirNaming::Id => id.to_string(),
+        DirNaming::Name => name.to_owned(),
+    }
+}
+
+/// Numbered variants of `name`, like `name (2)`, which fit within [`MAX_LEN`].
+fn numbered(name: &str) -> impl Iterator<Item = String> {
+    let name = name.to_owned();
+
+    (2..).map(move |i| {
+        let suffix = format!(" ({})", i);
+        let base = name
+            .chars()
+            .take(MAX_LEN - suffix.len())
+            .collect::<String>();
+        format!("{}{}", base, suffix)
+    })
+}
+
+impl ManagedGame {
+    /// Like [`check`], but also rejects names taken by other profiles of this game.
+    ///
+    /// `except` is the id of the profile being renamed, if any.
+    fn check_name(&self, name: &str, except: Option<i64>) -> Result<(), InvalidNameReason> {
+        check(name)?;
+
+        for profile in self.profiles.iter().filter(|p| Some(p.id) != except) {
+            if profile.name == name {
+                return Err(InvalidNameReason::Duplicate);
+            }
+
+            // profiles with names like this would share a directory
+            // on case-insensitive file systems
+            if profile.name.to_lowercase() == name.to_lowercase() {
+                return Err(InvalidNameReason::DuplicateCase);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that `name` is valid and not taken by another profile of this game.
+    ///
+    /// `except` is the id of the profile being renamed, if any.
+    pub fn check_profile_name(&self, name: &str, except: Option<i64>) -> Result<()> {
+        self.check_name(name, except).map_err(|reason| {
+            UserError::InvalidProfileName {
+                name: name.to_owned(),
+                reason,
+            }
+            .into()
+        })
+    }
+
+    /// Explains why `name` can't be used for a profile, if it can't, and suggests
+    /// a similar name that can. Unlike [`ManagedGame::check_profile_name`], this
+    /// doesn't fail, so it can be called as the user types.
+    pub fn validate_profile_name(&self, name: &str, except: Option<i64>) -> NameValidation {
+        let reason = match self.check_name(name, except) {
+            Ok(()) => {
+                return NameValidation {
+                    reason: None,
+                    message: None,
+                    suggestion: None,
+                }
+            }
+            Err(reason) => reason,
+        };
+
+        // there's nothing to base a suggestion on
+        let suggestion = (reason != InvalidNameReason::Empty).then(|| {
+            let name = sanitize(name);
+            let is_free = |candidate: &str| self.check_name(candidate, except).is_ok();
+
+            match is_free(&name) {
+                true => name,
+                false => numbered(&name)
+                    .find(|candidate| is_free(candidate))
+                    .expect("there should be a free name"),
+            }
+        });
+
+        NameValidation {
+            reason: Some(reason),
+            message: Some(reason.to_string()),
+            suggestion,
+        }
+    }
+
+    /// Sanitizes an imported profile name with [`sanitize`].
+    ///
+    /// A name which only differs in case from an existing profile is
+    /// given a number, while exact matches are kept, since importing
+    /// replaces the existing profile in that case.
+    pub fn sanitize_profile_name(&self, name: &str) -> String {
+        let name = sanitize(name);
+
+        let conflicts = |candidate: &str| {
+            self.profiles.iter().any(|profile| {
+                profile.name != candidate && profile.name.to_lowercase() == candidate.to_lowercase()
+            })
+        };
+
+        if !conflicts(&name) {
+            return name;
+        }
+
+        numbered(&name)
+            .find(|candidate| !conflicts(candidate) && self.profile_index(candidate).is_none())
+            .expect("there should be a free name")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+    };
+
+    use super::*;
+    use crate::{
+        db::Db,
+        game,
+        profile::{tests::test_profile, Profile},
+    };
+
+    fn named_profile(id: i64, name: &str, path: PathBuf) -> Profile {
+        Profile {
+            id,
+            name: name.to_owned(),
+            ..test_profile(path)
+        }
+    }
+
+    fn test_game(path: &Path, profiles: Vec<Profile>) -> ManagedGame {
+        ManagedGame {
+            id: 1,
+            game: game::from_slug("lethal-company").unwrap(),
+            path: path.to_path_buf(),
+            active_profile_id: profiles[0].id,
+            profiles,
+            favorite: false,
+            last_played: None,
+        }
+    }
+
+    #[test]
+    fn platform_rules_are_enforced() {
+        assert_eq!(check("My profile"), Ok(()));
+        assert_eq!(check("Console"), Ok(()));
+        assert_eq!(check(""), Err(InvalidNameReason::Empty));
+        assert_eq!(check("   "), Err(InvalidNameReason::Empty));
+        assert_eq!(check("CON"), Err(InvalidNameReason::Reserved));
+        assert_eq!(check("aux.txt"), Err(InvalidNameReason::Reserved));
+        assert_eq!(check("lpt1"), Err(InvalidNameReason::Reserved));
+        assert_eq!(check("a/b"), Err(InvalidNameReason::IllegalChar));
+        assert_eq!(check("tab\there"), Err(InvalidNameReason::IllegalChar));
+        assert_eq!(check("name."), Err(InvalidNameReason::TrailingDotOrSpace));
+        assert_eq!(check("name "), Err(InvalidNameReason::TrailingDotOrSpace));
+        assert_eq!(
+            check(&"a".repeat(MAX_LEN + 1)),
+            Err(InvalidNameReason::TooLong)
+        );
+    }
+
+    #[test]
+    fn sanitized_names_are_valid() {
+        for name in [
+            "CON",
+            "nul.",
+            "Modpack: Deluxe?",
+            "trailing. . ",
+            "...",
+            "",
+            &"ü".repeat(MAX_LEN * 2),
+        ] {
+            let sanitized = sanitize(name);
+            assert_eq!(check(&sanitized), Ok(()), "{:?} -> {:?}", name, sanitized);
+        }
+
+        assert_eq!(sanitize("Modpack: Deluxe?"), "Modpack_ Deluxe_");
+        assert_eq!(sanitize("CON"), "_CON");
+        assert_eq!(sanitize("..."), FALLBACK_NAME);
+    }
+
+    #[test]
+    fn validation_explains_and_suggests() {
+        let game = test_game(
+            Path::new("game"),
+            vec![
+                named_profile(1, "Default", PathBuf::from("1")),
+                named_profile(2, "Modded", PathBuf::from("2")),
+                named_profile(3, "Modded (2)", PathBuf::from("3")),
+            ],
+        );
+
+        let valid = game.validate_profile_name("Vanilla", None);
+        assert_eq!(valid.reason, None);
+        assert_eq!(valid.suggestion, None);
+
+        let duplicate = game.validate_profile_name("Modded", None);
+        assert_eq!(duplicate.reason, Some(InvalidNameReason::Duplicate));
+        assert_eq!(duplicate.suggestion.as_deref(), Some("Modded (3)"));
+
+        let case = game.validate_profile_name("default", None);
+        assert_eq!(case.reason, Some(InvalidNameReason::DuplicateCase));
+        assert_eq!(case.suggestion.as_deref(), Some("default (2)"));
+
+        // renaming a profile to its own name is fine
+        assert_eq!(game.validate_profile_name("Modded", Some(2)).reason, None);
+
+        let illegal = game.validate_profile_name("Modded?", None);
+        assert_eq!(illegal.reason, Some(InvalidNameReason::IllegalChar));
+        assert_eq!(illegal.suggestion.as_deref(), Some("Modded_"));
+
+        let empty = game.validate_profile_name(" ", None);
+        assert_eq!(empty.reason, Some(InvalidNameReason::Empty));
+        assert_eq!(empty.suggestion, None);
+    }
+
+    #[test]
+    fn name_dirs_are_moved_to_id_dirs() {
+        let root = tempfile::tempdir().unwrap();
+        let db = Db::in_memory();
+
+        let profiles_dir = root.path().join("profiles");
+        let old_path = profiles_dir.join("My profile");
+        fs::create_dir_all(&old_path).unwrap();
+        fs::write(old_path.join("file.txt"), "").unwrap();
+
+        // profiles at a custom location stay where they are
+        let custom_path = root.path().join("elsewhere");
+        fs::create_dir_all(&custom_path).unwrap();
+
+        let mut managed_game = test_game(
+            root.path(),
+            vec![
+                named_profile(3, "My profile", old_path.clone()),
+                named_profile(4, "Custom", custom_path.clone()),
+            ],
+        );
+
+        assert_eq!(dir_naming(), DirNaming::Id);
+        managed_game.apply_dir_naming(&db);
+
+        let new_path = profiles_dir.join("3");
+        assert_eq!(managed_game.profiles[0].path, new_path);
+        assert!(new_path.join("file.txt").exists());
+        assert!(!old_path.exists());
+        assert_eq!(managed_game.profiles[1].path, custom_path);
+
+        let (data, ..) = db.read().unwrap();
+        let saved = data
+            .profiles
+            .iter()
+            .find(|profile| profile.id == 3)
+            .unwrap();
+        assert_eq!(saved.path, new_path.to_string_lossy());
+    }
+}
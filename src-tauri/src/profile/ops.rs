@@ -0,0 +1,251 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use eyre::Result;
+use log::debug;
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+
+use crate::util::cmd::UserError;
+
+/// Serializes operations that modify a profile's mods, such as installs,
+/// updates, imports and bulk removals.
+///
+/// The lock for a profile is held for the whole operation, not just while
+/// the [`ModManager`](super::ModManager) is locked, so two operations
+/// can't interleave and, for example, install the same mod twice.
+#[derive(Default)]
+pub struct ProfileOps {
+    locks: Mutex<HashMap<i64, Arc<OpLock>>>,
+}
+
+#[derive(Default)]
+struct OpLock {
+    mutex: Arc<AsyncMutex<()>>,
+    /// A description of the operation currently holding the lock.
+    current: Mutex<Option<&'static str>>,
+}
+
+impl OpLock {
+    fn set_current(&self, operation: Option<&'static str>) {
+        *self.current.lock().unwrap_or_else(PoisonError::into_inner) = operation;
+    }
+
+    fn current(&self) -> Option<&'static str> {
+        *self.current.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Releases the profile when dropped.
+pub struct OpGuard {
+    lock: Arc<OpLock>,
+    _guard: OwnedMutexGuard<()>,
+}
+
+impl Drop for OpGuard {
+    fn drop(&mut self) {
+        self.lock.set_current(None);
+    }
+}
+
+impl ProfileOps {
+    fn get(&self, profile_id: i64) -> Arc<OpLock> {
+        self.locks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(profile_id)
+            .or_default()
+            .clone()
+    }
+
+    /// Waits until no other operation is running on the profile.
+    ///
+    /// Waiters are let through in the order they arrived.
+    pub async fn acquire(&self, profile_id: i64, operation: &'static str) -> OpGuard {
+        let lock = self.get(profile_id);
+
+        if let Some(current) = lock.current() {
+            debug!(
+                "waiting for profile {} to finish {} before {}",
+                profile_id, current, operation
+            );
+        }
+
+        let guard = lock.mutex.clone().lock_owned().await;
+        lock.set_current(Some(operation));
+
+        OpGuard {
+            lock,
+            _guard: guard,
+        }
+    }
+
+    /// Like [`ProfileOps::acquire`], but fails immediately with
+    /// [`UserError::ProfileBusy`] if the profile is in use.
+    pub fn try_acquire(&self, profile_id: i64, operation: &'static str) -> Result<OpGuard> {
+        let lock = self.get(profile_id);
+
+        let Ok(guard) = lock.mutex.clone().try_lock_owned() else {
+            let current = lock.current().unwrap_or("another operation");
+            return Err(UserError::ProfileBusy {
+                operation: current.to_owned(),
+            }
+            .into());
+        };
+
+        lock.set_current(Some(operation));
+
+        Ok(OpGuard {
+            lock,
+            _guard: guard,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path, time::Duration};
+
+    use itertools::Itertools;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::profile::{
+        install::tests::install_into, tests::test_profile, Profile, ProfileMod, ProfileModKind,
+    };
+
+    fn busy_with(err: eyre::Report) -> Option<String> {
+        match err.downcast::<UserError>() {
+            Ok(UserError::ProfileBusy { operation }) => Some(operation),
+            _ => None,
+        }
+    }
+
+    /// Installs `package_name` with an id of `id`, since the test mods are local.
+    fn install(profile: &mut Profile, extract_dir: &Path, package_name: &str, id: u128) {
+        install_into(profile, extract_dir, package_name);
+
+        if let ProfileModKind::Local(local_mod) = &mut profile.mods.last_mut().unwrap().kind {
+            local_mod.uuid = Uuid::from_u128(id);
+        }
+    }
+
+    #[tokio::test]
+    async fn overlapping_operations_do_not_interleave() {
+        const PACKAGES: [&str; 3] = ["Test-A", "Test-B", "Test-C"];
+
+        let root = tempfile::tempdir().unwrap();
+        let extract_dir = Arc::new(root.path().join("extract"));
+        let profile_dir = root.path().join("profile");
+        fs::create_dir_all(&profile_dir).unwrap();
+
+        let ops = Arc::new(ProfileOps::default());
+        let profile = Arc::new(Mutex::new(test_profile(profile_dir)));
+
+        // an earlier operation holds the profile while the installs queue up
+        let guard = ops.acquire(1, "importing a profile").await;
+
+        let installs = (0..12)
+            .map(|i| {
+                let ops = ops.clone();
+                let profile = profile.clone();
+                let extract_dir = extract_dir.clone();
+                let package_name = PACKAGES[i % PACKAGES.len()];
+
+                tokio::spawn(async move {
+                    let _guard = ops.acquire(1, "installing mods").await;
+
+                    // check for the mod, then install it after an await point,
+                    // like the installer does around downloads
+                    let installed = profile
+                        .lock()
+                        .unwrap()
+                        .mods
+                        .iter()
+                        .any(|profile_mod| profile_mod.full_name() == package_name);
+                    tokio::time::sleep(Duration::from_millis(1)).await;
+
+                    if !installed {
+                        let extract_dir = extract_dir.join(i.to_string());
+                        let mut profile = profile.lock().unwrap();
+                        install(&mut profile, &extract_dir, package_name, i as u128 + 1);
+                    }
+                })
+            })
+            .collect_vec();
+
+        assert_eq!(
+            busy_with(ops.try_acquire(1, "removing mods").err().unwrap()).as_deref(),
+            Some("importing a profile")
+        );
+
+        drop(guard);
+        for task in installs {
+            task.await.unwrap();
+        }
+
+        let uuids = {
+            let profile = profile.lock().unwrap();
+
+            assert_eq!(profile.mods.len(), PACKAGES.len());
+            assert!(profile
+                .mods
+                .iter()
+                .map(|profile_mod| profile_mod.full_name())
+                .all_unique());
+
+            profile.mods.iter().map(ProfileMod::uuid).collect_vec()
+        };
+
+        // a removal can't start in the middle of an install either
+        let guard = ops.acquire(1, "installing mods").await;
+
+        let removal = {
+            let ops = ops.clone();
+            let profile = profile.clone();
+
+            tokio::spawn(async move {
+                let _guard = ops.acquire(1, "removing mods").await;
+
+                profile
+                    .lock()
+                    .unwrap()
+                    .try_remove_mods(&uuids, false, |_| ())
+                    .unwrap()
+            })
+        };
+
+        assert_eq!(
+            busy_with(ops.try_acquire(1, "removing mods").err().unwrap()).as_deref(),
+            Some("installing mods")
+        );
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(profile.lock().unwrap().mods.len(), PACKAGES.len());
+
+        drop(guard);
+        let report = removal.await.unwrap();
+
+        assert_eq!(report.succeeded.len(), PACKAGES.len());
+        assert!(profile.lock().unwrap().mods.is_empty());
+    }
+
+    #[tokio::test]
+    async fn try_acquire_fails_while_busy() {
+        let ops = ProfileOps::default();
+
+        let guard = ops.acquire(1, "updating mods").await;
+
+        assert_eq!(
+            busy_with(ops.try_acquire(1, "removing mods").err().unwrap()).as_deref(),
+            Some("updating mods")
+        );
+
+        // other profiles are unaffected
+        assert!(ops.try_acquire(2, "removing mods").is_ok());
+
+        drop(guard);
+        assert!(ops.try_acquire(1, "removing mods").is_ok());
+    }
+}
@@ -0,0 +1,193 @@
+use std::{collections::HashSet, path::PathBuf};
+
+use chrono::{DateTime, Utc};
+use eyre::Result;
+use log::{debug, warn};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use super::{install, ManagedGame};
+use crate::{
+    game::Game,
+    state::ManagerExt,
+    thunderstore::{self, Thunderstore},
+    util,
+};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GameOverview {
+    pub slug: &'static str,
+    pub profile_count: usize,
+    pub mod_count: usize,
+    pub favorite: bool,
+    pub last_played: Option<DateTime<Utc>>,
+    /// The number of available updates across all profiles.
+    ///
+    /// This is `None` if the game's mods haven't been loaded yet,
+    /// in which case it's sent later with a `game_update_count` event.
+    pub update_count: Option<usize>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct UpdateCount {
+    slug: &'static str,
+    update_count: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppStats {
+    pub game_count: usize,
+    pub favorite_count: usize,
+    pub profile_count: usize,
+    /// The number of distinct mods, counting mods in several profiles once.
+    pub unique_mod_count: usize,
+    /// The combined size of every profile directory. Files linked from
+    /// the cache are counted in full for each profile.
+    pub installed_size: u64,
+    pub cache_size: u64,
+}
+
+/// Summarizes every managed game.
+///
+/// Update counts are only included for the active game if its mods
+/// have been fetched. The rest are counted in the background from
+/// each game's package cache.
+pub fn get(app: &AppHandle) -> Result<Vec<GameOverview>> {
+    let manager = app.lock_manager()?;
+    let thunderstore = app.lock_thunderstore()?;
+
+    let mut pending = Vec::new();
+
+    let overview = manager
+        .games
+        .values()
+        .map(|managed_game| {
+            let update_count =
+                if managed_game.game == manager.active_game && thunderstore.packages_fetched() {
+                    Some(count_updates(managed_game, &thunderstore))
+                } else {
+                    pending.push((managed_game.game, managed_game.path.clone()));
+                    None
+                };
+
+            GameOverview {
+                slug: &managed_game.game.slug,
+                profile_count: managed_game.profiles.len(),
+                mod_count: managed_game
+                    .profiles
+                    .iter()
+                    .map(|profile| profile.mods.len())
+                    .sum(),
+                favorite: managed_game.favorite,
+                last_played: managed_game.last_played,
+                update_count,
+            }
+        })
+        .collect();
+
+    if !pending.is_empty() {
+        let app = app.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            for (game, path) in pending {
+                if let Err(err) = emit_cached_update_count(game, path, &app) {
+                    warn!("failed to count updates for {}: {:#}", game.slug, err);
+                }
+            }
+        });
+    }
+
+    Ok(overview)
+}
+
+fn count_updates(managed_game: &ManagedGame, thunderstore: &Thunderstore) -> usize {
+    managed_game
+        .profiles
+        .iter()
+        .map(|profile| profile.count_updates(thunderstore))
+        .sum()
+}
+
+fn emit_cached_update_count(game: Game, path: PathBuf, app: &AppHandle) -> Result<()> {
+    let Some(packages) = thunderstore::read_cache_at(&path)? else {
+        debug!("no package cache for {}, skipping update count", game.slug);
+        return Ok(());
+    };
+
+    let thunderstore = Thunderstore::from_packages(packages);
+
+    let update_count = {
+        let manager = app.lock_manager()?;
+        let Some(managed_game) = manager.games.get(game) else {
+            return Ok(());
+        };
+
+        count_updates(managed_game, &thunderstore)
+    };
+
+    app.emit(
+        "game_update_count",
+        UpdateCount {
+            slug: &game.slug,
+            update_count,
+        },
+    )?;
+
+    Ok(())
+}
+
+/// Aggregates numbers across every game and profile.
+pub async fn stats(app: &AppHandle) -> Result<AppStats> {
+    let (mut stats, profile_paths) = {
+        let manager = app.lock_manager()?;
+
+        let profiles = manager
+            .games
+            .values()
+            .flat_map(|game| &game.profiles)
+            .collect::<Vec<_>>();
+
+        let unique_mod_count = profiles
+            .iter()
+            .flat_map(|profile| &profile.mods)
+            .map(|profile_mod| profile_mod.full_name())
+            .collect::<HashSet<_>>()
+            .len();
+
+        let stats = AppStats {
+            game_count: manager.games.len(),
+            favorite_count: manager.games.values().filter(|game| game.favorite).count(),
+            profile_count: profiles.len(),
+            unique_mod_count,
+            installed_size: 0,
+            cache_size: 0,
+        };
+
+        let paths = profiles
+            .iter()
+            .map(|profile| profile.path.clone())
+            .collect::<Vec<_>>();
+
+        (stats, paths)
+    };
+
+    let app = app.clone();
+    let (installed_size, cache_size) = tauri::async_runtime::spawn_blocking(move || {
+        let installed_size = profile_paths
+            .iter()
+            .map(util::fs::get_directory_size)
+            .sum::<u64>();
+
+        let cache_size = install::cache_size(&*app.lock_prefs()?, app.db())?;
+
+        Ok::<_, eyre::Report>((installed_size, cache_size))
+    })
+    .await??;
+
+    stats.installed_size = installed_size;
+    stats.cache_size = cache_size;
+
+    Ok(stats)
+}
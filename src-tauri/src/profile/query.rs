@@ -4,11 +4,14 @@ use chrono::{DateTime, Utc};
 use eyre::Result;
 use log::warn;
 
-use super::{Dependant, LocalMod, Profile, ProfileMod, ProfileModKind};
-use crate::thunderstore::{
-    self,
-    query::{QueryModsArgs, Queryable, SortBy, SortOrder},
-    BorrowedMod, FrontendProfileMod, IntoFrontendMod, Thunderstore,
+use super::{Dependant, LocalMod, Profile, ProfileMod, ProfileModKind, ThunderstoreMod};
+use crate::{
+    thunderstore::{
+        self,
+        query::{QueryModsArgs, Queryable, SortBy, SortOrder},
+        BorrowedMod, FrontendMod, FrontendProfileMod, IntoFrontendMod, ModMetadata, Thunderstore,
+    },
+    util,
 };
 
 struct QueryableProfileMod<'a> {
@@ -21,6 +24,8 @@ struct QueryableProfileMod<'a> {
 enum QueryableProfileModKind<'a> {
     Local(&'a LocalMod),
     Thunderstore(BorrowedMod<'a>),
+    /// A Thunderstore mod which isn't in the index yet, shown with its cached metadata.
+    Cached(&'a ThunderstoreMod, &'a ModMetadata),
 }
 
 impl<'a> QueryableProfileMod<'a> {
@@ -31,10 +36,18 @@ impl<'a> QueryableProfileMod<'a> {
     ) -> Result<QueryableProfileMod<'a>> {
         let kind = match &profile_mod.kind {
             ProfileModKind::Local(local) => QueryableProfileModKind::Local(local),
-            ProfileModKind::Thunderstore(ts_mod) => {
-                let borrow = ts_mod.id.borrow(thunderstore)?;
-                QueryableProfileModKind::Thunderstore(borrow)
-            }
+            ProfileModKind::Thunderstore(ts_mod) => match ts_mod.id.borrow(thunderstore) {
+                Ok(borrow) => QueryableProfileModKind::Thunderstore(borrow),
+                // once the index is fetched, a missing package is really gone
+                Err(err) if thunderstore.packages_fetched() => return Err(err),
+                Err(err) => {
+                    let metadata = thunderstore
+                        .cached_metadata(ts_mod.id.package_uuid)
+                        .ok_or(err)?;
+
+                    QueryableProfileModKind::Cached(ts_mod, metadata)
+                }
+            },
         };
 
         Ok(QueryableProfileMod {
@@ -53,6 +66,7 @@ impl Queryable for QueryableProfileMod<'_> {
         match &self.kind {
             Kind::Local(local) => &local.name,
             Kind::Thunderstore(remote) => remote.package.ident.as_str(),
+            Kind::Cached(ts_mod, _) => ts_mod.ident.full_name(),
         }
     }
 
@@ -70,6 +84,13 @@ impl Queryable for QueryableProfileMod<'_> {
         match &self.kind {
             Kind::Local(local) => local.matches(args),
             Kind::Thunderstore(remote) => remote.matches(args),
+            Kind::Cached(_, metadata) => {
+                let has_category = |category: &String| metadata.categories.contains(category);
+
+                (args.include_categories.is_empty()
+                    || args.include_categories.iter().any(has_category))
+                    && !args.exclude_categories.iter().any(has_category)
+            }
         }
     }
 
@@ -94,11 +115,37 @@ impl Queryable for QueryableProfileMod<'_> {
             (Kind::Local(a), Kind::Local(b)) => a.cmp(b, args),
             (Kind::Local(_), _) => Ordering::Less,
             (_, Kind::Local(_)) => Ordering::Greater,
+            // only the name is known for cached mods
+            (Kind::Cached(..), _) | (_, Kind::Cached(..)) => {
+                let order = match args.sort_by {
+                    SortBy::Name => util::cmp_ignore_case(
+                        self.full_name()
+                            .split_once('-')
+                            .map_or("", |(_, name)| name),
+                        other
+                            .full_name()
+                            .split_once('-')
+                            .map_or("", |(_, name)| name),
+                    ),
+                    SortBy::Author => util::cmp_ignore_case(self.full_name(), other.full_name()),
+                    _ => Ordering::Equal,
+                };
+
+                match args.sort_order {
+                    SortOrder::Ascending => order,
+                    SortOrder::Descending => order.reverse(),
+                }
+            }
         }
     }
 }
 
 impl Profile {
+    /// Sorts and filters the profile's mods, along with the mods which
+    /// couldn't be found.
+    ///
+    /// Until the index is fetched, mods that aren't in it are shown with
+    /// their cached metadata.
     pub(super) fn query_mods(
         &self,
         args: &QueryModsArgs,
@@ -132,12 +179,21 @@ impl Profile {
                     QueryableProfileModKind::Thunderstore(remote) => {
                         (remote.into_frontend(Some(self)), remote.package.uuid)
                     }
+                    QueryableProfileModKind::Cached(ts_mod, metadata) => (
+                        cached_frontend_mod(ts_mod, metadata),
+                        ts_mod.id.package_uuid,
+                    ),
                 };
 
+                let profile_mod = &self.mods[queryable.index];
+
                 FrontendProfileMod {
                     data,
+                    side: profile_mod.side(self.game, thunderstore),
+                    side_overridden: profile_mod.side.is_some(),
                     enabled: queryable.enabled,
                     config_file: self.linked_config.get(&uuid).cloned(),
+                    has_config: self.linked_config.contains_key(&uuid),
                 }
             })
             .collect();
@@ -146,6 +202,20 @@ impl Profile {
     }
 }
 
+fn cached_frontend_mod(ts_mod: &ThunderstoreMod, metadata: &ModMetadata) -> FrontendMod {
+    FrontendMod {
+        name: ts_mod.ident.name().to_owned(),
+        description: Some(metadata.description.clone()),
+        categories: Some(metadata.categories.clone()),
+        version: ts_mod.ident.version().parse().ok(),
+        author: Some(metadata.author.clone()),
+        uuid: ts_mod.id.package_uuid,
+        version_uuid: ts_mod.id.version_uuid,
+        is_installed: true,
+        ..Default::default()
+    }
+}
+
 impl Queryable for LocalMod {
     fn full_name(&self) -> &str {
         &self.name
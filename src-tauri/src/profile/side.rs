@@ -0,0 +1,161 @@
+//! Whether mods are needed on clients, dedicated servers or both.
+//!
+//! Unless the user overrides it, a mod's side comes from the bundled
+//! `mod_sides.json`, which maps game slugs to package full names:
+//!
+//! ```json
+//! { "valheim": { "Author-MapTweaks": "client" } }
+//! ```
+//!
+//! Packages without a rule fall back to their Thunderstore categories.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::OnceLock,
+};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{Profile, ProfileMod, ProfileModKind};
+use crate::{
+    game::Game,
+    thunderstore::{PackageListing, Thunderstore},
+};
+
+const BUNDLED_JSON: &str = include_str!("../../mod_sides.json");
+
+type RuleFile = HashMap<String, HashMap<String, ModSide>>;
+
+fn bundled() -> &'static RuleFile {
+    static BUNDLED: OnceLock<RuleFile> = OnceLock::new();
+
+    BUNDLED.get_or_init(|| serde_json::from_str(BUNDLED_JSON).unwrap())
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum ModSide {
+    /// Only needed by players, for example custom sounds or UI tweaks.
+    Client,
+    /// Only needed on a dedicated server.
+    Server,
+    #[default]
+    Both,
+}
+
+/// Who an export is meant for.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ExportTarget {
+    Client,
+    Server,
+}
+
+impl ModSide {
+    /// Guesses the side of a package, see the [module docs](self).
+    pub fn guess(package: &PackageListing, game: Game) -> Self {
+        if let Some(side) = bundled()
+            .get(&*game.slug)
+            .and_then(|rules| rules.get(package.full_name()))
+        {
+            return *side;
+        }
+
+        match (
+            package.has_category("Client-side"),
+            package.has_category("Server-side"),
+        ) {
+            (true, false) => ModSide::Client,
+            (false, true) => ModSide::Server,
+            _ => ModSide::Both,
+        }
+    }
+
+    /// Whether a mod on this side belongs in an export for `target`.
+    pub fn is_needed_on(self, target: ExportTarget) -> bool {
+        !matches!(
+            (self, target),
+            (ModSide::Client, ExportTarget::Server) | (ModSide::Server, ExportTarget::Client)
+        )
+    }
+}
+
+impl ProfileMod {
+    /// The side set by the user, or else a guess.
+    ///
+    /// Local mods and packages missing from Thunderstore count as [`ModSide::Both`].
+    pub fn side(&self, game: Game, thunderstore: &Thunderstore) -> ModSide {
+        if let Some(side) = self.side {
+            return side;
+        }
+
+        match &self.kind {
+            ProfileModKind::Thunderstore(ts_mod) => thunderstore
+                .get_package(ts_mod.id.package_uuid)
+                .map(|package| ModSide::guess(package, game))
+                .unwrap_or_default(),
+            ProfileModKind::Local(_) => ModSide::Both,
+        }
+    }
+}
+
+impl Profile {
+    /// The mods that belong in an export for `target`.
+    ///
+    /// Without a target, every mod does. Otherwise mods that aren't needed on
+    /// that side are left out, unless a mod that's kept depends on them.
+    pub fn exported_mods(
+        &self,
+        target: Option<ExportTarget>,
+        thunderstore: &Thunderstore,
+    ) -> HashSet<Uuid> {
+        let Some(target) = target else {
+            return self.mods.iter().map(ProfileMod::uuid).collect();
+        };
+
+        let kept = self
+            .mods
+            .iter()
+            .filter(|profile_mod| {
+                profile_mod
+                    .side(self.game, thunderstore)
+                    .is_needed_on(target)
+            })
+            .collect::<Vec<_>>();
+
+        let mut exported = kept
+            .iter()
+            .map(|profile_mod| profile_mod.uuid())
+            .collect::<HashSet<_>>();
+
+        // dependencies are recursive already
+        for profile_mod in kept {
+            exported.extend(
+                profile_mod
+                    .dependencies(thunderstore)
+                    .map(|dep| dep.package.uuid)
+                    .filter(|uuid| self.has_mod(*uuid)),
+            );
+        }
+
+        exported
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sides_match_targets() {
+        use ExportTarget::*;
+
+        assert!(ModSide::Both.is_needed_on(Client));
+        assert!(ModSide::Both.is_needed_on(Server));
+        assert!(ModSide::Client.is_needed_on(Client));
+        assert!(!ModSide::Client.is_needed_on(Server));
+        assert!(ModSide::Server.is_needed_on(Server));
+        assert!(!ModSide::Server.is_needed_on(Client));
+    }
+}
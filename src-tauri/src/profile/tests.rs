@@ -0,0 +1,21 @@
+use std::path::PathBuf;
+
+use super::*;
+use crate::thunderstore::{ModId, PackageListing};
+
+pub(crate) fn test_profile(path: PathBuf) -> Profile {
+    let game = game::from_slug("lethal-company").unwrap();
+
+    Profile::new(1, "Test".to_owned(), path, game)
+}
+
+/// A profile mod of the latest version of `package`.
+pub(crate) fn test_mod(package: &PackageListing) -> ProfileMod {
+    ProfileMod::new(ProfileModKind::Thunderstore(ThunderstoreMod {
+        ident: package.latest().ident.clone(),
+        id: ModId {
+            package_uuid: package.uuid,
+            version_uuid: package.latest().uuid,
+        },
+    }))
+}
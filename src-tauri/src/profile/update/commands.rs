@@ -1,29 +1,64 @@
 use tauri::{command, AppHandle};
 use uuid::Uuid;
 
+use super::{DependencyChanges, SafeUpdate, UpdateSummary, UpdateVerification};
 use crate::{state::ManagerExt, thunderstore::ModId, util::cmd::Result};
 
+/// Changes the version of a mod in a profile of the active game, the active profile by default.
 #[command]
-pub async fn change_mod_version(mod_ref: ModId, app: AppHandle) -> Result<()> {
-    super::change_version(mod_ref, &app).await?;
+pub async fn change_mod_version(
+    mod_ref: ModId,
+    profile_id: Option<i64>,
+    app: AppHandle,
+) -> Result<DependencyChanges> {
+    let changes = super::change_version(mod_ref, profile_id, &app).await?;
 
-    Ok(())
+    Ok(changes)
 }
 
+/// Updates mods in a profile of the active game, the active profile by default.
 #[command]
-pub async fn update_mods(uuids: Vec<Uuid>, respect_ignored: bool, app: AppHandle) -> Result<()> {
-    super::update_mods(uuids, respect_ignored, &app).await?;
+pub async fn update_mods(
+    uuids: Vec<Uuid>,
+    respect_ignored: bool,
+    profile_id: Option<i64>,
+    app: AppHandle,
+) -> Result<UpdateVerification> {
+    let verification = super::update_mods(uuids, respect_ignored, profile_id, &app).await?;
 
-    Ok(())
+    Ok(verification)
+}
+
+/// Updates mods with minor and patch updates, returning the skipped major updates.
+#[command]
+pub async fn update_safe(app: AppHandle) -> Result<SafeUpdate> {
+    let result = super::update_safe(&app).await?;
+
+    Ok(result)
 }
 
 #[command]
 pub fn ignore_update(version_uuid: Uuid, app: AppHandle) -> Result<()> {
-    let mut manager = app.lock_manager();
+    let mut manager = app.lock_manager()?;
 
     let profile = manager.active_profile_mut();
+    profile.ensure_unlocked()?;
     profile.ignored_updates.insert(version_uuid);
     profile.save(app.db())?;
 
     Ok(())
 }
+
+/// Lists the outdated mods of a profile in the active game.
+#[command]
+pub fn get_update_summary(profile_id: i64, app: AppHandle) -> Result<UpdateSummary> {
+    let manager = app.lock_manager()?;
+    let thunderstore = app.lock_thunderstore()?;
+
+    let summary = manager
+        .active_game()
+        .find_profile(profile_id)?
+        .update_summary(&thunderstore);
+
+    Ok(summary)
+}
@@ -1,13 +1,20 @@
+use std::collections::HashSet;
+
 use chrono::{DateTime, Utc};
 use eyre::Context;
 use itertools::Itertools;
+use serde::Serialize;
 use uuid::Uuid;
 
-use super::install::{InstallOptions, ModInstall};
+use super::{
+    actions::RemovalCandidate,
+    install::{InstallOptions, ModInstall},
+};
 use crate::{
-    profile::{install, Profile, Result},
+    profile::{install, DependencyCheck, InstallOrigin, ModSide, Profile, Result},
     state::ManagerExt,
-    thunderstore::{ModId, PackageListing, PackageVersion, Thunderstore},
+    thunderstore::{ModId, PackageListing, PackageVersion, Thunderstore, VersionIdent},
+    webhook::{self, WebhookEvent},
 };
 
 pub mod commands;
@@ -16,6 +23,8 @@ pub struct AvailableUpdate<'a> {
     pub enabled: bool,
     pub index: usize,
     pub install_time: DateTime<Utc>,
+    pub side: Option<ModSide>,
+    pub origin: Option<InstallOrigin>,
     pub package: &'a PackageListing,
     pub current: &'a PackageVersion,
     pub latest: &'a PackageVersion,
@@ -32,10 +41,23 @@ impl From<AvailableUpdate<'_>> for ModInstall {
             .with_state(value.enabled)
             .with_index(value.index)
             .with_time(value.install_time)
+            .with_side(value.side)
+            .with_origin(value.origin)
     }
 }
 
 impl Profile {
+    /// Counts the mods with available updates, excluding ignored ones.
+    pub fn count_updates(&self, thunderstore: &Thunderstore) -> usize {
+        self.mods
+            .iter()
+            .filter(|profile_mod| {
+                self.check_update(profile_mod.uuid(), true, thunderstore)
+                    .is_ok_and(|update| update.is_some())
+            })
+            .count()
+    }
+
     pub fn check_update<'a>(
         &'a self,
         uuid: Uuid,
@@ -63,7 +85,7 @@ impl Profile {
             return Ok(None);
         }
 
-        if respect_ignored && self.ignored_updates.contains(&uuid) {
+        if respect_ignored && self.is_update_ignored(package) {
             return Ok(None);
         }
 
@@ -74,41 +96,178 @@ impl Profile {
             latest: package.latest(),
             enabled: profile_mod.enabled,
             install_time: profile_mod.install_time,
+            side: profile_mod.side,
+            origin: profile_mod.origin,
         }))
     }
+
+    /// Whether the update to the latest version of `package` is ignored.
+    ///
+    /// The frontend ignores specific versions, so a newer one shows up again,
+    /// while older data may have ignored the whole package.
+    fn is_update_ignored(&self, package: &PackageListing) -> bool {
+        self.ignored_updates.contains(&package.uuid)
+            || self.ignored_updates.contains(&package.latest().uuid)
+    }
+
+    /// Lists the mods with available updates, including ignored ones.
+    pub fn update_summary(&self, thunderstore: &Thunderstore) -> UpdateSummary {
+        let mods = self
+            .mods
+            .iter()
+            .filter_map(|profile_mod| {
+                self.check_update(profile_mod.uuid(), false, thunderstore)
+                    .ok()
+                    .flatten()
+            })
+            .map(|update| OutdatedMod {
+                full_name: update.latest.ident.clone(),
+                package_uuid: update.package.uuid,
+                version_uuid: update.latest.uuid,
+                current: update.current.parsed_version(),
+                latest: update.latest.parsed_version(),
+                ignored: self.is_update_ignored(update.package),
+                download_size: update.latest.file_size,
+            })
+            .collect_vec();
+
+        let (ignored, available): (Vec<_>, Vec<_>) = mods.iter().partition(|update| update.ignored);
+
+        UpdateSummary {
+            profile_id: self.id,
+            available: available.len(),
+            ignored: ignored.len(),
+            download_size: available.iter().map(|update| update.download_size).sum(),
+            mods,
+        }
+    }
 }
 
-pub async fn change_version(mod_ref: ModId, app: &tauri::AppHandle) -> Result<()> {
-    let install = {
-        let manager = app.lock_manager();
+/// The outdated mods of a profile, see [`Profile::update_summary`].
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateSummary {
+    pub profile_id: i64,
+    pub mods: Vec<OutdatedMod>,
+    /// The number of updates which aren't ignored.
+    pub available: usize,
+    pub ignored: usize,
+    /// The total size of the updates which aren't ignored, in bytes.
+    pub download_size: u64,
+}
 
-        let profile = manager.active_profile();
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OutdatedMod {
+    /// The latest version of the mod.
+    pub full_name: VersionIdent,
+    pub package_uuid: Uuid,
+    pub version_uuid: Uuid,
+    pub current: semver::Version,
+    pub latest: semver::Version,
+    pub ignored: bool,
+    /// The size of the latest version, in bytes.
+    pub download_size: u64,
+}
+
+/// How a mod's dependencies changed after [`change_version`].
+#[derive(Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyChanges {
+    /// Dependencies of the new version which weren't installed before.
+    pub installed: Vec<VersionIdent>,
+    /// Dependencies of the old version which nothing in the profile uses anymore.
+    ///
+    /// These are left installed for the user to remove.
+    pub unused: Vec<RemovalCandidate>,
+}
+
+/// Changes the version of a mod, installing any new dependencies it has.
+///
+/// Changing to the installed version reinstalls it.
+pub async fn change_version(
+    mod_ref: ModId,
+    profile_id: Option<i64>,
+    app: &tauri::AppHandle,
+) -> Result<DependencyChanges> {
+    let (install, installed, old_deps, profile_id) = {
+        let manager = app.lock_manager()?;
+        let thunderstore = app.lock_thunderstore()?;
+
+        let game = manager.active_game();
+        let profile = match profile_id {
+            Some(id) => game.find_profile(id)?,
+            None => game.active_profile(),
+        };
 
         let index = profile.index_of(mod_ref.package_uuid)?;
-        let enabled = profile.mods[index].enabled;
-        let install_time = profile.mods[index].install_time;
+        let profile_mod = &profile.mods[index];
 
-        ModInstall::new(mod_ref)
-            .with_state(enabled)
+        let old_deps = profile_mod
+            .dependencies(&thunderstore)
+            .map(|dep| dep.package.uuid)
+            .collect::<HashSet<_>>();
+
+        let installed = profile
+            .missing_deps(
+                mod_ref.borrow(&thunderstore)?.dependencies(),
+                &HashSet::new(),
+                &thunderstore,
+            )
+            .map(|dep| dep.ident().clone())
+            .collect_vec();
+
+        let install = ModInstall::new(mod_ref)
+            .with_state(profile_mod.enabled)
             .with_index(index)
-            .with_time(install_time)
+            .with_time(profile_mod.install_time)
+            .with_side(profile_mod.side)
+            .with_origin(profile_mod.origin);
+
+        (install, installed, old_deps, profile.id)
     };
 
-    _update_mods(vec![install], app).await
+    _update_mods(vec![install], profile_id, app).await?;
+
+    let manager = app.lock_manager()?;
+    let thunderstore = app.lock_thunderstore()?;
+
+    let profile = manager.active_game().find_profile(profile_id)?;
+
+    // the new version counts as a dependant, so anything it still uses is kept
+    let unused = old_deps
+        .into_iter()
+        .filter_map(|uuid| profile.get_mod(uuid).ok())
+        .filter(|dep| {
+            profile
+                .dependants(dep.uuid(), &thunderstore)
+                .next()
+                .is_none()
+        })
+        .map(|dep| RemovalCandidate::new(dep, &thunderstore))
+        .collect();
+
+    Ok(DependencyChanges { installed, unused })
 }
 
+/// Updates mods in a profile of the active game, or the active profile if `profile_id` is `None`.
 pub async fn update_mods(
     uuids: Vec<Uuid>,
     respect_ignored: bool,
+    profile_id: Option<i64>,
     app: &tauri::AppHandle,
-) -> Result<()> {
-    let installs = {
-        let mut manager = app.lock_manager();
-        let thunderstore = app.lock_thunderstore();
+) -> Result<UpdateVerification> {
+    let (installs, profile_id) = {
+        let manager = app.lock_manager()?;
+        let thunderstore = app.lock_thunderstore()?;
 
-        let profile = manager.active_profile_mut();
+        let game = manager.active_game();
+        let profile = match profile_id {
+            Some(id) => game.find_profile(id)?,
+            None => game.active_profile(),
+        };
 
-        uuids
+        let installs = uuids
             .into_iter()
             .filter_map(|uuid| {
                 profile
@@ -116,30 +275,281 @@ pub async fn update_mods(
                     .transpose()
             })
             .map_ok(|update| update.into())
-            .collect::<Result<Vec<ModInstall>>>()?
+            .collect::<Result<Vec<ModInstall>>>()?;
+
+        (installs, profile.id)
     };
 
-    _update_mods(installs, app).await
+    update_and_verify(installs, profile_id, app).await
 }
 
-async fn _update_mods(installs: Vec<ModInstall>, app: &tauri::AppHandle) -> Result<()> {
-    install::install_with_deps(
-        installs,
-        InstallOptions::default().before_install(Box::new(|install, manager, _| {
-            // remove the old version
-            let profile = manager.active_profile_mut();
+/// Installs the updates, then checks the profile's dependencies.
+///
+/// Sends an update event to the webhook if anything changed.
+async fn update_and_verify(
+    installs: Vec<ModInstall>,
+    profile_id: i64,
+    app: &tauri::AppHandle,
+) -> Result<UpdateVerification> {
+    let updated = if installs.is_empty() {
+        Vec::new()
+    } else {
+        _update_mods(installs, profile_id, app).await?
+    };
 
-            // check since it could be a new dependency being installed, not an update itself
-            if profile.has_mod(install.uuid()) {
-                profile
-                    .force_remove_mod(install.uuid())
-                    .context("failed to remove existing version")?;
+    let verification = verify_dependencies(profile_id, app).await?;
+
+    webhook::dispatch(
+        WebhookEvent::Update {
+            updated,
+            installed_dependencies: verification.installed.clone(),
+        },
+        profile_id,
+        app,
+    );
+
+    Ok(verification)
+}
+
+/// The result of checking the profile's dependencies after an update.
+#[derive(Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateVerification {
+    /// Missing dependencies that were installed afterwards.
+    pub installed: Vec<VersionIdent>,
+    /// Issues that are left, such as disabled or unresolved dependencies.
+    pub remaining: DependencyCheck,
+}
+
+/// Checks that every enabled mod in the profile still has its dependencies,
+/// installing the ones that are missing.
+///
+/// Disabled dependencies are only reported, since the user may have disabled them on purpose.
+async fn verify_dependencies(
+    profile_id: i64,
+    app: &tauri::AppHandle,
+) -> Result<UpdateVerification> {
+    let installs = {
+        let manager = app.lock_manager()?;
+        let thunderstore = app.lock_thunderstore()?;
+
+        let check = manager
+            .active_game()
+            .find_profile(profile_id)?
+            .find_dependency_issues(&thunderstore);
+
+        check
+            .missing
+            .iter()
+            .filter_map(|issue| thunderstore.find_ident(&issue.dependency.ident).ok())
+            .map(|dep| ModInstall::from(dep).with_origin(Some(InstallOrigin::Update)))
+            .collect_vec()
+    };
+
+    let mut installed = Vec::new();
+
+    if !installs.is_empty() {
+        installed = install::install_with_deps(
+            installs,
+            InstallOptions::default()
+                .target_profile(profile_id)
+                .confirm_flagged(false),
+            true,
+            &HashSet::new(),
+            app,
+        )
+        .await
+        .context("failed to install missing dependencies")?;
+    }
+
+    let manager = app.lock_manager()?;
+    let thunderstore = app.lock_thunderstore()?;
+
+    let remaining = manager
+        .active_game()
+        .find_profile(profile_id)?
+        .find_dependency_issues(&thunderstore);
+
+    Ok(UpdateVerification {
+        installed,
+        remaining,
+    })
+}
+
+/// The result of [`update_safe`].
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SafeUpdate {
+    pub skipped: Vec<SkippedUpdate>,
+    pub verification: UpdateVerification,
+}
+
+/// An update which [`update_safe`] left out, since it may break the mod.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedUpdate {
+    pub full_name: VersionIdent,
+    pub package_uuid: Uuid,
+    pub current: semver::Version,
+    pub latest: semver::Version,
+}
+
+/// Whether going from `current` to `latest` is a major version bump.
+///
+/// Unlike Cargo, `0.x` minor bumps are not counted as breaking,
+/// since many mods never leave `0.x`.
+fn is_major_bump(current: &semver::Version, latest: &semver::Version) -> bool {
+    latest.major > current.major
+}
+
+/// Updates every mod in the active profile with a minor or patch update,
+/// returning the major updates that were skipped.
+///
+/// Ignored updates are skipped entirely.
+pub async fn update_safe(app: &tauri::AppHandle) -> Result<SafeUpdate> {
+    let (installs, skipped, profile_id) = {
+        let manager = app.lock_manager()?;
+        let thunderstore = app.lock_thunderstore()?;
+
+        let profile = manager.active_profile();
+
+        let mut installs = Vec::new();
+        let mut skipped = Vec::new();
+
+        for profile_mod in &profile.mods {
+            let Some(update) = profile.check_update(profile_mod.uuid(), true, &thunderstore)?
+            else {
+                continue;
+            };
+
+            let current = update.current.parsed_version();
+            let latest = update.latest.parsed_version();
+
+            if is_major_bump(&current, &latest) {
+                skipped.push(SkippedUpdate {
+                    full_name: update.latest.ident.clone(),
+                    package_uuid: update.package.uuid,
+                    current,
+                    latest,
+                });
+            } else {
+                installs.push(update.into());
             }
+        }
 
-            Ok(())
-        })),
+        (installs, skipped, profile.id)
+    };
+
+    Ok(SafeUpdate {
+        skipped,
+        verification: update_and_verify(installs, profile_id, app).await?,
+    })
+}
+
+async fn _update_mods(
+    installs: Vec<ModInstall>,
+    profile_id: i64,
+    app: &tauri::AppHandle,
+) -> Result<Vec<VersionIdent>> {
+    install::install_with_deps(
+        installs,
+        InstallOptions::default()
+            .target_profile(profile_id)
+            // the mods are already installed, don't block updating them
+            .confirm_flagged(false)
+            .dependency_origin(InstallOrigin::Update)
+            .before_install(Box::new(|install, profile, _| {
+                // remove the old version, checking since it could be
+                // a new dependency being installed, not an update itself
+                if profile.has_mod(install.uuid()) {
+                    profile
+                        .force_remove_mod(install.uuid())
+                        .context("failed to remove existing version")?;
+                }
+
+                Ok(())
+            })),
         true,
+        &HashSet::new(),
         app,
     )
     .await
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::{
+        profile::{tests::test_profile, ProfileMod, ProfileModKind, ThunderstoreMod},
+        thunderstore::tests::{test_package, test_thunderstore},
+    };
+
+    /// A package with a `2.0.0` update, and a mod of the outdated version.
+    fn outdated(name: &str, file_size: u64) -> (PackageListing, ProfileMod) {
+        let mut package = test_package(name, &[]);
+        let installed = package.versions[0].clone();
+
+        let mut latest = installed.clone();
+        latest.ident = VersionIdent::new("Test", name, "2.0.0");
+        latest.uuid = Uuid::new_v4();
+        latest.file_size = file_size;
+
+        // thunderstore lists the newest version first
+        package.versions.insert(0, latest);
+
+        let profile_mod = ProfileMod::new(ProfileModKind::Thunderstore(ThunderstoreMod {
+            ident: installed.ident.clone(),
+            id: ModId {
+                package_uuid: package.uuid,
+                version_uuid: installed.uuid,
+            },
+        }));
+
+        (package, profile_mod)
+    }
+
+    fn bump(current: &str, latest: &str) -> bool {
+        is_major_bump(&current.parse().unwrap(), &latest.parse().unwrap())
+    }
+
+    #[test]
+    fn only_major_bumps_are_breaking() {
+        assert!(bump("1.4.2", "2.0.0"));
+        assert!(bump("0.9.0", "1.0.0"));
+
+        assert!(!bump("1.4.2", "1.5.0"));
+        assert!(!bump("1.4.2", "1.4.3"));
+        assert!(!bump("0.1.0", "0.2.0"));
+        assert!(!bump("1.0.0", "1.0.1-beta"));
+    }
+
+    #[test]
+    fn summary_counts_ignored_versions() {
+        let (a, a_mod) = outdated("A", 100);
+        let (b, b_mod) = outdated("B", 50);
+
+        let ignored_version = b.latest().uuid;
+        let thunderstore = test_thunderstore([a, b]);
+
+        let profile = Profile {
+            mods: vec![a_mod, b_mod],
+            ignored_updates: HashSet::from([ignored_version]),
+            ..test_profile(PathBuf::new())
+        };
+
+        let summary = profile.update_summary(&thunderstore);
+
+        assert_eq!(summary.mods.len(), 2);
+        assert_eq!(summary.available, 1);
+        assert_eq!(summary.ignored, 1);
+        assert_eq!(summary.download_size, 100);
+        assert!(summary
+            .mods
+            .iter()
+            .all(|update| update.ignored == (update.version_uuid == ignored_version)));
+
+        assert_eq!(profile.count_updates(&thunderstore), 1);
+    }
+}
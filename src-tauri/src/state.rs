@@ -1,67 +1,276 @@
-use std::sync::{atomic::AtomicBool, Mutex, MutexGuard};
+use std::{
+    fmt,
+    ops::{Deref, DerefMut},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, MutexGuard, PoisonError, RwLock,
+    },
+};
 
-use eyre::{Context, Result};
+use eyre::{bail, Context, Result};
+use log::{error, warn};
 use tauri::{command, AppHandle, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+use tokio::sync::Notify;
 
 use crate::{
+    autosave,
     db::{self, Db},
+    logger,
     prefs::Prefs,
-    profile::{self, ModManager},
+    profile::{self, ModManager, ProfileOps},
     thunderstore::{self, Thunderstore},
 };
 
 pub struct AppState {
-    pub http: reqwest::Client,
-    prefs: Mutex<Prefs>,
-    manager: Mutex<ModManager>,
-    thunderstore: Mutex<Thunderstore>,
+    http: RwLock<reqwest::Client>,
+    prefs: StateMutex<Prefs>,
+    manager: StateMutex<ModManager>,
+    thunderstore: StateMutex<Thunderstore>,
     pub db: Db,
+    pub profile_ops: ProfileOps,
     pub cancel_install_flag: AtomicBool,
     pub is_first_run: bool,
 }
 
 impl AppState {
-    pub fn lock_prefs(&self) -> MutexGuard<'_, Prefs> {
-        self.prefs.lock().unwrap()
+    /// Gets the http client. This is cheap, since clients are reference counted.
+    pub fn http(&self) -> reqwest::Client {
+        self.http
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Replaces the http client with one using the current timeout prefs.
+    ///
+    /// Requests that are already in flight keep using the old client.
+    pub fn rebuild_http(&self, prefs: &Prefs) -> Result<()> {
+        *self.http.write().unwrap_or_else(PoisonError::into_inner) = build_http(prefs)?;
+        Ok(())
+    }
+
+    pub fn lock_prefs(&self) -> Result<StateGuard<'_, Prefs>> {
+        self.prefs.lock()
     }
 
-    pub fn lock_manager(&self) -> MutexGuard<'_, ModManager> {
-        self.manager.lock().unwrap()
+    pub fn lock_manager(&self) -> Result<StateGuard<'_, ModManager>> {
+        self.manager.lock()
     }
 
-    pub fn lock_thunderstore(&self) -> MutexGuard<'_, Thunderstore> {
-        self.thunderstore.lock().unwrap()
+    pub fn lock_thunderstore(&self) -> Result<StateGuard<'_, Thunderstore>> {
+        self.thunderstore.lock()
+    }
+
+    /// Saves the prefs and manager if they've changed since they were last saved,
+    /// returning whether anything was saved. See [`crate::autosave`].
+    pub fn save_dirty(&self) -> Result<bool> {
+        let prefs = self.prefs.lock()?;
+        let manager = self.manager.lock()?;
+
+        let prefs_dirty = self.prefs.is_dirty();
+        let manager_dirty = self.manager.is_dirty();
+
+        // the flags are only cleared once saved, so a failed save is retried
+        if prefs_dirty {
+            prefs.save(&self.db).context("failed to save prefs")?;
+            self.prefs.clear_dirty();
+        }
+
+        if manager_dirty {
+            manager
+                .save_all(&self.db)
+                .context("failed to save profiles")?;
+            self.manager.clear_dirty();
+        }
+
+        Ok(prefs_dirty || manager_dirty)
     }
 }
 
-pub fn setup(app: &AppHandle) -> Result<()> {
-    let http = reqwest::Client::builder()
+/// A mutex that can recover from being poisoned.
+///
+/// When a thread panics while holding the lock, the data is checked with
+/// `validate`. If it's still valid, the poison is cleared and the app keeps
+/// running as usual. Otherwise, every following lock returns an error
+/// asking the user to restart, instead of panicking as well.
+///
+/// A [`tracked`](StateMutex::tracked) mutex is marked dirty whenever its
+/// value is borrowed mutably, so the change is picked up by [`autosave`].
+pub struct StateMutex<T> {
+    inner: Mutex<T>,
+    name: &'static str,
+    validate: fn(&T) -> Result<()>,
+    dirty: AtomicBool,
+    on_dirty: Option<Arc<Notify>>,
+}
+
+impl<T> StateMutex<T> {
+    pub fn new(value: T, name: &'static str, validate: fn(&T) -> Result<()>) -> Self {
+        Self {
+            inner: Mutex::new(value),
+            name,
+            validate,
+            dirty: AtomicBool::new(false),
+            on_dirty: None,
+        }
+    }
+
+    /// Wakes `notify` the first time the value is changed after [`StateMutex::clear_dirty`].
+    pub fn tracked(mut self, notify: Arc<Notify>) -> Self {
+        self.on_dirty = Some(notify);
+        self
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty.load(Ordering::Acquire)
+    }
+
+    /// Call this while holding the lock, after the value has been saved.
+    pub fn clear_dirty(&self) {
+        self.dirty.store(false, Ordering::Release);
+    }
+
+    fn mark_dirty(&self) {
+        if let Some(notify) = &self.on_dirty {
+            if !self.dirty.swap(true, Ordering::AcqRel) {
+                notify.notify_one();
+            }
+        }
+    }
+
+    pub fn lock(&self) -> Result<StateGuard<'_, T>> {
+        let err = match self.inner.lock() {
+            Ok(guard) => return Ok(StateGuard { guard, mutex: self }),
+            Err(err) => err,
+        };
+
+        let guard = err.into_inner();
+
+        if let Err(err) = (self.validate)(&guard) {
+            error!("{} is invalid after a panic: {:#}", self.name, err);
+            bail!(
+                "an internal error left the {} in an invalid state, please restart Gale",
+                self.name
+            );
+        }
+
+        warn!(
+            "recovering {} after a panic: {}",
+            self.name,
+            logger::last_panic().as_deref().unwrap_or("unknown panic")
+        );
+
+        self.inner.clear_poison();
+
+        Ok(StateGuard { guard, mutex: self })
+    }
+}
+
+/// A lock on a [`StateMutex`], which marks it dirty when borrowed mutably.
+pub struct StateGuard<'a, T> {
+    guard: MutexGuard<'a, T>,
+    mutex: &'a StateMutex<T>,
+}
+
+impl<T> StateGuard<'_, T> {
+    /// Borrows the value mutably without marking it dirty,
+    /// for changes that aren't saved, like refreshing a cache.
+    pub fn untracked(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for StateGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.guard.fmt(f)
+    }
+}
+
+impl<T> Deref for StateGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T> DerefMut for StateGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.mutex.mark_dirty();
+        &mut self.guard
+    }
+}
+
+fn build_http(prefs: &Prefs) -> Result<reqwest::Client> {
+    let mut builder = reqwest::Client::builder()
         .user_agent("Kesomannen-gale")
-        .build()
-        .context("failed to init http client")?;
+        .connect_timeout(prefs.connect_timeout())
+        // not a total timeout, since that would also cut off large downloads
+        // and uploads on slow connections, which only need to keep receiving data
+        .read_timeout(prefs.request_timeout());
+
+    // by default the protocol is negotiated with ALPN, which picks
+    // HTTP/2 for Thunderstore and its CDN
+    if prefs.force_http1() {
+        builder = builder.http1_only();
+    }
 
+    builder.build().context("failed to init http client")
+}
+
+fn warn_quarantined(names: &[String], app: &AppHandle) {
+    app.dialog()
+        .message(format!(
+            "The following profiles could not be loaded because their data is corrupt: {}.\n\n\
+            Their files have been left untouched, and a copy of the data was kept in the database.",
+            names.join(", ")
+        ))
+        .title("Corrupt profiles")
+        .kind(MessageDialogKind::Warning)
+        .show(|_| ());
+}
+
+pub fn setup(app: &AppHandle) -> Result<()> {
     let (db, db_existed) = db::init().context("failed to init database")?;
 
     let (data, mut prefs, migrated) = db.read()?;
 
+    if !data.quarantined_profiles.is_empty() {
+        warn_quarantined(&data.quarantined_profiles, app);
+    }
+
     prefs.init(&db, app).context("failed to init prefs")?;
 
+    let http = build_http(&prefs)?;
+
     let manager = profile::setup(data, &prefs, &db, app).context("failed to init profiles")?;
-    let thunderstore = Thunderstore::default();
+    let mut thunderstore = Thunderstore::default();
+    *thunderstore.aliases_mut() = db
+        .package_aliases()
+        .context("failed to read package aliases")?;
+    *thunderstore.metadata_mut() = db
+        .mod_metadata()
+        .context("failed to read cached mod metadata")?;
+
+    let on_dirty = Arc::new(Notify::new());
 
     let state = AppState {
         db,
-        http,
-        prefs: Mutex::new(prefs),
-        manager: Mutex::new(manager),
-        thunderstore: Mutex::new(thunderstore),
+        profile_ops: ProfileOps::default(),
+        http: RwLock::new(http),
+        prefs: StateMutex::new(prefs, "preferences", |_| Ok(())).tracked(on_dirty.clone()),
+        manager: StateMutex::new(manager, "profile manager", ModManager::validate)
+            .tracked(on_dirty.clone()),
+        thunderstore: StateMutex::new(thunderstore, "mod index", |_| Ok(())),
         cancel_install_flag: AtomicBool::new(false),
         is_first_run: !db_existed && !migrated,
     };
 
     app.manage(state);
 
-    thunderstore::start(app);
+    autosave::start(on_dirty, app);
+    thunderstore::start(app)?;
 
     Ok(())
 }
@@ -69,25 +278,29 @@ pub fn setup(app: &AppHandle) -> Result<()> {
 pub trait ManagerExt<R> {
     fn app_state(&self) -> &AppState;
 
-    fn http(&self) -> &reqwest::Client {
-        &self.app_state().http
+    fn http(&self) -> reqwest::Client {
+        self.app_state().http()
     }
 
-    fn lock_prefs(&self) -> MutexGuard<'_, Prefs> {
+    fn lock_prefs(&self) -> Result<StateGuard<'_, Prefs>> {
         self.app_state().lock_prefs()
     }
 
-    fn lock_manager(&self) -> MutexGuard<'_, ModManager> {
+    fn lock_manager(&self) -> Result<StateGuard<'_, ModManager>> {
         self.app_state().lock_manager()
     }
 
-    fn lock_thunderstore(&self) -> MutexGuard<'_, Thunderstore> {
+    fn lock_thunderstore(&self) -> Result<StateGuard<'_, Thunderstore>> {
         self.app_state().lock_thunderstore()
     }
 
     fn db(&self) -> &Db {
         &self.app_state().db
     }
+
+    fn profile_ops(&self) -> &ProfileOps {
+        &self.app_state().profile_ops
+    }
 }
 
 impl<T, R> ManagerExt<R> for T
@@ -104,3 +317,56 @@ where
 pub fn is_first_run(app: AppHandle) -> bool {
     app.app_state().is_first_run
 }
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use eyre::ensure;
+
+    use super::*;
+
+    fn poison<T: Send>(mutex: &StateMutex<T>, new_value: T) {
+        let result = thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    let mut guard = mutex.lock().unwrap();
+                    *guard = new_value;
+                    panic!("panic while holding the lock");
+                })
+                .join()
+        });
+
+        assert!(result.is_err());
+        assert!(mutex.inner.is_poisoned());
+    }
+
+    fn non_negative(value: &i32) -> Result<()> {
+        ensure!(*value >= 0, "value is negative");
+        Ok(())
+    }
+
+    #[test]
+    fn recovers_from_poison_if_valid() {
+        let mutex = StateMutex::new(1, "test", non_negative);
+
+        poison(&mutex, 2);
+
+        assert_eq!(*mutex.lock().unwrap(), 2);
+        assert!(!mutex.inner.is_poisoned());
+    }
+
+    #[test]
+    fn returns_error_if_invalid() {
+        let mutex = StateMutex::new(1, "test", non_negative);
+
+        poison(&mutex, -1);
+
+        for _ in 0..2 {
+            let err = mutex.lock().unwrap_err();
+            assert!(err.to_string().contains("please restart Gale"));
+        }
+
+        assert!(mutex.inner.is_poisoned());
+    }
+}
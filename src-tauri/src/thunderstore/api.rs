@@ -0,0 +1,328 @@
+//! Scheduling for requests to the Thunderstore API.
+//!
+//! Bulk operations can easily send enough requests to get the client
+//! temporarily blocked, so every API request goes through [`send`], which:
+//!
+//! - caps the number of requests in flight,
+//! - spaces out requests to the same [`Endpoint`],
+//! - retries `429 Too Many Requests` responses, respecting `Retry-After`.
+//!
+//! File downloads and uploads to the CDN don't count towards the API limits
+//! and are sent directly.
+
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, OnceLock,
+    },
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use log::warn;
+use reqwest::{header::HeaderMap, RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use tauri::Url;
+use tokio::{sync::Semaphore, time::Instant};
+use uuid::Uuid;
+
+use super::VersionIdent;
+
+const MAX_CONCURRENT: usize = 4;
+const MAX_RETRIES: u32 = 4;
+const BASE_BACKOFF: Duration = Duration::from_secs(1);
+/// If the server asks us to wait longer than this, we give up instead of
+/// leaving the user hanging.
+const MAX_WAIT: Duration = Duration::from_secs(60);
+
+static SCHEDULER: Scheduler = Scheduler {
+    permits: Semaphore::const_new(MAX_CONCURRENT),
+    next_slot: Mutex::new(Vec::new()),
+    requests: AtomicU64::new(0),
+    throttled: AtomicU64::new(0),
+    retries: AtomicU64::new(0),
+    gave_up: AtomicU64::new(0),
+    delayed_ms: AtomicU64::new(0),
+};
+
+/// The groups of API endpoints we use, each with their own pacing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endpoint {
+    /// The full package list of a community.
+    PackageIndex,
+    /// Community metadata, requested for every game at once.
+    Community,
+    /// Legacy profile codes.
+    Profile,
+    /// The current user and modpack publishing.
+    Account,
+    /// Readmes and changelogs of individual packages.
+    Metadata,
+}
+
+impl Endpoint {
+    /// The minimum time between two requests to this endpoint.
+    fn min_interval(self) -> Duration {
+        match self {
+            Endpoint::PackageIndex => Duration::from_secs(1),
+            Endpoint::Community => Duration::from_millis(100),
+            Endpoint::Profile => Duration::from_millis(500),
+            Endpoint::Account => Duration::from_millis(250),
+            Endpoint::Metadata => Duration::from_millis(100),
+        }
+    }
+}
+
+/// Counters for every request sent through [`send`] since startup.
+#[derive(Serialize, Debug, Default, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiStats {
+    pub requests: u64,
+    /// Responses with `429 Too Many Requests`.
+    pub throttled: u64,
+    pub retries: u64,
+    /// Requests that were still throttled after all retries.
+    pub gave_up: u64,
+    /// Total time requests spent waiting before being sent.
+    pub delayed_ms: u64,
+}
+
+impl Display for ApiStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} requests, {} throttled, {} retries, {} gave up, {:.1}s delayed",
+            self.requests,
+            self.throttled,
+            self.retries,
+            self.gave_up,
+            self.delayed_ms as f64 / 1000.0
+        )
+    }
+}
+
+struct Scheduler {
+    permits: Semaphore,
+    /// The earliest time the next request to each endpoint may be sent.
+    next_slot: Mutex<Vec<(Endpoint, Instant)>>,
+    requests: AtomicU64,
+    throttled: AtomicU64,
+    retries: AtomicU64,
+    gave_up: AtomicU64,
+    delayed_ms: AtomicU64,
+}
+
+impl Scheduler {
+    /// Reserves the next slot for `endpoint`, pushed back by at least `delay`.
+    fn reserve(&self, endpoint: Endpoint, delay: Duration) -> Instant {
+        let mut next_slots = self.next_slot.lock().unwrap();
+
+        let now = Instant::now();
+        let index = match next_slots.iter().position(|(other, _)| *other == endpoint) {
+            Some(index) => index,
+            None => {
+                next_slots.push((endpoint, now));
+                next_slots.len() - 1
+            }
+        };
+
+        let next_slot = &mut next_slots[index].1;
+        let slot = (*next_slot).max(now + delay);
+        *next_slot = slot + endpoint.min_interval();
+
+        slot
+    }
+
+    async fn wait_for_slot(&self, endpoint: Endpoint, delay: Duration) {
+        let start = Instant::now();
+        tokio::time::sleep_until(self.reserve(endpoint, delay)).await;
+
+        self.delayed_ms
+            .fetch_add(start.elapsed().as_millis() as u64, Ordering::Relaxed);
+    }
+}
+
+/// Sends an API request to `endpoint`, waiting for a free slot first.
+///
+/// Throttled requests are retried a few times. If they still fail, or the
+/// request can't be retried (like when it has a streaming body), the last
+/// response is returned as is. Use [`send_rebuilt`] for streaming bodies.
+pub async fn send(endpoint: Endpoint, request: RequestBuilder) -> reqwest::Result<Response> {
+    send_inner(endpoint, request, RequestBuilder::try_clone).await
+}
+
+/// Like [`send`], but calls `build` for every attempt instead of cloning
+/// the request, so requests that can't be cloned are retried too.
+pub async fn send_rebuilt(
+    endpoint: Endpoint,
+    build: impl Fn() -> RequestBuilder,
+) -> reqwest::Result<Response> {
+    send_inner(endpoint, build(), |_| Some(build())).await
+}
+
+async fn send_inner(
+    endpoint: Endpoint,
+    mut request: RequestBuilder,
+    next_attempt: impl Fn(&RequestBuilder) -> Option<RequestBuilder>,
+) -> reqwest::Result<Response> {
+    let scheduler = &SCHEDULER;
+
+    let mut delay = Duration::ZERO;
+    let mut attempt = 0;
+
+    loop {
+        let start = Instant::now();
+        // keep the permit while backing off, so throttling slows everything down
+        let _permit = scheduler.permits.acquire().await.unwrap();
+        scheduler
+            .delayed_ms
+            .fetch_add(start.elapsed().as_millis() as u64, Ordering::Relaxed);
+
+        scheduler.wait_for_slot(endpoint, delay).await;
+
+        let retry = next_attempt(&request);
+        scheduler.requests.fetch_add(1, Ordering::Relaxed);
+        let response = request.send().await?;
+
+        if response.status() != StatusCode::TOO_MANY_REQUESTS {
+            return Ok(response);
+        }
+
+        scheduler.throttled.fetch_add(1, Ordering::Relaxed);
+
+        let wait = retry_delay(response.headers(), attempt, Utc::now());
+
+        let Some(retry) = retry.filter(|_| attempt < MAX_RETRIES && wait <= MAX_WAIT) else {
+            warn!(
+                "{:?} request was throttled, giving up after {} retries",
+                endpoint, attempt
+            );
+            scheduler.gave_up.fetch_add(1, Ordering::Relaxed);
+            return Ok(response);
+        };
+
+        warn!(
+            "{:?} request was throttled, retrying in {:.1}s",
+            endpoint,
+            wait.as_secs_f64()
+        );
+
+        scheduler.retries.fetch_add(1, Ordering::Relaxed);
+
+        request = retry;
+        delay = wait;
+        attempt += 1;
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum MarkdownKind {
+    Readme,
+    Changelog,
+}
+
+#[derive(Deserialize)]
+struct MarkdownResponse {
+    markdown: Option<String>,
+}
+
+/// Fetches the readme or changelog of a package version.
+///
+/// Returns `None` if the package doesn't have one.
+pub async fn package_markdown(
+    owner: &str,
+    name: &str,
+    version: &str,
+    kind: MarkdownKind,
+    http: &reqwest::Client,
+) -> eyre::Result<Option<String>> {
+    let kind = match kind {
+        MarkdownKind::Readme => "readme",
+        MarkdownKind::Changelog => "changelog",
+    };
+
+    let mut url = Url::parse("https://thunderstore.io/api/experimental/package/")?;
+    url.path_segments_mut()
+        .map_err(|_| eyre::eyre!("invalid base url"))?
+        .pop_if_empty()
+        .extend([owner, name, version, kind, ""]);
+
+    let response = send(Endpoint::Metadata, http.get(url)).await?;
+
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+
+    let response: MarkdownResponse = response.error_for_status()?.json().await?;
+
+    Ok(response.markdown)
+}
+
+/// Fetches the readme of a package version.
+///
+/// Readmes are kept in memory by version uuid, since published versions
+/// can't be changed. Returns `None` if the package doesn't have one.
+pub async fn package_readme(
+    version_uuid: Uuid,
+    ident: &VersionIdent,
+    http: &reqwest::Client,
+) -> eyre::Result<Option<String>> {
+    static CACHE: OnceLock<Mutex<HashMap<Uuid, Option<String>>>> = OnceLock::new();
+    let cache = CACHE.get_or_init(Default::default);
+
+    if let Some(readme) = cache.lock().unwrap().get(&version_uuid) {
+        return Ok(readme.clone());
+    }
+
+    let readme = package_markdown(
+        ident.owner(),
+        ident.name(),
+        ident.version(),
+        MarkdownKind::Readme,
+        http,
+    )
+    .await?;
+
+    cache.lock().unwrap().insert(version_uuid, readme.clone());
+
+    Ok(readme)
+}
+
+/// How long to wait before retrying a throttled request.
+///
+/// Uses the `Retry-After` header if there is one, in either seconds or
+/// as a date. Otherwise, backs off exponentially based on `attempt`.
+pub(super) fn retry_delay(headers: &HeaderMap, attempt: u32, now: DateTime<Utc>) -> Duration {
+    let retry_after = headers
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim);
+
+    let from_header = retry_after.and_then(|value| match value.parse::<u64>() {
+        Ok(secs) => Some(Duration::from_secs(secs)),
+        Err(_) => DateTime::parse_from_rfc2822(value).ok().map(|date| {
+            (date.with_timezone(&Utc) - now)
+                .to_std()
+                .unwrap_or_default()
+        }),
+    });
+
+    from_header.unwrap_or_else(|| BASE_BACKOFF * 2u32.pow(attempt))
+}
+
+/// Returns the counters of every request sent so far.
+pub fn stats() -> ApiStats {
+    let scheduler = &SCHEDULER;
+    let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+
+    ApiStats {
+        requests: load(&scheduler.requests),
+        throttled: load(&scheduler.throttled),
+        retries: load(&scheduler.retries),
+        gave_up: load(&scheduler.gave_up),
+        delayed_ms: load(&scheduler.delayed_ms),
+    }
+}
@@ -0,0 +1,225 @@
+//! An optional list of packages flagged by the community, for example for
+//! malware, which installs ask for confirmation before installing.
+//!
+//! The list is fetched from [`Prefs::blocklist_url`] at most every
+//! [`MAX_AGE_HOURS`] and kept on disk in between. Failing to fetch it is
+//! only logged, and the last known list is used instead. After a failure,
+//! fetching isn't tried again for [`RETRY_MINUTES`], so an unreachable list
+//! doesn't hold up every install.
+//!
+//! [`Prefs::blocklist_url`]: crate::prefs::Prefs::blocklist_url
+
+use std::{path::Path, time::Duration};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use eyre::Result;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use super::{Thunderstore, VersionIdent};
+use crate::{
+    state::ManagerExt,
+    util::{self, fs::JsonStyle},
+};
+
+const FILE_NAME: &str = "blocklist.json";
+
+/// How old the list may be before it's fetched again.
+pub const MAX_AGE_HOURS: i64 = 6;
+
+/// How long to wait before fetching the list again after a failure.
+pub const RETRY_MINUTES: i64 = 15;
+
+/// The list is optional, so installs shouldn't wait long for it.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A package on the blocklist.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct BlocklistEntry {
+    /// The full name of the package, like `Owner-Name`.
+    pub package: String,
+    /// The flagged versions. Every version is flagged if this is empty.
+    #[serde(default)]
+    pub versions: Vec<String>,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Blocklist {
+    url: String,
+    fetched_at: Option<DateTime<Utc>>,
+    entries: Vec<BlocklistEntry>,
+    /// When fetching the list last failed, since it was last fetched.
+    #[serde(skip)]
+    failed_at: Option<DateTime<Utc>>,
+}
+
+impl Blocklist {
+    /// Finds the entry that flags `ident`, if any.
+    pub fn find(&self, ident: &VersionIdent) -> Option<&BlocklistEntry> {
+        self.entries.iter().find(|entry| {
+            entry.package.eq_ignore_ascii_case(ident.full_name())
+                && (entry.versions.is_empty()
+                    || entry
+                        .versions
+                        .iter()
+                        .any(|version| version == ident.version()))
+        })
+    }
+
+    fn is_fresh(&self) -> bool {
+        self.fetched_at
+            .is_some_and(|time| Utc::now() - time < TimeDelta::hours(MAX_AGE_HOURS))
+    }
+
+    fn failed_recently(&self) -> bool {
+        self.failed_at
+            .is_some_and(|time| Utc::now() - time < TimeDelta::minutes(RETRY_MINUTES))
+    }
+}
+
+impl Thunderstore {
+    pub fn blocklist(&self) -> &Blocklist {
+        &self.blocklist
+    }
+
+    #[cfg(test)]
+    pub(crate) fn set_blocklist(&mut self, entries: Vec<BlocklistEntry>) {
+        self.blocklist = Blocklist {
+            entries,
+            ..Default::default()
+        };
+    }
+}
+
+/// Fetches the blocklist if it's stale, or clears it if there's no
+/// [`Prefs::blocklist_url`](crate::prefs::Prefs::blocklist_url).
+pub async fn refresh(app: &AppHandle) {
+    if let Err(err) = try_refresh(app).await {
+        warn!("failed to refresh package blocklist: {:#}", err);
+    }
+}
+
+async fn try_refresh(app: &AppHandle) -> Result<()> {
+    let (url, path) = {
+        let prefs = app.lock_prefs()?;
+        (
+            prefs.blocklist_url.clone(),
+            prefs.cache_dir().join(FILE_NAME),
+        )
+    };
+
+    let Some(url) = url else {
+        app.lock_thunderstore()?.blocklist = Blocklist::default();
+        return Ok(());
+    };
+
+    {
+        let mut thunderstore = app.lock_thunderstore()?;
+
+        if thunderstore.blocklist.url != url {
+            thunderstore.blocklist = read_cached(&path)
+                .filter(|cached| cached.url == url)
+                .unwrap_or_else(|| Blocklist {
+                    url: url.clone(),
+                    ..Default::default()
+                });
+        }
+
+        if thunderstore.blocklist.is_fresh() || thunderstore.blocklist.failed_recently() {
+            return Ok(());
+        }
+    }
+
+    let entries = match fetch(&url, app).await {
+        Ok(entries) => entries,
+        Err(err) => {
+            app.lock_thunderstore()?.blocklist.failed_at = Some(Utc::now());
+            return Err(err);
+        }
+    };
+
+    info!("fetched {} package blocklist entries", entries.len());
+
+    let blocklist = Blocklist {
+        url,
+        fetched_at: Some(Utc::now()),
+        entries,
+        failed_at: None,
+    };
+
+    if let Err(err) = util::fs::write_json(&path, &blocklist, JsonStyle::Compact) {
+        warn!("failed to write package blocklist: {:#}", err);
+    }
+
+    app.lock_thunderstore()?.blocklist = blocklist;
+
+    Ok(())
+}
+
+async fn fetch(url: &str, app: &AppHandle) -> Result<Vec<BlocklistEntry>> {
+    let entries = app
+        .http()
+        .get(url)
+        .timeout(TIMEOUT)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    Ok(entries)
+}
+
+fn read_cached(path: &Path) -> Option<Blocklist> {
+    if !path.exists() {
+        return None;
+    }
+
+    util::fs::read_json(path)
+        .inspect_err(|err| warn!("failed to read cached package blocklist: {:#}", err))
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_match_packages_and_versions() {
+        let blocklist = Blocklist {
+            entries: serde_json::from_str(
+                r#"[
+                    { "package": "Bad-Package", "reason": "steals tokens" },
+                    { "package": "Some-Mod", "versions": ["1.0.1"] }
+                ]"#,
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+
+        let find = |ident: &str| blocklist.find(&ident.parse().unwrap()).is_some();
+
+        assert!(find("Bad-Package-2.0.0"));
+        assert!(find("bad-package-1.0.0"));
+        assert!(find("Some-Mod-1.0.1"));
+        assert!(!find("Some-Mod-1.0.2"));
+        assert!(!find("Bad-PackageTwo-1.0.0"));
+    }
+
+    #[test]
+    fn failures_back_off() {
+        let mut blocklist = Blocklist::default();
+        assert!(!blocklist.failed_recently());
+
+        blocklist.failed_at = Some(Utc::now());
+        assert!(blocklist.failed_recently());
+
+        blocklist.failed_at = Some(Utc::now() - TimeDelta::minutes(RETRY_MINUTES + 1));
+        assert!(!blocklist.failed_recently());
+    }
+}
@@ -1,35 +1,88 @@
-use eyre::anyhow;
+use std::collections::HashMap;
+
+use chrono::Utc;
+use eyre::{anyhow, Context};
+use serde::Serialize;
 use tauri::{command, AppHandle};
+use uuid::Uuid;
 
 use super::{
-    models::FrontendMod,
-    query::{self, QueryModsArgs},
+    api::{self, MarkdownKind},
+    communities::{self, CommunityInfo},
+    models::{FrontendMod, IntoFrontendMod},
+    owned,
+    query::{self, Discovery, QueryModsArgs},
+    ModId, PackageIdent,
+};
+use crate::{
+    db::DownloadSample,
+    deep_link, logger,
+    state::ManagerExt,
+    util::cmd::{Result, UserError},
 };
-use crate::{logger, state::ManagerExt, util::cmd::Result};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThunderstoreQuery {
+    mods: Vec<FrontendMod>,
+    /// Category filters that aren't known for the active game. These are still applied.
+    unknown_categories: Vec<String>,
+}
 
 #[command]
-pub fn query_thunderstore(args: QueryModsArgs, app: AppHandle) -> Vec<FrontendMod> {
-    let manager = app.lock_manager();
-    let mut thunderstore = app.lock_thunderstore();
+pub fn query_thunderstore(args: QueryModsArgs, app: AppHandle) -> Result<ThunderstoreQuery> {
+    let manager = app.lock_manager()?;
+    let mut thunderstore = app.lock_thunderstore()?;
+
+    let unknown_categories = args
+        .unknown_categories(manager.active_game)
+        .map(str::to_owned)
+        .collect();
 
-    let result = query::query_frontend_mods(&args, thunderstore.latest(), manager.active_profile());
+    let mods = query::query_frontend_mods(&args, thunderstore.latest(), manager.active_profile());
 
     if !thunderstore.packages_fetched {
         thunderstore.current_query = Some(args);
     }
 
-    result
+    Ok(ThunderstoreQuery {
+        mods,
+        unknown_categories,
+    })
 }
 
+/// Returns popular mods of the active game, `count` of each kind (10 by default).
 #[command]
-pub fn stop_querying_thunderstore(app: AppHandle) {
-    app.lock_thunderstore().current_query = None;
+pub fn get_discovery(count: Option<usize>, app: AppHandle) -> Result<Discovery> {
+    let manager = app.lock_manager()?;
+    let thunderstore = app.lock_thunderstore()?;
+
+    let profile = manager.active_profile();
+    let (most_downloaded, top_rated) =
+        query::discover(thunderstore.latest(), count.unwrap_or(10), Utc::now());
+
+    Ok(Discovery {
+        most_downloaded: most_downloaded
+            .into_iter()
+            .map(|borrowed| borrowed.into_frontend(Some(profile)))
+            .collect(),
+        top_rated: top_rated
+            .into_iter()
+            .map(|borrowed| borrowed.into_frontend(Some(profile)))
+            .collect(),
+    })
+}
+
+#[command]
+pub fn stop_querying_thunderstore(app: AppHandle) -> Result<()> {
+    app.lock_thunderstore()?.current_query = None;
+    Ok(())
 }
 
 #[command]
 pub fn trigger_mod_fetch(app: AppHandle) -> Result<()> {
     let write_directly = {
-        let state = app.lock_thunderstore();
+        let state = app.lock_thunderstore()?;
 
         if state.is_fetching {
             return Err(anyhow!("already fetching mods").into());
@@ -38,7 +91,7 @@ pub fn trigger_mod_fetch(app: AppHandle) -> Result<()> {
         !state.packages_fetched
     };
 
-    let game = app.lock_manager().active_game;
+    let game = app.lock_manager()?.active_game;
 
     tauri::async_runtime::spawn(async move {
         if let Err(err) = super::fetch::fetch_packages(game, write_directly, &app).await {
@@ -49,6 +102,172 @@ pub fn trigger_mod_fetch(app: AppHandle) -> Result<()> {
     Ok(())
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModShareLinks {
+    pub deep_link: String,
+    /// The `owner-name` form, without a version.
+    pub dependency_string: String,
+}
+
+#[command]
+pub fn get_mod_share_links(id: ModId, app: AppHandle) -> Result<ModShareLinks> {
+    let thunderstore = app.lock_thunderstore()?;
+    let ident = id.borrow(&thunderstore)?.ident();
+
+    Ok(ModShareLinks {
+        deep_link: deep_link::mod_url(ident),
+        dependency_string: ident.full_name().to_owned(),
+    })
+}
+
+/// Opens the Thunderstore page of a package in the default browser.
+#[command]
+pub fn open_mod_page(uuid: Uuid, app: AppHandle) -> Result<()> {
+    let url = {
+        let manager = app.lock_manager()?;
+        let thunderstore = app.lock_thunderstore()?;
+
+        match thunderstore.get_package(uuid) {
+            Ok(package) => package.package_url.clone(),
+            Err(_) => {
+                let local = manager
+                    .active_profile()
+                    .get_mod(uuid)
+                    .ok()
+                    .and_then(|profile_mod| profile_mod.kind.as_local());
+
+                return Err(match local {
+                    Some(local) => {
+                        anyhow!("{} is a local mod and has no Thunderstore page", local.name).into()
+                    }
+                    None => UserError::ModNotFound.into(),
+                });
+            }
+        }
+    };
+
+    open::that_detached(&url).context("failed to open mod page")?;
+
+    Ok(())
+}
+
+#[command]
+pub fn watch_package(uuid: Uuid, app: AppHandle) -> Result<()> {
+    app.db().watch_package(uuid)?;
+
+    Ok(())
+}
+
+#[command]
+pub fn unwatch_package(uuid: Uuid, app: AppHandle) -> Result<()> {
+    app.db().unwatch_package(uuid)?;
+
+    Ok(())
+}
+
+#[command]
+pub fn get_watched_packages(app: AppHandle) -> Result<Vec<Uuid>> {
+    let uuids = app.db().watched_packages()?;
+
+    Ok(uuids)
+}
+
+#[command]
+pub fn get_download_history(uuid: Uuid, app: AppHandle) -> Result<Vec<DownloadSample>> {
+    let history = app.db().download_history(uuid)?;
+
+    Ok(history)
+}
+
+#[command]
+pub fn get_package_aliases(app: AppHandle) -> Result<HashMap<String, String>> {
+    let aliases = app.lock_thunderstore()?.aliases().clone();
+
+    Ok(aliases)
+}
+
+/// Makes dependencies on `old_name` resolve to `new_name`, for packages
+/// that have been renamed or transferred to another owner.
+#[command]
+pub fn set_package_alias(
+    old_name: PackageIdent,
+    new_name: PackageIdent,
+    app: AppHandle,
+) -> Result<()> {
+    if old_name == new_name {
+        return Err(anyhow!("a package can't be an alias of itself").into());
+    }
+
+    let mut thunderstore = app.lock_thunderstore()?;
+
+    app.db()
+        .set_package_alias(old_name.as_str(), new_name.as_str())?;
+
+    thunderstore
+        .aliases_mut()
+        .insert(old_name.into_string(), new_name.into_string());
+
+    Ok(())
+}
+
+#[command]
+pub fn remove_package_alias(old_name: PackageIdent, app: AppHandle) -> Result<()> {
+    let mut thunderstore = app.lock_thunderstore()?;
+
+    app.db().remove_package_alias(old_name.as_str())?;
+
+    thunderstore.aliases_mut().remove(old_name.as_str());
+
+    Ok(())
+}
+
+/// Fetches the readme or changelog of a package version from Thunderstore.
+#[command]
+pub async fn get_package_markdown(
+    author: String,
+    name: String,
+    version: String,
+    kind: MarkdownKind,
+    app: AppHandle,
+) -> Result<Option<String>> {
+    let markdown = api::package_markdown(&author, &name, &version, kind, &app.http()).await?;
+
+    Ok(markdown)
+}
+
+/// Fetches the readme of a package, of the version installed in the
+/// active profile if there is one, and the latest version otherwise.
+#[command]
+pub async fn get_mod_readme(uuid: Uuid, app: AppHandle) -> Result<Option<String>> {
+    let (version_uuid, ident) = {
+        let manager = app.lock_manager()?;
+        let thunderstore = app.lock_thunderstore()?;
+
+        let package = thunderstore.get_package(uuid)?;
+        let installed = manager
+            .active_profile()
+            .get_mod(uuid)
+            .ok()
+            .and_then(|profile_mod| profile_mod.kind.as_thunderstore())
+            .and_then(|ts_mod| package.get_version(ts_mod.id.version_uuid));
+
+        let version = installed.unwrap_or_else(|| package.latest());
+        (version.uuid, version.ident.clone())
+    };
+
+    let readme = api::package_readme(version_uuid, &ident, &app.http()).await?;
+
+    Ok(readme)
+}
+
+#[command]
+pub async fn get_communities(app: AppHandle) -> Result<Vec<CommunityInfo>> {
+    let communities = communities::list(&app).await?;
+
+    Ok(communities)
+}
+
 #[command]
 pub fn set_thunderstore_token(token: &str) -> Result<()> {
     super::token::set(token)?;
@@ -65,3 +284,17 @@ pub fn clear_thunderstore_token() -> Result<()> {
     super::token::clear()?;
     Ok(())
 }
+
+#[command]
+pub async fn get_my_packages(refresh: bool, app: AppHandle) -> Result<Vec<FrontendMod>> {
+    let teams = owned::teams(refresh, &app).await?;
+
+    let manager = app.lock_manager()?;
+    let thunderstore = app.lock_thunderstore()?;
+
+    let packages = owned::owned_by(&teams, &thunderstore)
+        .map(|borrowed| borrowed.into_frontend(Some(manager.active_profile())))
+        .collect();
+
+    Ok(packages)
+}
@@ -0,0 +1,91 @@
+use chrono::{DateTime, Utc};
+use eyre::Result;
+use futures_util::{stream, StreamExt};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use super::api::{self, Endpoint};
+use crate::{
+    game::{self, Game},
+    state::ManagerExt,
+};
+
+/// How many communities to query at the same time.
+const CONCURRENT_REQUESTS: usize = 8;
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CommunityInfo {
+    pub name: &'static str,
+    /// The Thunderstore community identifier, which is the same as the game's slug.
+    pub slug: &'static str,
+    pub reachable: bool,
+    pub package_count: Option<u64>,
+    pub error: Option<String>,
+    /// When the package list was last fetched for this game, during this session.
+    pub last_fetched: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize)]
+struct CommunityResponse {
+    total_package_count: u64,
+}
+
+/// Checks the Thunderstore community of every supported game.
+///
+/// This only requests the community metadata, not the full package list.
+pub async fn list(app: &AppHandle) -> Result<Vec<CommunityInfo>> {
+    let http = app.http();
+    let requests = game::all()
+        .map(|game| fetch_package_count(game, http.clone()))
+        .collect::<Vec<_>>();
+
+    let mut communities = stream::iter(requests)
+        .buffered(CONCURRENT_REQUESTS)
+        .collect::<Vec<_>>()
+        .await;
+
+    let thunderstore = app.lock_thunderstore()?;
+
+    for community in &mut communities {
+        if let Some(game) = game::from_slug(community.slug) {
+            community.last_fetched = thunderstore.last_fetched(game);
+        }
+    }
+
+    Ok(communities)
+}
+
+async fn fetch_package_count(game: Game, http: reqwest::Client) -> CommunityInfo {
+    let url = format!(
+        "https://thunderstore.io/api/cyberstorm/community/{}/",
+        game.slug
+    );
+
+    let result = async {
+        api::send(Endpoint::Community, http.get(url))
+            .await?
+            .error_for_status()?
+            .json::<CommunityResponse>()
+            .await
+    }
+    .await;
+
+    let (package_count, error) = match result {
+        Ok(response) => (Some(response.total_package_count), None),
+        Err(err) => {
+            debug!("failed to reach community {}: {:#}", game.slug, err);
+            (None, Some(err.to_string()))
+        }
+    };
+
+    CommunityInfo {
+        name: game.name,
+        slug: &game.slug,
+        reachable: error.is_none(),
+        package_count,
+        error,
+        last_fetched: None,
+    }
+}
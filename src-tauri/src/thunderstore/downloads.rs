@@ -0,0 +1,47 @@
+use chrono::Utc;
+use eyre::Result;
+use log::debug;
+use tauri::AppHandle;
+
+use crate::state::ManagerExt;
+
+/// Records the total downloads of watched packages which belong to the
+/// current game, if their last sample is older than the configured interval.
+pub fn record_samples(app: &AppHandle) -> Result<()> {
+    let db = app.db();
+
+    let watched = db.watched_packages()?;
+    if watched.is_empty() {
+        return Ok(());
+    }
+
+    let interval = app.lock_prefs()?.download_sample_interval();
+    let now = Utc::now();
+
+    let mut samples = Vec::new();
+    {
+        let thunderstore = app.lock_thunderstore()?;
+
+        for uuid in watched {
+            let Ok(package) = thunderstore.get_package(uuid) else {
+                continue; // package is from another game
+            };
+
+            let is_due = db
+                .last_download_sample(uuid)?
+                .and_then(|last| (now - last).to_std().ok())
+                .map_or(true, |elapsed| elapsed >= interval);
+
+            if is_due {
+                samples.push((uuid, package.total_downloads() as u64));
+            }
+        }
+    }
+
+    if !samples.is_empty() {
+        debug!("recording downloads of {} packages", samples.len());
+        db.insert_download_samples(&samples, now)?;
+    }
+
+    Ok(())
+}
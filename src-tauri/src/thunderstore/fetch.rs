@@ -1,25 +1,39 @@
 use core::str;
 use std::{
+    collections::HashMap,
     sync::LazyLock,
     time::{Duration, Instant},
 };
 
+use chrono::Utc;
 use eyre::Result;
 use indexmap::IndexMap;
 use log::{info, warn};
 use tauri::{AppHandle, Emitter};
+use uuid::Uuid;
 
-use crate::{game::Game, logger, state::ManagerExt, thunderstore::PackageListing};
+use super::{
+    api::{self, Endpoint},
+    intern::Interner,
+};
+use crate::{
+    db::UsageKind, game::Game, logger, profile::install, state::ManagerExt,
+    thunderstore::PackageListing,
+};
 
 pub(super) async fn fetch_package_loop(game: Game, app: AppHandle) {
     const FETCH_INTERVAL: Duration = Duration::from_secs(60 * 15);
 
-    read_and_insert_cache(&app);
+    if let Err(err) = read_and_insert_cache(&app) {
+        warn!("failed to read cache: {:#}", err);
+    }
 
     let mut is_first = true;
 
     loop {
-        let fetch_automatically = app.lock_prefs().fetch_mods_automatically();
+        let fetch_automatically = app
+            .lock_prefs()
+            .is_ok_and(|prefs| prefs.fetch_mods_automatically());
 
         // always fetch once, even if the setting is turned off
         if !fetch_automatically && !is_first {
@@ -35,14 +49,14 @@ pub(super) async fn fetch_package_loop(game: Game, app: AppHandle) {
     }
 
     async fn loop_iter(game: Game, is_first: &mut bool, app: &AppHandle) -> Result<()> {
-        if app.lock_thunderstore().is_fetching {
+        if app.lock_thunderstore()?.is_fetching {
             warn!("automatic fetch cancelled due to ongoing fetch");
             return Ok(());
         }
 
         let result = fetch_packages(game, *is_first, app).await;
 
-        let mut state = app.lock_thunderstore();
+        let mut state = app.lock_thunderstore()?;
 
         state.is_fetching = false;
         state.packages_fetched |= result.is_ok();
@@ -53,18 +67,18 @@ pub(super) async fn fetch_package_loop(game: Game, app: AppHandle) {
     }
 }
 
-fn read_and_insert_cache(app: &AppHandle) {
-    match super::read_cache(&app.lock_manager()) {
-        Ok(Some(mods)) => {
-            let mut thunderstore = app.lock_thunderstore();
+fn read_and_insert_cache(app: &AppHandle) -> Result<()> {
+    let Some(mods) = super::read_cache(&*app.lock_manager()?)? else {
+        return Ok(());
+    };
 
-            for package in mods {
-                thunderstore.packages.insert(package.uuid, package);
-            }
-        }
-        Ok(None) => (),
-        Err(err) => warn!("failed to read cache: {}", err),
+    let mut thunderstore = app.lock_thunderstore()?;
+
+    for package in mods {
+        thunderstore.packages.insert(package.uuid, package);
     }
+
+    Ok(())
 }
 
 const EXCLUDED_PACKAGES_STR: &str = include_str!("../../excluded_packages.txt");
@@ -89,21 +103,30 @@ pub(super) async fn fetch_packages(
         game.slug, write_directly
     );
 
+    let status_before = deprecation_in_active_profile(app)?;
+
     let url = format!("https://thunderstore.io/c/{}/api/v1/package/", game.slug);
-    let mut response = app.http().get(url).send().await?.error_for_status()?;
+    let timeout = app.lock_prefs()?.download_timeout();
+    let request = app.http().get(url).timeout(timeout);
+    let mut response = api::send(Endpoint::PackageIndex, request)
+        .await?
+        .error_for_status()?;
 
     let mut i = 0;
     let mut package_count = 0;
+    let mut total_bytes = 0;
 
     let mut byte_buffer = Vec::new();
     let mut str_buffer = String::new();
     let mut package_buffer = IndexMap::new();
+    let mut interner = Interner::default();
 
     let start_time = Instant::now();
     let mut last_update = Instant::now();
 
     // response is just one long JSON array
     while let Some(chunk) = response.chunk().await? {
+        total_bytes += chunk.len() as u64;
         byte_buffer.extend_from_slice(&chunk);
         let Ok(chunk) = str::from_utf8(&byte_buffer) else {
             continue;
@@ -122,8 +145,10 @@ pub(super) async fn fetch_packages(
             let (json, _) = str_buffer.split_at(index + 3);
 
             match serde_json::from_str::<PackageListing>(json) {
-                Ok(package) => {
+                Ok(mut package) => {
                     if !EXCLUDED_PACKAGES.contains(&package.full_name()) {
+                        package.intern_strings(&mut interner);
+                        package.classify();
                         package_buffer.insert(package.uuid, package);
                         package_count += 1;
                     }
@@ -136,7 +161,7 @@ pub(super) async fn fetch_packages(
 
         // do this in bigger chunks to not have to lock the state too often
         if write_directly && package_buffer.len() >= INSERT_EVERY {
-            let mut state = app.lock_thunderstore();
+            let mut state = app.lock_thunderstore()?;
             state.packages.extend(package_buffer.drain(..));
         }
 
@@ -148,7 +173,7 @@ pub(super) async fn fetch_packages(
         i += 1;
     }
 
-    let mut state = app.lock_thunderstore();
+    let mut state = app.lock_thunderstore()?;
     if write_directly {
         // add any remaining packages
         state.packages.extend(package_buffer.into_iter());
@@ -159,6 +184,7 @@ pub(super) async fn fetch_packages(
 
     state.packages_fetched = true;
     state.is_fetching = false;
+    state.last_fetched.insert(game, Utc::now());
 
     info!(
         "fetched {} packages for {} in {:?}",
@@ -167,8 +193,32 @@ pub(super) async fn fetch_packages(
         start_time.elapsed()
     );
 
+    drop(state);
+
+    // this is the decompressed size, so it overestimates the actual usage
+    install::record_network_usage(
+        game,
+        "Thunderstore package index",
+        total_bytes,
+        UsageKind::Metadata,
+        app,
+    );
+
     app.emit("status_update", None::<String>).ok();
 
+    if let Err(err) = super::downloads::record_samples(app) {
+        warn!("failed to record download history: {:#}", err);
+    }
+
+    if let Err(err) = emit_metadata_refresh(game, &status_before, app) {
+        warn!("failed to refresh profile metadata: {:#}", err);
+    }
+
+    super::metadata::update(game, app);
+
+    let handle = app.clone();
+    tauri::async_runtime::spawn(async move { super::blocklist::refresh(&handle).await });
+
     return Ok(());
 
     fn emit_update(mods: usize, app: &AppHandle) {
@@ -180,10 +230,47 @@ pub(super) async fn fetch_packages(
     }
 }
 
-pub async fn wait_for_fetch(app: &AppHandle) {
+fn deprecation_in_active_profile(app: &AppHandle) -> Result<HashMap<Uuid, bool>> {
+    let manager = app.lock_manager()?;
+    let thunderstore = app.lock_thunderstore()?;
+
+    Ok(manager.active_profile().deprecation_status(&thunderstore))
+}
+
+/// Lets the frontend know that the metadata of the active profile's mods
+/// may have changed, since the index was refreshed.
+fn emit_metadata_refresh(
+    game: Game,
+    status_before: &HashMap<Uuid, bool>,
+    app: &AppHandle,
+) -> Result<()> {
+    let manager = app.lock_manager()?;
+    let thunderstore = app.lock_thunderstore()?;
+
+    if manager.active_game != game {
+        return Ok(());
+    }
+
+    let refresh = manager
+        .active_profile()
+        .refresh_metadata(status_before, &thunderstore);
+
+    if !refresh.newly_deprecated.is_empty() {
+        info!(
+            "{} installed mods have been deprecated",
+            refresh.newly_deprecated.len()
+        );
+    }
+
+    app.emit("profile-metadata-refreshed", &refresh)?;
+
+    Ok(())
+}
+
+pub async fn wait_for_fetch(app: &AppHandle) -> Result<()> {
     loop {
-        if app.lock_thunderstore().packages_fetched() {
-            return;
+        if app.lock_thunderstore()?.packages_fetched() {
+            return Ok(());
         }
 
         tokio::time::sleep(Duration::from_secs(1)).await;
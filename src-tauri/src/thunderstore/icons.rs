@@ -0,0 +1,383 @@
+//! Caches mod icons on disk and serves them to the frontend through the
+//! [`SCHEME`] URI scheme, instead of every list item loading its icon
+//! from the Thunderstore CDN.
+//!
+//! Icons are requested as `<package uuid>/<version uuid>` and stored in
+//! [`Prefs::icon_cache_dir`] by version uuid, so a package's icon is fetched
+//! again when a new version is released. Stale and unused icons are evicted
+//! once the cache grows over [`CACHE_LIMIT`] bytes.
+//!
+//! [`Prefs::icon_cache_dir`]: crate::prefs::Prefs::icon_cache_dir
+
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+    time::SystemTime,
+};
+
+use eyre::{Context, Result};
+use itertools::Itertools;
+use log::warn;
+use tauri::{
+    http::{header, Request, Response, StatusCode},
+    AppHandle, UriSchemeResponder,
+};
+use tokio::sync::Semaphore;
+use uuid::Uuid;
+
+use super::{ident::VersionIdent, ModId};
+use crate::{
+    db::UsageKind, game::Game, profile::install, state::ManagerExt, util::error::IoResultExt,
+};
+
+/// The URI scheme icons are served from.
+pub const SCHEME: &str = "gale-icon";
+
+/// Name of the icon cache inside the mod cache directory.
+pub const DIR_NAME: &str = "icons";
+
+/// How many bytes of icons are kept before unused and old ones are removed.
+pub const CACHE_LIMIT: u64 = 64 * 1024 * 1024;
+
+/// How many icons are fetched from the CDN at once.
+const MAX_CONCURRENT_FETCHES: usize = 6;
+
+/// How many bytes are fetched before the cache is checked against [`CACHE_LIMIT`].
+const EVICT_INTERVAL: u64 = 2 * 1024 * 1024;
+
+static FETCH_PERMITS: Semaphore = Semaphore::const_new(MAX_CONCURRENT_FETCHES);
+static FETCHED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// Where the icon of a version is cached.
+pub fn path(dir: &Path, version_uuid: Uuid) -> PathBuf {
+    dir.join(format!("{}.png", version_uuid))
+}
+
+/// The CDN URL of a version's icon.
+pub fn url(ident: &VersionIdent) -> String {
+    format!(
+        "https://gcdn.thunderstore.io/live/repository/icons/{}.png",
+        ident
+    )
+}
+
+/// Handles a request to the [`SCHEME`] URI scheme.
+pub fn handle(app: &AppHandle, request: Request<Vec<u8>>, responder: UriSchemeResponder) {
+    let app = app.clone();
+
+    tauri::async_runtime::spawn(async move {
+        let response = match serve(request.uri().path(), &app).await {
+            Ok(Some(bytes)) => Response::builder()
+                .header(header::CONTENT_TYPE, content_type(&bytes))
+                .header(header::CACHE_CONTROL, "max-age=604800, immutable")
+                .body(bytes),
+            Ok(None) => Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(Vec::new()),
+            Err(err) => {
+                warn!("failed to serve icon {}: {:#}", request.uri(), err);
+
+                Response::builder()
+                    .status(StatusCode::BAD_GATEWAY)
+                    .body(Vec::new())
+            }
+        };
+
+        responder.respond(response.expect("icon response should be valid"));
+    });
+}
+
+async fn serve(path: &str, app: &AppHandle) -> Result<Option<Vec<u8>>> {
+    let Some(id) = parse_path(path) else {
+        return Ok(None);
+    };
+
+    let dir = app.lock_prefs()?.icon_cache_dir();
+    let file = self::path(&dir, id.version_uuid);
+
+    if let Some(bytes) = read_cached(&file)? {
+        return Ok(Some(bytes));
+    }
+
+    let url = {
+        let thunderstore = app.lock_thunderstore()?;
+
+        match id.borrow(&thunderstore) {
+            Ok(borrowed) => url(borrowed.ident()),
+            // installed mods can still be shown while the index isn't fetched
+            Err(_) => match thunderstore.cached_metadata(id.package_uuid) {
+                Some(metadata) => metadata.icon_url.clone(),
+                None => return Ok(None),
+            },
+        }
+    };
+
+    fetch(&url, &file, app).await.map(Some)
+}
+
+/// Parses a request path of `/<package uuid>/<version uuid>`.
+fn parse_path(path: &str) -> Option<ModId> {
+    // convertFileSrc on the frontend percent-encodes the separator
+    let path = path.trim_start_matches('/').replace("%2F", "/");
+    let (package, version) = path.split_once('/')?;
+
+    Some(ModId {
+        package_uuid: package.parse().ok()?,
+        version_uuid: version.parse().ok()?,
+    })
+}
+
+fn read_cached(file: &Path) -> Result<Option<Vec<u8>>> {
+    match fs::read(file) {
+        Ok(bytes) => {
+            // the modification time doubles as the last access for eviction
+            fs::File::options()
+                .write(true)
+                .open(file)
+                .and_then(|handle| handle.set_modified(SystemTime::now()))
+                .ok();
+
+            Ok(Some(bytes))
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err).fs_context("reading cached icon", file),
+    }
+}
+
+async fn fetch(url: &str, file: &Path, app: &AppHandle) -> Result<Vec<u8>> {
+    let _permit = FETCH_PERMITS.acquire().await?;
+
+    // the icon might have been fetched by another request while waiting
+    if let Some(bytes) = read_cached(file)? {
+        return Ok(bytes);
+    }
+
+    let bytes = app
+        .http()
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await
+        .with_context(|| format!("failed to fetch icon from {}", url))?;
+
+    if let Some(dir) = file.parent() {
+        fs::create_dir_all(dir).fs_context("creating icon cache", dir)?;
+    }
+
+    // write to a temporary file first, so other requests never read a partial icon
+    let temp_file = file.with_extension("part");
+    fs::write(&temp_file, &bytes).fs_context("writing icon", &temp_file)?;
+    fs::rename(&temp_file, file).fs_context("writing icon", file)?;
+
+    let total = FETCHED_BYTES.fetch_add(bytes.len() as u64, Ordering::Relaxed) + bytes.len() as u64;
+    if total >= EVICT_INTERVAL {
+        flush(app);
+    }
+
+    Ok(bytes.into())
+}
+
+/// Fetches the missing icons of `ids` in the background, so
+/// they're available when the index can't be fetched.
+pub fn prefetch(ids: Vec<(Uuid, String)>, app: &AppHandle) {
+    let app = app.clone();
+
+    tauri::async_runtime::spawn(async move {
+        let dir = match app.lock_prefs() {
+            Ok(prefs) => prefs.icon_cache_dir(),
+            Err(err) => {
+                warn!("failed to prefetch icons: {:#}", err);
+                return;
+            }
+        };
+
+        let fetches = ids.into_iter().map(|(version_uuid, url)| {
+            let file = path(&dir, version_uuid);
+            let app = app.clone();
+
+            async move {
+                if let Err(err) = fetch(&url, &file, &app).await {
+                    warn!("failed to prefetch icon from {}: {:#}", url, err);
+                }
+            }
+        });
+
+        futures_util::future::join_all(fetches).await;
+
+        flush(&app);
+    });
+}
+
+/// Records the bytes fetched since the last call and evicts icons if needed.
+fn flush(app: &AppHandle) {
+    let bytes = FETCHED_BYTES.swap(0, Ordering::Relaxed);
+    if bytes == 0 {
+        return;
+    }
+
+    let app = app.clone();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = (|| {
+            let dir = app.lock_prefs()?.icon_cache_dir();
+            let (game, installed) = installed(&app)?;
+
+            install::record_network_usage(game, "Mod icons", bytes, UsageKind::Metadata, &app);
+
+            evict(&dir, &installed, CACHE_LIMIT)
+        })();
+
+        if let Err(err) = result {
+            warn!("failed to evict cached icons: {:#}", err);
+        }
+    });
+}
+
+/// The active game and the version uuids of every installed mod, across all games.
+fn installed(app: &AppHandle) -> Result<(Game, HashSet<Uuid>)> {
+    let manager = app.lock_manager()?;
+
+    let installed = manager
+        .games
+        .values()
+        .flat_map(|managed_game| &managed_game.profiles)
+        .flat_map(|profile| &profile.mods)
+        .filter_map(|profile_mod| profile_mod.kind.as_thunderstore())
+        .map(|ts_mod| ts_mod.id.version_uuid)
+        .collect();
+
+    Ok((manager.active_game, installed))
+}
+
+/// Removes icons until the cache is at most `limit` bytes.
+///
+/// Icons of versions that aren't in `installed` are removed first,
+/// then the ones that were used the longest ago.
+fn evict(dir: &Path, installed: &HashSet<Uuid>, limit: u64) -> Result<()> {
+    let entries = match dir.read_dir() {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err).fs_context("reading icon cache", dir),
+    };
+
+    let mut icons = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            let path = entry.path();
+            let is_installed = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.parse::<Uuid>().ok())
+                .is_some_and(|uuid| installed.contains(&uuid));
+
+            Some((is_installed, metadata.modified().ok(), metadata.len(), path))
+        })
+        .collect_vec();
+
+    let mut total_size = icons.iter().map(|(_, _, size, _)| size).sum::<u64>();
+
+    icons.sort_by_key(|(is_installed, modified, _, _)| (*is_installed, *modified));
+
+    for (_, _, size, path) in icons {
+        if total_size <= limit {
+            break;
+        }
+
+        fs::remove_file(&path).fs_context("removing cached icon", &path)?;
+        total_size -= size;
+    }
+
+    Ok(())
+}
+
+/// Guesses the content type of an icon from its first bytes.
+fn content_type(bytes: &[u8]) -> &'static str {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        "image/png"
+    } else if bytes.starts_with(&[0xff, 0xd8, 0xff]) {
+        "image/jpeg"
+    } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        "image/webp"
+    } else if bytes.starts_with(b"GIF8") {
+        "image/gif"
+    } else {
+        "application/octet-stream"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{thread, time::Duration};
+
+    use super::*;
+
+    #[test]
+    fn uninstalled_and_old_icons_are_evicted_first() {
+        let temp = tempfile::tempdir().unwrap();
+        let [uninstalled, old, new] = [Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4()];
+
+        // the uninstalled icon is the newest, but it's still removed first
+        for uuid in [old, new, uninstalled] {
+            fs::write(path(temp.path(), uuid), [0; 100]).unwrap();
+            thread::sleep(Duration::from_millis(20));
+        }
+
+        let installed = HashSet::from([old, new]);
+
+        evict(temp.path(), &installed, 300).unwrap();
+        assert!(path(temp.path(), uninstalled).exists());
+
+        evict(temp.path(), &installed, 200).unwrap();
+        assert!(!path(temp.path(), uninstalled).exists());
+
+        evict(temp.path(), &installed, 100).unwrap();
+        assert!(!path(temp.path(), old).exists());
+        assert!(path(temp.path(), new).exists());
+    }
+
+    #[test]
+    fn reading_marks_icon_as_used() {
+        let temp = tempfile::tempdir().unwrap();
+        let [old, new] = [Uuid::new_v4(), Uuid::new_v4()];
+
+        for uuid in [old, new] {
+            fs::write(path(temp.path(), uuid), [0; 100]).unwrap();
+            thread::sleep(Duration::from_millis(20));
+        }
+
+        read_cached(&path(temp.path(), old)).unwrap().unwrap();
+        evict(temp.path(), &HashSet::new(), 100).unwrap();
+
+        assert!(path(temp.path(), old).exists());
+        assert!(!path(temp.path(), new).exists());
+    }
+
+    #[test]
+    fn paths_are_parsed() {
+        let [package, version] = [Uuid::new_v4(), Uuid::new_v4()];
+
+        for path in [
+            format!("/{}/{}", package, version),
+            format!("/{}%2F{}", package, version),
+        ] {
+            let id = parse_path(&path).unwrap();
+            assert_eq!(id.package_uuid, package);
+            assert_eq!(id.version_uuid, version);
+        }
+
+        assert!(parse_path("/icon.png").is_none());
+        assert!(parse_path(&format!("/{}", package)).is_none());
+    }
+
+    #[test]
+    fn content_type_is_sniffed() {
+        assert_eq!(content_type(b"\x89PNG\r\n\x1a\n...."), "image/png");
+        assert_eq!(content_type(b"RIFF\0\0\0\0WEBPVP8 "), "image/webp");
+        assert_eq!(content_type(b"<html>"), "application/octet-stream");
+    }
+}
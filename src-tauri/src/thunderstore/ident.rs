@@ -3,17 +3,23 @@ use std::{
     fmt::{self, Debug, Display},
     hash::Hash,
     str::FromStr,
+    sync::Arc,
 };
 
 use serde::{Deserialize, Serialize};
 
+use super::intern::Interner;
+
 /// A unique identifier for a specific version of a package.
 ///
 /// Often formatted as `owner-name-version`, also known as a dependency string.
+///
+/// The string is shared between clones, and identical identifiers in the
+/// package index share it as well, see [`VersionIdent::intern`].
 #[derive(Eq, Clone, Serialize, Deserialize)]
 #[serde(into = "String", try_from = "String")]
 pub struct VersionIdent {
-    repr: String,
+    repr: Arc<str>,
     name_start: u32,
     version_start: u32,
 }
@@ -29,7 +35,7 @@ impl VersionIdent {
         let version_start = name_start + name.len() as u32 + 1;
 
         Self {
-            repr,
+            repr: repr.into(),
             name_start,
             version_start,
         }
@@ -61,13 +67,21 @@ impl VersionIdent {
 
     #[inline]
     pub fn into_string(self) -> String {
-        self.repr
+        self.repr.to_string()
     }
 
     #[inline]
     pub fn as_str(&self) -> &str {
         &self.repr
     }
+
+    /// Shares the string with an identical identifier in `interner`.
+    ///
+    /// The same dependencies, and their owners, are repeated across
+    /// thousands of versions in the package index.
+    pub(super) fn intern(&mut self, interner: &mut Interner) {
+        interner.intern_in_place(&mut self.repr);
+    }
 }
 
 impl PartialEq for VersionIdent {
@@ -132,7 +146,7 @@ impl TryFrom<String> for VersionIdent {
 
     /// Parses a string into a `VersionIdent`.
     ///
-    /// This does not allocate or copy memory.
+    /// This moves the string into a shared allocation.
     fn try_from(value: String) -> Result<Self, ParseError> {
         let mut indices = value.match_indices('-').map(|(i, _)| i);
 
@@ -140,7 +154,7 @@ impl TryFrom<String> for VersionIdent {
         let name_start = indices.next_back().ok_or(ParseError)? as u32 + 1;
 
         Ok(Self {
-            repr: value,
+            repr: value.into(),
             name_start,
             version_start,
         })
@@ -322,17 +336,12 @@ impl From<(&str, &str)> for PackageIdent {
 impl From<VersionIdent> for PackageIdent {
     /// Converts a VersionIdent to a PackageIdent, discarding the version.
     ///
-    /// This shrinks the existing string, which may or may not reallocate
-    /// depending on the allocator.
+    /// This copies the full name into a new string.
     fn from(id: VersionIdent) -> Self {
-        let version_start = id.version_start;
-        let name_start = id.name_start;
-
-        let mut repr = id.into_string();
-        repr.truncate(version_start as usize - 1);
-        repr.shrink_to_fit();
-
-        Self { repr, name_start }
+        Self {
+            repr: id.full_name().to_owned(),
+            name_start: id.name_start,
+        }
     }
 }
 
@@ -0,0 +1,25 @@
+use std::{collections::HashSet, sync::Arc};
+
+/// Deduplicates strings by handing out shared references to
+/// the first occurrence of each unique value.
+///
+/// This is only meant to live while a batch of packages is being read;
+/// the interned strings stay shared after it's dropped.
+#[derive(Default)]
+pub struct Interner(HashSet<Arc<str>>);
+
+impl Interner {
+    pub fn intern(&mut self, value: Arc<str>) -> Arc<str> {
+        match self.0.get(&value) {
+            Some(existing) => Arc::clone(existing),
+            None => {
+                self.0.insert(Arc::clone(&value));
+                value
+            }
+        }
+    }
+
+    pub fn intern_in_place(&mut self, value: &mut Arc<str>) {
+        *value = self.intern(Arc::clone(value));
+    }
+}
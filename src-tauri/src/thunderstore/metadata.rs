@@ -0,0 +1,131 @@
+//! A copy of the index's metadata for installed mods, so the mod list of
+//! a profile can still be shown when the index can't be fetched.
+//!
+//! Their icons are prefetched into the [icon cache](super::icons).
+
+use std::collections::HashMap;
+
+use eyre::Result;
+use itertools::Itertools;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use uuid::Uuid;
+
+use super::{icons, BorrowedMod, ModId, Thunderstore};
+use crate::{game::Game, state::ManagerExt};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModMetadata {
+    pub description: String,
+    pub icon_url: String,
+    pub categories: Vec<String>,
+    pub author: String,
+}
+
+impl ModMetadata {
+    pub fn new(borrowed: BorrowedMod<'_>) -> Self {
+        Self {
+            description: borrowed.version.description.to_string(),
+            icon_url: icons::url(borrowed.ident()),
+            categories: borrowed
+                .package
+                .categories
+                .iter()
+                .map(|category| category.to_string())
+                .sorted()
+                .collect(),
+            author: borrowed.package.owner().to_owned(),
+        }
+    }
+}
+
+impl Thunderstore {
+    /// Returns the last known metadata of an installed package, which is
+    /// used when the package isn't in the index.
+    pub fn cached_metadata(&self, package_uuid: Uuid) -> Option<&ModMetadata> {
+        self.metadata.get(&package_uuid)
+    }
+
+    pub fn metadata_mut(&mut self) -> &mut HashMap<Uuid, ModMetadata> {
+        &mut self.metadata
+    }
+
+    /// Copies the metadata of `mods` from the index, returning the
+    /// packages whose metadata changed.
+    ///
+    /// Mods that aren't in the index keep their cached metadata.
+    fn cache_metadata<'a>(&mut self, mods: impl IntoIterator<Item = &'a ModId>) -> Vec<Uuid> {
+        let changed = mods
+            .into_iter()
+            .filter_map(|id| id.borrow(self).ok())
+            .map(|borrowed| (borrowed.package.uuid, ModMetadata::new(borrowed)))
+            .unique_by(|(uuid, _)| *uuid)
+            .filter(|(uuid, metadata)| self.metadata.get(uuid) != Some(metadata))
+            .collect_vec();
+
+        changed
+            .into_iter()
+            .map(|(uuid, metadata)| {
+                self.metadata.insert(uuid, metadata);
+                uuid
+            })
+            .collect()
+    }
+}
+
+/// Updates the cached metadata of the mods installed in `game`'s profiles
+/// from the index, and fetches their missing icons in the background.
+///
+/// Failures are only logged, since the cache is only needed when
+/// the index is unavailable.
+pub fn update(game: Game, app: &AppHandle) {
+    if let Err(err) = try_update(game, app) {
+        warn!("failed to update mod metadata cache: {:#}", err);
+    }
+}
+
+fn try_update(game: Game, app: &AppHandle) -> Result<()> {
+    let icon_dir = app.lock_prefs()?.icon_cache_dir();
+    let manager = app.lock_manager()?;
+    let mut thunderstore = app.lock_thunderstore()?;
+
+    let Some(managed_game) = manager.games.get(game) else {
+        return Ok(());
+    };
+
+    let ids = managed_game
+        .profiles
+        .iter()
+        .flat_map(|profile| &profile.mods)
+        .filter_map(|profile_mod| profile_mod.kind.as_thunderstore())
+        .map(|ts_mod| ts_mod.id.clone())
+        .unique_by(|id| id.version_uuid)
+        .collect_vec();
+
+    let changed = thunderstore.cache_metadata(&ids);
+
+    if !changed.is_empty() {
+        debug!("updating cached metadata of {} mods", changed.len());
+
+        app.db().save_mod_metadata(
+            changed
+                .iter()
+                .map(|uuid| (*uuid, &thunderstore.metadata[uuid])),
+        )?;
+    }
+
+    let missing = ids
+        .iter()
+        .filter(|id| !icons::path(&icon_dir, id.version_uuid).exists())
+        .filter_map(|id| id.borrow(&thunderstore).ok())
+        .map(|borrowed| (borrowed.version.uuid, icons::url(borrowed.ident())))
+        .collect_vec();
+
+    if !missing.is_empty() {
+        icons::prefetch(missing, app);
+    }
+
+    Ok(())
+}
@@ -1,14 +1,15 @@
 use std::{
-    collections::{HashSet, VecDeque},
+    collections::{HashMap, HashSet, VecDeque},
     iter::FusedIterator,
-    path::PathBuf,
+    path::{Path, PathBuf},
     str::{self},
     time::Instant,
 };
 
+use chrono::{DateTime, Utc};
 use eyre::{eyre, Context, Result};
 use indexmap::IndexMap;
-use log::{debug, info};
+use log::{debug, info, warn};
 use query::QueryModsArgs;
 use serde::{Deserialize, Serialize};
 use tauri::{async_runtime::JoinHandle, AppHandle};
@@ -21,25 +22,47 @@ use crate::{
     util::{self, fs::JsonStyle},
 };
 
+pub mod api;
+pub mod blocklist;
 pub mod commands;
+pub mod icons;
 pub mod query;
 pub mod token;
 
+mod communities;
+mod downloads;
+mod owned;
+
+pub mod metadata;
+pub use metadata::ModMetadata;
+
+use blocklist::Blocklist;
+
 mod fetch;
 pub use fetch::wait_for_fetch;
 
+mod intern;
+
 mod models;
 pub use models::*;
 
 mod ident;
 pub use ident::*;
 
-pub fn start(app: &AppHandle) {
+#[cfg(test)]
+pub(crate) mod tests;
+
+pub fn start(app: &AppHandle) -> Result<()> {
     query::setup(app);
-    app.lock_thunderstore()
-        .switch_game(app.lock_manager().active_game, app.clone());
+    app.lock_thunderstore()?
+        .switch_game(app.lock_manager()?.active_game, app.clone());
+
+    Ok(())
 }
 
+/// How many times a package may have been renamed before aliases give up.
+const MAX_ALIAS_DEPTH: usize = 8;
+
 /// A pair of a package and one of its versions.
 ///
 /// This is tied to the lifetime of the `Thunderstore` struct and thus
@@ -116,15 +139,47 @@ pub struct Thunderstore {
     // since we iterate over all mods when resolving identifiers and querying.
     packages: IndexMap<Uuid, PackageListing>,
     current_query: Option<QueryModsArgs>,
+    /// When packages were last successfully fetched for each game.
+    ///
+    /// This is kept across calls to [`Thunderstore::switch_game`].
+    last_fetched: HashMap<Game, DateTime<Utc>>,
+    /// Maps the old full names of renamed or transferred packages to their current ones.
+    ///
+    /// This is kept across calls to [`Thunderstore::switch_game`].
+    aliases: HashMap<String, String>,
+    /// The last known metadata of installed packages, see [`metadata`].
+    ///
+    /// This is kept across calls to [`Thunderstore::switch_game`].
+    metadata: HashMap<Uuid, ModMetadata>,
+    /// Packages flagged by the community, see [`blocklist`].
+    ///
+    /// This is kept across calls to [`Thunderstore::switch_game`].
+    blocklist: Blocklist,
 }
 
 impl Thunderstore {
+    /// Creates a registry which only holds `packages`, for example to
+    /// check a game's mods without switching to it.
+    pub fn from_packages(packages: impl IntoIterator<Item = PackageListing>) -> Self {
+        Self {
+            packages: packages
+                .into_iter()
+                .map(|package| (package.uuid, package))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
     /// Whether packages have been succesfully fetched at least one since
     /// the last call to [`Thunderstore::switch_game`].
     pub fn packages_fetched(&self) -> bool {
         self.packages_fetched
     }
 
+    pub fn last_fetched(&self, game: Game) -> Option<DateTime<Utc>> {
+        self.last_fetched.get(game).copied()
+    }
+
     /// Returns an iterator over the lastest versions of every package.
     pub fn latest(&self) -> impl Iterator<Item = BorrowedMod<'_>> {
         self.packages.values().map(move |package| BorrowedMod {
@@ -139,11 +194,49 @@ impl Thunderstore {
             .ok_or_else(|| eyre!("package with id {} not found", uuid))
     }
 
-    /// Finds a package with the given `full_name` (formatted as `owner-name`).
-    pub fn find_package<'a>(&'a self, full_name: &str) -> Result<&'a PackageListing> {
+    pub fn aliases(&self) -> &HashMap<String, String> {
+        &self.aliases
+    }
+
+    pub fn aliases_mut(&mut self) -> &mut HashMap<String, String> {
+        &mut self.aliases
+    }
+
+    fn find_exact(&self, full_name: &str) -> Option<&PackageListing> {
         self.packages
             .values()
             .find(|package| package.ident.as_str() == full_name)
+    }
+
+    /// Looks up the current name of a renamed package in [`Thunderstore::aliases`].
+    ///
+    /// Aliases are followed until an existing package is found, in case
+    /// a package has been renamed multiple times.
+    fn find_alias(&self, full_name: &str) -> Option<&PackageListing> {
+        let mut current = full_name;
+
+        for _ in 0..MAX_ALIAS_DEPTH {
+            current = self.aliases.get(current)?;
+
+            if let Some(package) = self.find_exact(current) {
+                debug!("resolved renamed package {} as {}", full_name, current);
+                return Some(package);
+            }
+        }
+
+        warn!(
+            "too many aliases for package {}, is there a cycle?",
+            full_name
+        );
+        None
+    }
+
+    /// Finds a package with the given `full_name` (formatted as `owner-name`).
+    ///
+    /// If no package has the name, it's looked up in [`Thunderstore::aliases`].
+    pub fn find_package<'a>(&'a self, full_name: &str) -> Result<&'a PackageListing> {
+        self.find_exact(full_name)
+            .or_else(|| self.find_alias(full_name))
             .ok_or_else(|| eyre!("package {} not found", full_name))
     }
 
@@ -174,6 +267,7 @@ impl Thunderstore {
             .packages
             .values()
             .find(|package| package.owner() == owner && package.name() == name)
+            .or_else(|| self.find_alias(&format!("{}-{}", owner, name)))
             .ok_or_else(|| eyre!("package {}-{} not found", owner, name))?;
 
         let version = package.get_version_with_num(version).ok_or_else(|| {
@@ -210,9 +304,64 @@ impl Thunderstore {
 pub struct Dependencies<'a> {
     queue: VecDeque<&'a VersionIdent>,
     visited: HashSet<&'a str>,
+    /// The dependencies of each package yielded so far.
+    edges: HashMap<&'a str, &'a [VersionIdent]>,
+    /// Packages which are skipped, along with their dependencies.
+    excluded: HashSet<Uuid>,
     thunderstore: &'a Thunderstore,
 }
 
+impl<'a> Dependencies<'a> {
+    /// Skips the given packages. Their dependencies are only
+    /// yielded if another package depends on them as well.
+    pub fn exclude(mut self, uuids: impl IntoIterator<Item = Uuid>) -> Self {
+        self.excluded.extend(uuids);
+        self
+    }
+
+    /// Checks if `package` can be reached from `dependency`, in which case
+    /// depending on it closes a cycle. Only the packages yielded so far are
+    /// searched, so a cycle is found once its last package is reached.
+    ///
+    /// Returns the packages that make up the cycle, starting and ending with `dependency`.
+    fn find_cycle(&self, package: &'a str, dependency: &'a str) -> Option<Vec<&'a str>> {
+        let mut path = self.find_path(dependency, package, &mut HashSet::new())?;
+        path.push(dependency);
+        Some(path)
+    }
+
+    /// Depth-first search for a path from `from` to `to`. `on_stack` holds the
+    /// packages on the current path, so other cycles don't loop forever.
+    fn find_path(
+        &self,
+        from: &'a str,
+        to: &'a str,
+        on_stack: &mut HashSet<&'a str>,
+    ) -> Option<Vec<&'a str>> {
+        if from == to {
+            return Some(vec![to]);
+        }
+
+        if !on_stack.insert(from) {
+            return None;
+        }
+
+        let path = self
+            .edges
+            .get(from)
+            .into_iter()
+            .flat_map(|deps| deps.iter())
+            .find_map(|dep| self.find_path(dep.full_name(), to, on_stack))
+            .map(|mut path| {
+                path.insert(0, from);
+                path
+            });
+
+        on_stack.remove(from);
+        path
+    }
+}
+
 impl<'a> Iterator for Dependencies<'a> {
     type Item = BorrowedMod<'a>;
 
@@ -223,8 +372,22 @@ impl<'a> Iterator for Dependencies<'a> {
                 continue;
             };
 
+            if self.excluded.contains(&current.package.uuid) {
+                continue;
+            }
+
+            let name = current.package.full_name();
+            self.edges.insert(name, &current.version.dependencies);
+
             for dependency in &current.version.dependencies {
-                if !self.visited.insert(dependency.full_name()) {
+                let dep_name = dependency.full_name();
+
+                if !self.visited.insert(dep_name) {
+                    // the package has already been yielded, so cycles are broken here
+                    if let Some(cycle) = self.find_cycle(name, dep_name) {
+                        warn!("circular dependency detected: {}", cycle.join(" -> "));
+                    }
+
                     continue;
                 }
 
@@ -245,6 +408,9 @@ impl Thunderstore {
     /// Duplicates of the same package are removed. The specific
     /// version of a package that is chosen depends on which
     /// is encountered first.
+    ///
+    /// Circular dependencies are broken at the first package that is
+    /// encountered twice and logged as a warning.
     pub fn dependencies<'a>(
         &'a self,
         idents: impl IntoIterator<Item = &'a VersionIdent>,
@@ -258,23 +424,36 @@ impl Thunderstore {
         Dependencies {
             queue,
             visited,
+            edges: HashMap::new(),
+            excluded: HashSet::new(),
             thunderstore: self,
         }
     }
 }
 
 pub fn read_cache(manager: &ModManager) -> Result<Option<Vec<PackageListing>>> {
+    read_cache_at(&manager.active_game().path)
+}
+
+/// Reads the package cache of the game whose data is stored in `game_dir`.
+pub fn read_cache_at(game_dir: &Path) -> Result<Option<Vec<PackageListing>>> {
     let start = Instant::now();
-    let path = cache_path(manager);
+    let path = cache_path(game_dir);
 
     if !path.exists() {
         info!("no cache file found at {}", path.display());
         return Ok(None);
     }
 
-    let result: Vec<PackageListing> =
+    let mut result: Vec<PackageListing> =
         util::fs::read_json(path).context("failed to deserialize cache")?;
 
+    let mut interner = intern::Interner::default();
+    for package in &mut result {
+        package.intern_strings(&mut interner);
+        package.classify();
+    }
+
     info!(
         "read {} packages from cache in {:?}",
         result.len(),
@@ -292,8 +471,12 @@ pub fn write_cache(packages: &[&PackageListing], manager: &ModManager) -> Result
 
     let start = Instant::now();
 
-    util::fs::write_json(cache_path(manager), packages, JsonStyle::Compact)
-        .context("failed to write mod cache")?;
+    util::fs::write_json(
+        cache_path(&manager.active_game().path),
+        packages,
+        JsonStyle::Compact,
+    )
+    .context("failed to write mod cache")?;
 
     debug!(
         "wrote {} packages to cache in {:?}",
@@ -304,6 +487,6 @@ pub fn write_cache(packages: &[&PackageListing], manager: &ModManager) -> Result
     Ok(())
 }
 
-fn cache_path(manager: &ModManager) -> PathBuf {
-    manager.active_game().path.join("thunderstore_cache.json")
+fn cache_path(game_dir: &Path) -> PathBuf {
+    game_dir.join("thunderstore_cache.json")
 }
@@ -2,20 +2,24 @@ use std::{
     collections::{HashMap, HashSet},
     hash::Hash,
     path::PathBuf,
+    sync::Arc,
 };
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use super::{PackageIdent, VersionIdent};
-use crate::{game::Game, profile::Profile};
+use super::{intern::Interner, PackageIdent, VersionIdent};
+use crate::{
+    game::Game,
+    profile::{ModSide, Profile},
+};
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq)]
 pub struct PackageListing {
     #[serde(rename = "full_name")]
     pub ident: PackageIdent,
-    pub categories: HashSet<String>,
+    pub categories: HashSet<Arc<str>>,
     pub date_created: DateTime<Utc>,
     pub date_updated: DateTime<Utc>,
     pub donation_link: Option<String>,
@@ -27,6 +31,19 @@ pub struct PackageListing {
     #[serde(rename = "uuid4")]
     pub uuid: Uuid,
     pub versions: Vec<PackageVersion>,
+    /// Derived from the categories and latest version, see [`PackageListing::classify`].
+    #[serde(skip)]
+    pub kind: PackageKind,
+}
+
+/// What a package is used for, beyond its categories.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PackageKind {
+    #[default]
+    Mod,
+    /// A package that other mods depend on, but does little on its own.
+    Library,
+    Modpack,
 }
 
 impl PackageListing {
@@ -47,7 +64,31 @@ impl PackageListing {
     }
 
     pub fn is_modpack(&self) -> bool {
-        self.categories.contains("Modpacks")
+        self.kind == PackageKind::Modpack
+    }
+
+    pub fn is_library(&self) -> bool {
+        self.kind == PackageKind::Library
+    }
+
+    /// Sets [`PackageListing::kind`]. This should be called once the package is loaded.
+    ///
+    /// Not every library is in the `Libraries` category, so packages
+    /// without dependencies and with `Lib` in their name count as well.
+    pub(super) fn classify(&mut self) {
+        self.kind = if self.has_category("Modpacks") {
+            PackageKind::Modpack
+        } else if self.has_category("Libraries")
+            || (self.name().contains("Lib") && self.latest().dependencies.is_empty())
+        {
+            PackageKind::Library
+        } else {
+            PackageKind::Mod
+        };
+    }
+
+    pub fn has_category(&self, category: &str) -> bool {
+        self.categories.contains(category)
     }
 
     pub fn get_version(&self, uuid: Uuid) -> Option<&PackageVersion> {
@@ -58,10 +99,47 @@ impl PackageListing {
         self.versions.iter().find(|v| v.version() == version)
     }
 
+    /// Finds the version closest to `version`, preferring newer versions over older ones.
+    pub fn get_nearest_version(&self, version: &semver::Version) -> Option<&PackageVersion> {
+        let (older, newer): (Vec<_>, Vec<_>) = self
+            .versions
+            .iter()
+            .partition(|v| v.parsed_version() < *version);
+
+        newer
+            .into_iter()
+            .min_by_key(|v| v.parsed_version())
+            .or_else(|| older.into_iter().max_by_key(|v| v.parsed_version()))
+    }
+
     pub fn total_downloads(&self) -> u32 {
         self.versions.iter().map(|v| v.downloads).sum()
     }
 
+    /// Replaces repeated strings with shared references from `interner`.
+    ///
+    /// Categories are shared between most packages, and descriptions and website
+    /// urls rarely change between versions, so this saves a lot of memory
+    /// for communities with large amounts of packages. The same goes for
+    /// dependency strings, which also covers the owners of popular packages.
+    pub(super) fn intern_strings(&mut self, interner: &mut Interner) {
+        self.categories = self
+            .categories
+            .drain()
+            .map(|category| interner.intern(category))
+            .collect();
+
+        for version in &mut self.versions {
+            interner.intern_in_place(&mut version.description);
+            interner.intern_in_place(&mut version.website_url);
+
+            version.ident.intern(interner);
+            for dependency in &mut version.dependencies {
+                dependency.intern(interner);
+            }
+        }
+    }
+
     pub fn owner_url(&self, game: Game) -> String {
         format!(
             "https://thunderstore.io/c/{}/p/{}/",
@@ -98,13 +176,13 @@ pub struct PackageVersion {
     pub ident: VersionIdent,
     pub date_created: DateTime<Utc>,
     pub dependencies: Vec<VersionIdent>,
-    pub description: String,
+    pub description: Arc<str>,
     pub downloads: u32,
     pub file_size: u64,
     pub is_active: bool,
     #[serde(rename = "uuid4")]
     pub uuid: Uuid,
-    pub website_url: String,
+    pub website_url: Arc<str>,
 }
 
 impl PackageVersion {
@@ -262,6 +340,8 @@ pub struct FrontendMod {
     pub dependencies: Option<Vec<VersionIdent>>,
     pub is_pinned: bool,
     pub is_deprecated: bool,
+    pub is_modpack: bool,
+    pub is_library: bool,
     pub contains_nsfw: bool,
     pub uuid: Uuid,
     pub version_uuid: Uuid,
@@ -285,6 +365,10 @@ pub struct FrontendVersion {
 pub struct FrontendProfileMod {
     pub enabled: bool,
     pub config_file: Option<PathBuf>,
+    pub has_config: bool,
+    pub side: ModSide,
+    /// Whether the side was set by the user, instead of being guessed.
+    pub side_overridden: bool,
     #[serde(flatten)]
     pub data: FrontendMod,
 }
@@ -0,0 +1,82 @@
+use std::{
+    sync::{Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use eyre::{bail, OptionExt, Result};
+use log::debug;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use tauri::AppHandle;
+
+use super::{
+    api::{self, Endpoint},
+    token, BorrowedMod, Thunderstore,
+};
+use crate::{state::ManagerExt, util::cmd::UserError};
+
+const CACHE_DURATION: Duration = Duration::from_secs(60 * 5);
+
+static CACHED_TEAMS: Mutex<Option<CachedTeams>> = Mutex::new(None);
+
+struct CachedTeams {
+    /// The token the teams were fetched with, so we can tell if it was changed.
+    token: String,
+    teams: Vec<String>,
+    fetched_at: Instant,
+}
+
+#[derive(Deserialize)]
+struct CurrentUser {
+    #[serde(default)]
+    teams: Vec<String>,
+}
+
+/// Gets the names of the teams the current Thunderstore token belongs to.
+///
+/// Results are cached for a few minutes, unless `refresh` is set.
+pub async fn teams(refresh: bool, app: &AppHandle) -> Result<Vec<String>> {
+    const URL: &str = "https://thunderstore.io/api/experimental/current-user/";
+
+    let token = token::get()?.ok_or_eyre("no thunderstore API token found")?;
+
+    if !refresh {
+        let cached = CACHED_TEAMS.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if let Some(cached) = &*cached {
+            if cached.token == token && cached.fetched_at.elapsed() < CACHE_DURATION {
+                return Ok(cached.teams.clone());
+            }
+        }
+    }
+
+    debug!("fetching teams of current user");
+
+    let request = app.http().get(URL).bearer_auth(&token);
+    let response = api::send(Endpoint::Account, request).await?;
+
+    if response.status() == StatusCode::UNAUTHORIZED {
+        bail!(UserError::TokenInvalid);
+    }
+
+    let user: CurrentUser = response.error_for_status()?.json().await?;
+
+    *CACHED_TEAMS.lock().unwrap_or_else(PoisonError::into_inner) = Some(CachedTeams {
+        token,
+        teams: user.teams.clone(),
+        fetched_at: Instant::now(),
+    });
+
+    Ok(user.teams)
+}
+
+/// Finds the packages owned by any of `teams`.
+pub fn owned_by<'a>(
+    teams: &'a [String],
+    thunderstore: &'a Thunderstore,
+) -> impl Iterator<Item = BorrowedMod<'a>> + 'a {
+    thunderstore.latest().filter(move |borrowed| {
+        let owner = borrowed.package.owner();
+        teams.iter().any(|team| team.eq_ignore_ascii_case(owner))
+    })
+}
@@ -1,5 +1,6 @@
 use std::{cmp::Ordering, collections::HashSet, time::Duration};
 
+use chrono::{DateTime, Utc};
 use eyre::Result;
 use itertools::Itertools;
 use log::info;
@@ -11,6 +12,7 @@ use super::{
     BorrowedMod,
 };
 use crate::{
+    game::Game,
     profile::{LocalMod, Profile},
     state::ManagerExt,
     util,
@@ -52,19 +54,41 @@ pub struct QueryModsArgs {
     pub include_deprecated: bool,
     pub include_disabled: bool,
     pub include_enabled: bool,
+    /// Only show packages in the `Modpacks` category.
+    #[serde(default)]
+    pub modpacks_only: bool,
+    /// Hide libraries, see [`PackageListing::classify`].
+    ///
+    /// [`PackageListing::classify`]: super::PackageListing::classify
+    #[serde(default)]
+    pub hide_libraries: bool,
     pub sort_by: SortBy,
     pub sort_order: SortOrder,
 }
 
+impl QueryModsArgs {
+    /// Returns the category filters which aren't known for `game`.
+    ///
+    /// These are still applied, since Thunderstore may have added
+    /// categories that aren't in the game's list yet.
+    pub fn unknown_categories<'a>(&'a self, game: Game) -> impl Iterator<Item = &'a str> + 'a {
+        self.include_categories
+            .iter()
+            .chain(&self.exclude_categories)
+            .map(String::as_str)
+            .filter(move |category| !game.has_category(category))
+    }
+}
+
 pub async fn query_loop(app: AppHandle) -> Result<()> {
     const INTERVAL: Duration = Duration::from_millis(500);
 
     loop {
         {
-            let mut thunderstore = app.lock_thunderstore();
+            let mut thunderstore = app.lock_thunderstore()?;
 
             if let Some(args) = &thunderstore.current_query {
-                let manager = app.lock_manager();
+                let manager = app.lock_manager()?;
 
                 let mods =
                     query_frontend_mods(args, thunderstore.latest(), manager.active_profile());
@@ -113,18 +137,25 @@ impl Queryable for BorrowedMod<'_> {
 
         if !args.include_nsfw && pkg.has_nsfw_content
             || !args.include_deprecated && pkg.is_deprecated
+            || args.modpacks_only && !pkg.is_modpack()
+            || args.hide_libraries && pkg.is_library()
         {
             return false;
         }
 
         if !args.include_categories.is_empty()
-            && args.include_categories.is_disjoint(&pkg.categories)
+            && !args
+                .include_categories
+                .iter()
+                .any(|category| pkg.has_category(category))
         {
             return false;
         }
 
-        if !args.exclude_categories.is_empty()
-            && !args.exclude_categories.is_disjoint(&pkg.categories)
+        if args
+            .exclude_categories
+            .iter()
+            .any(|category| pkg.has_category(category))
         {
             return false;
         }
@@ -162,21 +193,28 @@ impl IntoFrontendMod for BorrowedMod<'_> {
         let vers = pkg.get_version(self.version.uuid).unwrap();
         FrontendMod {
             name: pkg.name().to_owned(),
-            description: Some(vers.description.clone()),
+            description: Some(vers.description.to_string()),
             version: Some(vers.parsed_version()),
-            categories: Some(pkg.categories.iter().cloned().collect()),
+            categories: Some(
+                pkg.categories
+                    .iter()
+                    .map(|category| category.to_string())
+                    .collect(),
+            ),
             author: Some(pkg.owner().to_owned()),
             rating: Some(pkg.rating_score),
             downloads: Some(pkg.total_downloads()),
             file_size: vers.file_size,
             website_url: match vers.website_url.is_empty() {
                 true => None,
-                false => Some(vers.website_url.clone()),
+                false => Some(vers.website_url.to_string()),
             },
             donate_url: pkg.donation_link.clone(),
             dependencies: Some(vers.dependencies.clone()),
             is_pinned: pkg.is_pinned,
             is_deprecated: pkg.is_deprecated,
+            is_modpack: pkg.is_modpack(),
+            is_library: pkg.is_library(),
             contains_nsfw: pkg.has_nsfw_content,
             uuid: pkg.uuid,
             version_uuid: vers.uuid,
@@ -278,3 +316,51 @@ where
     results.sort_by(|a, b| a.cmp(b, args));
     results.into_iter().take(args.max_count)
 }
+
+/// How recently a package must have been updated to show up in [`Discovery::top_rated`].
+///
+/// Thunderstore only exposes a lifetime rating score, so this keeps
+/// long abandoned packages from crowding out the current favourites.
+const RECENT_RATING_WINDOW: chrono::Duration = chrono::Duration::days(90);
+
+/// Popular mods of the active game, as a starting point for new users.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Discovery {
+    pub most_downloaded: Vec<FrontendMod>,
+    pub top_rated: Vec<FrontendMod>,
+}
+
+/// Picks the `count` most downloaded and best rated mods out of `mods`.
+///
+/// Deprecated and NSFW packages are skipped, as well as libraries and
+/// modpacks since they're rarely what a new user is looking for.
+pub fn discover<'a>(
+    mods: impl Iterator<Item = BorrowedMod<'a>>,
+    count: usize,
+    now: DateTime<Utc>,
+) -> (Vec<BorrowedMod<'a>>, Vec<BorrowedMod<'a>>) {
+    let candidates = mods
+        .filter(|borrowed| {
+            let pkg = borrowed.package;
+
+            !pkg.is_deprecated && !pkg.has_nsfw_content && !pkg.is_library() && !pkg.is_modpack()
+        })
+        .collect_vec();
+
+    let most_downloaded = candidates
+        .iter()
+        .copied()
+        .sorted_by_key(|borrowed| std::cmp::Reverse(borrowed.package.total_downloads()))
+        .take(count)
+        .collect();
+
+    let top_rated = candidates
+        .into_iter()
+        .filter(|borrowed| now - borrowed.package.date_updated <= RECENT_RATING_WINDOW)
+        .sorted_by_key(|borrowed| std::cmp::Reverse(borrowed.package.rating_score))
+        .take(count)
+        .collect();
+
+    (most_downloaded, top_rated)
+}
@@ -0,0 +1,272 @@
+use std::{collections::HashSet, sync::Arc};
+
+use chrono::Utc;
+use itertools::Itertools;
+use uuid::Uuid;
+
+use super::*;
+
+pub(crate) fn test_package(name: &str, dependencies: &[&str]) -> PackageListing {
+    let version = PackageVersion {
+        ident: VersionIdent::new("Test", name, "1.0.0"),
+        date_created: Utc::now(),
+        dependencies: dependencies
+            .iter()
+            .map(|dep| VersionIdent::new("Test", dep, "1.0.0"))
+            .collect(),
+        description: Arc::from(""),
+        downloads: 0,
+        file_size: 0,
+        is_active: true,
+        uuid: Uuid::new_v4(),
+        website_url: Arc::from(""),
+    };
+
+    PackageListing {
+        ident: PackageIdent::new("Test", name),
+        categories: HashSet::new(),
+        date_created: Utc::now(),
+        date_updated: Utc::now(),
+        donation_link: None,
+        has_nsfw_content: false,
+        is_deprecated: false,
+        is_pinned: false,
+        package_url: String::new(),
+        rating_score: 0,
+        uuid: Uuid::new_v4(),
+        versions: vec![version],
+        kind: PackageKind::Mod,
+    }
+}
+
+pub(crate) fn test_thunderstore(
+    packages: impl IntoIterator<Item = PackageListing>,
+) -> Thunderstore {
+    Thunderstore {
+        packages: packages
+            .into_iter()
+            .map(|package| (package.uuid, package))
+            .collect(),
+        ..Default::default()
+    }
+}
+
+fn full_names<'a>(deps: impl Iterator<Item = BorrowedMod<'a>>) -> Vec<&'a str> {
+    deps.map(|borrowed| borrowed.package.full_name())
+        .collect_vec()
+}
+
+#[test]
+fn dependencies_are_sorted_by_depth() {
+    let thunderstore = test_thunderstore([
+        test_package("A", &["B", "C"]),
+        test_package("B", &["D"]),
+        test_package("C", &["D"]),
+        test_package("D", &[]),
+    ]);
+
+    let root = VersionIdent::new("Test", "A", "1.0.0");
+    let deps = full_names(thunderstore.dependencies([&root]));
+
+    assert_eq!(deps, ["Test-A", "Test-B", "Test-C", "Test-D"]);
+}
+
+#[test]
+fn circular_dependencies_are_broken() {
+    let thunderstore = test_thunderstore([test_package("A", &["B"]), test_package("B", &["A"])]);
+
+    let root = VersionIdent::new("Test", "A", "1.0.0");
+    let mut deps = thunderstore.dependencies([&root]);
+
+    assert_eq!(full_names(deps.by_ref()), ["Test-A", "Test-B"]);
+    assert_eq!(
+        deps.find_cycle("Test-B", "Test-A"),
+        Some(vec!["Test-A", "Test-B", "Test-A"])
+    );
+}
+
+#[test]
+fn self_dependency_is_a_cycle() {
+    let thunderstore = test_thunderstore([test_package("A", &["A"])]);
+
+    let root = VersionIdent::new("Test", "A", "1.0.0");
+    let mut deps = thunderstore.dependencies([&root]);
+
+    assert_eq!(full_names(deps.by_ref()), ["Test-A"]);
+    assert_eq!(
+        deps.find_cycle("Test-A", "Test-A"),
+        Some(vec!["Test-A", "Test-A"])
+    );
+}
+
+#[test]
+fn cycles_between_siblings_are_found() {
+    let thunderstore = test_thunderstore([
+        test_package("A", &["B", "C"]),
+        test_package("B", &["C"]),
+        test_package("C", &["B"]),
+    ]);
+
+    let root = VersionIdent::new("Test", "A", "1.0.0");
+    let mut deps = thunderstore.dependencies([&root]);
+
+    assert_eq!(full_names(deps.by_ref()), ["Test-A", "Test-B", "Test-C"]);
+    assert_eq!(
+        deps.find_cycle("Test-C", "Test-B"),
+        Some(vec!["Test-B", "Test-C", "Test-B"])
+    );
+}
+
+#[test]
+fn shared_dependency_is_not_a_cycle() {
+    let thunderstore = test_thunderstore([
+        test_package("A", &["B", "C"]),
+        test_package("B", &["C"]),
+        test_package("C", &[]),
+    ]);
+
+    let root = VersionIdent::new("Test", "A", "1.0.0");
+    let mut deps = thunderstore.dependencies([&root]);
+
+    assert_eq!(full_names(deps.by_ref()), ["Test-A", "Test-B", "Test-C"]);
+    assert_eq!(deps.find_cycle("Test-B", "Test-C"), None);
+}
+
+#[test]
+fn renamed_packages_resolve_through_aliases() {
+    let mut thunderstore = test_thunderstore([
+        test_package("A", &["OldB"]),
+        test_package("B", &[]),
+        test_package("C", &[]),
+    ]);
+
+    assert!(thunderstore.find_package("Test-OldB").is_err());
+
+    thunderstore.aliases_mut().extend([
+        ("Test-OldB".to_owned(), "Test-MidB".to_owned()),
+        ("Test-MidB".to_owned(), "Test-B".to_owned()),
+        ("Test-X".to_owned(), "Test-Y".to_owned()),
+        ("Test-Y".to_owned(), "Test-X".to_owned()),
+    ]);
+
+    assert_eq!(
+        thunderstore.find_package("Test-OldB").unwrap().full_name(),
+        "Test-B"
+    );
+    assert!(thunderstore.find_mod("Test", "OldB", "1.0.0").is_ok());
+    assert!(thunderstore.find_package("Test-X").is_err());
+
+    let root = VersionIdent::new("Test", "A", "1.0.0");
+    assert_eq!(
+        full_names(thunderstore.dependencies([&root])),
+        ["Test-A", "Test-B"]
+    );
+}
+
+#[test]
+fn excluded_dependencies_are_skipped() {
+    let packages = [
+        test_package("A", &["B", "C"]),
+        test_package("B", &["D", "E"]),
+        test_package("C", &["E"]),
+        test_package("D", &[]),
+        test_package("E", &[]),
+    ];
+    let excluded = packages[1].uuid;
+    let thunderstore = test_thunderstore(packages);
+
+    let root = VersionIdent::new("Test", "A", "1.0.0");
+    let deps = thunderstore.dependencies([&root]).exclude([excluded]);
+
+    // E is still needed by C
+    assert_eq!(full_names(deps), ["Test-A", "Test-C", "Test-E"]);
+}
+
+#[test]
+fn packages_are_classified() {
+    let classify = |mut package: PackageListing, categories: &[&str]| {
+        package.categories = categories.iter().map(|&category| category.into()).collect();
+        package.classify();
+        package.kind
+    };
+
+    assert_eq!(
+        classify(test_package("Pack", &["A"]), &["Modpacks"]),
+        PackageKind::Modpack
+    );
+    assert_eq!(
+        classify(test_package("Hooks", &[]), &["Libraries"]),
+        PackageKind::Library
+    );
+    assert_eq!(
+        classify(test_package("CoolLib", &[]), &[]),
+        PackageKind::Library
+    );
+    assert_eq!(
+        classify(test_package("CoolLib", &["A"]), &[]),
+        PackageKind::Mod
+    );
+    assert_eq!(classify(test_package("Mod", &[]), &[]), PackageKind::Mod);
+}
+
+#[test]
+fn discovery_skips_deprecated_and_stale() {
+    let package = |name: &str, downloads: u32, rating: u32| {
+        let mut package = test_package(name, &[]);
+        package.versions[0].downloads = downloads;
+        package.rating_score = rating;
+        package
+    };
+
+    let mut deprecated = package("Deprecated", 1000, 1000);
+    deprecated.is_deprecated = true;
+
+    let mut stale = package("Stale", 500, 500);
+    stale.date_updated = Utc::now() - chrono::Duration::days(365);
+
+    let thunderstore = test_thunderstore([
+        package("A", 10, 30),
+        package("B", 30, 10),
+        package("C", 20, 20),
+        deprecated,
+        stale,
+    ]);
+
+    let (most_downloaded, top_rated) = query::discover(thunderstore.latest(), 3, Utc::now());
+
+    assert_eq!(
+        full_names(most_downloaded.into_iter()),
+        ["Test-Stale", "Test-B", "Test-C"]
+    );
+    assert_eq!(
+        full_names(top_rated.into_iter()),
+        ["Test-A", "Test-C", "Test-B"]
+    );
+}
+
+#[test]
+fn retry_delay_respects_retry_after() {
+    use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+
+    let now = chrono::DateTime::parse_from_rfc2822("Wed, 21 Oct 2015 07:28:00 GMT")
+        .unwrap()
+        .with_timezone(&Utc);
+
+    let with_header = |value: &'static str| {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static(value));
+        headers
+    };
+
+    let delay = |headers: &HeaderMap, attempt| api::retry_delay(headers, attempt, now).as_secs();
+
+    assert_eq!(delay(&with_header("5"), 3), 5);
+    assert_eq!(delay(&with_header("Wed, 21 Oct 2015 07:28:30 GMT"), 0), 30);
+    // dates in the past mean we can retry right away
+    assert_eq!(delay(&with_header("Wed, 21 Oct 2015 07:00:00 GMT"), 0), 0);
+
+    // falls back to exponential backoff
+    assert_eq!(delay(&HeaderMap::new(), 0), 1);
+    assert_eq!(delay(&HeaderMap::new(), 3), 8);
+    assert_eq!(delay(&with_header("soon"), 1), 2);
+}
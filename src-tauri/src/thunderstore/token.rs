@@ -1,38 +1,271 @@
-use std::sync::LazyLock;
+//! Storage of the Thunderstore API token.
+//!
+//! The token is kept in the OS credential store: the Windows Credential Manager,
+//! the macOS Keychain or the Secret Service on Linux. Linux setups without a
+//! Secret Service fall back to an encrypted file instead.
+//!
+//! A token left in the fallback file is moved to the credential store the
+//! first time it's accessed while one is available.
 
-use eyre::Result;
+use std::{fs, io, path::PathBuf, sync::OnceLock};
+
+use base64::{prelude::BASE64_STANDARD, Engine};
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use eyre::{bail, eyre, Context, Result};
 use keyring::Entry;
-use log::info;
+use log::{info, warn};
+use sha2::{Digest, Sha256};
+
+use crate::util::{self, error::IoResultExt};
+
+const SERVICE: &str = "thunderstore";
+const USER: &str = "api_token";
+
+trait Store: Send + Sync {
+    fn get(&self) -> Result<Option<String>>;
+    fn set(&self, token: &str) -> Result<()>;
+    fn clear(&self) -> Result<()>;
+}
 
-static ENTRY: LazyLock<keyring::Result<Entry>> =
-    LazyLock::new(|| Entry::new("thunderstore", "api_token"));
+struct KeyringStore(Entry);
+
+impl Store for KeyringStore {
+    fn get(&self) -> Result<Option<String>> {
+        match self.0.get_password() {
+            Ok(token) => Ok(Some(token)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn set(&self, token: &str) -> Result<()> {
+        self.0.set_password(token)?;
+        Ok(())
+    }
 
-fn entry() -> Result<&'static keyring::Entry> {
-    match &*ENTRY {
-        Ok(entry) => Ok(entry),
-        Err(err) => Err(err.into()),
+    fn clear(&self) -> Result<()> {
+        match self.0.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(err) => Err(err.into()),
+        }
     }
 }
 
-pub fn get() -> Result<Option<String>> {
-    match entry()?.get_password() {
-        Ok(token) => Ok(Some(token)),
-        Err(keyring::Error::NoEntry) => Ok(None),
-        Err(err) => Err(err.into()),
+/// Stores the token in a file, encrypted with a key derived from the machine id.
+///
+/// This only keeps the token from being read as plain text, since anything
+/// that can read the file can derive the key as well.
+struct FileStore {
+    path: PathBuf,
+    key: Key,
+}
+
+impl FileStore {
+    fn new(path: PathBuf) -> Self {
+        let machine_id = fs::read_to_string("/etc/machine-id")
+            .or_else(|_| fs::read_to_string("/var/lib/dbus/machine-id"))
+            .unwrap_or_default();
+
+        let digest = Sha256::new()
+            .chain_update("gale-thunderstore-token")
+            .chain_update(machine_id.trim())
+            .finalize();
+
+        Self {
+            path,
+            key: Key::clone_from_slice(&digest),
+        }
+    }
+
+    fn cipher(&self) -> ChaCha20Poly1305 {
+        ChaCha20Poly1305::new(&self.key)
+    }
+}
+
+impl Store for FileStore {
+    fn get(&self) -> Result<Option<String>> {
+        let text = match fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err).fs_context("reading token file", &self.path),
+        };
+
+        let bytes = BASE64_STANDARD
+            .decode(text.trim())
+            .context("token file is corrupted")?;
+
+        const NONCE_LEN: usize = 12;
+        if bytes.len() < NONCE_LEN {
+            bail!("token file is corrupted");
+        }
+        let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+
+        let plaintext = self
+            .cipher()
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| eyre!("failed to decrypt token file"))?;
+
+        Ok(Some(String::from_utf8(plaintext)?))
+    }
+
+    fn set(&self, token: &str) -> Result<()> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher()
+            .encrypt(&nonce, token.as_bytes())
+            .map_err(|_| eyre!("failed to encrypt token"))?;
+
+        let mut bytes = nonce.to_vec();
+        bytes.extend(ciphertext);
+
+        fs::create_dir_all(self.path.parent().unwrap())
+            .fs_context("creating token directory", &self.path)?;
+        fs::write(&self.path, BASE64_STANDARD.encode(bytes))
+            .fs_context("writing token file", &self.path)?;
+
+        Ok(())
+    }
+
+    fn clear(&self) -> Result<()> {
+        match fs::remove_file(&self.path) {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(err).fs_context("removing token file", &self.path),
+        }
+    }
+}
+
+fn file_store() -> FileStore {
+    FileStore::new(util::path::default_app_data_dir().join("thunderstore_token"))
+}
+
+/// Picks the credential store, or the fallback file if it's unavailable.
+fn store() -> &'static dyn Store {
+    static STORE: OnceLock<Box<dyn Store>> = OnceLock::new();
+
+    STORE
+        .get_or_init(|| {
+            let file = file_store();
+
+            let keyring = match Entry::new(SERVICE, USER) {
+                Ok(entry) => KeyringStore(entry),
+                Err(err) => {
+                    warn!(
+                        "credential store is unavailable, using a file instead: {}",
+                        err
+                    );
+                    return Box::new(file);
+                }
+            };
+
+            // fails with a platform error if there's no secret service
+            if let Err(err) = keyring.get() {
+                warn!(
+                    "credential store is unavailable, using a file instead: {:#}",
+                    err
+                );
+                return Box::new(file);
+            }
+
+            if let Err(err) = migrate(&file, &keyring) {
+                warn!("failed to move token into the credential store: {:#}", err);
+            }
+
+            Box::new(keyring)
+        })
+        .as_ref()
+}
+
+/// Moves the token from `from` to `to`, unless `to` already has one.
+fn migrate(from: &dyn Store, to: &dyn Store) -> Result<()> {
+    let Some(token) = from.get()? else {
+        return Ok(());
+    };
+
+    if to.get()?.is_none() {
+        info!("moving thunderstore token into the credential store");
+        to.set(&token)?;
     }
+
+    from.clear()
+}
+
+pub fn get() -> Result<Option<String>> {
+    store().get()
 }
 
 pub fn set(token: &str) -> Result<()> {
     info!("setting thunderstore token");
-    entry()?.set_password(token)?;
-    Ok(())
+    store().set(token)
 }
 
 pub fn clear() -> Result<()> {
     info!("clearing thunderstore token");
-    match entry()?.delete_credential() {
-        Ok(()) => Ok(()),
-        Err(keyring::Error::NoEntry) => Ok(()),
-        Err(err) => Err(err.into()),
+    store().clear()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct MockStore(Mutex<Option<String>>);
+
+    impl Store for MockStore {
+        fn get(&self) -> Result<Option<String>> {
+            Ok(self.0.lock().unwrap().clone())
+        }
+
+        fn set(&self, token: &str) -> Result<()> {
+            *self.0.lock().unwrap() = Some(token.to_owned());
+            Ok(())
+        }
+
+        fn clear(&self) -> Result<()> {
+            *self.0.lock().unwrap() = None;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn token_is_migrated_once() {
+        let file = MockStore::default();
+        let keyring = MockStore::default();
+
+        file.set("old").unwrap();
+        migrate(&file, &keyring).unwrap();
+
+        assert_eq!(keyring.get().unwrap().as_deref(), Some("old"));
+        assert_eq!(file.get().unwrap(), None);
+
+        // a token that's already in the credential store wins
+        file.set("stale").unwrap();
+        keyring.set("new").unwrap();
+        migrate(&file, &keyring).unwrap();
+
+        assert_eq!(keyring.get().unwrap().as_deref(), Some("new"));
+        assert_eq!(file.get().unwrap(), None);
+    }
+
+    #[test]
+    fn file_store_is_encrypted() {
+        let temp = tempfile::tempdir().unwrap();
+        let store = FileStore::new(temp.path().join("token"));
+
+        assert_eq!(store.get().unwrap(), None);
+
+        store.set("secret-token").unwrap();
+        assert_eq!(store.get().unwrap().as_deref(), Some("secret-token"));
+
+        let contents = fs::read_to_string(temp.path().join("token")).unwrap();
+        assert!(!contents.contains("secret-token"));
+
+        store.clear().unwrap();
+        assert_eq!(store.get().unwrap(), None);
     }
 }
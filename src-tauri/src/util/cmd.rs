@@ -1,13 +1,89 @@
 use std::{
     fmt::{self, Display},
+    io,
     result::Result as StdResult,
 };
 
 use serde::Serialize;
 
+use crate::{profile::InvalidNameReason, util};
+
+/// A common failure which the frontend can show in the user's language.
+///
+/// Return these wrapped in an [`eyre::Report`] like any other error. Context
+/// added on top is kept in the message, which is sent along as a fallback.
+#[derive(thiserror::Error, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase", tag = "kind", content = "params")]
+pub enum UserError {
+    #[error("mod not found in profile")]
+    ModNotFound,
+    #[error("{name} is already installed")]
+    AlreadyInstalled { name: String },
+    #[error("could not connect to the server, check your internet connection")]
+    Offline,
+    #[error("invalid profile name '{name}': {reason}")]
+    InvalidProfileName {
+        name: String,
+        reason: InvalidNameReason,
+    },
+    #[error("thunderstore API token is invalid")]
+    TokenInvalid,
+    #[error("the profile is busy {operation}, try again when it's done")]
+    ProfileBusy { operation: String },
+    #[error("profile '{name}' is locked, unlock it to make changes")]
+    ProfileLocked { name: String },
+    #[error("the game directory at {path} is not writable")]
+    GameDirNotWritable { path: String },
+    #[error("{path} already exists in the game directory and was not installed by Gale")]
+    GameDirFileConflict { path: String },
+    #[error("files in {path} are in use by {}, close it and try again", in_use_by(.processes))]
+    FilesInUse {
+        path: String,
+        /// The programs with files open, if they could be determined.
+        processes: Vec<String>,
+    },
+    #[error("{game} is running, close it and try again")]
+    GameRunning { game: String },
+    #[error("there is not enough disk space")]
+    InsufficientSpace,
+    #[error("{path} does not exist")]
+    PathNotFound { path: String },
+    #[error("{path} is not a file")]
+    NotAFile { path: String },
+    #[error("{path} could not be read: {reason}")]
+    PathNotReadable { path: String, reason: String },
+}
+
+fn in_use_by(processes: &[String]) -> String {
+    match processes.is_empty() {
+        true => "another program".to_owned(),
+        false => processes.join(", "),
+    }
+}
+
 #[derive(Debug)]
 pub struct CommandError(eyre::Error);
 
+impl CommandError {
+    /// Finds the first [`UserError`] in the error chain.
+    ///
+    /// Connection errors from [`reqwest`] are treated as [`UserError::Offline`],
+    /// and IO errors from a full disk as [`UserError::InsufficientSpace`].
+    pub fn user_error(&self) -> Option<UserError> {
+        self.0.chain().find_map(|err| {
+            if let Some(err) = err.downcast_ref::<UserError>() {
+                Some(err.clone())
+            } else if let Some(err) = err.downcast_ref::<reqwest::Error>() {
+                err.is_connect().then_some(UserError::Offline)
+            } else if let Some(err) = err.downcast_ref::<io::Error>() {
+                util::fs::is_storage_full(err).then_some(UserError::InsufficientSpace)
+            } else {
+                None
+            }
+        })
+    }
+}
+
 impl Display for CommandError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{:#}", self.0)
@@ -19,7 +95,18 @@ impl Serialize for CommandError {
     where
         S: serde::Serializer,
     {
-        serializer.serialize_str(&self.to_string())
+        #[derive(Serialize)]
+        struct Payload {
+            message: String,
+            #[serde(flatten)]
+            user_error: Option<UserError>,
+        }
+
+        Payload {
+            message: self.to_string(),
+            user_error: self.user_error(),
+        }
+        .serialize(serializer)
     }
 }
 
@@ -1,16 +1,18 @@
 use std::{
     ffi::OsStr,
     fs::{self, File},
-    io::{BufReader, BufWriter},
+    io::{self, BufReader, BufWriter},
     path::{Path, PathBuf},
 };
 
+use eyre::{bail, Context};
 use log::warn;
 use serde::{de::DeserializeOwned, Serialize};
+use sha2::{Digest, Sha256};
 use walkdir::WalkDir;
 use zip::ZipArchive;
 
-use super::error::IoResultExt;
+use super::{cmd::UserError, error::IoResultExt};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Overwrite {
@@ -94,6 +96,178 @@ pub fn get_directory_size(path: impl AsRef<Path>) -> u64 {
         .sum()
 }
 
+/// Whether `err` means that a file is open in another program, which
+/// prevents it from being moved or removed on Windows.
+pub fn is_in_use(err: &io::Error) -> bool {
+    const ERROR_ACCESS_DENIED: i32 = 5;
+    const ERROR_SHARING_VIOLATION: i32 = 32;
+    const ERROR_LOCK_VIOLATION: i32 = 33;
+
+    cfg!(windows)
+        && matches!(
+            err.raw_os_error(),
+            Some(ERROR_ACCESS_DENIED | ERROR_SHARING_VIOLATION | ERROR_LOCK_VIOLATION)
+        )
+}
+
+/// Whether `err` means that the disk is out of space.
+///
+/// [`io::ErrorKind::StorageFull`] isn't stable on our MSRV, so this
+/// checks the raw OS error instead.
+pub fn is_storage_full(err: &io::Error) -> bool {
+    #[cfg(windows)]
+    const CODES: &[i32] = &[
+        39,  // ERROR_HANDLE_DISK_FULL
+        112, // ERROR_DISK_FULL
+    ];
+    #[cfg(not(windows))]
+    const CODES: &[i32] = &[
+        28, // ENOSPC
+    ];
+
+    err.raw_os_error().is_some_and(|code| CODES.contains(&code))
+}
+
+/// Finds the names of the programs which have files in `dir` open.
+///
+/// This uses the Restart Manager, so it's only supported on Windows.
+#[cfg(windows)]
+pub fn processes_using(dir: &Path) -> Vec<String> {
+    use std::{iter, mem, os::windows::ffi::OsStrExt, ptr};
+
+    use windows_sys::Win32::{
+        Foundation::{ERROR_MORE_DATA, ERROR_SUCCESS},
+        System::RestartManager::{
+            RmEndSession, RmGetList, RmRegisterResources, RmStartSession, CCH_RM_SESSION_KEY,
+            RM_PROCESS_INFO,
+        },
+    };
+
+    /// Checking more files than this gets slow, and a running game
+    /// usually holds the first files it loaded anyway.
+    const MAX_FILES: usize = 1000;
+
+    let files = WalkDir::new(dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .take(MAX_FILES)
+        .map(|entry| {
+            entry
+                .path()
+                .as_os_str()
+                .encode_wide()
+                .chain(iter::once(0))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    if files.is_empty() {
+        return Vec::new();
+    }
+
+    let file_ptrs = files.iter().map(|file| file.as_ptr()).collect::<Vec<_>>();
+
+    let mut session = 0;
+    let mut key = [0u16; CCH_RM_SESSION_KEY as usize + 1];
+
+    // SAFETY: every pointer is valid for the duration of the calls, and
+    // the process buffer holds as many entries as we tell RmGetList
+    unsafe {
+        if RmStartSession(&mut session, 0, key.as_mut_ptr()) != ERROR_SUCCESS {
+            return Vec::new();
+        }
+
+        let mut processes = Vec::new();
+
+        let registered = RmRegisterResources(
+            session,
+            file_ptrs.len() as u32,
+            file_ptrs.as_ptr(),
+            0,
+            ptr::null(),
+            0,
+            ptr::null(),
+        ) == ERROR_SUCCESS;
+
+        let mut needed = 0;
+        let mut count = 0;
+        let mut reasons = 0;
+
+        if registered
+            && RmGetList(
+                session,
+                &mut needed,
+                &mut count,
+                ptr::null_mut(),
+                &mut reasons,
+            ) == ERROR_MORE_DATA
+        {
+            let mut infos = vec![mem::zeroed::<RM_PROCESS_INFO>(); needed as usize];
+            count = needed;
+
+            if RmGetList(
+                session,
+                &mut needed,
+                &mut count,
+                infos.as_mut_ptr(),
+                &mut reasons,
+            ) == ERROR_SUCCESS
+            {
+                processes = infos[..count as usize]
+                    .iter()
+                    .map(|info| {
+                        let name = &info.strAppName;
+                        let len = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+                        String::from_utf16_lossy(&name[..len])
+                    })
+                    .collect();
+            }
+        }
+
+        RmEndSession(session);
+
+        processes
+    }
+}
+
+#[cfg(not(windows))]
+pub fn processes_using(_dir: &Path) -> Vec<String> {
+    Vec::new()
+}
+
+/// Checks that `path` is an existing file which can be opened for reading.
+///
+/// Picked paths sometimes don't make it through file dialogs intact, so this gives
+/// a specific [`UserError`] up front instead of failing somewhere down the line.
+pub fn ensure_readable_file(path: &Path) -> eyre::Result<()> {
+    let display = || path.display().to_string();
+
+    let metadata = match path.metadata() {
+        Ok(metadata) => metadata,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            bail!(UserError::PathNotFound { path: display() })
+        }
+        Err(err) => bail!(UserError::PathNotReadable {
+            path: display(),
+            reason: err.to_string()
+        }),
+    };
+
+    if !metadata.is_file() {
+        bail!(UserError::NotAFile { path: display() });
+    }
+
+    if let Err(err) = File::open(path) {
+        bail!(UserError::PathNotReadable {
+            path: display(),
+            reason: err.to_string()
+        });
+    }
+
+    Ok(())
+}
+
 pub fn read_json<T: DeserializeOwned>(path: impl AsRef<Path>) -> eyre::Result<T> {
     let string = fs::read_to_string(path)?;
     let result = serde_json::from_str(&string)?;
@@ -119,15 +293,49 @@ pub fn write_json<T: Serialize + ?Sized>(
     value: &T,
     style: JsonStyle,
 ) -> eyre::Result<()> {
-    let writer = File::create(path).map(BufWriter::new)?;
+    write_atomic(path, |writer| {
+        if style == JsonStyle::Pretty {
+            serde_json::to_writer_pretty(writer, value)?;
+        } else {
+            serde_json::to_writer(writer, value)?;
+        }
+
+        Ok(())
+    })
+}
+
+/// Writes a file by first writing to a temporary file next to it, then
+/// renaming it over the original.
+///
+/// This makes sure a crash or power loss never leaves a half-written file behind;
+/// readers see either the old or the new contents.
+pub fn write_atomic<F>(path: impl AsRef<Path>, write: F) -> eyre::Result<()>
+where
+    F: FnOnce(&mut BufWriter<File>) -> eyre::Result<()>,
+{
+    let path = path.as_ref();
+
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    let temp_path = PathBuf::from(temp_path);
+
+    let result = (|| {
+        let mut writer = File::create(&temp_path).map(BufWriter::new)?;
+        write(&mut writer)?;
+
+        let file = writer.into_inner().map_err(|err| err.into_error())?;
+        file.sync_all()?;
+
+        fs::rename(&temp_path, path)?;
+
+        eyre::Ok(())
+    })();
 
-    if style == JsonStyle::Pretty {
-        serde_json::to_writer_pretty(writer, value)?;
-    } else {
-        serde_json::to_writer(writer, value)?;
+    if result.is_err() {
+        fs::remove_file(&temp_path).ok();
     }
 
-    Ok(())
+    result.with_context(|| format!("failed to write {}", path.display()))
 }
 
 pub fn file_name_owned(path: impl AsRef<Path>) -> String {
@@ -191,3 +399,59 @@ impl PathExt for PathBuf {
         };
     }
 }
+
+/// Returns the hex encoded SHA-256 hash of a file's contents.
+pub fn hash_file(path: impl AsRef<Path>) -> eyre::Result<String> {
+    let path = path.as_ref();
+
+    let mut file = File::open(path).fs_context("opening file", path)?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher).fs_context("reading file", path)?;
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unreadable_paths_are_reported() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let user_error = |path: &Path| {
+            ensure_readable_file(path)
+                .unwrap_err()
+                .downcast::<UserError>()
+                .unwrap()
+        };
+
+        assert!(matches!(
+            user_error(&dir.path().join("missing.r2z")),
+            UserError::PathNotFound { .. }
+        ));
+        assert!(matches!(user_error(dir.path()), UserError::NotAFile { .. }));
+
+        let file = dir.path().join("profile.r2z");
+        fs::write(&file, "").unwrap();
+        assert!(ensure_readable_file(&file).is_ok());
+    }
+
+    #[test]
+    fn full_disk_is_an_insufficient_space_error() {
+        #[cfg(windows)]
+        let err = io::Error::from_raw_os_error(112);
+        #[cfg(not(windows))]
+        let err = io::Error::from_raw_os_error(28);
+
+        let err = crate::util::cmd::CommandError::from(
+            eyre::Report::new(err).wrap_err("failed to extract mod"),
+        );
+        assert!(matches!(
+            err.user_error(),
+            Some(UserError::InsufficientSpace)
+        ));
+
+        assert!(!is_storage_full(&io::Error::from(io::ErrorKind::NotFound)));
+    }
+}
@@ -6,6 +6,7 @@ pub mod cmd;
 pub mod error;
 pub mod fs;
 pub mod path;
+pub mod temp;
 pub mod window;
 pub mod zip;
 
@@ -0,0 +1,208 @@
+//! Per-operation scratch directories inside of [`Prefs::temp_dir`].
+//!
+//! Every operation gets its own uniquely named directory, which is removed
+//! when its [`TempDir`] is dropped, even if the operation fails halfway.
+//! Directories of running operations are registered, so [`clean`] never
+//! removes them from under their owner.
+//!
+//! [`Prefs::temp_dir`]: crate::prefs::Prefs::temp_dir
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError},
+    time::{Duration, SystemTime},
+};
+
+use eyre::Result;
+use log::warn;
+use uuid::Uuid;
+
+use super::error::IoResultExt;
+
+static LIVE: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+fn register(path: &Path) {
+    let mut live = LIVE.lock().unwrap_or_else(PoisonError::into_inner);
+
+    if !live.iter().any(|live| live == path) {
+        live.push(path.to_path_buf());
+    }
+}
+
+fn unregister(path: &Path) {
+    LIVE.lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .retain(|live| live != path);
+}
+
+/// A temporary directory that is removed when dropped.
+///
+/// Unlike [`tempfile::TempDir`], this is protected from [`clean`] while it's alive.
+#[derive(Debug)]
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// Creates a new directory in `root`, named `<prefix>-<uuid>`.
+    pub fn new_in(root: &Path, prefix: &str) -> Result<Self> {
+        let path = root.join(format!("{}-{}", prefix, Uuid::new_v4()));
+        fs::create_dir_all(&path).fs_context("creating temp dir", &path)?;
+
+        Ok(Self::adopt(path))
+    }
+
+    /// Takes ownership of an existing directory, which was
+    /// previously kept with [`TempDir::into_path`].
+    pub fn adopt(path: PathBuf) -> Self {
+        register(&path);
+        Self { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Keeps the directory on disk, for a later operation to [`adopt`](TempDir::adopt).
+    ///
+    /// It stays protected from [`clean`] until then, or until the app restarts.
+    pub fn into_path(mut self) -> PathBuf {
+        std::mem::take(&mut self.path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        if self.path.as_os_str().is_empty() {
+            return;
+        }
+
+        if let Err(err) = fs::remove_dir_all(&self.path) {
+            if err.kind() != io::ErrorKind::NotFound {
+                warn!(
+                    "failed to remove temp dir at {}: {}",
+                    self.path.display(),
+                    err
+                );
+            }
+        }
+
+        unregister(&self.path);
+    }
+}
+
+/// Removes entries in `root` which were last modified at least `min_age` ago,
+/// skipping the directories of running operations.
+///
+/// Returns the number of bytes freed.
+pub fn clean(root: &Path, min_age: Duration) -> Result<u64> {
+    if !root.exists() {
+        return Ok(0);
+    }
+
+    let live = LIVE.lock().unwrap_or_else(PoisonError::into_inner).clone();
+
+    let now = SystemTime::now();
+    let mut freed = 0;
+
+    for entry in root.read_dir().fs_context("reading temp dir", root)? {
+        let entry = entry.fs_context("reading temp dir", root)?;
+        let path = entry.path();
+
+        if live.iter().any(|live| live.starts_with(&path)) {
+            continue;
+        }
+
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+
+        // entries with an unknown age are left alone, unless everything is removed
+        let age = metadata
+            .modified()
+            .ok()
+            .and_then(|time| now.duration_since(time).ok())
+            .unwrap_or_default();
+
+        if age < min_age {
+            continue;
+        }
+
+        let (size, result) = match metadata.is_dir() {
+            true => (
+                super::fs::get_directory_size(&path),
+                fs::remove_dir_all(&path),
+            ),
+            false => (metadata.len(), fs::remove_file(&path)),
+        };
+
+        match result {
+            Ok(()) => freed += size,
+            Err(err) => warn!("failed to remove {} from temp dir: {}", path.display(), err),
+        }
+    }
+
+    Ok(freed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_file(dir: &Path) {
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        fs::write(dir.join("nested/file.txt"), "content").unwrap();
+    }
+
+    #[test]
+    fn removed_when_operation_fails() {
+        let root = tempfile::tempdir().unwrap();
+
+        let operation = || -> Result<()> {
+            let temp_dir = TempDir::new_in(root.path(), "test")?;
+            write_file(temp_dir.path());
+
+            eyre::bail!("simulated failure");
+        };
+
+        assert!(operation().is_err());
+        assert_eq!(root.path().read_dir().unwrap().count(), 0);
+    }
+
+    #[test]
+    fn kept_dir_can_be_adopted() {
+        let root = tempfile::tempdir().unwrap();
+
+        let path = TempDir::new_in(root.path(), "test").unwrap().into_path();
+        write_file(&path);
+
+        // still protected after being kept
+        assert_eq!(clean(root.path(), Duration::ZERO).unwrap(), 0);
+        assert!(path.exists());
+
+        drop(TempDir::adopt(path.clone()));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn clean_skips_live_and_recent_entries() {
+        let root = tempfile::tempdir().unwrap();
+
+        let live = TempDir::new_in(root.path(), "live").unwrap();
+        write_file(live.path());
+
+        // leftovers of an interrupted operation
+        let stale = root.path().join("stale");
+        write_file(&stale);
+        fs::write(root.path().join("stale.zip"), "zip").unwrap();
+
+        assert_eq!(clean(root.path(), Duration::from_secs(3600)).unwrap(), 0);
+        assert!(stale.exists());
+
+        let freed = clean(root.path(), Duration::ZERO).unwrap();
+        assert_eq!(freed, ("content".len() + "zip".len()) as u64);
+        assert!(!stale.exists());
+        assert!(live.path().join("nested/file.txt").exists());
+    }
+}
@@ -1,13 +1,14 @@
 use std::{
     fs::{self, File},
-    io::{self, Read, Seek},
+    io::{self, Cursor, Read, Seek, Write},
     path::{Path, PathBuf},
 };
 
 use log::{debug, warn};
-use zip::ZipArchive;
+use walkdir::WalkDir;
+use zip::{write::SimpleFileOptions, CompressionMethod, DateTime, ZipArchive, ZipWriter};
 
-use crate::util;
+use crate::util::{self, error::IoResultExt};
 
 #[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
@@ -51,6 +52,53 @@ pub fn extract(src: impl Read + Seek, target: &Path) -> io::Result<()> {
     Ok(())
 }
 
+/// Options for archive entries which don't depend on when or where the archive
+/// was created, so that the same files always produce the same bytes.
+pub fn file_options() -> SimpleFileOptions {
+    SimpleFileOptions::default()
+        .last_modified_time(DateTime::default())
+        .unix_permissions(0o644)
+}
+
+/// Writes every file in `source` to `zip`, with paths relative to `prefix`.
+///
+/// Files are written in a sorted order, see [`file_options`].
+pub fn write_dir<W: Write + Seek>(
+    source: &Path,
+    prefix: &Path,
+    compression: CompressionMethod,
+    zip: &mut ZipWriter<W>,
+) -> eyre::Result<()> {
+    for entry in WalkDir::new(source).sort_by_file_name() {
+        let entry = entry?;
+
+        if !entry.file_type().is_file() {
+            continue;
+        }
+
+        let relative = entry.path().strip_prefix(source).unwrap();
+        let name = prefix.join(relative).to_string_lossy().replace('\\', "/");
+
+        let options = file_options()
+            .compression_method(compression)
+            .large_file(entry.metadata()?.len() >= u32::MAX as u64);
+        zip.start_file(name, options)?;
+
+        let mut reader = File::open(entry.path()).fs_context("opening file", entry.path())?;
+        io::copy(&mut reader, zip)?;
+    }
+
+    Ok(())
+}
+
+/// Packs the contents of `source` into an uncompressed archive in memory.
+pub fn pack_dir(source: &Path) -> eyre::Result<Vec<u8>> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    write_dir(source, Path::new(""), CompressionMethod::Stored, &mut zip)?;
+
+    Ok(zip.finish()?.into_inner())
+}
+
 #[cfg(unix)]
 fn set_unix_mode(file: &zip::read::ZipFile, path: &Path) -> io::Result<()> {
     if let Some(mode) = file.unix_mode() {
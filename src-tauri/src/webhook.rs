@@ -0,0 +1,267 @@
+//! Notifies a user-configured webhook about completed operations.
+//!
+//! Each event is sent as a JSON [`WebhookPayload`] in a POST request. The `content`
+//! field holds a short summary, so the URL of a Discord webhook can be used as is.
+//!
+//! Sending happens in the background and failures are only logged,
+//! so they never affect the operation itself.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use eyre::{bail, Result};
+use itertools::Itertools;
+use log::{debug, warn};
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::{prefs::WebhookPrefs, state::ManagerExt, thunderstore::VersionIdent};
+
+const MAX_ATTEMPTS: u32 = 3;
+/// Discord rejects messages with longer content.
+const MAX_CONTENT_CHARS: usize = 2000;
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// The body of a webhook request.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookPayload {
+    /// A human readable summary of the event.
+    pub content: String,
+    /// The slug of the game, for example `lethal-company`.
+    pub game: String,
+    pub profile: String,
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: WebhookEvent,
+}
+
+/// What happened, tagged by the `event` field.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase", tag = "event")]
+pub enum WebhookEvent {
+    /// Mods were installed into the profile, including dependencies.
+    #[serde(rename_all = "camelCase")]
+    Install { mods: Vec<VersionIdent> },
+    /// Mods in the profile were updated to the given versions.
+    #[serde(rename_all = "camelCase")]
+    Update {
+        updated: Vec<VersionIdent>,
+        /// Missing dependencies that were installed after the update.
+        installed_dependencies: Vec<VersionIdent>,
+    },
+    /// The profile was uploaded to Thunderstore as a modpack.
+    #[serde(rename_all = "camelCase")]
+    PackUpload { name: String, version: String },
+}
+
+impl WebhookEvent {
+    fn is_enabled(&self, prefs: &WebhookPrefs) -> bool {
+        match self {
+            WebhookEvent::Install { .. } => prefs.on_install,
+            WebhookEvent::Update { .. } => prefs.on_update,
+            WebhookEvent::PackUpload { .. } => prefs.on_pack_upload,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        match self {
+            WebhookEvent::Install { mods } => mods.is_empty(),
+            WebhookEvent::Update {
+                updated,
+                installed_dependencies,
+            } => updated.is_empty() && installed_dependencies.is_empty(),
+            WebhookEvent::PackUpload { .. } => false,
+        }
+    }
+
+    fn summary(&self, profile: &str) -> String {
+        match self {
+            WebhookEvent::Install { mods } => format!(
+                "Installed {} mods into {}: {}",
+                mods.len(),
+                profile,
+                mods.iter().join(", ")
+            ),
+            WebhookEvent::Update {
+                updated,
+                installed_dependencies,
+            } => {
+                let mut summary = format!(
+                    "Updated {} mods in {}: {}",
+                    updated.len(),
+                    profile,
+                    updated.iter().join(", ")
+                );
+
+                if !installed_dependencies.is_empty() {
+                    summary.push_str(&format!(
+                        "\nInstalled missing dependencies: {}",
+                        installed_dependencies.iter().join(", ")
+                    ));
+                }
+
+                summary
+            }
+            WebhookEvent::PackUpload { name, version } => {
+                format!("Uploaded {} {} from {}", name, version, profile)
+            }
+        }
+    }
+}
+
+/// Sends `event` about the profile with `profile_id` to the active game's webhook,
+/// if one is set and the event type is turned on.
+///
+/// This returns immediately, the request is sent in the background.
+pub fn dispatch(event: WebhookEvent, profile_id: i64, app: &AppHandle) {
+    if event.is_empty() {
+        return;
+    }
+
+    let (url, payload) = match prepare(event, profile_id, app) {
+        Ok(Some(prepared)) => prepared,
+        Ok(None) => return,
+        Err(err) => {
+            warn!("failed to prepare webhook: {:#}", err);
+            return;
+        }
+    };
+
+    let http = app.http();
+
+    tauri::async_runtime::spawn(async move {
+        if let Err(err) = send(&url, &payload, &http).await {
+            warn!("failed to send webhook: {:#}", err);
+        }
+    });
+}
+
+fn prepare(
+    event: WebhookEvent,
+    profile_id: i64,
+    app: &AppHandle,
+) -> Result<Option<(String, WebhookPayload)>> {
+    let prefs = app.lock_prefs()?;
+    let manager = app.lock_manager()?;
+
+    let game = manager.active_game;
+
+    let Some(webhook) = prefs
+        .game_prefs
+        .get(&*game.slug)
+        .and_then(|game_prefs| game_prefs.webhook.as_ref())
+    else {
+        return Ok(None);
+    };
+
+    if !event.is_enabled(webhook) {
+        return Ok(None);
+    }
+
+    let profile = manager.active_game().find_profile(profile_id)?.name.clone();
+
+    let payload = WebhookPayload {
+        content: truncate_content(event.summary(&profile)),
+        game: game.slug.to_string(),
+        profile,
+        timestamp: Utc::now(),
+        event,
+    };
+
+    Ok(Some((webhook.url.clone(), payload)))
+}
+
+/// Cuts `content` down to [`MAX_CONTENT_CHARS`], ending it with an ellipsis if it was cut.
+fn truncate_content(mut content: String) -> String {
+    if content.chars().count() > MAX_CONTENT_CHARS {
+        // leave room for the ellipsis
+        let (end, _) = content
+            .char_indices()
+            .nth(MAX_CONTENT_CHARS - 1)
+            .expect("content should be longer than the limit");
+
+        content.truncate(end);
+        content.push('…');
+    }
+
+    content
+}
+
+async fn send(url: &str, payload: &WebhookPayload, http: &reqwest::Client) -> Result<()> {
+    let mut attempt = 1;
+
+    loop {
+        let result = http.post(url).json(payload).send().await;
+
+        let retryable = match result {
+            Ok(response) if response.status().is_success() => {
+                debug!("sent webhook after {} attempts", attempt);
+                return Ok(());
+            }
+            Ok(response) => {
+                let status = response.status();
+
+                if status.is_client_error() && status != reqwest::StatusCode::TOO_MANY_REQUESTS {
+                    bail!("webhook responded with {}", status);
+                }
+
+                eyre::eyre!("webhook responded with {}", status)
+            }
+            Err(err) => err.into(),
+        };
+
+        if attempt >= MAX_ATTEMPTS {
+            return Err(retryable.wrap_err(format!("gave up after {} attempts", attempt)));
+        }
+
+        debug!("webhook attempt {} failed: {:#}", attempt, retryable);
+
+        tokio::time::sleep(RETRY_DELAY * attempt).await;
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn payload_is_flattened() {
+        let event = WebhookEvent::Update {
+            updated: vec![VersionIdent::new("Author", "Mod", "1.1.0")],
+            installed_dependencies: Vec::new(),
+        };
+
+        let payload = WebhookPayload {
+            content: event.summary("Server"),
+            game: "lethal-company".to_owned(),
+            profile: "Server".to_owned(),
+            timestamp: DateTime::from_timestamp(0, 0).unwrap(),
+            event,
+        };
+
+        assert_eq!(
+            serde_json::to_value(&payload).unwrap(),
+            serde_json::json!({
+                "content": "Updated 1 mods in Server: Author-Mod-1.1.0",
+                "game": "lethal-company",
+                "profile": "Server",
+                "timestamp": "1970-01-01T00:00:00Z",
+                "event": "update",
+                "updated": ["Author-Mod-1.1.0"],
+                "installedDependencies": []
+            })
+        );
+    }
+
+    #[test]
+    fn long_content_is_truncated() {
+        let short = "a".repeat(MAX_CONTENT_CHARS);
+        assert_eq!(truncate_content(short.clone()), short);
+
+        let long = truncate_content("ä".repeat(MAX_CONTENT_CHARS + 10));
+        assert_eq!(long.chars().count(), MAX_CONTENT_CHARS);
+        assert!(long.ends_with("ä…"));
+    }
+}
@@ -1,5 +1,13 @@
 import { invokeCommand } from './invoke';
-import type { ConfigEntryId, ConfigValue } from './models';
+import type {
+	ConfigEntry,
+	ConfigEntryId,
+	ConfigFileData,
+	ConfigRename,
+	ConfigRenameMode,
+	ConfigSection,
+	ConfigValue
+} from './models';
 
 export function isNum(value: ConfigValue) {
 	return value.type === 'int' || value.type === 'float';
@@ -17,3 +25,31 @@ export async function setConfigEntry(id: ConfigEntryId, value: ConfigValue) {
 
 	id.entry.value = value;
 }
+
+/**
+ * Renames `entry`, or `section` itself if `entry` is omitted.
+ *
+ * A `display` rename only changes what's shown here, while a `key`
+ * rename changes the file, which the mod may not pick up on.
+ */
+export async function renameConfigEntry(
+	file: ConfigFileData,
+	section: ConfigSection,
+	entry: ConfigEntry | null,
+	newName: string,
+	mode: ConfigRenameMode
+) {
+	let result = await invokeCommand<ConfigRename>('rename_config_entry', {
+		file: file.relativePath,
+		section: section.name,
+		entry: entry?.name ?? null,
+		newName,
+		mode
+	});
+
+	let target = entry ?? section;
+	target.name = result.key;
+	target.displayName = result.displayName;
+
+	return result;
+}
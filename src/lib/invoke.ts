@@ -8,6 +8,38 @@ type Error = {
 	message: string;
 };
 
+/**
+ * The error returned by commands. Common failures also carry a `kind`
+ * (and sometimes `params`), which can be used instead of the English message.
+ */
+export type CommandError = {
+	message: string;
+	kind?:
+		| 'modNotFound'
+		| 'alreadyInstalled'
+		| 'offline'
+		| 'invalidProfileName'
+		| 'tokenInvalid'
+		| 'profileBusy'
+		| 'profileLocked'
+		| 'gameDirNotWritable'
+		| 'gameDirFileConflict'
+		| 'filesInUse'
+		| 'gameRunning'
+		| 'insufficientSpace'
+		| 'pathNotFound'
+		| 'notAFile'
+		| 'pathNotReadable';
+	params?: {
+		name?: string;
+		game?: string;
+		operation?: string;
+		path?: string;
+		reason?: string;
+		processes?: string[];
+	};
+};
+
 listen<Error>('error', (evt) =>
 	pushToast({
 		type: 'error',
@@ -19,9 +51,9 @@ export async function invokeCommand<T>(cmd: string, args?: any): Promise<T> {
 	try {
 		return await invoke<T>(cmd, args);
 	} catch (error: any) {
-		let errStr = error as string;
+		let errStr = errorMessage(error);
 		let name = `Failed to ${sentenceCase(cmd).toLowerCase()}`;
-		let message = errStr[0].toUpperCase() + errStr.slice(1);
+		let message = errStr.length > 0 ? errStr[0].toUpperCase() + errStr.slice(1) : 'Unknown error';
 
 		if (!message.endsWith('.') && !message.endsWith('?') && !message.endsWith('!')) {
 			message += '.';
@@ -32,6 +64,21 @@ export async function invokeCommand<T>(cmd: string, args?: any): Promise<T> {
 	}
 }
 
+/**
+ * Gets the message of a rejected command. Tauri rejects with a plain
+ * string for some errors, instead of a {@link CommandError}.
+ */
+function errorMessage(error: unknown): string {
+	if (typeof error === 'string') return error;
+
+	if (typeof error === 'object' && error !== null && 'message' in error) {
+		let message = (error as CommandError).message;
+		if (typeof message === 'string') return message;
+	}
+
+	return String(error);
+}
+
 function pushError(error: Error) {
 	let msg = `${error.name}: ${error.message}`;
 	invoke('log_err', { msg });
@@ -20,6 +20,7 @@ export type ConfigValue =
 
 export type ConfigEntry = {
 	name: string;
+	displayName: string | null;
 	description: string | null;
 	default: ConfigValue | null;
 	value: ConfigValue;
@@ -27,6 +28,7 @@ export type ConfigEntry = {
 
 export type ConfigSection = {
 	name: string;
+	displayName: string | null;
 	entries: ConfigEntry[];
 };
 
@@ -59,12 +61,39 @@ export type ConfigFile = { relativePath: string; displayName: string | null } &
 	  }
 );
 
+export type InvalidNameReason =
+	| 'empty'
+	| 'tooLong'
+	| 'reserved'
+	| 'illegalChar'
+	| 'trailingDotOrSpace'
+	| 'duplicate'
+	| 'duplicateCase';
+
+export type NameValidation = {
+	reason: InvalidNameReason | null;
+	message: string | null;
+	suggestion: string | null;
+};
+
 export type ProfileInfo = {
 	id: number;
 	name: string;
 	modCount: number;
+	locked: boolean;
+	loaderSettings: LoaderSettings | null;
+};
+
+export type LogLevel = 'fatal' | 'error' | 'warning' | 'message' | 'info' | 'debug';
+
+export type LoaderSettings = {
+	console: boolean;
+	logLevel: LogLevel;
+	dumpAssemblies: boolean | null;
 };
 
+export type LoaderSettingKey = 'console' | 'logLevel' | 'dumpAssemblies';
+
 export type ProfilesInfo = {
 	profiles: ProfileInfo[];
 	activeId: number;
@@ -90,6 +119,8 @@ export type Mod = {
 	dependencies: string[] | null;
 	isPinned: boolean;
 	isDeprecated: boolean;
+	isModpack: boolean;
+	isLibrary: boolean;
 	isInstalled: boolean | undefined;
 	containsNsfw: boolean;
 	uuid: string;
@@ -103,6 +134,34 @@ export type Mod = {
 	enabled?: boolean | null;
 	icon: string | null;
 	configFile: string | null;
+	hasConfig?: boolean;
+	side?: ModSide;
+	sideOverridden?: boolean;
+};
+
+export type ModSide = 'client' | 'server' | 'both';
+
+export type ExportTarget = 'client' | 'server';
+
+export type ModListFormat = 'markdown' | 'bbcode' | 'plain' | 'csv';
+
+export type DescriptionStyle = 'workshop' | 'discord';
+
+export type SaveStatus = 'saved' | 'saving' | 'dirty';
+
+export type GameBuild = { type: 'steam'; value: string } | { type: 'exeModified'; value: string };
+
+export type GameUpdated = {
+	gameName: string;
+	previous: GameBuild;
+	current: GameBuild;
+};
+
+export type ConfigConfidence = 'possible' | 'likely' | 'exact';
+
+export type ModConfigFile = {
+	relativePath: string;
+	confidence: ConfigConfidence;
 };
 
 export enum ModType {
@@ -135,10 +194,22 @@ export type QueryModsArgs = {
 	includeDeprecated: boolean;
 	includeDisabled: boolean;
 	includeEnabled: boolean;
+	modpacksOnly: boolean;
+	hideLibraries: boolean;
 	sortBy: SortBy;
 	sortOrder: SortOrder;
 };
 
+export type ConfigRenameMode = 'display' | 'key';
+
+export type ConfigRename = {
+	mode: ConfigRenameMode;
+	key: string;
+	displayName: string | null;
+	written: boolean;
+	warning: string | null;
+};
+
 export type ConfigEntryId = {
 	file: { relativePath: string };
 	section: ConfigSection;
@@ -150,12 +221,176 @@ export type Dependant = {
 	uuid: string;
 };
 
+export type DependantList = {
+	dependants: string[];
+	total: number;
+};
+
+export type InstallOrigin = 'explicit' | 'dependency' | 'import' | 'update';
+
+export type ModExplanation = {
+	reason: 'explicit' | 'dependency';
+	chains: Dependant[][];
+	origin: InstallOrigin | null;
+	installTime: string;
+};
+
+export type DependencyIssue = {
+	dependency: Dependant;
+	requiredBy: Dependant[];
+};
+
+export type DependencyCheck = {
+	missing: DependencyIssue[];
+	disabled: DependencyIssue[];
+	unresolved: string[];
+	compatibility: CompatWarning[];
+};
+
+export type CompatWarning =
+	| { type: 'incompatible'; mods: [string, string]; reason: string | null }
+	| { type: 'superseded'; installed: string; by: string; reason: string | null };
+
+export type Discovery = {
+	mostDownloaded: Mod[];
+	topRated: Mod[];
+};
+
+export type ConfigDefault = {
+	file: string;
+	section: string | null;
+	entry: string;
+	value: string;
+};
+
+export type GameDefaultsReport = {
+	changed: ConfigDefault[];
+	alreadySet: ConfigDefault[];
+};
+
+export type BisectResult = 'good' | 'bad';
+
+export type BisectStatus = {
+	suspects: Dependant[];
+	disabled: Dependant[];
+	steps: number;
+	remainingSteps: number;
+	done: boolean;
+};
+
+export type ModRef = {
+	packageUuid: string;
+	versionUuid: string;
+};
+
+export type InstallResponse =
+	| {
+			type: 'installed';
+			warnings: CompatWarning[];
+			skippedDependencies: string[];
+			needsGameDirConsent: boolean;
+	  }
+	| ({ type: 'alreadyInstalled' } & AlreadyInstalled)
+	| { type: 'needsConfirmation'; flagged: FlaggedPackage[] }
+	| { type: 'alreadySatisfied' };
+
+export type PackageFlag = { type: 'deprecated' } | { type: 'blocklisted'; reason: string | null };
+
+export type FlaggedPackage = {
+	fullName: string;
+	version: string;
+	flags: PackageFlag[];
+};
+
+export type AlreadyInstalled = {
+	packageUuid: string;
+	name: string;
+	installed: string | null;
+	requested: string;
+	actions: AlreadyInstalledAction[];
+};
+
+export type AlreadyInstalledAction =
+	| { type: 'changeVersion'; modRef: ModRef }
+	| { type: 'reinstall'; modRef: ModRef; cached: boolean };
+
+export type OrphanedDir = {
+	path: string;
+	size: number;
+};
+
+export type ProfileOrphans = {
+	profileId: number;
+	profileName: string;
+	dirs: OrphanedDir[];
+};
+
+export type ModMetadata = {
+	uuid: string;
+	downloads: number;
+	rating: number;
+	isDeprecated: boolean;
+	latestVersion: string;
+};
+
+export type MetadataRefresh = {
+	profileId: number;
+	mods: ModMetadata[];
+	newlyDeprecated: Dependant[];
+	updates: UpdateSummary;
+};
+
+export type SkippedUpdate = {
+	fullName: string;
+	packageUuid: string;
+	current: string;
+	latest: string;
+};
+
+export type UpdateVerification = {
+	installed: string[];
+	remaining: DependencyCheck;
+};
+
+export type SafeUpdate = {
+	skipped: SkippedUpdate[];
+	verification: UpdateVerification;
+};
+
+export type DependencyChanges = {
+	installed: string[];
+	unused: RemovalCandidate[];
+};
+
+export type RemovalCandidate = Dependant & {
+	size: number;
+};
+
+export type BatchReport = {
+	succeeded: string[];
+	failed: { uuid: string; name: string; reason: string }[];
+	reverted: boolean;
+	partial: boolean;
+};
+
+export type ToggleAllReport = {
+	changed: string[];
+	skipped: string[];
+	failed: BatchReport['failed'];
+};
+
 export type ModActionResponse =
 	| { type: 'done' }
-	| { type: 'hasDependants'; dependants: Dependant[] };
+	| { type: 'confirm'; dependants: Dependant[]; orphans: RemovalCandidate[] | null };
+
+export type InstallCounts = {
+	cached: number;
+	reused: number;
+	downloaded: number;
+};
 
 export type InstallTask =
-	| { kind: 'done' }
+	| { kind: 'done'; payload: InstallCounts }
 	| { kind: 'error' }
 	| { kind: 'installing' }
 	| { kind: 'extracting' }
@@ -173,6 +408,8 @@ export type InstallProgress = {
 	installedMods: number;
 	totalMods: number;
 	currentName: string;
+	profileId: number;
+	profileName: string;
 	canCancel: boolean;
 	task: InstallTask;
 };
@@ -190,6 +427,7 @@ export type ModpackArgs = {
 	websiteUrl: string;
 	includeDisabled: boolean;
 	includeFileMap: Map<string, boolean>;
+	target: ExportTarget | null;
 };
 
 export type Game = {
@@ -199,6 +437,76 @@ export type Game = {
 	favorite: boolean;
 	modLoader: ModLoader;
 	popular: boolean;
+	categories: string[];
+};
+
+export type Subdir = {
+	name: string;
+	target: string;
+	mode: 'separate' | 'separateFlatten' | 'track' | 'none';
+	mutable: boolean;
+	extension: string | null;
+	gameDir: boolean;
+};
+
+export type SubdirFiles = {
+	name: string;
+	path: string;
+	mode: Subdir['mode'];
+	fileCount: number;
+};
+
+export type GameMetadata = {
+	modLoader: ModLoader;
+	subdirs: Subdir[];
+	deployDir: string | null;
+	logPath: string | null;
+	configPath: string;
+	platforms: Platform[];
+	server: boolean;
+};
+
+export type LoaderRepair = {
+	package: string;
+	profileFiles: string[];
+	gameFiles: string[];
+};
+
+export type ModShareLinks = {
+	deepLink: string;
+	dependencyString: string;
+};
+
+export type DownloadSample = {
+	time: string;
+	downloads: number;
+};
+
+export type GameOverview = {
+	slug: string;
+	profileCount: number;
+	modCount: number;
+	favorite: boolean;
+	lastPlayed: string | null;
+	updateCount: number | null;
+};
+
+export type AppStats = {
+	gameCount: number;
+	favoriteCount: number;
+	profileCount: number;
+	uniqueModCount: number;
+	installedSize: number;
+	cacheSize: number;
+};
+
+export type CommunityInfo = {
+	name: string;
+	slug: string;
+	reachable: boolean;
+	packageCount: number | null;
+	error: string | null;
+	lastFetched: string | null;
 };
 
 export enum ModLoader {
@@ -222,26 +530,125 @@ export type LaunchMode =
 	| { type: 'launcher'; content?: undefined }
 	| { type: 'direct'; content: { instances: number; intervalSecs: number } };
 
-export type AvailableUpdate = {
+export type GameCrash = {
+	profileId: number;
+	exitCode: number | null;
+	durationSecs: number;
+	logTail: string[];
+};
+
+export type LaunchRecord = {
+	profileId: number;
+	launchedAt: string;
+	durationMs: number | null;
+	exitCode: number | null;
+	crashed: boolean;
+};
+
+export type LaunchCommand = {
+	program: string;
+	args: string[];
+	mode: LaunchMode;
+	commandLine: string;
+};
+
+export type DoorstopSettings = {
+	monoDllSearchPath: string | null;
+	ignoreDisableSwitch: boolean;
+	useEnvVars: boolean;
+};
+
+export type OutdatedMod = {
 	fullName: string;
-	ignore: boolean;
 	packageUuid: string;
 	versionUuid: string;
-	old: string;
-	new: string;
+	current: string;
+	latest: string;
+	ignored: boolean;
+	downloadSize: number;
+};
+
+export type UpdateSummary = {
+	profileId: number;
+	mods: OutdatedMod[];
+	available: number;
+	ignored: number;
+	downloadSize: number;
+};
+
+export type ThunderstoreQuery = {
+	mods: Mod[];
+	unknownCategories: string[];
 };
 
 export type ProfileQuery = {
 	mods: Mod[];
 	totalModCount: number;
 	unknownMods: Dependant[];
-	updates: AvailableUpdate[];
+	updates: UpdateSummary;
+};
+
+export type VersionSubstitution = {
+	fullName: string;
+	requested: string;
+	substitute: string;
+};
+
+export type R2modmanInstall = {
+	path: string;
+	games: string[];
+};
+
+export type OnboardingInfo = {
+	isFirstRun: boolean;
+	steamExePath: string | null;
+	installedGames: string[];
+	r2modman: R2modmanInstall | null;
+};
+
+export type ExportedFile = {
+	path: string;
+	sha256: string;
 };
 
 export type ImportData = {
 	name: string;
 	game: string | null;
 	modNames: string[];
+	substitutions: VersionSubstitution[];
+	missing: string[];
+	loaderMismatch: LoaderMismatch | null;
+};
+
+export type LoaderMismatch = {
+	from: string;
+	to: string;
+	skipped: string[];
+};
+
+export type R2ImportPhase =
+	| 'fetchingMods'
+	| 'readingManifest'
+	| 'resolving'
+	| 'installing'
+	| 'copyingConfigs';
+
+export type R2ImportProgress = {
+	game: string;
+	profile: string | null;
+	profileIndex: number;
+	totalProfiles: number;
+	modsProcessed: number;
+	totalMods: number;
+	phase: R2ImportPhase;
+};
+
+export type R2ImportReport = {
+	imported: string[];
+	skipped: string[];
+	failed: { profile: string; error: string }[];
+	reused: number;
+	downloaded: number;
 };
 
 export type R2ImportData = {
@@ -250,18 +657,26 @@ export type R2ImportData = {
 	include: boolean[];
 };
 
-export type MarkdownResponse = {
-	markdown: string | null;
-	detail?: string;
-};
-
 export type Prefs = {
 	steamExePath: string | null;
 	dataDir: string;
 	cacheDir: string;
 	sendTelemetry: boolean;
 	fetchModsAutomatically: boolean;
+	enableModCache: boolean;
+	maxCacheSize: number;
+	monthlyDownloadCap: number;
+	tempCleanupAgeHours: number;
+	extraCacheDirs: string[];
 	zoomFactor: number;
+	connectTimeoutSecs: number;
+	requestTimeoutSecs: number;
+	downloadTimeoutSecs: number;
+	forceHttp1: boolean;
+	disableStrategy: 'rename' | 'stage';
+	profileDirNaming: 'id' | 'name';
+	downloadSampleIntervalHours: number;
+	blocklistUrl: string | null;
 	gamePrefs: Map<string, GamePrefs>;
 };
 
@@ -270,8 +685,28 @@ export type GamePrefs = {
 	customArgs: string[] | null;
 	launchMode: LaunchMode;
 	platform: Platform | null;
+	webhook: WebhookPrefs | null;
 };
 
+export type WebhookPrefs = {
+	url: string;
+	onInstall: boolean;
+	onUpdate: boolean;
+	onPackUpload: boolean;
+};
+
+/** The JSON body Gale sends to the webhook URL. */
+export type WebhookPayload = {
+	content: string;
+	game: string;
+	profile: string;
+	timestamp: string;
+} & (
+	| { event: 'install'; mods: string[] }
+	| { event: 'update'; updated: string[]; installedDependencies: string[] }
+	| { event: 'packUpload'; name: string; version: string }
+);
+
 export enum Platform {
 	Steam = 'steam',
 	EpicGames = 'epicGames',
@@ -287,3 +722,87 @@ export type ModContextItem = {
 	onclick: (mod: Mod) => void;
 	children?: (mod: Mod) => ModContextItem[];
 };
+
+export type CachedVersion = {
+	fullName: string;
+	version: string;
+	size: number;
+	lastAccessed: string | null;
+};
+
+export type NetworkStatsRange = 'week' | 'month' | 'year';
+
+export type DailyUsage = {
+	day: string;
+	gameSlug: string;
+	bytes: number;
+};
+
+export type DownloadRecord = {
+	name: string;
+	gameSlug: string;
+	bytes: number;
+	time: string;
+};
+
+export type NetworkStats = {
+	total: number;
+	days: DailyUsage[];
+	largest: DownloadRecord[];
+};
+
+export type InstallPreview = {
+	downloadSize: number;
+	downloadCap: { used: number; cap: number } | null;
+	compatibility: CompatWarning[];
+	flagged: FlaggedPackage[];
+};
+
+export type PlannedMod = {
+	fullName: string;
+	version: string;
+	fileSize: number;
+	cached: boolean;
+	installed: boolean;
+};
+
+export type ImportPreview = Omit<InstallPreview, 'compatibility' | 'flagged'> & {
+	mods: PlannedMod[];
+	substitutions: VersionSubstitution[];
+	missing: string[];
+	loaderMismatch: LoaderMismatch | null;
+};
+
+export type CacheCompaction = {
+	versions: number;
+	saved: number;
+};
+
+export type GameDirInstall = {
+	gameDir: string;
+	files: string[];
+	risks: string[];
+};
+
+export type DeploymentStatus = {
+	profileId: number;
+	profileName: string | null;
+	target: string;
+	method: 'link' | 'copy';
+	active: boolean;
+	complete: boolean;
+};
+
+export type LogLevel = 'debug' | 'info' | 'message' | 'warning' | 'error' | 'fatal';
+
+export type LogEntry = {
+	level: LogLevel;
+	time: string | null;
+	source: string | null;
+	message: string;
+};
+
+export type LocalModDependencies = {
+	missing: string[];
+	unresolved: string[];
+};
@@ -29,6 +29,8 @@ const defaultModQuery = () => ({
 	includeDeprecated: false,
 	includeEnabled: false,
 	includeDisabled: false,
+	modpacksOnly: false,
+	hideLibraries: false,
 	sortBy: SortBy.LastUpdated,
 	sortOrder: SortOrder.Descending
 });
@@ -41,6 +43,8 @@ const defaultProfileQuery = () => ({
 	includeDeprecated: true,
 	includeEnabled: true,
 	includeDisabled: true,
+	modpacksOnly: false,
+	hideLibraries: false,
 	sortBy: SortBy.Custom,
 	sortOrder: SortOrder.Descending
 });
@@ -1,7 +1,18 @@
 import { get } from 'svelte/store';
-import type { Mod, ConfigEntry, Dependant } from './models';
+import type {
+	Mod,
+	ConfigEntry,
+	Dependant,
+	CompatWarning,
+	UpdateVerification,
+	FlaggedPackage,
+	PackageFlag,
+	LoaderRepair,
+	InstallPreview
+} from './models';
 import { activeGame } from './stores';
 import { convertFileSrc } from '@tauri-apps/api/core';
+import { confirm } from '@tauri-apps/plugin-dialog';
 
 export function shortenFileSize(size: number): string {
 	var i = size == 0 ? 0 : Math.floor(Math.log(size) / Math.log(1024));
@@ -76,22 +87,14 @@ export function timeSince(date: Date): string {
 	return Math.floor(seconds) + ' seconds';
 }
 
-export function isOutdated(mod: Mod): boolean {
-	if (mod.versions.length === 0) {
-		return false;
-	}
-
-	return mod.version !== mod.versions[0].name;
-}
-
 export function communityUrl(path: string) {
 	return `https://thunderstore.io/c/${get(activeGame)?.slug}/p/${path}/`;
 }
 
 export function iconSrc(mod: Mod) {
 	if (mod.type === 'remote') {
-		let fullName = `${mod.author}-${mod.name}-${mod.version}`;
-		return thunderstoreIconUrl(fullName);
+		// fetched and cached by the backend, see thunderstore/icons.rs
+		return convertFileSrc(`${mod.uuid}/${mod.versionUuid}`, 'gale-icon');
 	} else if (mod.icon !== null) {
 		return convertFileSrc(mod.icon);
 	} else {
@@ -99,8 +102,55 @@ export function iconSrc(mod: Mod) {
 	}
 }
 
-export function thunderstoreIconUrl(fullName: string) {
-	return `https://gcdn.thunderstore.io/live/repository/icons/${fullName}.png`;
+export function formatCompatWarning(warning: CompatWarning): string {
+	let message =
+		warning.type === 'incompatible'
+			? `${warning.mods[0]} is known to conflict with ${warning.mods[1]}`
+			: `${warning.installed} has been superseded by ${warning.by}`;
+
+	return warning.reason ? `${message} (${warning.reason}).` : `${message}.`;
+}
+
+export function formatLoaderRepair({
+	package: name,
+	profileFiles,
+	gameFiles
+}: LoaderRepair): string {
+	if (profileFiles.length === 0 && gameFiles.length === 0) {
+		return `${name} is set up correctly, nothing to repair.`;
+	}
+
+	let parts = [];
+
+	if (profileFiles.length > 0) {
+		parts.push(`reinstalled ${profileFiles.join(', ')} in the profile`);
+	}
+
+	if (gameFiles.length > 0) {
+		parts.push(`restored ${gameFiles.join(', ')} in the game folder`);
+	}
+
+	return `Repaired ${name}: ${parts.join(' and ')}.`;
+}
+
+export function describeUpdateVerification({
+	installed,
+	remaining
+}: UpdateVerification): string[] {
+	let messages = [];
+
+	if (installed.length > 0) {
+		messages.push(`Installed missing dependencies: ${installed.join(', ')}`);
+	}
+
+	let broken = remaining.missing.length + remaining.disabled.length + remaining.unresolved.length;
+	if (broken > 0) {
+		messages.push(
+			`${broken} dependencies are still missing or disabled, check the profile's dependencies to fix them`
+		);
+	}
+
+	return messages;
 }
 
 export function capitalize(str: string): string {
@@ -137,3 +187,41 @@ export function fileToBase64(file: File): Promise<string> {
 		reader.onerror = (error) => reject(error);
 	});
 }
+
+function formatPackageFlag(flag: PackageFlag): string {
+	switch (flag.type) {
+		case 'deprecated':
+			return 'deprecated';
+		case 'blocklisted':
+			return flag.reason ? `blocklisted: ${flag.reason}` : 'blocklisted';
+	}
+}
+
+/**
+ * Asks the user whether to install deprecated or blocklisted packages anyway.
+ */
+export async function confirmFlagged(flagged: FlaggedPackage[]): Promise<boolean> {
+	let lines = flagged.map(
+		(pkg) => `- ${pkg.fullName} ${pkg.version} (${pkg.flags.map(formatPackageFlag).join(', ')})`
+	);
+
+	return await confirm(
+		`The following packages are flagged:\n\n${lines.join('\n')}\n\nInstall them anyway?`,
+		{ title: 'Flagged packages', kind: 'warning' }
+	);
+}
+
+/**
+ * Asks the user whether to go over their monthly download cap, if the install would.
+ */
+export async function confirmDownloadCap({
+	downloadSize,
+	downloadCap
+}: InstallPreview): Promise<boolean> {
+	if (downloadCap === null) return true;
+
+	return await confirm(
+		`This install downloads ${shortenFileSize(downloadSize)}, which goes over your monthly download cap (${shortenFileSize(downloadCap.used)} of ${shortenFileSize(downloadCap.cap)} used).\n\nDownload anyway?`,
+		{ title: 'Download cap reached', kind: 'warning' }
+	);
+}